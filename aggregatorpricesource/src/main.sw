@@ -0,0 +1,244 @@
+contract;
+
+use yama_interfaces::{
+    pricesource_abi::{
+        PriceSource,
+        TimestampedPriceSource
+    },
+    aggregatorpricesource_abi::{
+        AggregatorPriceSource,
+        AggregationMode,
+        Feed
+    },
+    errors::AggregatorPriceSourceError
+};
+use std::{
+    storage::StorageVec,
+    block::timestamp
+};
+use fixed_point::ufp128::UFP128;
+use yama_types::ufp128::*;
+use stablecoin_library::{
+    helpers::sender_id,
+    constants::{
+        ZERO_B256,
+        INITIAL_OWNER
+    }
+};
+
+// Combines other price sources, skipping stale ones, and trips when the
+// combined price moves too far from the last accepted one
+storage {
+    owner: Identity = Identity::Address(Address{
+        value: INITIAL_OWNER}),
+    feeds: StorageVec<Feed> = StorageVec{},
+    mode: AggregationMode = AggregationMode::Median,
+    min_feeds: u64 = 1,
+    max_deviation: UFP128 = UFP128::zero(),
+    last_price: UFP128 = UFP128::zero(),
+    last_update: u64 = 0
+}
+
+impl AggregatorPriceSource for Contract {
+    #[storage(read)]
+    fn price() -> UFP128 {
+        checked_price()
+    }
+
+    #[storage(read, write)]
+    fn add_feed(source: b256, max_age: u64) {
+        verify_owner();
+        require(source != ZERO_B256, AggregatorPriceSourceError::InvalidFeed);
+        storage.feeds.push(Feed {
+            source: source,
+            max_age: max_age
+        });
+    }
+
+    #[storage(read, write)]
+    fn set_feed(index: u64, source: b256, max_age: u64) {
+        verify_owner();
+        require(
+            index < storage.feeds.len() && source != ZERO_B256,
+            AggregatorPriceSourceError::InvalidFeed
+        );
+        storage.feeds.set(index, Feed {
+            source: source,
+            max_age: max_age
+        });
+    }
+
+    #[storage(read, write)]
+    fn remove_feed(index: u64) {
+        verify_owner();
+        require(
+            index < storage.feeds.len(),
+            AggregatorPriceSourceError::InvalidFeed
+        );
+        storage.feeds.remove(index);
+    }
+
+    #[storage(read, write)]
+    fn set_mode(mode: AggregationMode) {
+        verify_owner();
+        storage.mode = mode;
+    }
+
+    #[storage(read, write)]
+    fn set_min_feeds(min_feeds: u64) {
+        verify_owner();
+        storage.min_feeds = min_feeds;
+    }
+
+    #[storage(read, write)]
+    fn set_max_deviation(max_deviation: UFP128) {
+        verify_owner();
+        storage.max_deviation = max_deviation;
+    }
+
+    #[storage(read, write)]
+    fn set_last_price(price: UFP128) {
+        verify_owner();
+        storage.last_price = price;
+        storage.last_update = timestamp();
+    }
+
+    #[storage(read, write)]
+    fn set_owner(owner: Identity) {
+        verify_owner();
+        storage.owner = owner;
+    }
+
+    #[storage(read, write)]
+    fn update() -> UFP128 {
+        let price = checked_price();
+        storage.last_price = price;
+        storage.last_update = timestamp();
+        price
+    }
+
+    #[storage(read)]
+    fn get_feed_count() -> u64 {
+        storage.feeds.len()
+    }
+
+    #[storage(read)]
+    fn get_feed(index: u64) -> Feed {
+        storage.feeds.get(index).unwrap()
+    }
+
+    #[storage(read)]
+    fn get_mode() -> AggregationMode {
+        storage.mode
+    }
+
+    #[storage(read)]
+    fn get_min_feeds() -> u64 {
+        storage.min_feeds
+    }
+
+    #[storage(read)]
+    fn get_max_deviation() -> UFP128 {
+        storage.max_deviation
+    }
+
+    #[storage(read)]
+    fn get_last_price() -> UFP128 {
+        storage.last_price
+    }
+
+    #[storage(read)]
+    fn get_last_update() -> u64 {
+        storage.last_update
+    }
+
+    #[storage(read)]
+    fn get_owner() -> Identity {
+        storage.owner
+    }
+}
+
+#[storage(read)]
+fn verify_owner() {
+    require(
+        sender_id() == storage.owner,
+        AggregatorPriceSourceError::NotOwner
+    );
+}
+
+// None if the feed is stale or has no price. Staleness is judged here from
+// updated_at(), since a feed that reverts takes the whole price down,
+// fallback or not.
+fn fresh_price(feed: Feed) -> Option<UFP128> {
+    if feed.max_age > 0 {
+        let source = abi(TimestampedPriceSource, feed.source);
+        if source.updated_at() + feed.max_age < timestamp() {
+            return Option::None;
+        }
+    }
+    let price = abi(PriceSource, feed.source).price();
+    if price == UFP128::zero() {
+        Option::None
+    } else {
+        Option::Some(price)
+    }
+}
+
+#[storage(read)]
+fn aggregate() -> UFP128 {
+    let fallback = match storage.mode {
+        AggregationMode::Median => false,
+        AggregationMode::Fallback => true,
+    };
+
+    // Kept sorted for the median
+    let mut prices: Vec<UFP128> = Vec::new();
+    let mut i = 0;
+    while i < storage.feeds.len() {
+        match fresh_price(storage.feeds.get(i).unwrap()) {
+            Option::Some(price) => {
+                if fallback {
+                    return price;
+                }
+                let mut j = 0;
+                while j < prices.len() && prices.get(j).unwrap() < price {
+                    j += 1;
+                }
+                prices.insert(j, price);
+            },
+            Option::None => {},
+        }
+        i += 1;
+    }
+
+    let count = prices.len();
+    require(
+        count > 0 && count >= storage.min_feeds,
+        AggregatorPriceSourceError::NotEnoughFreshFeeds
+    );
+    if count % 2 == 1 {
+        prices.get(count / 2).unwrap()
+    } else {
+        (prices.get(count / 2 - 1).unwrap() + prices.get(count / 2).unwrap())
+            / UFP128::from_u64(2)
+    }
+}
+
+#[storage(read)]
+fn checked_price() -> UFP128 {
+    let price = aggregate();
+    let last_price = storage.last_price;
+    let max_deviation = storage.max_deviation;
+    if !(max_deviation == UFP128::zero() || last_price == UFP128::zero()) {
+        let deviation = if price > last_price {
+            price - last_price
+        } else {
+            last_price - price
+        };
+        require(
+            deviation.le(last_price * max_deviation),
+            AggregatorPriceSourceError::ExceedsMaxDeviation
+        );
+    }
+    price
+}
\ No newline at end of file
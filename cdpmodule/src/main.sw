@@ -18,7 +18,8 @@ use yama_interfaces::{
         AddCollateralType,
         SetCollateralType,
         UpdateInterest,
-        ClearVault
+        ClearVault,
+        PartialClearVault
     },
     pricesource_abi::PriceSource,
     collateralmanager_abi::CollateralManager,
@@ -215,6 +216,35 @@ impl CDPModule for Contract {
         });
     }
 
+    #[storage(read, write)]
+    fn partial_clear_vault(
+        vault_id: u64,
+        collateral_amount: u64,
+        debt_repaid: u64
+    ) {
+        verify_sender_allowed(storage.stablecoin_contract);
+        update_interest(get_vault(vault_id).collateral_type_id);
+        let mut vault: Vault = get_vault(vault_id);
+        let mut c_type: CollateralType = get_collateral_type_of(vault_id);
+        c_type.total_collateral -= collateral_amount;
+        let collateral_manager = abi(CollateralManager,
+            storage.collateral_manager);
+        collateral_manager.handle_collateral_withdrawal(vault_id,
+            collateral_amount);
+        vault.collateral_amount -= collateral_amount;
+        vault.is_liquidated = false;
+        storage.collateral_types.set(vault.collateral_type_id, c_type);
+        storage.vaults.set(vault_id, vault);
+
+        set_debt(vault_id, get_debt(vault_id) - tokens_to_fp(debt_repaid));
+        log(PartialClearVault {
+            account: sender_id(),
+            vault_id: vault_id,
+            collateral_amount: collateral_amount,
+            debt_repaid: debt_repaid
+        });
+    }
+
     #[storage(read, write)]
     fn set_liquidator(contract_id: b256) {
         verify_sender_allowed(storage.stablecoin_contract);
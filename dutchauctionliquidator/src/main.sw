@@ -4,7 +4,9 @@ use yama_interfaces::{
     dutchauctionliquidator_abi::{
         DutchAuctionLiquidator,
         CTypeParams,
-        Auction
+        Auction,
+        PartialLiquidationParams,
+        PartialLot
     },
     errors::DutchAuctionLiquidatorError,
     events::{
@@ -12,7 +14,9 @@ use yama_interfaces::{
         ResetAuction,
         ClaimAuction,
         SetDefaultCTypeParams,
-        SetCTypeParams
+        SetCTypeParams,
+        SetPartialLiquidationParams,
+        InitializePartialAuction
     },
     cdpmodule_abi::CDPModule
 };
@@ -34,7 +38,10 @@ use stablecoin_library::{
         verify_sender_allowed,
         verify_tokens_from,
         tokens_to_fp,
-        u64_to_i256
+        fp_to_tokens,
+        u64_to_i256,
+        dutch_auction_price,
+        dutch_auction_expired
     }
 };
 
@@ -63,7 +70,11 @@ storage {
         enabled: DAL_ENABLED  // Ignored
     },
     auctions: StorageVec<Auction> = StorageVec{},
-    c_type_params_mapping: StorageMap<u64, CTypeParams> = StorageMap{}
+    c_type_params_mapping: StorageMap<u64, CTypeParams> = StorageMap{},
+    partial_liquidation_params: StorageMap<u64, PartialLiquidationParams>
+        = StorageMap{},
+    // auction_id => lot, for auctions of partial liquidations
+    partial_lots: StorageMap<u64, PartialLot> = StorageMap{}
 }
 
 impl DutchAuctionLiquidator for Contract {
@@ -119,6 +130,40 @@ impl DutchAuctionLiquidator for Contract {
         });
     }
 
+    #[storage(read, write)]
+    fn set_partial_liquidation_params(
+        collateral_type_id: u64,
+        enabled: bool,
+        target_ratio: UFP128,
+        penalty: UFP128
+    ) {
+        verify_sender_allowed(storage.stablecoin_contract);
+        require(!enabled || (UFP128::from_u64(1) + penalty) < target_ratio,
+            DutchAuctionLiquidatorError::InvalidPartialLiquidationParams);
+        if enabled {
+            // A vault cleared below the collateral ratio could be liquidated
+            // again right away, with another penalty
+            let collateral_ratio = abi(CDPModule, storage.cdp_module)
+                .get_collateral_ratio(collateral_type_id);
+            require(collateral_ratio < target_ratio,
+                DutchAuctionLiquidatorError::InvalidPartialLiquidationParams);
+        }
+        storage.partial_liquidation_params.insert(collateral_type_id,
+            PartialLiquidationParams {
+                enabled: enabled,
+                target_ratio: target_ratio,
+                penalty: penalty
+            }
+        );
+
+        log(SetPartialLiquidationParams {
+            collateral_type_id: collateral_type_id,
+            enabled: enabled,
+            target_ratio: target_ratio,
+            penalty: penalty
+        });
+    }
+
     #[storage(read, write)]
     fn liquidate(vault_id: u64) {
         verify_sender_allowed(storage.stablecoin_contract);
@@ -154,22 +199,56 @@ impl DutchAuctionLiquidator for Contract {
         let cdpmodule = abi(CDPModule, storage.cdp_module);
 
         let token = cdpmodule.get_collateral_token(auction.vault_id);
-        let amount = cdpmodule.get_collateral_amount(auction.vault_id);
+        let partial_lot = storage.partial_lots.get(auction_id);
+
+        if partial_lot.is_some() {
+            let lot: PartialLot = partial_lot.unwrap();
+            cdpmodule.transfer(
+                token,
+                sender_id(),
+                lot.collateral_amount
+            );
 
-        cdpmodule.transfer(
-            token,
-            sender_id(),
-            amount
-        );
+            cdpmodule.update_interest(
+                cdpmodule.get_collateral_type_id(auction.vault_id));
+            // Whatever the auction raises beyond the debt it covers is the
+            // penalty
+            let mut debt_repaid: u64 = price;
+            if lot.debt < debt_repaid {
+                debt_repaid = lot.debt;
+            }
+            let debt: u64 = cdpmodule.get_debt(auction.vault_id);
+            if debt < debt_repaid {
+                debt_repaid = debt;
+            }
+            add_surplus(
+                u64_to_i256(price - debt_repaid),
+                storage.balancesheet_module
+            );
 
-        cdpmodule.update_interest(
-            cdpmodule.get_collateral_type_id(auction.vault_id));
-        add_surplus(
-            u64_to_i256(price) - u64_to_i256(cdpmodule.get_debt(auction.vault_id)),
-            storage.balancesheet_module
-        );
+            cdpmodule.partial_clear_vault(
+                auction.vault_id,
+                lot.collateral_amount,
+                debt_repaid
+            );
+        } else {
+            let amount = cdpmodule.get_collateral_amount(auction.vault_id);
+
+            cdpmodule.transfer(
+                token,
+                sender_id(),
+                amount
+            );
 
-        cdpmodule.clear_vault(auction.vault_id);
+            cdpmodule.update_interest(
+                cdpmodule.get_collateral_type_id(auction.vault_id));
+            add_surplus(
+                u64_to_i256(price) - u64_to_i256(cdpmodule.get_debt(auction.vault_id)),
+                storage.balancesheet_module
+            );
+
+            cdpmodule.clear_vault(auction.vault_id);
+        }
 
         auction.done = true;
         storage.auctions.set(auction_id, auction);
@@ -225,6 +304,18 @@ impl DutchAuctionLiquidator for Contract {
         get_collateral_amount_of_auction(auction_id)
     }
 
+    #[storage(read)]
+    fn get_partial_liquidation_params(
+        collateral_type_id: u64
+    ) -> PartialLiquidationParams {
+        get_partial_liquidation_params(collateral_type_id)
+    }
+
+    #[storage(read)]
+    fn get_partial_lot(auction_id: u64) -> Option<PartialLot> {
+        storage.partial_lots.get(auction_id)
+    }
+
     #[storage(read, write)]
     fn set_balancesheet_module(value: b256) {
         verify_sender_allowed(storage.stablecoin_contract);
@@ -263,22 +354,102 @@ fn initialize_auction(vault_id: u64) {
         get_collateral_type_id_of_vault(vault_id)
     );
 
+    let partial_lot: Option<PartialLot> = get_partial_lot_of_vault(vault_id);
+    let mut collateral_value: UFP128 = get_collateral_value(vault_id);
+    if partial_lot.is_some() {
+        let cdpmodule = abi(CDPModule, storage.cdp_module);
+        collateral_value = tokens_to_fp(fp_to_tokens(
+            tokens_to_fp(partial_lot.unwrap().collateral_amount)
+                * cdpmodule.get_collateral_price(vault_id)
+        ));
+    }
+
     let auction: Auction = Auction {
         vault_id: vault_id,
-        start_price: get_collateral_value(vault_id)
-            * c_type_params.initial_price_ratio,
+        start_price: collateral_value * c_type_params.initial_price_ratio,
         start_time: timestamp(),
         done: false
     };
 
     storage.auctions.push(auction);
+    let auction_id: u64 = storage.auctions.len() - 1;
 
     log(InitializeAuction {
         vault_id: vault_id,
-        auction_id: storage.auctions.len() - 1,
+        auction_id: auction_id,
         start_price: auction.start_price,
         start_time: auction.start_time
     });
+
+    if partial_lot.is_some() {
+        let lot: PartialLot = partial_lot.unwrap();
+        storage.partial_lots.insert(auction_id, lot);
+
+        log(InitializePartialAuction {
+            vault_id: vault_id,
+            auction_id: auction_id,
+            collateral_amount: lot.collateral_amount,
+            debt: lot.debt
+        });
+    }
+}
+
+#[storage(read)]
+fn get_partial_liquidation_params(
+    collateral_type_id: u64
+) -> PartialLiquidationParams {
+    let params = storage.partial_liquidation_params.get(collateral_type_id);
+
+    if params.is_some() {
+        return params.unwrap();
+    } else {
+        return PartialLiquidationParams {
+            enabled: false,
+            target_ratio: UFP128::zero(),
+            penalty: UFP128::zero()
+        };
+    }
+}
+
+// Sizes a partial liquidation of the vault, or returns None when the whole
+// vault has to be sold
+#[storage(read)]
+fn get_partial_lot_of_vault(vault_id: u64) -> Option<PartialLot> {
+    let params: PartialLiquidationParams = get_partial_liquidation_params(
+        get_collateral_type_id_of_vault(vault_id)
+    );
+    if !params.enabled {
+        return Option::None;
+    }
+
+    let cdpmodule = abi(CDPModule, storage.cdp_module);
+    let debt: u64 = cdpmodule.get_debt(vault_id);
+    let price: UFP128 = cdpmodule.get_collateral_price(vault_id);
+    let collateral_value: UFP128 = get_collateral_value(vault_id);
+    let target_value: UFP128 = tokens_to_fp(debt) * params.target_ratio;
+    if price == UFP128::zero() || collateral_value.ge(target_value) {
+        return Option::None;
+    }
+
+    // Repaying r of the debt sells r * (1 + penalty) worth of collateral, so
+    // the vault is back at the target ratio when
+    // value - r * (1 + penalty) == (debt - r) * target_ratio
+    let one: UFP128 = UFP128::from_u64(1);
+    let debt_to_cover: UFP128 = (target_value - collateral_value)
+        / (params.target_ratio - one - params.penalty);
+    let lot: PartialLot = PartialLot {
+        collateral_amount: fp_to_tokens(
+            debt_to_cover * (one + params.penalty) / price),
+        debt: fp_to_tokens(debt_to_cover)
+    };
+
+    if lot.collateral_amount == 0
+        || lot.debt >= debt
+        || lot.collateral_amount >= cdpmodule.get_collateral_amount(vault_id)
+    {
+        return Option::None;
+    }
+    Option::Some(lot)
 }
 
 #[storage(read)]
@@ -324,6 +495,11 @@ fn get_auction_c_type_params(auction_id: u64) -> CTypeParams {
 
 #[storage(read)]
 fn get_collateral_amount_of_auction(auction_id: u64) -> u64 {
+    let partial_lot = storage.partial_lots.get(auction_id);
+    if partial_lot.is_some() {
+        return partial_lot.unwrap().collateral_amount;
+    }
+
     let cdpmodule = abi(CDPModule, storage.cdp_module);
     cdpmodule.get_collateral_amount(get_auction(auction_id).vault_id)
 }
@@ -344,17 +520,21 @@ fn get_price(auction_id: u64) -> u64 {
 
     let c_type_params: CTypeParams = get_auction_c_type_params(auction_id);
 
-    let intervals_elapsed: u64 = (timestamp() - auction.start_time)
-        / c_type_params.time_interval;
-    
-    (auction.start_price * c_type_params.change_rate.powu(intervals_elapsed))
-        .to_u64()
+    dutch_auction_price(
+        auction.start_price,
+        auction.start_time,
+        c_type_params.time_interval,
+        c_type_params.change_rate
+    )
 }
 
 #[storage(read)]
 fn is_expired(auction_id: u64) -> bool {
     let c_type_params: CTypeParams = get_auction_c_type_params(auction_id);
 
-    timestamp() >= (get_auction(auction_id).start_time
-        + (c_type_params.time_interval * c_type_params.reset_threshold))
+    dutch_auction_expired(
+        get_auction(auction_id).start_time,
+        c_type_params.time_interval,
+        c_type_params.reset_threshold
+    )
 }
\ No newline at end of file
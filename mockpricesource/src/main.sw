@@ -0,0 +1,64 @@
+contract;
+
+use yama_interfaces::{
+    pricesource_abi::TimestampedPriceSource,
+    mockpricesource_abi::MockPriceSource,
+    errors::MockPriceSourceError
+};
+use std::block::timestamp;
+use fixed_point::ufp128::UFP128;
+use yama_types::ufp128::*;
+use stablecoin_library::{
+    helpers::sender_id,
+    constants::INITIAL_OWNER
+};
+
+// A price source for tests whose price is set by its owner
+storage {
+    owner: Identity = Identity::Address(Address{
+        value: INITIAL_OWNER}),
+    // Due to limitations with initialization of structs, this must be set upon
+    // deployment
+    price: UFP128 = UFP128::zero(),
+    updated_at: u64 = 0
+}
+
+impl TimestampedPriceSource for Contract {
+    #[storage(read)]
+    fn price() -> UFP128 {
+        storage.price
+    }
+
+    #[storage(read)]
+    fn updated_at() -> u64 {
+        storage.updated_at
+    }
+}
+
+impl MockPriceSource for Contract {
+    #[storage(read, write)]
+    fn set_price(price: UFP128, updated_at: Option<u64>) {
+        verify_owner();
+        storage.price = price;
+        storage.updated_at = match updated_at {
+            Option::Some(time) => time,
+            Option::None => timestamp()
+        };
+    }
+
+    #[storage(read, write)]
+    fn set_owner(owner: Identity) {
+        verify_owner();
+        storage.owner = owner;
+    }
+
+    #[storage(read)]
+    fn get_owner() -> Identity {
+        storage.owner
+    }
+}
+
+#[storage(read)]
+fn verify_owner() {
+    require(sender_id() == storage.owner, MockPriceSourceError::NotOwner);
+}
\ No newline at end of file
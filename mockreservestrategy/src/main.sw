@@ -0,0 +1,84 @@
+contract;
+
+use yama_interfaces::{
+    reservestrategy_abi::ReserveStrategy,
+    mockreservestrategy_abi::MockReserveStrategy,
+    errors::MockReserveStrategyError
+};
+use std::{
+    auth::caller_contract_id,
+    call_frames::msg_asset_id,
+    context::msg_amount,
+    token::transfer
+};
+use stablecoin_library::{
+    helpers::sender_id,
+    constants::ZERO_B256
+};
+
+// A strategy for tests that holds reserves without putting them anywhere.
+// Yield and losses are simulated by sending tokens in and out.
+storage {
+    psm: b256 = ZERO_B256,
+    balances: StorageMap<b256, u64> = StorageMap{}
+}
+
+impl ReserveStrategy for Contract {
+    #[payable]
+    #[storage(read, write)]
+    fn deposit() {
+        let token = msg_asset_id().value;
+        storage.balances.insert(token, get_balance(token) + msg_amount());
+    }
+
+    #[storage(read, write)]
+    fn withdraw(token: b256, amount: u64) {
+        require(
+            caller_contract_id().value == storage.psm,
+            MockReserveStrategyError::NotPSM
+        );
+        take(token, amount);
+        transfer(
+            amount,
+            ContractId::from(token),
+            Identity::ContractId(ContractId::from(storage.psm))
+        );
+    }
+
+    #[storage(read)]
+    fn balance(token: b256) -> u64 {
+        get_balance(token)
+    }
+}
+
+impl MockReserveStrategy for Contract {
+    #[payable]
+    #[storage(read, write)]
+    fn add_yield() {
+        let token = msg_asset_id().value;
+        storage.balances.insert(token, get_balance(token) + msg_amount());
+    }
+
+    #[storage(read, write)]
+    fn lose(token: b256, amount: u64) {
+        take(token, amount);
+        transfer(amount, ContractId::from(token), sender_id());
+    }
+
+    #[storage(read)]
+    fn get_psm() -> b256 {
+        storage.psm
+    }
+}
+
+#[storage(read)]
+fn get_balance(token: b256) -> u64 {
+    storage.balances.get(token).unwrap_or(0)
+}
+
+#[storage(read, write)]
+fn take(token: b256, amount: u64) {
+    let balance = get_balance(token);
+    require(amount <= balance, MockReserveStrategyError::InsufficientBalance);
+    storage.balances.insert(token, balance - amount);
+}
\ No newline at end of file
@@ -2,6 +2,8 @@ contract;
 
 use std::{
     token::transfer,
+    storage::StorageVec,
+    call_frames::msg_asset_id,
     context::{
         msg_amount,
         this_balance
@@ -14,7 +16,12 @@ use stablecoin_library::{
         DECIMALS,
         PSM_CEILING,
         ZERO_B256,
-        PSM_TOKEN_DECIMALS
+        PSM_TOKEN_DECIMALS,
+        PSM_TIN,
+        PSM_TOUT,
+        PSM_FEE_DENOMINATOR,
+        PSM_MAX_ALLOCATION,
+        PSM_ALLOCATION_DENOMINATOR
     },
     helpers::{
         sender_id,
@@ -22,14 +29,29 @@ use stablecoin_library::{
         burn,
         verify_sender_allowed,
         verify_tokens_from,
-        convert_amount
+        convert_amount,
+        add_surplus,
+        add_deficit,
+        u64_to_i256,
+        safe_unwrap_bool
     }
 };
 
 use yama_interfaces::{
-    pegstabilitymodule_abi::PegStabilityModule,
+    pegstabilitymodule_abi::{
+        PegStabilityModule,
+        TokenConfig
+    },
+    reservestrategy_abi::ReserveStrategy,
     events::{
         SetDebtCeiling,
+        SetTokenConfig,
+        SetFees,
+        SetStrategy,
+        SetMaxAllocation,
+        Allocate,
+        Recall,
+        ReportStrategy,
         Deposit,
         Withdraw
     },
@@ -41,14 +63,29 @@ storage {
     token: b256 = ZERO_B256,
     debt_ceiling: u64 = PSM_CEILING,
     yss_decimals: u8 = DECIMALS,
-    external_stable_decimals: u8 = PSM_TOKEN_DECIMALS
+    external_stable_decimals: u8 = PSM_TOKEN_DECIMALS,
+    // The fields above configure the primary token until set_token_config
+    // does. Other tokens are listed here in the order they were added.
+    tokens: StorageVec<b256> = StorageVec{},
+    token_configs: StorageMap<b256, TokenConfig> = StorageMap{},
+    balancesheet_module: b256 = ZERO_B256,
+    tin: u64 = PSM_TIN,
+    tout: u64 = PSM_TOUT,
+    strategies: StorageMap<b256, bool> = StorageMap{},
+    max_allocation: u64 = PSM_MAX_ALLOCATION,
+    // Reserves held by strategies, by (strategy, token) and by token
+    allocations: StorageMap<(b256, b256), u64> = StorageMap{},
+    allocated: StorageMap<b256, u64> = StorageMap{}
 }
 
 impl PegStabilityModule for Contract {
     #[storage(read, write)]
     fn set_debt_ceiling(debt_ceiling: u64) {
         verify_sender_allowed(storage.stablecoin_contract);
+        let mut config = get_token_config(storage.token);
+        config.debt_ceiling = debt_ceiling;
         storage.debt_ceiling = debt_ceiling;
+        storage.token_configs.insert(storage.token, config);
 
         log(SetDebtCeiling {
             account: sender_id(),
@@ -56,6 +93,35 @@ impl PegStabilityModule for Contract {
         });
     }
 
+    #[storage(read, write)]
+    fn set_token_config(
+        token: b256,
+        decimals: u8,
+        debt_ceiling: u64,
+        enabled: bool
+    ) {
+        verify_sender_allowed(storage.stablecoin_contract);
+        require(token != ZERO_B256, PSMError::UnsupportedToken);
+        if token != storage.token
+            && storage.token_configs.get(token).is_none()
+        {
+            storage.tokens.push(token);
+        }
+        storage.token_configs.insert(token, TokenConfig {
+            decimals: decimals,
+            debt_ceiling: debt_ceiling,
+            enabled: enabled
+        });
+
+        log(SetTokenConfig {
+            account: sender_id(),
+            token: token,
+            decimals: decimals,
+            debt_ceiling: debt_ceiling,
+            enabled: enabled
+        });
+    }
+
     #[storage(read)]
     fn get_stablecoin_contract() -> b256 {
         storage.stablecoin_contract
@@ -68,7 +134,7 @@ impl PegStabilityModule for Contract {
 
     #[storage(read)]
     fn debt_ceiling() -> u64 {
-        storage.debt_ceiling
+        get_token_config(storage.token).debt_ceiling
     }
 
     #[storage(read)]
@@ -78,7 +144,211 @@ impl PegStabilityModule for Contract {
 
     #[storage(read)]
     fn get_external_stable_decimals() -> u8 {
-        storage.external_stable_decimals
+        get_token_config(storage.token).decimals
+    }
+
+    #[storage(read)]
+    fn get_token_config(token: b256) -> TokenConfig {
+        get_token_config(token)
+    }
+
+    #[storage(read)]
+    fn get_token_count() -> u64 {
+        storage.tokens.len() + 1
+    }
+
+    #[storage(read)]
+    fn get_token_at(index: u64) -> b256 {
+        if index == 0 {
+            storage.token
+        } else {
+            storage.tokens.get(index - 1).unwrap()
+        }
+    }
+
+    #[storage(read, write)]
+    fn set_fees(tin: u64, tout: u64) {
+        verify_sender_allowed(storage.stablecoin_contract);
+        require(
+            tin <= PSM_FEE_DENOMINATOR && tout <= PSM_FEE_DENOMINATOR,
+            PSMError::FeeExceedsDenominator
+        );
+        storage.tin = tin;
+        storage.tout = tout;
+
+        log(SetFees {
+            account: sender_id(),
+            tin: tin,
+            tout: tout
+        });
+    }
+
+    #[storage(read, write)]
+    fn set_balancesheet_module(value: b256) {
+        verify_sender_allowed(storage.stablecoin_contract);
+        storage.balancesheet_module = value;
+    }
+
+    #[storage(read, write)]
+    fn set_strategy(strategy: b256, allowed: bool) {
+        verify_sender_allowed(storage.stablecoin_contract);
+        storage.strategies.insert(strategy, allowed);
+
+        log(SetStrategy {
+            account: sender_id(),
+            strategy: strategy,
+            allowed: allowed
+        });
+    }
+
+    #[storage(read, write)]
+    fn set_max_allocation(max_allocation: u64) {
+        verify_sender_allowed(storage.stablecoin_contract);
+        require(
+            max_allocation <= PSM_ALLOCATION_DENOMINATOR,
+            PSMError::MaxAllocationExceedsDenominator
+        );
+        storage.max_allocation = max_allocation;
+
+        log(SetMaxAllocation {
+            account: sender_id(),
+            max_allocation: max_allocation
+        });
+    }
+
+    #[storage(read, write)]
+    fn allocate(strategy: b256, token: b256, amount: u64) {
+        verify_sender_allowed(storage.stablecoin_contract);
+        require(
+            safe_unwrap_bool(storage.strategies.get(strategy)),
+            PSMError::StrategyNotAllowed
+        );
+        let allocated = get_allocated(token) + amount;
+        require(
+            allocated * PSM_ALLOCATION_DENOMINATOR
+                <= storage.max_allocation * get_reserves(token),
+            PSMError::ExceedsMaxAllocation
+        );
+        set_allocation(
+            strategy,
+            token,
+            get_allocation(strategy, token) + amount
+        );
+        storage.allocated.insert(token, allocated);
+
+        abi(ReserveStrategy, strategy).deposit {
+            coins: amount,
+            asset_id: token
+        } ();
+
+        log(Allocate {
+            account: sender_id(),
+            strategy: strategy,
+            token: token,
+            amount: amount
+        });
+    }
+
+    #[storage(read, write)]
+    fn recall(strategy: b256, token: b256, amount: u64) {
+        verify_sender_allowed(storage.stablecoin_contract);
+        let allocation = get_allocation(strategy, token);
+        require(amount <= allocation, PSMError::ExceedsAllocation);
+        set_allocation(strategy, token, allocation - amount);
+        storage.allocated.insert(token, get_allocated(token) - amount);
+
+        abi(ReserveStrategy, strategy).withdraw(token, amount);
+
+        log(Recall {
+            account: sender_id(),
+            strategy: strategy,
+            token: token,
+            amount: amount
+        });
+    }
+
+    #[storage(read, write)]
+    fn report(strategy: b256, token: b256) {
+        verify_sender_allowed(storage.stablecoin_contract);
+        require(
+            safe_unwrap_bool(storage.strategies.get(strategy)),
+            PSMError::StrategyNotAllowed
+        );
+        let decimals = get_token_config(token).decimals;
+        let strategy_contract = abi(ReserveStrategy, strategy);
+        let balance = strategy_contract.balance(token);
+        let allocation = get_allocation(strategy, token);
+        let mut gain = 0;
+        let mut loss = 0;
+        if balance > allocation {
+            // Only what actually arrives is booked, not what the strategy
+            // reports
+            let balance_before = this_balance(ContractId::from(token));
+            strategy_contract.withdraw(token, balance - allocation);
+            gain = this_balance(ContractId::from(token)) - balance_before;
+            add_surplus(
+                u64_to_i256(
+                    convert_amount(gain, decimals, storage.yss_decimals)),
+                storage.balancesheet_module
+            );
+        } else if balance < allocation {
+            loss = allocation - balance;
+            set_allocation(strategy, token, balance);
+            storage.allocated.insert(token, get_allocated(token) - loss);
+            add_deficit(
+                u64_to_i256(
+                    convert_amount(loss, decimals, storage.yss_decimals)),
+                storage.balancesheet_module
+            );
+        }
+
+        log(ReportStrategy {
+            account: sender_id(),
+            strategy: strategy,
+            token: token,
+            gain: gain,
+            loss: loss
+        });
+    }
+
+    #[storage(read)]
+    fn get_tin() -> u64 {
+        storage.tin
+    }
+
+    #[storage(read)]
+    fn get_tout() -> u64 {
+        storage.tout
+    }
+
+    #[storage(read)]
+    fn get_balancesheet_module() -> b256 {
+        storage.balancesheet_module
+    }
+
+    #[storage(read)]
+    fn is_strategy(strategy: b256) -> bool {
+        safe_unwrap_bool(storage.strategies.get(strategy))
+    }
+
+    #[storage(read)]
+    fn get_max_allocation() -> u64 {
+        storage.max_allocation
+    }
+
+    #[storage(read)]
+    fn get_allocation(strategy: b256, token: b256) -> u64 {
+        get_allocation(strategy, token)
+    }
+
+    #[storage(read)]
+    fn get_allocated(token: b256) -> u64 {
+        get_allocated(token)
+    }
+
+    #[storage(read)]
+    fn get_reserves(token: b256) -> u64 {
+        get_reserves(token)
     }
 
     #[storage(read)]
@@ -92,23 +362,23 @@ impl PegStabilityModule for Contract {
     #[payable]
     #[storage(read)]
     fn deposit() -> u64 {
-        verify_tokens_from(storage.token);
-        let yama_amount: u64 = convert_amount(
-            msg_amount(),
-            storage.external_stable_decimals,
-            storage.yss_decimals
-        );
+        let token = msg_asset_id().value;
+        let (yama_amount, fee) = quote_deposit(token, msg_amount());
         mint(
             yama_amount,
             sender_id(),
             storage.stablecoin_contract
         );
+        book_fee(fee);
         log(Deposit {
             account: sender_id(),
-            ext_stable_amount: msg_amount()
+            ext_stable_amount: msg_amount(),
+            token: token,
+            yss_amount: yama_amount,
+            fee: fee
         });
         require(
-            this_balance(ContractId::from(storage.token)) <= storage.debt_ceiling,
+            get_reserves(token) <= get_token_config(token).debt_ceiling,
             PSMError::ExceedsDebtCeiling
         );
         yama_amount
@@ -116,24 +386,113 @@ impl PegStabilityModule for Contract {
 
     #[payable]
     #[storage(read)]
-    fn withdraw() -> u64 {
+    fn withdraw(token: b256) -> u64 {
         verify_tokens_from(storage.stablecoin_contract);
         burn(msg_amount(), storage.stablecoin_contract);
-        let ext_stable_amount: u64 = convert_amount(
-            msg_amount(),
-            storage.yss_decimals,
-            storage.external_stable_decimals
-        );
+        let (ext_stable_amount, fee) = quote_withdraw(token, msg_amount());
         transfer(
             ext_stable_amount,
-            ContractId::from(storage.token),
+            ContractId::from(token),
             sender_id()
         );
+        book_fee(fee);
         log(Withdraw {
             account: sender_id(),
-            yss_amount: msg_amount()
+            yss_amount: msg_amount(),
+            token: token,
+            ext_stable_amount: ext_stable_amount,
+            fee: fee
         });
         
         ext_stable_amount
     }
+
+    #[storage(read)]
+    fn preview_deposit(token: b256, amount: u64) -> u64 {
+        let (yama_amount, _) = quote_deposit(token, amount);
+        require(
+            get_reserves(token) + amount
+                <= get_token_config(token).debt_ceiling,
+            PSMError::ExceedsDebtCeiling
+        );
+        yama_amount
+    }
+
+    #[storage(read)]
+    fn preview_withdraw(token: b256, amount: u64) -> u64 {
+        let (ext_stable_amount, _) = quote_withdraw(token, amount);
+        ext_stable_amount
+    }
+}
+
+// The YSS minted for `amount` of `token` and the fee taken from it
+#[storage(read)]
+fn quote_deposit(token: b256, amount: u64) -> (u64, u64) {
+    let config = get_token_config(token);
+    require(config.enabled, PSMError::TokenDisabled);
+    let gross_amount: u64 = convert_amount(
+        amount,
+        config.decimals,
+        storage.yss_decimals
+    );
+    let fee = gross_amount * storage.tin / PSM_FEE_DENOMINATOR;
+    (gross_amount - fee, fee)
+}
+
+// The `token` paid out for `amount` of YSS and the fee taken from it
+#[storage(read)]
+fn quote_withdraw(token: b256, amount: u64) -> (u64, u64) {
+    let config = get_token_config(token);
+    let fee = amount * storage.tout / PSM_FEE_DENOMINATOR;
+    let ext_stable_amount: u64 = convert_amount(
+        amount - fee,
+        storage.yss_decimals,
+        config.decimals
+    );
+    (ext_stable_amount, fee)
+}
+
+// Fees are kept as reserves, backing no YSS, so they're surplus
+#[storage(read)]
+fn book_fee(fee: u64) {
+    if fee > 0 {
+        add_surplus(u64_to_i256(fee), storage.balancesheet_module);
+    }
+}
+
+#[storage(read)]
+fn get_allocation(strategy: b256, token: b256) -> u64 {
+    storage.allocations.get((strategy, token)).unwrap_or(0)
 }
+
+#[storage(write)]
+fn set_allocation(strategy: b256, token: b256, allocation: u64) {
+    storage.allocations.insert((strategy, token), allocation);
+}
+
+#[storage(read)]
+fn get_allocated(token: b256) -> u64 {
+    storage.allocated.get(token).unwrap_or(0)
+}
+
+#[storage(read)]
+fn get_reserves(token: b256) -> u64 {
+    this_balance(ContractId::from(token)) + get_allocated(token)
+}
+
+#[storage(read)]
+fn get_token_config(token: b256) -> TokenConfig {
+    let config = storage.token_configs.get(token);
+    if config.is_some() {
+        return config.unwrap();
+    }
+    require(
+        token == storage.token && token != ZERO_B256,
+        PSMError::UnsupportedToken
+    );
+    TokenConfig {
+        decimals: storage.external_stable_decimals,
+        debt_ceiling: storage.debt_ceiling,
+        enabled: true
+    }
+}
\ No newline at end of file
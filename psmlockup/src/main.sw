@@ -1,15 +1,25 @@
 contract;
 
 use yama_interfaces::{
-    psmlockup_abi::PSMLockup,
+    psmlockup_abi::{
+        PSMLockup,
+        ExitTicket
+    },
     simplebsh_abi::SimpleBSH,
     pegstabilitymodule_abi::PegStabilityModule,
     events::{
         Lockup,
-        Redeem
-    }
+        Redeem,
+        SetExitQueue,
+        RequestRedeem,
+        CancelRedeem,
+        ClaimRedeem,
+        AbsorbDeficit
+    },
+    errors::PSMLockupError
 };
 use fixed_point::ufp128::UFP128;
+use signed_integers::i256::I256;
 use yama_types::ufp128::*;
 use std::{
     token::{
@@ -18,6 +28,9 @@ use std::{
         transfer
     },
     logging::log,
+    block::timestamp,
+    storage::StorageVec,
+    auth::caller_contract_id,
     context::{
         msg_amount,
         this_balance
@@ -30,11 +43,14 @@ use stablecoin_library::{
         verify_tokens_from,
         tokens_to_fp,
         fp_to_tokens,
-        sender_id
+        sender_id,
+        u64_to_i256,
+        i256_to_u64
     },
     constants::{
         ZERO_B256,
         DECIMALS,
+        PSMLOCKUP_EXIT_DELAY,
         STR_64,
         STR_32
     }
@@ -47,7 +63,10 @@ storage {
     bsh_contract: b256 = ZERO_B256,
     name: str[64] = STR_64,
     symbol: str[32] = STR_32,
-    total_supply: u64 = 0
+    total_supply: u64 = 0,
+    exit_queue_enabled: bool = false,
+    exit_delay: u64 = PSMLOCKUP_EXIT_DELAY,
+    tickets: StorageVec<ExitTicket> = StorageVec{}
 }
 
 impl PSMLockup for Contract {
@@ -87,20 +106,88 @@ impl PSMLockup for Contract {
     #[storage(read, write)]
     fn redeem() -> u64 {
         verify_tokens_from(contract_id().value);
-        let yama_amount = fp_to_tokens(tokens_to_fp(msg_amount()) * value());
-        burn(msg_amount());
-        transfer(
-            yama_amount,
-            ContractId::from(storage.stablecoin_contract),
-            sender_id()
-        );
+        require(!storage.exit_queue_enabled, PSMLockupError::QueueEnabled);
+        let yama_amount = pay_out(msg_amount(), sender_id());
         log(Redeem {
             account: sender_id(),
             yama_amount: yama_amount,
             lockup_amount: msg_amount()
         });
 
-        storage.total_supply -= msg_amount();
+        yama_amount
+    }
+
+    #[storage(read, write)]
+    fn set_exit_queue(enabled: bool, delay: u64) {
+        verify_sender_allowed(storage.stablecoin_contract);
+        storage.exit_queue_enabled = enabled;
+        storage.exit_delay = delay;
+        log(SetExitQueue {
+            account: sender_id(),
+            enabled: enabled,
+            delay: delay
+        });
+    }
+
+    #[payable]
+    #[storage(read, write)]
+    fn request_redeem() -> u64 {
+        verify_tokens_from(contract_id().value);
+        require(storage.exit_queue_enabled, PSMLockupError::QueueDisabled);
+        let ticket_id = storage.tickets.len();
+        storage.tickets.push(ExitTicket {
+            owner: sender_id(),
+            lockup_amount: msg_amount(),
+            unlock_time: timestamp() + storage.exit_delay,
+            done: false
+        });
+        log(RequestRedeem {
+            account: sender_id(),
+            ticket_id: ticket_id,
+            lockup_amount: msg_amount()
+        });
+
+        ticket_id
+    }
+
+    #[storage(read, write)]
+    fn cancel_redeem(ticket_id: u64) {
+        let mut ticket = get_open_ticket(ticket_id);
+        require(
+            sender_id() == ticket.owner,
+            PSMLockupError::NotTicketOwner
+        );
+        ticket.done = true;
+        storage.tickets.set(ticket_id, ticket);
+        transfer(ticket.lockup_amount, contract_id(), ticket.owner);
+        log(CancelRedeem {
+            account: sender_id(),
+            ticket_id: ticket_id,
+            lockup_amount: ticket.lockup_amount
+        });
+    }
+
+    #[storage(read, write)]
+    fn claim_redeem(ticket_id: u64) -> u64 {
+        let mut ticket = get_open_ticket(ticket_id);
+        let balance = stablecoin_balance();
+        let mut yama_amount = redeem_amount(ticket.lockup_amount);
+        if yama_amount > balance {
+            require(
+                timestamp() >= ticket.unlock_time,
+                PSMLockupError::ExitDelayNotElapsed
+            );
+            yama_amount = balance;
+        }
+        ticket.done = true;
+        storage.tickets.set(ticket_id, ticket);
+        send_redemption(ticket.lockup_amount, yama_amount, ticket.owner);
+        log(ClaimRedeem {
+            account: sender_id(),
+            ticket_id: ticket_id,
+            yama_amount: yama_amount,
+            lockup_amount: ticket.lockup_amount
+        });
 
         yama_amount
     }
@@ -110,6 +197,28 @@ impl PSMLockup for Contract {
         value()
     }
 
+    #[storage(read)]
+    fn preview_lockup(amount: u64) -> u64 {
+        let psm = abi(PegStabilityModule, storage.psm_contract);
+        let yama_amount = psm.preview_deposit(storage.token, amount);
+        // Any later lockup is in a later block, so it mints the pending share
+        // before the lockup is valued
+        let bsh = abi(SimpleBSH, storage.bsh_contract);
+        let pending = bsh.get_pending_share_amount();
+        let mut balance = stablecoin_balance();
+        if bsh.get_target() == contract_id().value
+            && pending > u64_to_i256(0)
+        {
+            balance += i256_to_u64(pending);
+        }
+        fp_to_tokens(tokens_to_fp(yama_amount) / value_of(balance))
+    }
+
+    #[storage(read)]
+    fn preview_redeem(amount: u64) -> u64 {
+        redeem_amount(amount)
+    }
+
     #[storage(read)]
     fn total_supply() -> u64 {
         storage.total_supply
@@ -145,18 +254,119 @@ impl PSMLockup for Contract {
         storage.psm_contract
     }
 
+    #[storage(read)]
+    fn absorb_deficit(deficit: u64) -> u64 {
+        require(
+            caller_contract_id().value == storage.bsh_contract,
+            PSMLockupError::NotBSH
+        );
+        let balance = stablecoin_balance();
+        let yama_amount = if deficit < balance { deficit } else { balance };
+        if yama_amount > 0 {
+            transfer(
+                yama_amount,
+                ContractId::from(storage.stablecoin_contract),
+                Identity::ContractId(caller_contract_id())
+            );
+        }
+        log(AbsorbDeficit {
+            account: sender_id(),
+            deficit: deficit,
+            yama_amount: yama_amount
+        });
+
+        yama_amount
+    }
+
     #[storage(read)]
     fn get_bsh_contract() -> b256 {
         storage.bsh_contract
     }
+
+    #[storage(read)]
+    fn is_exit_queue_enabled() -> bool {
+        storage.exit_queue_enabled
+    }
+
+    #[storage(read)]
+    fn get_exit_delay() -> u64 {
+        storage.exit_delay
+    }
+
+    #[storage(read)]
+    fn get_ticket_count() -> u64 {
+        storage.tickets.len()
+    }
+
+    #[storage(read)]
+    fn get_ticket(ticket_id: u64) -> ExitTicket {
+        require(
+            ticket_id < storage.tickets.len(),
+            PSMLockupError::InvalidTicket
+        );
+        storage.tickets.get(ticket_id).unwrap()
+    }
 }
 
 #[storage(read)]
 fn value() -> UFP128 {
+    value_of(stablecoin_balance())
+}
+
+// The value of a lockup token were the lockup to hold `balance` of YSS
+#[storage(read)]
+fn value_of(balance: u64) -> UFP128 {
     if storage.total_supply == 0 {
         return UFP128::from_uint(1);
     }
-    tokens_to_fp(this_balance(
-        ContractId::from(storage.stablecoin_contract)))
-        / tokens_to_fp(storage.total_supply)
+    tokens_to_fp(balance) / tokens_to_fp(storage.total_supply)
+}
+
+#[storage(read)]
+fn stablecoin_balance() -> u64 {
+    this_balance(ContractId::from(storage.stablecoin_contract))
+}
+
+#[storage(read)]
+fn redeem_amount(amount: u64) -> u64 {
+    fp_to_tokens(tokens_to_fp(amount) * value())
+}
+
+// Burns `amount` of lockup tokens held by the lockup and sends what they
+// redeem for to `to`
+#[storage(read, write)]
+fn pay_out(amount: u64, to: Identity) -> u64 {
+    let yama_amount = redeem_amount(amount);
+    require(
+        yama_amount <= stablecoin_balance(),
+        PSMLockupError::InsufficientLiquidity
+    );
+    send_redemption(amount, yama_amount, to);
+    yama_amount
+}
+
+// Burns `amount` of lockup tokens held by the lockup and sends `yama_amount`
+// of YSS to `to`
+#[storage(read, write)]
+fn send_redemption(amount: u64, yama_amount: u64, to: Identity) {
+    burn(amount);
+    if yama_amount > 0 {
+        transfer(
+            yama_amount,
+            ContractId::from(storage.stablecoin_contract),
+            to
+        );
+    }
+    storage.total_supply -= amount;
+}
+
+#[storage(read)]
+fn get_open_ticket(ticket_id: u64) -> ExitTicket {
+    require(
+        ticket_id < storage.tickets.len(),
+        PSMLockupError::InvalidTicket
+    );
+    let ticket = storage.tickets.get(ticket_id).unwrap();
+    require(!ticket.done, PSMLockupError::TicketClosed);
+    ticket
 }
\ No newline at end of file
@@ -13,6 +13,10 @@ abigen!(
         name="DutchAuctionLiquidator",
         abi="../dutchauctionliquidator/out/debug/dutchauctionliquidator-abi.json"
     ),
+    Contract(
+        name="SurplusAuctionModule",
+        abi="../surplusauctionmodule/out/debug/surplusauctionmodule-abi.json"
+    ),
     Contract(
         name="EmptyCollateralManager",
         abi="../emptycollateralmanager/out/debug/emptycollateralmanager-abi.json"
@@ -29,10 +33,26 @@ abigen!(
         name="PSMPriceSource",
         abi="../psmpricesource/out/debug/psmpricesource-abi.json"
     ),
+    Contract(
+        name="MockPriceSource",
+        abi="../mockpricesource/out/debug/mockpricesource-abi.json"
+    ),
+    Contract(
+        name="MockReserveStrategy",
+        abi="../mockreservestrategy/out/debug/mockreservestrategy-abi.json"
+    ),
+    Contract(
+        name="AggregatorPriceSource",
+        abi="../aggregatorpricesource/out/debug/aggregatorpricesource-abi.json"
+    ),
     Contract(
         name="SimpleBSH",
         abi="../simplebsh/out/debug/simplebsh-abi.json"
     ),
+    Contract(
+        name="SplitBSH",
+        abi="../splitbsh/out/debug/splitbsh-abi.json"
+    ),
     Contract(
         name="PSMLockup",
         abi="../psmlockup/out/debug/psmlockup-abi.json"
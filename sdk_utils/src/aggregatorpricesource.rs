@@ -0,0 +1,259 @@
+use fuels::{prelude::*, tx::ContractId, types::{Bits256, Identity}};
+use crate::{
+  abigen::*,
+  errors::{
+    register_contract,
+    YamaContract,
+    YamaError
+  },
+  storage::StorageLayout,
+  ufp128::Ufp128,
+  utils::get_id
+};
+
+#[derive(Clone, Debug)]
+pub struct FeedConfig {
+  // Must return 0 rather than revert without a price, and report staleness
+  // through TimestampedPriceSource rather than revert, as MockPriceSource does
+  pub source: ContractId,
+  // 0 for feeds that don't implement TimestampedPriceSource
+  pub max_age: u64
+}
+
+#[derive(Clone, Debug)]
+pub struct AggregatorConfig {
+  pub feeds: Vec<FeedConfig>,
+  pub mode: AggregationMode,
+  pub min_feeds: u64,
+  // As a fraction of the last accepted price, 0 to disable
+  pub max_deviation: UFP128
+}
+
+impl Default for AggregatorConfig {
+  fn default() -> Self {
+    AggregatorConfig {
+      feeds: vec![],
+      mode: AggregationMode::Median(),
+      min_feeds: 1,
+      max_deviation: Ufp128::ZERO.into()
+    }
+  }
+}
+
+// Owned by `wallet`. Whatever reads its price has to include the feeds in
+// its contract IDs too; see feed_ids.
+pub async fn init_aggregatorpricesource(
+  wallet: &WalletUnlocked,
+  config: &AggregatorConfig
+) -> Result<AggregatorPriceSource, YamaError> {
+  let storage_path = "../aggregatorpricesource/out/debug/aggregatorpricesource-storage_slots.json";
+  let storage = StorageLayout::new()
+    .field(0, &get_id(wallet))
+    .field(3, &config.min_feeds)
+    .field(4, &config.max_deviation);
+
+  let id = Contract::deploy(
+    "../aggregatorpricesource/out/debug/aggregatorpricesource.bin",
+    wallet,
+    TxParameters::default(),
+    storage.configuration(storage_path)
+  )
+  .await?;
+
+  register_contract(&id, YamaContract::AggregatorPriceSource);
+  let instance = AggregatorPriceSource::new(id, wallet.clone());
+  set_mode(&instance, config.mode.clone()).await?;
+  for feed in config.feeds.iter() {
+    add_feed(&instance, &feed.source, feed.max_age).await?;
+  }
+  Ok(instance)
+}
+
+pub async fn add_feed(
+  instance: &AggregatorPriceSource,
+  source: &ContractId,
+  max_age: u64
+) -> Result<(), YamaError> {
+  instance
+    .methods()
+    .add_feed(Bits256(**source), max_age)
+    .call()
+    .await?;
+  Ok(())
+}
+
+pub async fn set_feed(
+  instance: &AggregatorPriceSource,
+  index: u64,
+  source: &ContractId,
+  max_age: u64
+) -> Result<(), YamaError> {
+  instance
+    .methods()
+    .set_feed(index, Bits256(**source), max_age)
+    .call()
+    .await?;
+  Ok(())
+}
+
+pub async fn remove_feed(
+  instance: &AggregatorPriceSource,
+  index: u64
+) -> Result<(), YamaError> {
+  instance
+    .methods()
+    .remove_feed(index)
+    .call()
+    .await?;
+  Ok(())
+}
+
+pub async fn set_mode(
+  instance: &AggregatorPriceSource,
+  mode: AggregationMode
+) -> Result<(), YamaError> {
+  instance
+    .methods()
+    .set_mode(mode)
+    .call()
+    .await?;
+  Ok(())
+}
+
+pub async fn set_min_feeds(
+  instance: &AggregatorPriceSource,
+  min_feeds: u64
+) -> Result<(), YamaError> {
+  instance
+    .methods()
+    .set_min_feeds(min_feeds)
+    .call()
+    .await?;
+  Ok(())
+}
+
+pub async fn set_max_deviation(
+  instance: &AggregatorPriceSource,
+  max_deviation: &UFP128
+) -> Result<(), YamaError> {
+  instance
+    .methods()
+    .set_max_deviation(max_deviation.clone())
+    .call()
+    .await?;
+  Ok(())
+}
+
+pub async fn set_last_price(
+  instance: &AggregatorPriceSource,
+  price: &UFP128
+) -> Result<(), YamaError> {
+  instance
+    .methods()
+    .set_last_price(price.clone())
+    .call()
+    .await?;
+  Ok(())
+}
+
+pub async fn set_owner(
+  instance: &AggregatorPriceSource,
+  owner: Identity
+) -> Result<(), YamaError> {
+  instance
+    .methods()
+    .set_owner(owner)
+    .call()
+    .await?;
+  Ok(())
+}
+
+pub async fn get_feeds(
+  instance: &AggregatorPriceSource
+) -> Result<Vec<Feed>, YamaError> {
+  let count = instance
+    .methods()
+    .get_feed_count()
+    .simulate()
+    .await?
+    .value;
+  let mut feeds: Vec<Feed> = vec![];
+  for index in 0..count {
+    feeds.push(instance.methods().get_feed(index).simulate().await?.value);
+  }
+  Ok(feeds)
+}
+
+// The contracts price() and update() call into
+pub async fn feed_ids(
+  instance: &AggregatorPriceSource
+) -> Result<Vec<Bech32ContractId>, YamaError> {
+  Ok(get_feeds(instance)
+    .await?
+    .into_iter()
+    .map(|feed| Bech32ContractId::from(ContractId::new(feed.source.0)))
+    .collect())
+}
+
+// `price_source`, then its feeds if it's an aggregator. Calls that read a
+// collateral type's price need all of them in their contract IDs.
+pub async fn price_source_ids(
+  price_source: &Bech32ContractId,
+  wallet: &WalletUnlocked
+) -> Result<Vec<Bech32ContractId>, YamaError> {
+  let mut ids = vec![price_source.clone()];
+  let aggregator = AggregatorPriceSource::new(
+    price_source.clone(), wallet.clone());
+  // Other price sources have no feeds to count, so this reverts for them
+  if aggregator.methods().get_feed_count().simulate().await.is_ok() {
+    ids.extend(feed_ids(&aggregator).await?);
+  }
+  Ok(ids)
+}
+
+pub async fn get_price(
+  instance: &AggregatorPriceSource
+) -> Result<UFP128, YamaError> {
+  Ok(instance
+    .methods()
+    .price()
+    .set_contract_ids(&feed_ids(instance).await?)
+    .simulate()
+    .await?
+    .value)
+}
+
+// Accepts the current price as the one later prices deviate from
+pub async fn update(
+  instance: &AggregatorPriceSource
+) -> Result<UFP128, YamaError> {
+  Ok(instance
+    .methods()
+    .update()
+    .set_contract_ids(&feed_ids(instance).await?)
+    .call()
+    .await?
+    .value)
+}
+
+pub async fn get_last_price(
+  instance: &AggregatorPriceSource
+) -> Result<UFP128, YamaError> {
+  Ok(instance
+    .methods()
+    .get_last_price()
+    .simulate()
+    .await?
+    .value)
+}
+
+pub async fn get_last_update(
+  instance: &AggregatorPriceSource
+) -> Result<u64, YamaError> {
+  Ok(instance
+    .methods()
+    .get_last_update()
+    .simulate()
+    .await?
+    .value)
+}
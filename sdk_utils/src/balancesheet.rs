@@ -1,8 +1,12 @@
 use fuels::{prelude::*, tx::ContractId, types::{Identity, Bits256}};
 use crate::{
+  errors::{
+    register_contract,
+    YamaContract,
+    YamaError
+  },
+  storage::StorageLayout,
   utils::{
-      set_storage_val,
-      get_storage_val,
       get_test_wallet
   },
   modulartoken::{
@@ -10,14 +14,9 @@ use crate::{
       set_allowlist,
       get_cid
   },
-  abigen::*, simplebsh::init_custom_simplebsh
+  abigen::*, simplebsh::init_custom_simplebsh,
+  splitbsh::get_recipients
 };
-use tokio::sync::Mutex;
-use lazy_static::lazy_static;
-
-lazy_static! {
-    static ref MUTEX: Mutex<i32> = Mutex::new(0i32);
-}
 
 pub async fn init_balancesheet() -> (
   WalletUnlocked,
@@ -27,12 +26,12 @@ pub async fn init_balancesheet() -> (
 ) {
   let wallet: WalletUnlocked = get_test_wallet().await;
   let stable_i: ModularToken
-    = init_custom_modulartoken(&wallet).await;
+    = init_custom_modulartoken(&wallet).await.unwrap();
   let (
     balancesheet,
     simplebsh
   ) = init_custom_balancesheet(&wallet, &stable_i,
-      &ContractId::from(stable_i.get_contract_id())).await;
+      &ContractId::from(stable_i.get_contract_id())).await.unwrap();
   // Setting the stablecoin as the BSH target is kinda hacky but it's
   // convenient for testing
 
@@ -48,56 +47,48 @@ pub async fn init_custom_balancesheet(
   wallet: &WalletUnlocked,
   stable_i: &ModularToken,
   bsh_target: &ContractId
-) -> (BalanceSheet, SimpleBSH)
+) -> Result<(BalanceSheet, SimpleBSH), YamaError>
 {
   let storage_path = "../balancesheetmodule/out/debug/balancesheetmodule-storage_slots.json";
 
-  let guard = MUTEX.lock().await;
-
-  set_storage_val(
-    &storage_path,
-    "storage_0",
-    &get_cid(stable_i).to_string()
-  );
+  let storage = StorageLayout::new()
+    .field(0, &get_cid(stable_i));
 
   let id = Contract::deploy(
     "../balancesheetmodule/out/debug/balancesheetmodule.bin",
     &wallet,
     TxParameters::default(),
-    StorageConfiguration::with_storage_path(Some(
-      storage_path.to_string()
-    ))
+    storage.configuration(storage_path)
   )
-  .await
-  .unwrap();
-  drop(guard);
+  .await?;
 
+  register_contract(&id, YamaContract::BalanceSheet);
   let instance = BalanceSheet::new(id.clone(), wallet.clone());
 
   set_allowlist(&stable_i, Identity::ContractId 
-    (id.clone().into()), true).await;
+    (id.clone().into()), true).await?;
 
   let simplebsh = init_custom_simplebsh(
     &wallet,
     &stable_i,
     &ContractId::from(id.clone()),
     &bsh_target
-  ).await;
+  ).await?;
 
   set_handler(
     &instance,
     stable_i.get_contract_id(),
     &ContractId::from(simplebsh.id())
-  ).await;
+  ).await?;
 
-  (instance, simplebsh)
+  Ok((instance, simplebsh))
 }
 
 pub async fn set_handler(
   balancesheet: &BalanceSheet,
   stable_id: &Bech32ContractId,
   handler: &ContractId
-) {
+) -> Result<(), YamaError> {
   balancesheet
     .methods()
     .set_handler(
@@ -107,67 +98,100 @@ pub async fn set_handler(
       stable_id.clone()
     ])
     .call()
-    .await
-    .unwrap();
+    .await?;
+  Ok(())
+}
+
+// Which handler `handler` is, and the contracts it calls into as surplus and
+// deficits are booked: SimpleBSH's target, or SplitBSH's contract recipients
+// and backstop
+pub async fn resolve_handler(
+  handler: &Bech32ContractId,
+  wallet: &WalletUnlocked
+) -> Result<(YamaContract, Vec<Bech32ContractId>), YamaError> {
+  let splitbsh = SplitBSH::new(handler.clone(), wallet.clone());
+  // SimpleBSH has no recipients to count, so this reverts for it
+  if splitbsh.methods().get_recipient_count().simulate().await.is_ok() {
+    let mut targets: Vec<Bech32ContractId> = get_recipients(&splitbsh)
+      .await?
+      .iter()
+      .filter_map(|recipient| match &recipient.recipient {
+        Identity::ContractId(id) => Some(Bech32ContractId::from(*id)),
+        Identity::Address(_) => None
+      })
+      .collect();
+    let backstop = splitbsh.methods().get_backstop().simulate().await?.value;
+    if backstop.0 != [0u8; 32] {
+      targets.push(Bech32ContractId::from(ContractId::new(backstop.0)));
+    }
+    return Ok((YamaContract::SplitBSH, targets));
+  }
+
+  let target = SimpleBSH::new(handler.clone(), wallet.clone())
+    .methods()
+    .get_target()
+    .simulate()
+    .await?
+    .value;
+  Ok((
+    YamaContract::SimpleBSH,
+    vec![Bech32ContractId::from(ContractId::new(target.0))]
+  ))
 }
 
 pub async fn add_surplus(
   balancesheet: &BalanceSheet,
   stable_id: &Bech32ContractId,
-  simplebsh: &Bech32ContractId,
-  bsh_target: &Bech32ContractId,
+  bsh: &Bech32ContractId,
+  bsh_targets: &[Bech32ContractId],
   amount: I256
-) {
+) -> Result<(), YamaError> {
   let tx_params = TxParameters::new(
     None, Some(32_000_000), None);
 
+  let mut ids = vec![stable_id.clone(), bsh.clone()];
+  ids.extend_from_slice(bsh_targets);
   balancesheet
     .methods()
     .add_surplus(
       amount,
     )
-    .set_contract_ids(&[
-      stable_id.clone(),
-      simplebsh.clone(),
-      bsh_target.clone()
-    ])
+    .set_contract_ids(&ids)
     .tx_params(tx_params)
     .call()
-    .await
-    .unwrap();
+    .await?;
+  Ok(())
 }
 
 pub async fn add_deficit(
   balancesheet: &BalanceSheet,
   stable_id: &Bech32ContractId,
-  simplebsh: &Bech32ContractId,
-  bsh_target: &Bech32ContractId,
+  bsh: &Bech32ContractId,
+  bsh_targets: &[Bech32ContractId],
   amount: I256
-) {
+) -> Result<(), YamaError> {
   let tx_params = TxParameters::new(
     None, Some(32_000_000), None);
 
+  let mut ids = vec![stable_id.clone(), bsh.clone()];
+  ids.extend_from_slice(bsh_targets);
   balancesheet
     .methods()
     .add_deficit(
       amount,
     )
-    .set_contract_ids(&[
-      stable_id.clone(),
-      simplebsh.clone(),
-      bsh_target.clone()
-    ])
+    .set_contract_ids(&ids)
     .tx_params(tx_params)
     .call()
-    .await
-    .unwrap();
+    .await?;
+  Ok(())
 }
 
 pub async fn set_surplus(
   balancesheet: &BalanceSheet,
   stable_id: &Bech32ContractId,
   amount: I256
-) {
+) -> Result<(), YamaError> {
   let tx_params = TxParameters::new(
     None, Some(32_000_000), None);
 
@@ -181,22 +205,21 @@ pub async fn set_surplus(
     ])
     .tx_params(tx_params)
     .call()
-    .await
-    .unwrap();
+    .await?;
+  Ok(())
 }
 
 pub async fn total_surplus(
   balancesheet: &BalanceSheet,
   stable_id: &Bech32ContractId
-) -> I256 {
-  balancesheet
+) -> Result<I256, YamaError> {
+  Ok(balancesheet
     .methods()
     .total_surplus()
     .set_contract_ids(&[
       stable_id.clone()
     ])
     .call()
-    .await
-    .unwrap()
-    .value
+    .await?
+    .value)
 }
\ No newline at end of file
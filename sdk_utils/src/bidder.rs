@@ -0,0 +1,510 @@
+use std::{
+  future::Future,
+  pin::Pin,
+  time::Duration
+};
+use async_std::task;
+use fuels::{prelude::*, tx::ContractId};
+use crate::{
+  abigen::*,
+  client::YamaClient,
+  dutchauctionliquidator,
+  errors::YamaError,
+  indexer::{
+    AuctionState,
+    Indexer
+  },
+  keeper::{
+    with_retries,
+    KeeperConfig,
+    Retried
+  },
+  psmpricesource,
+  testswapper,
+  ufp128::Ufp128,
+  utils::{
+    get_timestamp,
+    unix_to_tai64
+  }
+};
+
+// Boxed, since traits can't have async methods before Rust 1.75 and the
+// crate's rust-version is older
+pub type VenueFuture<'a>
+  = Pin<Box<dyn Future<Output = Result<u64, YamaError>> + 'a>>;
+
+// Somewhere collateral won at auction can be sold for the stablecoin
+pub trait SwapVenue {
+  // Stablecoin received for selling `amount` of `collateral`
+  fn quote<'a>(
+    &'a self,
+    collateral: &'a ContractId,
+    amount: u64
+  ) -> VenueFuture<'a>;
+
+  // Sells `amount` of `collateral` for at least `min_output` of the
+  // stablecoin, returning the amount received
+  fn sell<'a>(
+    &'a self,
+    collateral: &'a ContractId,
+    amount: u64,
+    min_output: u64
+  ) -> VenueFuture<'a>;
+}
+
+// Swaps 1:1 through a TestSwapper, which only takes a single collateral token
+pub struct TestSwapperVenue {
+  pub swapper: TestSwapper,
+  pub stablecoin: Bech32ContractId,
+  pub collateral: ContractId
+}
+
+impl SwapVenue for TestSwapperVenue {
+  fn quote<'a>(
+    &'a self,
+    collateral: &'a ContractId,
+    amount: u64
+  ) -> VenueFuture<'a> {
+    Box::pin(async move {
+      if *collateral == self.collateral {
+        Ok(amount)
+      } else {
+        Ok(0)
+      }
+    })
+  }
+
+  fn sell<'a>(
+    &'a self,
+    collateral: &'a ContractId,
+    amount: u64,
+    min_output: u64
+  ) -> VenueFuture<'a> {
+    Box::pin(async move {
+      testswapper::swap_to_yama(
+        &self.swapper,
+        &self.stablecoin,
+        &Bech32ContractId::from(*collateral),
+        amount,
+        min_output
+      ).await
+    })
+  }
+}
+
+// amount * price, saturating where the contracts would revert
+pub fn collateral_value(amount: u64, price: &UFP128) -> u64 {
+  Ufp128::from_u64(amount)
+    .checked_mul(Ufp128::from(price))
+    .map_or(u64::MAX, Ufp128::to_u64)
+}
+
+// start_price * change_rate^interval, as get_price in
+// dutchauctionliquidator/src/main.sw computes it. Saturates where the
+// contract would revert.
+pub fn price_at_interval(
+  start_price: u128,
+  params: &CTypeParams,
+  interval: u64
+) -> u64 {
+  Ufp128::from(&params.change_rate)
+    .checked_powu(interval)
+    .and_then(|change| Ufp128::from_bits(start_price).checked_mul(change))
+    .map_or(u64::MAX, Ufp128::to_u64)
+}
+
+pub fn interval_at(params: &CTypeParams, start_time: u64, at: u64) -> u64 {
+  at.saturating_sub(start_time) / params.time_interval.max(1)
+}
+
+pub fn expires_at(params: &CTypeParams, start_time: u64) -> u64 {
+  start_time.saturating_add(
+    params.time_interval.saturating_mul(params.reset_threshold))
+}
+
+// Projected price of an auction at TAI64 time `at`, 0 once it expired
+pub fn projected_price(
+  start_price: u128,
+  params: &CTypeParams,
+  start_time: u64,
+  at: u64
+) -> u64 {
+  let interval = interval_at(params, start_time, at);
+  if interval >= params.reset_threshold {
+    return 0;
+  }
+  price_at_interval(start_price, params, interval)
+}
+
+// The first interval before expiry priced at or below `max_price`
+pub fn first_profitable_interval(
+  start_price: u128,
+  params: &CTypeParams,
+  max_price: u64
+) -> Option<u64> {
+  if price_at_interval(start_price, params, 0) <= max_price {
+    return Some(0);
+  }
+  // The price never comes down
+  if Ufp128::from(&params.change_rate) >= Ufp128::ONE {
+    return None;
+  }
+  (1..params.reset_threshold)
+    .find(|interval| price_at_interval(start_price, params, *interval)
+      <= max_price)
+}
+
+#[derive(Clone, Debug)]
+pub struct BidderConfig {
+  // Minimum profit in the stablecoin, on top of `min_profit_bps`
+  pub min_profit: u64,
+  // Minimum profit as a share of the expected proceeds
+  pub min_profit_bps: u64,
+  // Sell claimed collateral through the venue right away
+  pub sell_collateral: bool,
+  pub reset_expired: bool,
+  // Retries, backoff and poll interval
+  pub retry: KeeperConfig
+}
+
+impl Default for BidderConfig {
+  fn default() -> Self {
+    BidderConfig {
+      min_profit: 0,
+      min_profit_bps: 100,
+      sell_collateral: true,
+      reset_expired: true,
+      retry: KeeperConfig::default()
+    }
+  }
+}
+
+impl BidderConfig {
+  // The highest price still worth paying for collateral expected to sell
+  // for `proceeds`
+  pub fn max_price(&self, proceeds: u64) -> Option<u64> {
+    let margin = (proceeds as u128 * self.min_profit_bps as u128 / 10_000)
+      as u64;
+    proceeds.checked_sub(margin)?.checked_sub(self.min_profit)
+  }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuctionValuation {
+  pub collateral_amount: u64,
+  // Per the collateral type's price source
+  pub oracle_value: u64,
+  // Per the swap venue's quote
+  pub venue_value: u64
+}
+
+impl AuctionValuation {
+  // The lower of the two, so neither a stale price nor a bad quote gets the
+  // bidder to overpay
+  pub fn proceeds(&self) -> u64 {
+    self.oracle_value.min(self.venue_value)
+  }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuctionPlan {
+  pub auction_id: u64,
+  pub collateral_type_id: u64,
+  pub collateral: ContractId,
+  pub params: CTypeParams,
+  pub valuation: AuctionValuation,
+  pub max_price: Option<u64>,
+  // TAI64 time the price first drops to `max_price`, unset if it doesn't
+  // before the auction expires
+  pub claim_at: Option<u64>,
+  pub expires_at: u64
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClaimedAuction {
+  pub auction_id: u64,
+  pub price: u64,
+  pub collateral_amount: u64,
+  // Stablecoin received from the venue, if the collateral was sold
+  pub sold_for: Option<u64>
+}
+
+#[derive(Debug, Default)]
+pub struct BidderReport {
+  pub claimed: Vec<ClaimedAuction>,
+  // Auction IDs
+  pub reset: Vec<u64>,
+  // Auctions that aren't profitable yet
+  pub waiting: Vec<AuctionPlan>,
+  // Auction ID => the error that outlasted the retries
+  pub failures: Vec<(u64, YamaError)>,
+  // Auction ID => why the claimed collateral didn't sell
+  pub sell_failures: Vec<(u64, YamaError)>,
+  // Auction ID => an error that was retried
+  pub retried: Vec<(u64, YamaError)>
+}
+
+impl BidderReport {
+  fn record_retried(&mut self, id: u64, retried: Vec<YamaError>) {
+    self.retried.extend(retried.into_iter().map(|error| (id, error)));
+  }
+}
+
+// Watches auctions through the indexer, claims each one once its price drops
+// below what the collateral sells for and resets the ones that expire
+pub struct AuctionBidder<V: SwapVenue> {
+  pub client: YamaClient,
+  pub indexer: Indexer,
+  pub venue: V,
+  pub config: BidderConfig
+}
+
+impl<V: SwapVenue> AuctionBidder<V> {
+  pub async fn new(
+    cdp_id: &Bech32ContractId,
+    wallet: &WalletUnlocked,
+    venue: V,
+    config: BidderConfig
+  ) -> Result<AuctionBidder<V>, YamaError> {
+    let client = YamaClient::new(cdp_id, wallet).await?;
+    Ok(AuctionBidder {
+      client,
+      indexer: Indexer::new(wallet),
+      venue,
+      config
+    })
+  }
+
+  pub async fn value(
+    &mut self,
+    auction_id: u64,
+    vault_id: u64,
+    collateral_type_id: u64
+  ) -> Result<(ContractId, AuctionValuation), YamaError> {
+    let collateral_amount =
+      dutchauctionliquidator::get_collateral_amount_of_auction(
+        &self.client.dutchauctionliquidator,
+        self.client.cdp.get_contract_id(),
+        auction_id
+      ).await?;
+    let collateral = self.client.collateral_token_of(vault_id).await?;
+    let price_source = self.client.price_source(collateral_type_id).await?;
+    let price = psmpricesource::get_price(
+      &price_source, &self.client.wallet).await?;
+
+    Ok((collateral, AuctionValuation {
+      collateral_amount,
+      oracle_value: collateral_value(collateral_amount, &price),
+      venue_value: self.venue.quote(&collateral, collateral_amount).await?
+    }))
+  }
+
+  pub async fn plan(
+    &mut self,
+    auction: &AuctionState
+  ) -> Result<AuctionPlan, YamaError> {
+    let collateral_type_id = dutchauctionliquidator::get_collateral_type_id(
+      &self.client.dutchauctionliquidator,
+      self.client.cdp.get_contract_id(),
+      auction.auction_id
+    ).await?;
+    let params = dutchauctionliquidator::get_c_type_params(
+      &self.client.dutchauctionliquidator,
+      collateral_type_id
+    ).await?;
+    let (collateral, valuation) = self.value(
+      auction.auction_id,
+      auction.vault_id,
+      collateral_type_id
+    ).await?;
+
+    let max_price = self.config.max_price(valuation.proceeds());
+    let claim_at = max_price
+      .and_then(|max_price| first_profitable_interval(
+        auction.start_price, &params, max_price))
+      .map(|interval| auction.start_time
+        + interval * params.time_interval);
+
+    Ok(AuctionPlan {
+      auction_id: auction.auction_id,
+      collateral_type_id,
+      collateral,
+      expires_at: expires_at(&params, auction.start_time),
+      params,
+      valuation,
+      max_price,
+      claim_at
+    })
+  }
+
+  pub async fn tick(&mut self) -> Result<BidderReport, YamaError> {
+    self.indexer.sync().await?;
+    let mut report = BidderReport::default();
+    let auctions: Vec<AuctionState> = self.indexer.state
+      .active_auctions()
+      .into_iter()
+      .cloned()
+      .collect();
+
+    for auction in auctions {
+      if let Err(error) = self.process(&auction, &mut report).await {
+        report.failures.push((auction.auction_id, error));
+      }
+    }
+    Ok(report)
+  }
+
+  async fn process(
+    &mut self,
+    auction: &AuctionState,
+    report: &mut BidderReport
+  ) -> Result<(), YamaError> {
+    let auction_id = auction.auction_id;
+    let cdp_id = self.client.cdp.get_contract_id().clone();
+    let expired = dutchauctionliquidator::is_expired(
+      &self.client.dutchauctionliquidator,
+      &cdp_id,
+      auction_id
+    ).await?;
+    if expired {
+      if self.config.reset_expired {
+        self.reset(auction_id, report).await?;
+        report.reset.push(auction_id);
+      }
+      return Ok(());
+    }
+
+    let plan = self.plan(auction).await?;
+    let price = dutchauctionliquidator::get_price(
+      &self.client.dutchauctionliquidator,
+      &cdp_id,
+      auction_id
+    ).await?;
+    match plan.max_price {
+      Some(max_price) if price <= max_price => {
+        let claimed = self.claim(&plan, price, max_price, report).await?;
+        report.claimed.push(claimed);
+      },
+      _ => report.waiting.push(plan)
+    }
+    Ok(())
+  }
+
+  async fn claim(
+    &mut self,
+    plan: &AuctionPlan,
+    price: u64,
+    max_price: u64,
+    report: &mut BidderReport
+  ) -> Result<ClaimedAuction, YamaError> {
+    let deps = self.client.dependencies().clone();
+    let dal = &self.client.dutchauctionliquidator;
+    let cdp_id = self.client.cdp.get_contract_id();
+    // The price only decays, so paying the current price is enough and any
+    // excess is refunded
+    let Retried { result, retried } = with_retries(
+      &self.config.retry,
+      || dutchauctionliquidator::claim(
+        dal,
+        cdp_id,
+        &deps.stablecoin,
+        &deps.balancesheet,
+        &deps.bsh,
+        &deps.bsh_targets,
+        plan.auction_id,
+        max_price,
+        price
+      )
+    ).await;
+    report.record_retried(plan.auction_id, retried);
+    result?;
+
+    let collateral_amount = plan.valuation.collateral_amount;
+    let sold_for = if self.config.sell_collateral {
+      match self.venue.sell(&plan.collateral, collateral_amount, price).await {
+        Ok(amount) => Some(amount),
+        Err(error) => {
+          report.sell_failures.push((plan.auction_id, error));
+          None
+        }
+      }
+    } else {
+      None
+    };
+
+    Ok(ClaimedAuction {
+      auction_id: plan.auction_id,
+      price,
+      collateral_amount,
+      sold_for
+    })
+  }
+
+  async fn reset(
+    &mut self,
+    auction_id: u64,
+    report: &mut BidderReport
+  ) -> Result<(), YamaError> {
+    let cdp_id = self.client.cdp.get_contract_id().clone();
+    let collateral_type_id = dutchauctionliquidator::get_collateral_type_id(
+      &self.client.dutchauctionliquidator,
+      &cdp_id,
+      auction_id
+    ).await?;
+    let price_source = self.client.price_source(collateral_type_id).await?;
+    let deps = self.client.dependencies().clone();
+    let dal = &self.client.dutchauctionliquidator;
+    let Retried { result, retried } = with_retries(
+      &self.config.retry,
+      || dutchauctionliquidator::reset_auction(
+        dal,
+        &cdp_id,
+        &price_source,
+        &deps.stablecoin,
+        &deps.balancesheet,
+        &deps.bsh,
+        &deps.bsh_targets,
+        auction_id
+      )
+    ).await;
+    report.record_retried(auction_id, retried);
+    result
+  }
+
+  // Wakes up for the next projected claim or expiry when it comes before
+  // the next poll
+  async fn next_wait(&self, report: &BidderReport) -> Duration {
+    let poll_interval = self.config.retry.poll_interval;
+    let provider = self.client.wallet.get_provider().unwrap();
+    let now = match get_timestamp(provider).await {
+      Ok(timestamp) => unix_to_tai64(timestamp),
+      Err(_) => return poll_interval
+    };
+    report.waiting
+      .iter()
+      .flat_map(|plan| plan.claim_at.into_iter().chain([plan.expires_at]))
+      .filter(|time| *time > now)
+      .map(|time| Duration::from_secs(time - now))
+      .min()
+      .map_or(poll_interval, |wait| wait.min(poll_interval))
+  }
+
+  // Runs `ticks` ticks, or forever when unset, passing each tick's number and
+  // outcome to `on_tick`
+  pub async fn run<F>(&mut self, ticks: Option<u64>, mut on_tick: F)
+  where
+    F: FnMut(u64, Result<BidderReport, YamaError>)
+  {
+    let mut tick: u64 = 0;
+    while ticks.map_or(true, |ticks| tick < ticks) {
+      let outcome = self.tick().await;
+      let wait = match &outcome {
+        Ok(report) => self.next_wait(report).await,
+        Err(_) => self.config.retry.poll_interval
+      };
+      on_tick(tick, outcome);
+      tick += 1;
+      task::sleep(wait).await;
+    }
+  }
+}
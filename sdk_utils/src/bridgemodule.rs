@@ -0,0 +1,69 @@
+use fuels::{prelude::*, tx::ContractId, types::{Identity, Bits256}};
+use crate::{
+  errors::{
+    register_contract,
+    YamaContract,
+    YamaError
+  },
+  storage::StorageLayout,
+  modulartoken::{
+      set_allowlist,
+      get_cid
+  },
+  abigen::*
+};
+
+pub async fn init_custom_bridgemodule(
+  wallet: &WalletUnlocked,
+  stable_i: &ModularToken,
+  mailbox: &ContractId
+) -> Result<BridgeModule, YamaError> {
+  let storage_path = "../bridgemodule/out/debug/bridgemodule-storage_slots.json";
+
+  let storage = StorageLayout::new()
+    .field(0, &get_cid(stable_i))
+    .field(1, mailbox);
+  let id = Contract::deploy(
+    "../bridgemodule/out/debug/bridgemodule.bin",
+    &wallet,
+    TxParameters::default(),
+    storage.configuration(storage_path)
+  )
+  .await?;
+
+  register_contract(&id, YamaContract::BridgeModule);
+  let instance = BridgeModule::new(id.clone(), wallet.clone());
+  set_allowlist(&stable_i, Identity::ContractId(id.clone().into()),
+    true).await?;
+  Ok(instance)
+}
+
+pub async fn set_bridge(
+  instance: &BridgeModule,
+  stable_id: &Bech32ContractId,
+  chain: u32,
+  bridge: Bits256
+) -> Result<(), YamaError> {
+  instance
+    .methods()
+    .set_bridge(chain, bridge)
+    .set_contract_ids(&[stable_id.clone()])
+    .call()
+    .await?;
+  Ok(())
+}
+
+pub async fn set_decimals(
+  instance: &BridgeModule,
+  stable_id: &Bech32ContractId,
+  chain: u32,
+  decimals: u8
+) -> Result<(), YamaError> {
+  instance
+    .methods()
+    .set_decimals(chain, decimals)
+    .set_contract_ids(&[stable_id.clone()])
+    .call()
+    .await?;
+  Ok(())
+}
@@ -1,83 +1,61 @@
-use tokio::sync::Mutex;
-use lazy_static::lazy_static;
 use fuels::{prelude::*, tx::ContractId, types::{
   Identity,
   Bits256
 }};
 use crate::{
-  utils::{
-      set_storage_val,
-      get_storage_val,
-      get_test_wallet
+  errors::{
+    register_contract,
+    YamaContract,
+    YamaError
   },
+  storage::StorageLayout,
   modulartoken::{
-      init_custom_modulartoken,
       set_allowlist,
       get_cid
   },
+  aggregatorpricesource::price_source_ids,
   abigen::*
 };
 
-lazy_static! {
-    static ref MUTEX: Mutex<i32> = Mutex::new(0i32);
-}
-
 // Must set liquidator after init
 pub async fn init_custom_cdp(
   wallet: &WalletUnlocked,
   stable_i: &ModularToken,
   collateral_manager: &ContractId,
   balancesheet_module: &ContractId,
-) -> CDP {
+) -> Result<CDP, YamaError> {
   let storage_path = "../cdpmodule/out/debug/cdpmodule-storage_slots.json";
 
-  let guard = MUTEX.lock().await;
-
-  // Create a Mutex to synchronize access to the storage slots
-  set_storage_val(
-    &storage_path,
-    "storage_0",
-    &collateral_manager.to_string()
-  );
-  set_storage_val(
-    &storage_path,
-    "storage_2",
-    &get_cid(stable_i).to_string()
-  );
-  set_storage_val(
-    &storage_path,
-    "storage_3",
-    &balancesheet_module.to_string()
-  );
+  let storage = StorageLayout::new()
+    .field(0, collateral_manager)
+    .field(2, &get_cid(stable_i))
+    .field(3, balancesheet_module);
   let id = Contract::deploy(
     "../cdpmodule/out/debug/cdpmodule.bin",
     &wallet,
     TxParameters::default(),
-    StorageConfiguration::with_storage_path(Some(
-      storage_path.to_string()
-    ))
+    storage.configuration(storage_path)
   )
-  .await
-  .unwrap();
-  drop(guard);
+  .await?;
+  register_contract(&id, YamaContract::CDP);
   let instance: CDP = CDP::new(id.clone(), wallet.clone());
   set_allowlist(&stable_i, Identity::ContractId(id.clone().into()),
-    true).await;
-  instance
+    true).await?;
+  Ok(instance)
 }
 
 pub async fn set_liquidator(
   instance: &CDP,
   stable_id: &ContractId,
   liquidator: &ContractId
-) {
+) -> Result<(), YamaError> {
   instance
     .methods()
     .set_liquidator(Bits256(*liquidator.clone()))
     .set_contract_ids(&[Bech32ContractId::from(*stable_id)])
     .call()
-    .await
-    .unwrap();
+    .await?;
+  Ok(())
 }
 
 pub async fn add_collateral_type(
@@ -91,8 +69,8 @@ pub async fn add_collateral_type(
   interest_rate: UFP128,
   borrowing_enabled: bool,
   allowlist_enabled: bool,
-) -> u64 {
-  instance
+) -> Result<u64, YamaError> {
+  Ok(instance
     .methods()
     .add_collateral_type(
       Bits256::from_hex_str(&*token.to_string()).unwrap(),
@@ -106,23 +84,21 @@ pub async fn add_collateral_type(
     )
     .set_contract_ids(&[Bech32ContractId::from(*stable_id)])
     .call()
-    .await
-    .unwrap()
-    .value
+    .await?
+    .value)
 }
 
 pub async fn get_collateral_manager(
   instance: &CDP,
-) -> Bech32ContractId {
+) -> Result<Bech32ContractId, YamaError> {
   let bits = instance
     .methods()
     .get_collateral_manager()
     .simulate()
-    .await
-    .unwrap()
+    .await?
     .value;
   let cid = ContractId::new(bits.0);
-  Bech32ContractId::from(cid)
+  Ok(Bech32ContractId::from(cid))
 }
 
 pub async fn create_vault(
@@ -131,12 +107,12 @@ pub async fn create_vault(
   collateral: &AssetId,
   collateral_amount: u64,
   alt_owner: Option<Identity>,
-) -> u64 {
+) -> Result<u64, YamaError> {
   
   let tx_params = TxParameters::new(
     None, Some(1_010_000), None);
 
-  instance
+  Ok(instance
     .methods()
     .create_vault(
       collateral_type_id,
@@ -148,12 +124,35 @@ pub async fn create_vault(
       None,
     ))
     .append_variable_outputs(1)
-    .set_contract_ids(&[get_collateral_manager(instance).await])
+    .set_contract_ids(&[get_collateral_manager(instance).await?])
     .tx_params(tx_params)
     .call()
-    .await
-    .unwrap()
-    .value
+    .await?
+    .value)
+}
+
+pub async fn add_collateral(
+  instance: &CDP,
+  vault_id: u64,
+  collateral: &AssetId,
+  amount: u64,
+) -> Result<(), YamaError> {
+  let tx_params = TxParameters::new(
+    None, Some(1_010_000), None);
+
+  instance
+    .methods()
+    .add_collateral(vault_id)
+    .call_params(CallParameters::new(
+      Some(amount),
+      Some(collateral.clone()),
+      None,
+    ))
+    .set_contract_ids(&[get_collateral_manager(instance).await?])
+    .tx_params(tx_params)
+    .call()
+    .await?;
+  Ok(())
 }
 
 pub async fn liquidate(
@@ -163,28 +162,53 @@ pub async fn liquidate(
   price_source: &Bech32ContractId,
   balancesheet: &Bech32ContractId,
   bsh: &Bech32ContractId,
-  bsh_target: &Bech32ContractId,
+  bsh_targets: &[Bech32ContractId],
   vault_id: u64
-) {
+) -> Result<(), YamaError> {
   let tx_params = TxParameters::new(
     None, Some(4_000_000), None);
 
+  liquidate_with_params(
+    instance,
+    stable_i,
+    liquidator,
+    price_source,
+    balancesheet,
+    bsh,
+    bsh_targets,
+    vault_id,
+    tx_params
+  ).await
+}
+
+pub async fn liquidate_with_params(
+  instance: &CDP,
+  stable_i: &Bech32ContractId,
+  liquidator: &Bech32ContractId,
+  price_source: &Bech32ContractId,
+  balancesheet: &Bech32ContractId,
+  bsh: &Bech32ContractId,
+  bsh_targets: &[Bech32ContractId],
+  vault_id: u64,
+  tx_params: TxParameters
+) -> Result<(), YamaError> {
+  let mut ids = vec![
+    instance.get_contract_id().clone(),
+    stable_i.clone(),
+    liquidator.clone(),
+    balancesheet.clone(),
+    bsh.clone()
+  ];
+  ids.extend(price_source_ids(price_source, &instance.get_wallet()).await?);
+  ids.extend_from_slice(bsh_targets);
   instance
     .methods()
     .liquidate(vault_id)
-    .set_contract_ids(&[
-      instance.get_contract_id().clone(),
-      stable_i.clone(),
-      liquidator.clone(),
-      price_source.clone(),
-      balancesheet.clone(),
-      bsh.clone(),
-      bsh_target.clone()
-    ])
+    .set_contract_ids(&ids)
     .tx_params(tx_params)
     .call()
-    .await
-    .unwrap();
+    .await?;
+  Ok(())
 }
 
 pub async fn update_interest(
@@ -193,26 +217,49 @@ pub async fn update_interest(
   price_source: &Bech32ContractId,
   balancesheet: &Bech32ContractId,
   bsh: &Bech32ContractId,
-  bsh_target: &Bech32ContractId,
+  bsh_targets: &[Bech32ContractId],
   collateral_type_id: u64
-) {
+) -> Result<(), YamaError> {
   let tx_params = TxParameters::new(
     None, Some(32_000_000), None);
 
+  update_interest_with_params(
+    instance,
+    stable_id,
+    price_source,
+    balancesheet,
+    bsh,
+    bsh_targets,
+    collateral_type_id,
+    tx_params
+  ).await
+}
+
+pub async fn update_interest_with_params(
+  instance: &CDP,
+  stable_id: &Bech32ContractId,
+  price_source: &Bech32ContractId,
+  balancesheet: &Bech32ContractId,
+  bsh: &Bech32ContractId,
+  bsh_targets: &[Bech32ContractId],
+  collateral_type_id: u64,
+  tx_params: TxParameters
+) -> Result<(), YamaError> {
+  let mut ids = vec![
+    stable_id.clone(),
+    balancesheet.clone(),
+    bsh.clone()
+  ];
+  ids.extend(price_source_ids(price_source, &instance.get_wallet()).await?);
+  ids.extend_from_slice(bsh_targets);
   instance
     .methods()
     .update_interest(collateral_type_id)
-    .set_contract_ids(&[
-      stable_id.clone(),
-      price_source.clone(),
-      balancesheet.clone(),
-      bsh.clone(),
-      bsh_target.clone()
-    ])
+    .set_contract_ids(&ids)
     .tx_params(tx_params)
     .call()
-    .await
-    .unwrap();
+    .await?;
+  Ok(())
 }
 
 pub async fn borrow(
@@ -222,27 +269,28 @@ pub async fn borrow(
   price_source: &Bech32ContractId,
   balancesheet: &Bech32ContractId,
   bsh: &Bech32ContractId,
-  bsh_target: &Bech32ContractId,
+  bsh_targets: &[Bech32ContractId],
   amount: u64,
-) {
+) -> Result<(), YamaError> {
   let tx_params = TxParameters::new(
     None, Some(32_000_000), None);
   
+  let mut ids = vec![
+    balancesheet.clone(),
+    stable_id.clone(),
+    bsh.clone()
+  ];
+  ids.extend(price_source_ids(price_source, &instance.get_wallet()).await?);
+  ids.extend_from_slice(bsh_targets);
   instance
     .methods()
     .borrow(vault_id, amount)
-    .set_contract_ids(&[
-      price_source.clone(),
-      balancesheet.clone(),
-      stable_id.clone(),
-      bsh.clone(),
-      bsh_target.clone()
-    ])
+    .set_contract_ids(&ids)
     .append_variable_outputs(1)
     .tx_params(tx_params)
     .call()
-    .await
-    .unwrap();
+    .await?;
+  Ok(())
 }
 
 pub async fn repay(
@@ -252,22 +300,23 @@ pub async fn repay(
   price_source: &Bech32ContractId,
   balancesheet: &Bech32ContractId,
   bsh: &Bech32ContractId,
-  bsh_target: &Bech32ContractId,
+  bsh_targets: &[Bech32ContractId],
   amount: u64,
-) {
+) -> Result<(), YamaError> {
   let tx_params = TxParameters::new(
     None, Some(32_000_000), None);
   
+  let mut ids = vec![
+    balancesheet.clone(),
+    stable_id.clone(),
+    bsh.clone()
+  ];
+  ids.extend(price_source_ids(price_source, &instance.get_wallet()).await?);
+  ids.extend_from_slice(bsh_targets);
   instance
     .methods()
     .repay(vault_id)
-    .set_contract_ids(&[
-      price_source.clone(),
-      balancesheet.clone(),
-      stable_id.clone(),
-      bsh.clone(),
-      bsh_target.clone()
-    ])
+    .set_contract_ids(&ids)
     .call_params(CallParameters::new(
       Some(amount),
       Some(AssetId::new(*ContractId::from(stable_id.clone()))),
@@ -276,8 +325,8 @@ pub async fn repay(
     .append_variable_outputs(1)
     .tx_params(tx_params)
     .call()
-    .await
-    .unwrap();
+    .await?;
+  Ok(())
 }
 
 pub async fn remove_collateral(
@@ -288,75 +337,111 @@ pub async fn remove_collateral(
   price_source: &Bech32ContractId,
   balancesheet: &Bech32ContractId,
   bsh: &Bech32ContractId,
-  bsh_target: &Bech32ContractId,
+  bsh_targets: &[Bech32ContractId],
   amount: u64,
-) {
+) -> Result<(), YamaError> {
   let tx_params = TxParameters::new(
     None, Some(32_000_000), None);
   
+  let mut ids = vec![
+    balancesheet.clone(),
+    collat_id.clone(),
+    stable_id.clone(),
+    bsh.clone(),
+    get_collateral_manager(instance).await?
+  ];
+  ids.extend(price_source_ids(price_source, &instance.get_wallet()).await?);
+  ids.extend_from_slice(bsh_targets);
   instance
     .methods()
     .remove_collateral(vault_id, amount)
-    .set_contract_ids(&[
-      price_source.clone(),
-      balancesheet.clone(),
-      collat_id.clone(),
-      stable_id.clone(),
-      bsh.clone(),
-      bsh_target.clone(),
-      get_collateral_manager(instance).await
-    ])
+    .set_contract_ids(&ids)
     .append_variable_outputs(1)
     .tx_params(tx_params)
     .call()
-    .await
-    .unwrap();
+    .await?;
+  Ok(())
 }
 
 pub async fn get_collateral_value(
   instance: &CDP, vault_id: u64
-) -> u64 {
-  instance
+) -> Result<u64, YamaError> {
+  Ok(instance
     .methods()
     .get_collateral_value(vault_id)
     .simulate()
-    .await
-    .unwrap()
-    .value
+    .await?
+    .value)
 }
 
 pub async fn get_debt(
   instance: &CDP, vault_id: u64
-) -> u64 {
-  instance
+) -> Result<u64, YamaError> {
+  Ok(instance
     .methods()
     .get_debt(vault_id)
     .simulate()
-    .await
-    .unwrap()
-    .value
+    .await?
+    .value)
 }
 
 pub async fn get_target_collateral_value(
   instance: &CDP, vault_id: u64
-) -> u64 {
-  instance
+) -> Result<u64, YamaError> {
+  Ok(instance
     .methods()
     .get_target_collateral_value(vault_id)
     .simulate()
-    .await
-    .unwrap()
-    .value
+    .await?
+    .value)
 }
 
 pub async fn get_collateral_amount(
   instance: &CDP, vault_id: u64
-) -> u64 {
-  instance
+) -> Result<u64, YamaError> {
+  Ok(instance
     .methods()
     .get_collateral_amount(vault_id)
     .simulate()
-    .await
-    .unwrap()
-    .value
-}
\ No newline at end of file
+    .await?
+    .value)
+}
+
+pub async fn get_annual_interest(
+  instance: &CDP, collateral_type_id: u64
+) -> Result<UFP128, YamaError> {
+  Ok(instance
+    .methods()
+    .get_annual_interest(collateral_type_id)
+    .simulate()
+    .await?
+    .value)
+}
+
+pub async fn get_ps_interest(
+  instance: &CDP, collateral_type_id: u64
+) -> Result<UFP128, YamaError> {
+  Ok(instance
+    .methods()
+    .get_ps_interest(collateral_type_id)
+    .simulate()
+    .await?
+    .value)
+}
+
+// Uses the collateral price and accrued interest as of the last
+// update_interest
+pub async fn is_undercollateralized(
+  instance: &CDP,
+  price_source: &Bech32ContractId,
+  vault_id: u64
+) -> Result<bool, YamaError> {
+  Ok(instance
+    .methods()
+    .is_undercollateralized(vault_id)
+    .set_contract_ids(
+      &price_source_ids(price_source, &instance.get_wallet()).await?)
+    .simulate()
+    .await?
+    .value)
+}
@@ -0,0 +1,385 @@
+use std::collections::HashMap;
+use fuels::{prelude::*, tx::ContractId, types::{Bits256, Identity}};
+use crate::{
+  abigen::*,
+  errors::{
+    register_contract,
+    YamaContract,
+    YamaError
+  },
+  balancesheet::resolve_handler,
+  cdp,
+  dutchauctionliquidator,
+  psmpricesource,
+  risk::VaultRisk
+};
+
+fn bits_to_bech32(bits: Bits256) -> Bech32ContractId {
+  Bech32ContractId::from(ContractId::new(bits.0))
+}
+
+// Contracts the CDP module depends on, as read from chain
+#[derive(Clone, Debug)]
+pub struct YamaDependencies {
+  pub stablecoin: Bech32ContractId,
+  pub balancesheet: Bech32ContractId,
+  pub bsh: Bech32ContractId,
+  pub bsh_targets: Vec<Bech32ContractId>,
+  pub collateral_manager: Bech32ContractId,
+  pub liquidator: Bech32ContractId
+}
+
+// Wraps a CDP module and resolves the contracts each call touches, so callers
+// only pass vault, auction and collateral type IDs
+pub struct YamaClient {
+  pub wallet: WalletUnlocked,
+  pub cdp: CDP,
+  pub dutchauctionliquidator: DutchAuctionLiquidator,
+  dependencies: YamaDependencies,
+  // collateral_type_id => price source
+  price_sources: HashMap<u64, Bech32ContractId>,
+  // vault_id => collateral_type_id
+  vault_collateral_types: HashMap<u64, u64>
+}
+
+impl YamaClient {
+  pub async fn new(
+    cdp_id: &Bech32ContractId,
+    wallet: &WalletUnlocked
+  ) -> Result<YamaClient, YamaError> {
+    let cdp = CDP::new(cdp_id.clone(), wallet.clone());
+    let dependencies = resolve_dependencies(&cdp, wallet).await?;
+    let dutchauctionliquidator = DutchAuctionLiquidator::new(
+      dependencies.liquidator.clone(), wallet.clone());
+
+    Ok(YamaClient {
+      wallet: wallet.clone(),
+      cdp,
+      dutchauctionliquidator,
+      dependencies,
+      price_sources: HashMap::new(),
+      vault_collateral_types: HashMap::new()
+    })
+  }
+
+  pub fn dependencies(&self) -> &YamaDependencies {
+    &self.dependencies
+  }
+
+  // Re-reads the dependency graph, e.g. after the handler, liquidator or a
+  // collateral type's price source was changed
+  pub async fn refresh(&mut self) -> Result<(), YamaError> {
+    self.dependencies = resolve_dependencies(&self.cdp, &self.wallet).await?;
+    self.dutchauctionliquidator = DutchAuctionLiquidator::new(
+      self.dependencies.liquidator.clone(), self.wallet.clone());
+    self.price_sources.clear();
+    self.vault_collateral_types.clear();
+    Ok(())
+  }
+
+  pub async fn price_source(
+    &mut self,
+    collateral_type_id: u64
+  ) -> Result<Bech32ContractId, YamaError> {
+    if let Some(price_source) = self.price_sources.get(&collateral_type_id) {
+      return Ok(price_source.clone());
+    }
+    let c_type = self.cdp
+      .methods()
+      .get_collateral_type(collateral_type_id)
+      .simulate()
+      .await?
+      .value;
+    let price_source = bits_to_bech32(c_type.price_source);
+    self.price_sources.insert(collateral_type_id, price_source.clone());
+    Ok(price_source)
+  }
+
+  pub async fn collateral_type_of(
+    &mut self,
+    vault_id: u64
+  ) -> Result<u64, YamaError> {
+    if let Some(c_type_id) = self.vault_collateral_types.get(&vault_id) {
+      return Ok(*c_type_id);
+    }
+    let c_type_id = self.cdp
+      .methods()
+      .get_collateral_type_id(vault_id)
+      .simulate()
+      .await?
+      .value;
+    self.vault_collateral_types.insert(vault_id, c_type_id);
+    Ok(c_type_id)
+  }
+
+  pub async fn price_source_of(
+    &mut self,
+    vault_id: u64
+  ) -> Result<Bech32ContractId, YamaError> {
+    let c_type_id = self.collateral_type_of(vault_id).await?;
+    self.price_source(c_type_id).await
+  }
+
+  pub async fn collateral_token_of(
+    &mut self,
+    vault_id: u64
+  ) -> Result<ContractId, YamaError> {
+    Ok(self.cdp
+      .methods()
+      .get_collateral_token(vault_id)
+      .simulate()
+      .await?
+      .value)
+  }
+
+  // The vault priced at its collateral type's current price. Call
+  // VaultRisk::accrue to account for interest since the last update.
+  pub async fn vault_risk(
+    &mut self,
+    vault_id: u64
+  ) -> Result<VaultRisk, YamaError> {
+    let vault = self.cdp
+      .methods()
+      .get_vault(vault_id)
+      .simulate()
+      .await?
+      .value;
+    let c_type = self.cdp
+      .methods()
+      .get_collateral_type(vault.collateral_type_id)
+      .simulate()
+      .await?
+      .value;
+    let price_source = self.price_source(vault.collateral_type_id).await?;
+    let price = psmpricesource::get_price(&price_source, &self.wallet).await?;
+    self.vault_collateral_types.insert(vault_id, vault.collateral_type_id);
+    Ok(VaultRisk::new(vault, c_type, &price))
+  }
+
+  pub async fn create_vault(
+    &mut self,
+    collateral_type_id: u64,
+    collateral_amount: u64,
+    alt_owner: Option<Identity>
+  ) -> Result<u64, YamaError> {
+    let c_type = self.cdp
+      .methods()
+      .get_collateral_type(collateral_type_id)
+      .simulate()
+      .await?
+      .value;
+    let vault_id = cdp::create_vault(
+      &self.cdp,
+      collateral_type_id,
+      &AssetId::new(c_type.token.0),
+      collateral_amount,
+      alt_owner
+    ).await?;
+    self.vault_collateral_types.insert(vault_id, collateral_type_id);
+    Ok(vault_id)
+  }
+
+  pub async fn add_collateral(
+    &mut self,
+    vault_id: u64,
+    amount: u64
+  ) -> Result<(), YamaError> {
+    let collateral = self.collateral_token_of(vault_id).await?;
+    cdp::add_collateral(
+      &self.cdp,
+      vault_id,
+      &AssetId::new(*collateral),
+      amount
+    ).await
+  }
+
+  pub async fn borrow(
+    &mut self,
+    vault_id: u64,
+    amount: u64
+  ) -> Result<(), YamaError> {
+    let price_source = self.price_source_of(vault_id).await?;
+    let deps = &self.dependencies;
+    cdp::borrow(
+      &self.cdp,
+      vault_id,
+      &deps.stablecoin,
+      &price_source,
+      &deps.balancesheet,
+      &deps.bsh,
+      &deps.bsh_targets,
+      amount
+    ).await
+  }
+
+  pub async fn repay(
+    &mut self,
+    vault_id: u64,
+    amount: u64
+  ) -> Result<(), YamaError> {
+    let price_source = self.price_source_of(vault_id).await?;
+    let deps = &self.dependencies;
+    cdp::repay(
+      &self.cdp,
+      vault_id,
+      &deps.stablecoin,
+      &price_source,
+      &deps.balancesheet,
+      &deps.bsh,
+      &deps.bsh_targets,
+      amount
+    ).await
+  }
+
+  pub async fn remove_collateral(
+    &mut self,
+    vault_id: u64,
+    amount: u64
+  ) -> Result<(), YamaError> {
+    let price_source = self.price_source_of(vault_id).await?;
+    let collateral = Bech32ContractId::from(
+      self.collateral_token_of(vault_id).await?);
+    let deps = &self.dependencies;
+    cdp::remove_collateral(
+      &self.cdp,
+      vault_id,
+      &collateral,
+      &deps.stablecoin,
+      &price_source,
+      &deps.balancesheet,
+      &deps.bsh,
+      &deps.bsh_targets,
+      amount
+    ).await
+  }
+
+  pub async fn update_interest(
+    &mut self,
+    collateral_type_id: u64
+  ) -> Result<(), YamaError> {
+    let price_source = self.price_source(collateral_type_id).await?;
+    let deps = &self.dependencies;
+    cdp::update_interest(
+      &self.cdp,
+      &deps.stablecoin,
+      &price_source,
+      &deps.balancesheet,
+      &deps.bsh,
+      &deps.bsh_targets,
+      collateral_type_id
+    ).await
+  }
+
+  pub async fn liquidate(&mut self, vault_id: u64) -> Result<(), YamaError> {
+    let price_source = self.price_source_of(vault_id).await?;
+    let deps = &self.dependencies;
+    cdp::liquidate(
+      &self.cdp,
+      &deps.stablecoin,
+      &deps.liquidator,
+      &price_source,
+      &deps.balancesheet,
+      &deps.bsh,
+      &deps.bsh_targets,
+      vault_id
+    ).await
+  }
+
+  pub async fn get_price(&self, auction_id: u64) -> Result<u64, YamaError> {
+    dutchauctionliquidator::get_price(
+      &self.dutchauctionliquidator,
+      self.cdp.get_contract_id(),
+      auction_id
+    ).await
+  }
+
+  pub async fn claim(
+    &mut self,
+    auction_id: u64,
+    max_price: u64,
+    amount: u64
+  ) -> Result<(), YamaError> {
+    let deps = &self.dependencies;
+    dutchauctionliquidator::claim(
+      &self.dutchauctionliquidator,
+      self.cdp.get_contract_id(),
+      &deps.stablecoin,
+      &deps.balancesheet,
+      &deps.bsh,
+      &deps.bsh_targets,
+      auction_id,
+      max_price,
+      amount
+    ).await
+  }
+
+  pub async fn reset_auction(
+    &mut self,
+    auction_id: u64
+  ) -> Result<(), YamaError> {
+    let c_type_id = self.dutchauctionliquidator
+      .methods()
+      .get_collateral_type_id(auction_id)
+      .set_contract_ids(&[self.cdp.get_contract_id().clone()])
+      .simulate()
+      .await?
+      .value;
+    let price_source = self.price_source(c_type_id).await?;
+    let deps = &self.dependencies;
+    dutchauctionliquidator::reset_auction(
+      &self.dutchauctionliquidator,
+      self.cdp.get_contract_id(),
+      &price_source,
+      &deps.stablecoin,
+      &deps.balancesheet,
+      &deps.bsh,
+      &deps.bsh_targets,
+      auction_id
+    ).await
+  }
+}
+
+// Walks CDP -> balance sheet -> handler -> handler targets
+pub async fn resolve_dependencies(
+  cdp: &CDP,
+  wallet: &WalletUnlocked
+) -> Result<YamaDependencies, YamaError> {
+  let stablecoin = bits_to_bech32(
+    cdp.methods().get_stablecoin().simulate().await?.value);
+  let balancesheet = bits_to_bech32(
+    cdp.methods().get_balance_sheet().simulate().await?.value);
+  let collateral_manager = bits_to_bech32(
+    cdp.methods().get_collateral_manager().simulate().await?.value);
+  let liquidator = bits_to_bech32(
+    cdp.methods().get_liquidator().simulate().await?.value);
+
+  let balancesheet_instance = BalanceSheet::new(
+    balancesheet.clone(), wallet.clone());
+  let bsh = bits_to_bech32(
+    balancesheet_instance
+      .methods()
+      .get_handler()
+      .simulate()
+      .await?
+      .value
+  );
+
+  let (bsh_contract, bsh_targets) = resolve_handler(&bsh, wallet).await?;
+
+  // So reverts in any of them decode to a YamaError
+  register_contract(cdp.get_contract_id(), YamaContract::CDP);
+  register_contract(&stablecoin, YamaContract::ModularToken);
+  register_contract(&balancesheet, YamaContract::BalanceSheet);
+  register_contract(&bsh, bsh_contract);
+  register_contract(&collateral_manager, YamaContract::EmptyCollateralManager);
+  register_contract(&liquidator, YamaContract::DutchAuctionLiquidator);
+
+  Ok(YamaDependencies {
+    stablecoin,
+    balancesheet,
+    bsh,
+    bsh_targets,
+    collateral_manager,
+    liquidator
+  })
+}
@@ -0,0 +1,166 @@
+use chrono::{Duration, TimeZone, Utc};
+use fuels::{prelude::*, signers::provider::TimeParameters};
+use crate::{
+  abigen::*,
+  bidder::price_at_interval,
+  client::YamaClient,
+  dutchauctionliquidator,
+  errors::YamaError,
+  ufp128::Ufp128,
+  utils::{
+    get_timestamp,
+    unix_to_tai64
+  }
+};
+
+fn provider_error(error: impl ToString) -> YamaError {
+  YamaError::Sdk(Error::ProviderError(error.to_string()))
+}
+
+// Moves a local node's clock forward by producing blocks with explicit
+// timestamps, so tests don't have to sleep for time to pass. Needs manual
+// block production, which get_test_wallet's node has enabled.
+#[derive(Clone, Debug)]
+pub struct Clock {
+  provider: Provider
+}
+
+impl Clock {
+  pub fn new(provider: &Provider) -> Clock {
+    Clock { provider: provider.clone() }
+  }
+
+  pub fn of(wallet: &WalletUnlocked) -> Clock {
+    Clock::new(wallet.get_provider().unwrap())
+  }
+
+  // Unix time of the latest block
+  pub async fn now(&self) -> Result<i64, YamaError> {
+    Ok(get_timestamp(&self.provider).await?)
+  }
+
+  // The latest block's time as contracts read it
+  pub async fn now_tai64(&self) -> Result<u64, YamaError> {
+    Ok(unix_to_tai64(self.now().await?))
+  }
+
+  // Produces `blocks` blocks `interval` seconds apart, the first one
+  // `interval` after the latest block. Returns the last one's TAI64 time.
+  pub async fn produce_blocks(
+    &self,
+    blocks: u64,
+    interval: u64
+  ) -> Result<u64, YamaError> {
+    let interval = i64::try_from(interval)
+      .map_err(|_| provider_error("interval overflows"))?;
+    let start_time = self.now()
+      .await?
+      .checked_add(interval)
+      .and_then(|start| Utc.timestamp_opt(start, 0).single())
+      .ok_or_else(|| provider_error("block time overflows"))?;
+    self.provider
+      .produce_blocks(blocks, Some(TimeParameters {
+        start_time,
+        block_time_interval: Duration::seconds(interval)
+      }))
+      .await
+      .map_err(provider_error)?;
+    self.now_tai64().await
+  }
+
+  // Produces a block `seconds` after the latest one, so the next
+  // transaction lands at least that much later
+  pub async fn advance(&self, seconds: u64) -> Result<u64, YamaError> {
+    self.produce_blocks(1, seconds).await
+  }
+
+  // Produces a block at TAI64 time `time`, unless the chain is already
+  // past it
+  pub async fn advance_to(&self, time: u64) -> Result<u64, YamaError> {
+    let now = self.now_tai64().await?;
+    if time <= now {
+      return Ok(now);
+    }
+    self.advance(time - now).await
+  }
+
+  // Advances by `intervals` of an auction's price steps
+  pub async fn advance_intervals(
+    &self,
+    params: &CTypeParams,
+    intervals: u64
+  ) -> Result<u64, YamaError> {
+    self.advance(params.time_interval.saturating_mul(intervals)).await
+  }
+}
+
+// Advances to `intervals` intervals after the auction started and asserts
+// the liquidator prices it as price_at_interval does, or at 0 once it's
+// expired. Returns the price.
+pub async fn assert_price_after_intervals(
+  clock: &Clock,
+  client: &YamaClient,
+  auction_id: u64,
+  intervals: u64
+) -> u64 {
+  let liquidator = &client.dutchauctionliquidator;
+  let auction = dutchauctionliquidator::get_auction(liquidator, auction_id)
+    .await
+    .unwrap();
+  let c_type_id = dutchauctionliquidator::get_collateral_type_id(
+    liquidator, client.cdp.get_contract_id(), auction_id).await.unwrap();
+  let params = dutchauctionliquidator::get_c_type_params(
+    liquidator, c_type_id).await.unwrap();
+
+  clock.advance_to(auction.start_time
+    + params.time_interval.saturating_mul(intervals)).await.unwrap();
+  let expected = if intervals >= params.reset_threshold {
+    0
+  } else {
+    price_at_interval(
+      Ufp128::from(&auction.start_price).bits(), &params, intervals)
+  };
+  let price = client.get_price(auction_id).await.unwrap();
+  assert_eq!(
+    price, expected,
+    "auction {} after {} intervals", auction_id, intervals
+  );
+  price
+}
+
+// Advances `seconds` past the collateral type's last update, updates its
+// interest and asserts the cumulative interest grew by rate^elapsed over the
+// time the contract recorded. Returns the seconds it accrued over.
+pub async fn assert_interest_after(
+  clock: &Clock,
+  client: &mut YamaClient,
+  collateral_type_id: u64,
+  seconds: u64
+) -> u64 {
+  let before = client.cdp
+    .methods()
+    .get_collateral_type(collateral_type_id)
+    .simulate()
+    .await
+    .unwrap()
+    .value;
+  clock.advance_to(before.last_update_time + seconds).await.unwrap();
+  client.update_interest(collateral_type_id).await.unwrap();
+  let after = client.cdp
+    .methods()
+    .get_collateral_type(collateral_type_id)
+    .simulate()
+    .await
+    .unwrap()
+    .value;
+
+  let elapsed = after.last_update_time - before.last_update_time;
+  assert!(
+    elapsed >= seconds,
+    "accrued over {} seconds, expected at least {}", elapsed, seconds
+  );
+  let expected = Ufp128::from(&before.cumulative_interest)
+    * Ufp128::from(&before.interest_rate).powu(elapsed);
+  assert_eq!(Ufp128::from(&after.cumulative_interest), expected);
+  elapsed
+}
@@ -0,0 +1,472 @@
+use fuels::{prelude::*, tx::ContractId, types::{Identity, Bits256}};
+use crate::{
+  abigen::*,
+  utils::{
+    u64_to_fp,
+    u128_to_fp
+  },
+  modulartoken::{
+    init_custom_modulartoken,
+    set_allowlist,
+    get_cid
+  },
+  psm::{
+    init_configured_psm,
+    set_balancesheet_module,
+    set_fees
+  },
+  psmlockup::{
+    init_custom_psmlockup,
+    set_bsh_contract
+  },
+  balancesheet::{
+    init_custom_balancesheet,
+    set_handler
+  },
+  simplebsh::set_absorb_deficits,
+  splitbsh::{
+    init_custom_splitbsh,
+    add_recipient,
+    set_backstop
+  },
+  emptycollateralmanager::init_emptycollateralmanager,
+  cdp::{
+    init_custom_cdp,
+    add_collateral_type
+  },
+  dutchauctionliquidator::{
+    init_custom_dutchauctionliquidator,
+    set_partial_liquidation_params
+  },
+  psmpricesource::init_psmpricesource,
+  mockpricesource::init_mockpricesource,
+  flashmintmodule::init_custom_flashmintmodule,
+  leverageproxy::{
+    init_custom_lproxy,
+    set_collateral_type_config
+  },
+  testswapper::init_custom_testswapper,
+  bridgemodule::{
+    init_custom_bridgemodule,
+    set_bridge,
+    set_decimals
+  },
+  client::YamaClient,
+  errors::YamaError
+};
+
+// Default collateral type params of the DutchAuctionLiquidator
+#[derive(Clone, Debug)]
+pub struct LiquidatorPlan {
+  pub initial_price_ratio: UFP128,
+  pub time_interval: u64,
+  pub change_rate: UFP128,
+  pub reset_threshold: u64
+}
+
+// Storage overrides for the PSM; forc's defaults are used when unset
+#[derive(Clone, Debug, Default)]
+pub struct PSMPlan {
+  pub debt_ceiling: Option<u64>,
+  pub external_stable_decimals: Option<u8>,
+  // Fees in basis points, booked as surplus
+  pub tin: u64,
+  pub tout: u64
+}
+
+// A collateral type backed by the deployment's collateral token and priced by
+// its PSMPriceSource
+#[derive(Clone, Debug)]
+pub struct CollateralTypePlan {
+  pub debt_floor: UFP128,
+  pub debt_ceiling: UFP128,
+  pub collateral_ratio: UFP128,
+  pub interest_rate: UFP128,
+  pub borrowing_enabled: bool,
+  pub allowlist_enabled: bool,
+  // Liquidates the type's vaults partially instead of selling them whole
+  pub partial_liquidation: Option<PartialLiquidationPlan>
+}
+
+// Sells just enough collateral to bring a vault back to target_ratio, with
+// the penalty on top of the debt repaid booked as surplus
+#[derive(Clone, Debug)]
+pub struct PartialLiquidationPlan {
+  pub target_ratio: UFP128,
+  pub penalty: UFP128
+}
+
+// Where SimpleBSH sends its revenue share
+#[derive(Clone, Debug)]
+pub enum BSHTarget {
+  PSMLockup,
+  Stablecoin,
+  Contract(ContractId)
+}
+
+#[derive(Clone, Debug)]
+pub struct RecipientPlan {
+  pub target: BSHTarget,
+  // Out of SPLITBSH_DENOMINATOR
+  pub weight: u64,
+  // 0 for uncapped
+  pub cap: u64
+}
+
+// A SplitBSH to replace SimpleBSH as the balance sheet's handler. Minting to
+// BSHTarget::Stablecoin takes the stablecoin out of circulation, as a
+// buy-and-burn.
+#[derive(Clone, Debug)]
+pub struct SplitPlan {
+  pub recipients: Vec<RecipientPlan>,
+  // Covers deficits with the PSMLockup's YSS
+  pub backstop: bool
+}
+
+#[derive(Clone, Debug)]
+pub struct RemoteBridge {
+  pub chain: u32,
+  pub bridge: Bits256,
+  pub decimals: u8
+}
+
+#[derive(Clone, Debug)]
+pub struct BridgePlan {
+  pub mailbox: ContractId,
+  pub remote_bridges: Vec<RemoteBridge>
+}
+
+// Describes which modules to deploy and how to configure them. The
+// stablecoin, PSM, PSMLockup, balance sheet, SimpleBSH, CDP and liquidator are
+// always deployed; the rest are optional.
+#[derive(Clone, Debug)]
+pub struct DeploymentPlan {
+  pub psm: PSMPlan,
+  pub bsh_target: BSHTarget,
+  // Has SimpleBSH cover deficits with the PSMLockup's YSS, which requires
+  // BSHTarget::PSMLockup
+  pub absorb_deficits: bool,
+  pub liquidator: LiquidatorPlan,
+  pub collateral_types: Vec<CollateralTypePlan>,
+  // Prices collateral with a MockPriceSource starting at this price instead
+  // of the PSMPriceSource's fixed 1
+  pub mock_price: Option<UFP128>,
+  pub flash_mint: bool,
+  // Also deploys the flash mint module and a TestSwapper
+  pub leverage_proxy: bool,
+  pub bridge: Option<BridgePlan>
+}
+
+impl Default for DeploymentPlan {
+  fn default() -> Self {
+    DeploymentPlan {
+      psm: PSMPlan::default(),
+      bsh_target: BSHTarget::PSMLockup,
+      absorb_deficits: false,
+      liquidator: LiquidatorPlan {
+        initial_price_ratio: u128_to_fp(0b11 << 63), // 1.5
+        time_interval: 1,
+        change_rate: u128_to_fp(0b1 << 63), // 0.5
+        reset_threshold: 3
+      },
+      collateral_types: vec![CollateralTypePlan {
+        debt_floor: u64_to_fp(100),
+        debt_ceiling: u64_to_fp(10000),
+        collateral_ratio: u128_to_fp(0b11 << 63), // 1.5
+        interest_rate: u64_to_fp(2),
+        borrowing_enabled: true,
+        allowlist_enabled: false,
+        partial_liquidation: None
+      }],
+      mock_price: None,
+      flash_mint: false,
+      leverage_proxy: false,
+      bridge: None
+    }
+  }
+}
+
+pub struct YamaDeployment {
+  pub wallet: WalletUnlocked,
+  pub stable_i: ModularToken,
+  // Used both as the PSM's external stablecoin and as CDP collateral
+  pub collat_i: ModularToken,
+  pub psm: PSM,
+  pub psm_lockup: PSMLockup,
+  pub balancesheet: BalanceSheet,
+  pub simplebsh: SimpleBSH,
+  pub emptycollateralmanager: EmptyCollateralManager,
+  pub cdp: CDP,
+  pub dutchauctionliquidator: DutchAuctionLiquidator,
+  // Prices the collateral types: the MockPriceSource when there is one,
+  // otherwise the PSMPriceSource
+  pub price_source: Bech32ContractId,
+  pub psm_price_source: Option<PSMPriceSource>,
+  pub mock_price_source: Option<MockPriceSource>,
+  pub collateral_type_ids: Vec<u64>,
+  pub flashmintmodule: Option<FlashMintModule>,
+  pub lproxy: Option<LeverageProxy>,
+  pub swapper: Option<TestSwapper>,
+  pub bridgemodule: Option<BridgeModule>
+}
+
+impl YamaDeployment {
+  pub async fn client(&self) -> Result<YamaClient, YamaError> {
+    YamaClient::new(self.cdp.get_contract_id(), &self.wallet).await
+  }
+}
+
+// Deploys every module in the plan in dependency order, with each contract's
+// storage initialized in memory through a StorageLayout, and wires the
+// allowlist, balance sheet handler and liquidator. Plans that can't be
+// deployed as described are rejected before anything is deployed.
+pub async fn deploy(
+  wallet: &WalletUnlocked,
+  plan: &DeploymentPlan
+) -> Result<YamaDeployment, YamaError> {
+  if plan.absorb_deficits && !matches!(plan.bsh_target, BSHTarget::PSMLockup) {
+    return Err(YamaError::InvalidPlan(
+      "absorb_deficits requires BSHTarget::PSMLockup".to_string()));
+  }
+
+  let stable_i: ModularToken = init_custom_modulartoken(wallet).await?;
+  let collat_i: ModularToken = init_custom_modulartoken(wallet).await?;
+
+  let psm = init_configured_psm(
+    wallet,
+    &stable_i,
+    &collat_i,
+    plan.psm.debt_ceiling,
+    plan.psm.external_stable_decimals
+  ).await?;
+  let psm_lockup = init_custom_psmlockup(
+    wallet, &stable_i, &get_cid(&collat_i),
+    &ContractId::from(psm.get_contract_id())).await?;
+
+  let bsh_target = target_id(&plan.bsh_target, &stable_i, &psm_lockup);
+  let (balancesheet, simplebsh) = init_custom_balancesheet(
+    wallet, &stable_i, &bsh_target).await?;
+  set_bsh_contract(
+    &psm_lockup,
+    stable_i.get_contract_id(),
+    &ContractId::from(simplebsh.get_contract_id())
+  ).await?;
+  if plan.absorb_deficits {
+    set_absorb_deficits(&simplebsh, stable_i.get_contract_id(), true).await?;
+  }
+  set_balancesheet_module(
+    &psm,
+    &get_cid(&stable_i),
+    &ContractId::from(balancesheet.get_contract_id())
+  ).await?;
+  if plan.psm.tin > 0 || plan.psm.tout > 0 {
+    set_fees(&psm, &get_cid(&stable_i), plan.psm.tin, plan.psm.tout).await?;
+  }
+
+  let emptycollateralmanager
+    = init_emptycollateralmanager(wallet.clone()).await?;
+  let cdp = init_custom_cdp(
+    wallet,
+    &stable_i,
+    &ContractId::from(emptycollateralmanager.get_contract_id()),
+    &ContractId::from(balancesheet.get_contract_id()),
+  ).await?;
+
+  // Also sets itself as the CDP's liquidator
+  let dutchauctionliquidator = init_custom_dutchauctionliquidator(
+    wallet,
+    &stable_i,
+    &ContractId::from(balancesheet.get_contract_id()),
+    &cdp,
+    &plan.liquidator.initial_price_ratio,
+    plan.liquidator.time_interval,
+    &plan.liquidator.change_rate,
+    plan.liquidator.reset_threshold
+  ).await?;
+
+  let (price_source, psm_price_source, mock_price_source)
+    = match &plan.mock_price {
+      Some(price) => {
+        let mock = init_mockpricesource(wallet, price).await?;
+        (mock.get_contract_id().clone(), None, Some(mock))
+      },
+      None => {
+        let psm_price_source = init_psmpricesource(wallet).await?;
+        let id = psm_price_source.get_contract_id().clone();
+        (id, Some(psm_price_source), None)
+      }
+    };
+  let mut collateral_type_ids: Vec<u64> = vec![];
+  for c_type in plan.collateral_types.iter() {
+    let collateral_type_id = add_collateral_type(
+      &cdp,
+      &get_cid(&stable_i),
+      &get_cid(&collat_i),
+      &ContractId::from(&price_source),
+      c_type.debt_floor.clone(),
+      c_type.debt_ceiling.clone(),
+      c_type.collateral_ratio.clone(),
+      c_type.interest_rate.clone(),
+      c_type.borrowing_enabled,
+      c_type.allowlist_enabled
+    ).await?;
+    if let Some(partial) = &c_type.partial_liquidation {
+      set_partial_liquidation_params(
+        &dutchauctionliquidator,
+        cdp.get_contract_id(),
+        stable_i.get_contract_id(),
+        collateral_type_id,
+        true,
+        &partial.target_ratio,
+        &partial.penalty
+      ).await?;
+    }
+    collateral_type_ids.push(collateral_type_id);
+  }
+
+  let flashmintmodule = if plan.flash_mint || plan.leverage_proxy {
+    let flashmintmodule
+      = init_custom_flashmintmodule(wallet, &stable_i).await?;
+    set_allowlist(
+      &stable_i,
+      Identity::ContractId(ContractId::from(flashmintmodule.get_contract_id())),
+      true
+    ).await?;
+    Some(flashmintmodule)
+  } else {
+    None
+  };
+
+  let (lproxy, swapper) = if plan.leverage_proxy {
+    let lproxy = init_custom_lproxy(
+      wallet,
+      &stable_i,
+      &ContractId::from(
+        flashmintmodule.as_ref().unwrap().get_contract_id()),
+      &ContractId::from(cdp.get_contract_id())
+    ).await?;
+    let swapper = init_custom_testswapper(
+      wallet, &stable_i, &collat_i).await?;
+    let swapper_id = Identity::ContractId(
+      ContractId::from(swapper.get_contract_id()));
+    set_allowlist(&stable_i, swapper_id.clone(), true).await?;
+    set_allowlist(&collat_i, swapper_id, true).await?;
+
+    for collateral_type_id in collateral_type_ids.iter() {
+      set_collateral_type_config(
+        &lproxy,
+        stable_i.get_contract_id(),
+        *collateral_type_id,
+        &get_cid(&collat_i),
+        &ContractId::from(swapper.get_contract_id())
+      ).await?;
+    }
+    (Some(lproxy), Some(swapper))
+  } else {
+    (None, None)
+  };
+
+  let bridgemodule = match &plan.bridge {
+    Some(bridge_plan) => {
+      let bridgemodule = init_custom_bridgemodule(
+        wallet, &stable_i, &bridge_plan.mailbox).await?;
+      for remote in bridge_plan.remote_bridges.iter() {
+        set_bridge(
+          &bridgemodule,
+          stable_i.get_contract_id(),
+          remote.chain,
+          remote.bridge.clone()
+        ).await?;
+        set_decimals(
+          &bridgemodule,
+          stable_i.get_contract_id(),
+          remote.chain,
+          remote.decimals
+        ).await?;
+      }
+      Some(bridgemodule)
+    },
+    None => None
+  };
+
+  Ok(YamaDeployment {
+    wallet: wallet.clone(),
+    stable_i,
+    collat_i,
+    psm,
+    psm_lockup,
+    balancesheet,
+    simplebsh,
+    emptycollateralmanager,
+    cdp,
+    dutchauctionliquidator,
+    price_source,
+    psm_price_source,
+    mock_price_source,
+    collateral_type_ids,
+    flashmintmodule,
+    lproxy,
+    swapper,
+    bridgemodule
+  })
+}
+
+fn target_id(
+  target: &BSHTarget,
+  stable_i: &ModularToken,
+  psm_lockup: &PSMLockup
+) -> ContractId {
+  match target {
+    BSHTarget::PSMLockup => ContractId::from(psm_lockup.get_contract_id()),
+    BSHTarget::Stablecoin => get_cid(stable_i),
+    BSHTarget::Contract(id) => id.clone()
+  }
+}
+
+// Deploys a SplitBSH for `plan` and makes it the balance sheet's handler.
+// The Simulator only models SimpleBSH, so it won't match the deployment
+// afterwards.
+pub async fn deploy_splitbsh(
+  deployment: &YamaDeployment,
+  plan: &SplitPlan
+) -> Result<SplitBSH, YamaError> {
+  let YamaDeployment {
+    wallet,
+    stable_i,
+    psm_lockup,
+    balancesheet,
+    ..
+  } = deployment;
+  let splitbsh = init_custom_splitbsh(
+    wallet,
+    stable_i,
+    &ContractId::from(balancesheet.get_contract_id())
+  ).await?;
+
+  for recipient in plan.recipients.iter() {
+    add_recipient(
+      &splitbsh,
+      stable_i.get_contract_id(),
+      Identity::ContractId(
+        target_id(&recipient.target, stable_i, psm_lockup)),
+      recipient.weight,
+      recipient.cap
+    ).await?;
+  }
+  if plan.backstop {
+    let lockup_id = ContractId::from(psm_lockup.get_contract_id());
+    set_backstop(&splitbsh, stable_i.get_contract_id(), &lockup_id).await?;
+    set_bsh_contract(
+      psm_lockup,
+      stable_i.get_contract_id(),
+      &ContractId::from(splitbsh.get_contract_id())
+    ).await?;
+  }
+  set_handler(
+    balancesheet,
+    stable_i.get_contract_id(),
+    &ContractId::from(splitbsh.get_contract_id())
+  ).await?;
+
+  Ok(splitbsh)
+}
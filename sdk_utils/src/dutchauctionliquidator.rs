@@ -1,11 +1,11 @@
-use tokio::sync::Mutex;
-use lazy_static::lazy_static;
 use fuels::{prelude::*, tx::ContractId, types::Identity};
 use crate::{
-  utils::{
-      set_storage_val,
-      int_to_hex,
+  errors::{
+    register_contract,
+    YamaContract,
+    YamaError
   },
+  storage::StorageLayout,
   modulartoken::{
       set_allowlist,
       get_cid
@@ -13,13 +13,10 @@ use crate::{
   cdp::{
     set_liquidator,
   },
+  aggregatorpricesource::price_source_ids,
   abigen::*,
 };
 
-lazy_static! {
-  static ref MUTEX: Mutex<i32> = Mutex::new(0i32);
-}
-
 pub async fn init_custom_dutchauctionliquidator(
   wallet: &WalletUnlocked,
   stable_i: &ModularToken,
@@ -29,101 +26,105 @@ pub async fn init_custom_dutchauctionliquidator(
   default_time_interval: u64,
   default_change_rate: &UFP128,
   default_reset_threshold: u64
-) -> DutchAuctionLiquidator
+) -> Result<DutchAuctionLiquidator, YamaError>
 {
   let storage_path = "../dutchauctionliquidator/out/debug/dutchauctionliquidator-storage_slots.json";
-  let guard = MUTEX.lock().await;
-  set_storage_val(
-    &storage_path,
-    "storage_0",
-    &get_cid(&stable_i).to_string()
-  );
-  set_storage_val(
-    &storage_path,
-    "storage_1",
-    &balancesheet.to_string()
-  );
-  set_storage_val(
-    &storage_path,
-    "storage_2",
-    &ContractId::from(cdp.get_contract_id()).to_string()
-  );
-
-  write_fp(
-    &storage_path,
-    "storage_3_0",
-    &default_initial_price_ratio
-  );
-
-  set_storage_val(
-    &storage_path,
-    "storage_3_1",
-    &int_to_hex(default_time_interval)
-  );
-  
-  write_fp(
-    &storage_path,
-    "storage_3_2",
-    &default_change_rate
-  );
-
-  set_storage_val(
-    &storage_path,
-    "storage_3_3",
-    &int_to_hex(default_reset_threshold)
-  );
+  let storage = StorageLayout::new()
+    .field(0, &get_cid(&stable_i))
+    .field(1, balancesheet)
+    .field(2, &ContractId::from(cdp.get_contract_id()))
+    .field(3, &CTypeParams {
+      initial_price_ratio: default_initial_price_ratio.clone(),
+      time_interval: default_time_interval,
+      change_rate: default_change_rate.clone(),
+      reset_threshold: default_reset_threshold,
+      enabled: true
+    });
 
   let id = Contract::deploy(
     "../dutchauctionliquidator/out/debug/dutchauctionliquidator.bin",
     &wallet,
     TxParameters::default(),
-    StorageConfiguration::with_storage_path(Some(
-      storage_path.to_string()
-    ))
+    storage.configuration(storage_path)
   )
-  .await
-  .unwrap();
-
-  drop(guard);
+  .await?;
   
+  register_contract(&id, YamaContract::DutchAuctionLiquidator);
   let instance = DutchAuctionLiquidator::new(id.clone(), wallet.clone());
 
   set_allowlist(&stable_i, Identity::ContractId 
-    (id.clone().into()), true).await;
+    (id.clone().into()), true).await?;
   
 
   set_liquidator(
     &cdp,
     &get_cid(&stable_i),
     &ContractId::from(id)
-  ).await;
+  ).await?;
   
+  Ok(instance)
+}
+
+// target_ratio has to exceed 1 + penalty and the collateral type's
+// collateral ratio when enabled
+pub async fn set_partial_liquidation_params(
+  instance: &DutchAuctionLiquidator,
+  cdp: &Bech32ContractId,
+  stable_id: &Bech32ContractId,
+  collateral_type_id: u64,
+  enabled: bool,
+  target_ratio: &UFP128,
+  penalty: &UFP128
+) -> Result<(), YamaError> {
   instance
+    .methods()
+    .set_partial_liquidation_params(
+      collateral_type_id,
+      enabled,
+      target_ratio.clone(),
+      penalty.clone()
+    )
+    .set_contract_ids(&[stable_id.clone(), cdp.clone()])
+    .call()
+    .await?;
+  Ok(())
 }
 
-pub fn write_fp(
-  json_file_path: &str,
-  key: &str,
-  value: &UFP128
-) {
-  set_storage_val(
-    json_file_path,
-    &format!("{}_0_0", key),
-    &int_to_hex(value.value.upper)
-  );
-  set_storage_val(
-    json_file_path,
-    &format!("{}_0_1", key),
-    &int_to_hex(value.value.lower)
-  );
+pub async fn get_partial_liquidation_params(
+  instance: &DutchAuctionLiquidator,
+  collateral_type_id: u64
+) -> Result<PartialLiquidationParams, YamaError> {
+  Ok(instance
+    .methods()
+    .get_partial_liquidation_params(
+      collateral_type_id
+    )
+    .simulate()
+    .await?
+    .value)
+}
+
+// None for auctions that sell the whole vault
+pub async fn get_partial_lot(
+  instance: &DutchAuctionLiquidator,
+  auction_id: u64
+) -> Result<Option<PartialLot>, YamaError> {
+  Ok(instance
+    .methods()
+    .get_partial_lot(
+      auction_id
+    )
+    .simulate()
+    .await?
+    .value)
 }
 
 pub async fn get_price(
   instance: &DutchAuctionLiquidator,
   cdp: &Bech32ContractId,
   auction_id: u64,
-) -> u64 {
-  instance
+) -> Result<u64, YamaError> {
+  Ok(instance
     .methods()
     .get_price(
       auction_id
@@ -132,17 +133,16 @@ pub async fn get_price(
       cdp.clone(),
     ])
     .simulate()
-    .await
-    .unwrap()
-    .value
+    .await?
+    .value)
 }
 
 pub async fn is_expired(
   instance: &DutchAuctionLiquidator,
   cdp: &Bech32ContractId,
   auction_id: u64,
-) -> bool {
-  instance
+) -> Result<bool, YamaError> {
+  Ok(instance
     .methods()
     .is_expired(
       auction_id
@@ -151,17 +151,16 @@ pub async fn is_expired(
       cdp.clone(),
     ])
     .simulate()
-    .await
-    .unwrap()
-    .value
+    .await?
+    .value)
 }
 
 pub async fn get_collateral_amount_of_auction(
   instance: &DutchAuctionLiquidator,
   cdp: &Bech32ContractId,
   auction_id: u64,
-) -> u64 {
-  instance
+) -> Result<u64, YamaError> {
+  Ok(instance
     .methods()
     .get_collateral_amount_of_auction(
       auction_id
@@ -170,21 +169,65 @@ pub async fn get_collateral_amount_of_auction(
       cdp.clone(),
     ])
     .simulate()
-    .await
-    .unwrap()
-    .value
+    .await?
+    .value)
 }
 
 pub async fn get_default_c_type_params(
   instance: &DutchAuctionLiquidator
-) -> CTypeParams {
-  instance
+) -> Result<CTypeParams, YamaError> {
+  Ok(instance
     .methods()
     .get_default_c_type_params()
     .simulate()
-    .await
-    .unwrap()
-    .value
+    .await?
+    .value)
+}
+
+pub async fn get_c_type_params(
+  instance: &DutchAuctionLiquidator,
+  collateral_type_id: u64
+) -> Result<CTypeParams, YamaError> {
+  Ok(instance
+    .methods()
+    .get_c_type_params(
+      collateral_type_id
+    )
+    .simulate()
+    .await?
+    .value)
+}
+
+pub async fn get_auction(
+  instance: &DutchAuctionLiquidator,
+  auction_id: u64
+) -> Result<Auction, YamaError> {
+  Ok(instance
+    .methods()
+    .get_auction(
+      auction_id
+    )
+    .simulate()
+    .await?
+    .value)
+}
+
+pub async fn get_collateral_type_id(
+  instance: &DutchAuctionLiquidator,
+  cdp: &Bech32ContractId,
+  auction_id: u64
+) -> Result<u64, YamaError> {
+  Ok(instance
+    .methods()
+    .get_collateral_type_id(
+      auction_id
+    )
+    .set_contract_ids(&[
+      cdp.clone(),
+    ])
+    .simulate()
+    .await?
+    .value)
 }
 
 pub async fn claim(
@@ -193,10 +236,11 @@ pub async fn claim(
   stable_i: &Bech32ContractId,
   balancesheet: &Bech32ContractId,
   bsh: &Bech32ContractId,
-  bsh_target: &Bech32ContractId,
+  bsh_targets: &[Bech32ContractId],
   auction_id: u64,
+  max_price: u64,
   amount: u64,
-) {
+) -> Result<(), YamaError> {
   let call_params = CallParameters::new(
     Some(amount),
     Some(AssetId::new(*ContractId::from(stable_i.clone()))),
@@ -206,24 +250,26 @@ pub async fn claim(
   let tx_params = TxParameters::new(
     None, Some(32_000_000), None);
 
+  let mut ids = vec![
+    cdp.clone(),
+    stable_i.clone(),
+    balancesheet.clone(),
+    bsh.clone()
+  ];
+  ids.extend_from_slice(bsh_targets);
   instance
     .methods()
     .claim(
-      auction_id
+      auction_id,
+      max_price
     )
-    .set_contract_ids(&[
-      cdp.clone(),
-      stable_i.clone(),
-      balancesheet.clone(),
-      bsh.clone(),
-      bsh_target.clone(),
-    ])
+    .set_contract_ids(&ids)
     .append_variable_outputs(2)
     .call_params(call_params)
     .tx_params(tx_params)
     .call()
-    .await
-    .unwrap();
+    .await?;
+  Ok(())
 }
 
 pub async fn reset_auction(
@@ -233,27 +279,28 @@ pub async fn reset_auction(
   stable_i: &Bech32ContractId,
   balancesheet: &Bech32ContractId,
   bsh: &Bech32ContractId,
-  bsh_target: &Bech32ContractId,
+  bsh_targets: &[Bech32ContractId],
   auction_id: u64,
-) {
+) -> Result<(), YamaError> {
   let tx_params = TxParameters::new(
     None, Some(32_000_000), None);
 
+  let mut ids = vec![
+    cdp.clone(),
+    stable_i.clone(),
+    balancesheet.clone(),
+    bsh.clone()
+  ];
+  ids.extend(price_source_ids(price_source, &instance.get_wallet()).await?);
+  ids.extend_from_slice(bsh_targets);
   instance
     .methods()
     .reset_auction(
       auction_id
     )
-    .set_contract_ids(&[
-      cdp.clone(),
-      price_source.clone(),
-      stable_i.clone(),
-      balancesheet.clone(),
-      bsh.clone(),
-      bsh_target.clone(),
-    ])
+    .set_contract_ids(&ids)
     .tx_params(tx_params)
     .call()
-    .await
-    .unwrap();
+    .await?;
+  Ok(())
 }
\ No newline at end of file
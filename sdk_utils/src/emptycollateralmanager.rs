@@ -1,8 +1,15 @@
 use fuels::{prelude::*, tx::ContractId};
-use crate::abigen::*;
+use crate::{
+  abigen::*,
+  errors::{
+    register_contract,
+    YamaContract,
+    YamaError
+  }
+};
 
 pub async fn init_emptycollateralmanager(
-  wallet: WalletUnlocked) -> EmptyCollateralManager
+  wallet: WalletUnlocked) -> Result<EmptyCollateralManager, YamaError>
   {
   let id = Contract::deploy(
     "../emptycollateralmanager/out/debug/emptycollateralmanager.bin",
@@ -10,8 +17,8 @@ pub async fn init_emptycollateralmanager(
     TxParameters::default(),
     StorageConfiguration::default(),
   )
-  .await
-  .unwrap();
+  .await?;
   
-  EmptyCollateralManager::new(id, wallet)
+  register_contract(&id, YamaContract::EmptyCollateralManager);
+  Ok(EmptyCollateralManager::new(id, wallet))
 }
\ No newline at end of file
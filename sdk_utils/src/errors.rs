@@ -0,0 +1,449 @@
+use std::{
+  collections::HashMap,
+  fmt,
+  sync::{Arc, OnceLock, RwLock}
+};
+use fuels::{prelude::*, tx::{ContractId, Receipt}};
+use serde_json::Value;
+
+// Mirrors an error enum from yama_interfaces/src/errors.sw. Variants are
+// matched by name against the contract's ABI, so the order doesn't matter.
+macro_rules! yama_error_enum {
+  ($name:ident { $($variant:ident),* $(,)? }) => {
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum $name {
+      $($variant),*
+    }
+
+    impl $name {
+      fn from_variant(variant: &str) -> Option<Self> {
+        match variant {
+          $(stringify!($variant) => Some($name::$variant),)*
+          _ => None
+        }
+      }
+    }
+  };
+}
+
+yama_error_enum!(BridgeError {
+  InvalidMetadata,
+  InvalidSourceBridge,
+  NotMailbox,
+  InvalidCallbackContract
+});
+
+yama_error_enum!(CDPError {
+  InvalidDebtAmount,
+  Undercollateralized,
+  NotUndercollateralized,
+  LoanAmountIsZero,
+  BorrowingDisabled,
+  CollateralTypeBorrowingDisabled,
+  NotVaultOwner,
+  Liquidated,
+  BorrowerNotAllowed,
+  ExceedsDebtCeiling,
+  RepayOverpayment,
+  Reentrancy
+});
+
+yama_error_enum!(DutchAuctionLiquidatorError {
+  AuctionDone,
+  AuctionExpired,
+  InvalidPayment,
+  AuctionNotExpired,
+  ExceedsMaxPrice,
+  InvalidPartialLiquidationParams
+});
+
+yama_error_enum!(ModularTokenError {
+  Uninitialized,
+  UnauthorizedUser
+});
+
+yama_error_enum!(PSMError {
+  ExceedsDebtCeiling,
+  UnsupportedToken,
+  TokenDisabled,
+  FeeExceedsDenominator,
+  StrategyNotAllowed,
+  ExceedsMaxAllocation,
+  ExceedsAllocation,
+  MaxAllocationExceedsDenominator
+});
+
+yama_error_enum!(PSMLockupError {
+  QueueEnabled,
+  QueueDisabled,
+  InsufficientLiquidity,
+  InvalidTicket,
+  NotTicketOwner,
+  TicketClosed,
+  ExitDelayNotElapsed,
+  NotBSH
+});
+
+yama_error_enum!(FlashMintModuleError {
+  InvalidRepaySender,
+  NoLoanToRepay,
+  NotRepaid,
+  RepayOverpayment,
+  ExceedsMax
+});
+
+yama_error_enum!(YamaLibraryError {
+  InvalidToken
+});
+
+yama_error_enum!(SimpleBSHError {
+  NotBalanceSheet,
+  RevenueShareExceedsDenominator
+});
+
+yama_error_enum!(SurplusAuctionModuleError {
+  AuctionsDisabled,
+  BelowThreshold,
+  InvalidAuction,
+  WrongAuctionType,
+  AuctionDone,
+  AuctionExpired,
+  AuctionNotExpired,
+  InvalidPayment,
+  ExceedsMaxPrice,
+  BelowMinAmount,
+  InvalidParams
+});
+
+yama_error_enum!(SplitBSHError {
+  NotBalanceSheet,
+  WeightsExceedDenominator,
+  InvalidRecipient
+});
+
+yama_error_enum!(LeverageProxyError {
+  NotFlashMintModule,
+  InitiatorNotThis,
+  NotVaultOwner
+});
+
+yama_error_enum!(SwapperError {
+  InsufficientInput
+});
+
+yama_error_enum!(MockPriceSourceError {
+  NotOwner
+});
+
+yama_error_enum!(MockReserveStrategyError {
+  NotPSM,
+  InsufficientBalance
+});
+
+yama_error_enum!(AggregatorPriceSourceError {
+  NotOwner,
+  NotEnoughFreshFeeds,
+  ExceedsMaxDeviation,
+  InvalidFeed
+});
+
+#[derive(Debug)]
+pub enum YamaError {
+  Bridge(BridgeError),
+  CDP(CDPError),
+  DutchAuctionLiquidator(DutchAuctionLiquidatorError),
+  ModularToken(ModularTokenError),
+  PSM(PSMError),
+  PSMLockup(PSMLockupError),
+  FlashMintModule(FlashMintModuleError),
+  YamaLibrary(YamaLibraryError),
+  SimpleBSH(SimpleBSHError),
+  SplitBSH(SplitBSHError),
+  SurplusAuctionModule(SurplusAuctionModuleError),
+  LeverageProxy(LeverageProxyError),
+  Swapper(SwapperError),
+  MockPriceSource(MockPriceSourceError),
+  MockReserveStrategy(MockReserveStrategyError),
+  AggregatorPriceSource(AggregatorPriceSourceError),
+  // The transaction reverted without logging a Yama error, e.g. on an
+  // arithmetic overflow or in a contract that isn't registered
+  Reverted {
+    revert_id: u64,
+    reason: String
+  },
+  // Rejected by the SDK before anything was sent, e.g. a DeploymentPlan
+  // that can't be deployed as described
+  InvalidPlan(String),
+  // An off-chain quote whose arithmetic overflows, named by the method
+  // quoted; the contract would revert on the same amounts
+  QuoteOverflow(String),
+  Sdk(Error)
+}
+
+impl YamaError {
+  fn from_variant(error_enum: &str, variant: &str) -> Option<YamaError> {
+    match error_enum {
+      "BridgeError" => BridgeError::from_variant(variant)
+        .map(YamaError::Bridge),
+      "CDPError" => CDPError::from_variant(variant)
+        .map(YamaError::CDP),
+      "DutchAuctionLiquidatorError"
+        => DutchAuctionLiquidatorError::from_variant(variant)
+          .map(YamaError::DutchAuctionLiquidator),
+      "ModularTokenError" => ModularTokenError::from_variant(variant)
+        .map(YamaError::ModularToken),
+      "PSMError" => PSMError::from_variant(variant)
+        .map(YamaError::PSM),
+      "PSMLockupError" => PSMLockupError::from_variant(variant)
+        .map(YamaError::PSMLockup),
+      "FlashMintModuleError" => FlashMintModuleError::from_variant(variant)
+        .map(YamaError::FlashMintModule),
+      "YamaLibraryError" => YamaLibraryError::from_variant(variant)
+        .map(YamaError::YamaLibrary),
+      "SimpleBSHError" => SimpleBSHError::from_variant(variant)
+        .map(YamaError::SimpleBSH),
+      "SplitBSHError" => SplitBSHError::from_variant(variant)
+        .map(YamaError::SplitBSH),
+      "SurplusAuctionModuleError"
+        => SurplusAuctionModuleError::from_variant(variant)
+          .map(YamaError::SurplusAuctionModule),
+      "LeverageProxyError" => LeverageProxyError::from_variant(variant)
+        .map(YamaError::LeverageProxy),
+      "SwapperError" => SwapperError::from_variant(variant)
+        .map(YamaError::Swapper),
+      "MockPriceSourceError" => MockPriceSourceError::from_variant(variant)
+        .map(YamaError::MockPriceSource),
+      "MockReserveStrategyError"
+        => MockReserveStrategyError::from_variant(variant)
+          .map(YamaError::MockReserveStrategy),
+      "AggregatorPriceSourceError"
+        => AggregatorPriceSourceError::from_variant(variant)
+          .map(YamaError::AggregatorPriceSource),
+      _ => None
+    }
+  }
+
+  // Finds the error a `require` logged right before the transaction reverted
+  pub fn from_receipts(receipts: &[Receipt]) -> Option<YamaError> {
+    let mut error: Option<YamaError> = None;
+    for receipt in receipts.iter() {
+      match receipt {
+        Receipt::LogData { id, rb, data, .. } => {
+          if let Some(decoded) = decode_log(id, *rb, data) {
+            error = Some(decoded);
+          }
+        },
+        Receipt::Revert { .. } | Receipt::Panic { .. } => return error,
+        _ => {}
+      }
+    }
+    None
+  }
+}
+
+impl From<Error> for YamaError {
+  fn from(error: Error) -> Self {
+    match error {
+      Error::RevertTransactionError(reason, receipts) => {
+        if let Some(decoded) = YamaError::from_receipts(&receipts) {
+          return decoded;
+        }
+        let revert_id = receipts
+          .iter()
+          .find_map(|receipt| match receipt {
+            Receipt::Revert { ra, .. } => Some(*ra),
+            _ => None
+          })
+          .unwrap_or(0);
+        YamaError::Reverted { revert_id, reason }
+      },
+      error => YamaError::Sdk(error)
+    }
+  }
+}
+
+impl fmt::Display for YamaError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      YamaError::Bridge(error) => write!(f, "BridgeError::{:?}", error),
+      YamaError::CDP(error) => write!(f, "CDPError::{:?}", error),
+      YamaError::DutchAuctionLiquidator(error)
+        => write!(f, "DutchAuctionLiquidatorError::{:?}", error),
+      YamaError::ModularToken(error)
+        => write!(f, "ModularTokenError::{:?}", error),
+      YamaError::PSM(error) => write!(f, "PSMError::{:?}", error),
+      YamaError::PSMLockup(error)
+        => write!(f, "PSMLockupError::{:?}", error),
+      YamaError::FlashMintModule(error)
+        => write!(f, "FlashMintModuleError::{:?}", error),
+      YamaError::YamaLibrary(error)
+        => write!(f, "YamaLibraryError::{:?}", error),
+      YamaError::SimpleBSH(error) => write!(f, "SimpleBSHError::{:?}", error),
+      YamaError::SplitBSH(error) => write!(f, "SplitBSHError::{:?}", error),
+      YamaError::SurplusAuctionModule(error)
+        => write!(f, "SurplusAuctionModuleError::{:?}", error),
+      YamaError::LeverageProxy(error)
+        => write!(f, "LeverageProxyError::{:?}", error),
+      YamaError::Swapper(error) => write!(f, "SwapperError::{:?}", error),
+      YamaError::MockPriceSource(error)
+        => write!(f, "MockPriceSourceError::{:?}", error),
+      YamaError::MockReserveStrategy(error)
+        => write!(f, "MockReserveStrategyError::{:?}", error),
+      YamaError::AggregatorPriceSource(error)
+        => write!(f, "AggregatorPriceSourceError::{:?}", error),
+      YamaError::Reverted { revert_id, reason }
+        => write!(f, "reverted with {:#x}: {}", revert_id, reason),
+      YamaError::InvalidPlan(reason)
+        => write!(f, "invalid deployment plan: {}", reason),
+      YamaError::QuoteOverflow(method)
+        => write!(f, "{} quote overflows", method),
+      YamaError::Sdk(error) => write!(f, "{}", error)
+    }
+  }
+}
+
+impl std::error::Error for YamaError {}
+
+// The Yama contracts whose logs can be decoded
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum YamaContract {
+  AggregatorPriceSource,
+  BalanceSheet,
+  BridgeModule,
+  CDP,
+  DutchAuctionLiquidator,
+  EmptyCollateralManager,
+  FlashMintModule,
+  LeverageProxy,
+  MockPriceSource,
+  MockReserveStrategy,
+  ModularToken,
+  PSM,
+  PSMLockup,
+  PSMPriceSource,
+  SimpleBSH,
+  SplitBSH,
+  SurplusAuctionModule,
+  TestSwapper
+}
+
+// Compiled in, as abigen! does, so logs decode wherever the binary runs
+macro_rules! abi_json {
+  ($project:literal) => {
+    include_str!(concat!(
+      env!("CARGO_MANIFEST_DIR"),
+      "/../", $project, "/out/debug/", $project, "-abi.json"
+    ))
+  };
+}
+
+impl YamaContract {
+  pub fn abi_json(&self) -> &'static str {
+    match self {
+      YamaContract::AggregatorPriceSource => abi_json!("aggregatorpricesource"),
+      YamaContract::BalanceSheet => abi_json!("balancesheetmodule"),
+      YamaContract::BridgeModule => abi_json!("bridgemodule"),
+      YamaContract::CDP => abi_json!("cdpmodule"),
+      YamaContract::DutchAuctionLiquidator
+        => abi_json!("dutchauctionliquidator"),
+      YamaContract::EmptyCollateralManager
+        => abi_json!("emptycollateralmanager"),
+      YamaContract::FlashMintModule => abi_json!("flashmintmodule"),
+      YamaContract::LeverageProxy => abi_json!("leverageproxy"),
+      YamaContract::MockPriceSource => abi_json!("mockpricesource"),
+      YamaContract::MockReserveStrategy => abi_json!("mockreservestrategy"),
+      YamaContract::ModularToken => abi_json!("modulartoken"),
+      YamaContract::PSM => abi_json!("pegstabilitymodule"),
+      YamaContract::PSMLockup => abi_json!("psmlockup"),
+      YamaContract::PSMPriceSource => abi_json!("psmpricesource"),
+      YamaContract::SimpleBSH => abi_json!("simplebsh"),
+      YamaContract::SplitBSH => abi_json!("splitbsh"),
+      YamaContract::SurplusAuctionModule => abi_json!("surplusauctionmodule"),
+      YamaContract::TestSwapper => abi_json!("testswapper")
+    }
+  }
+}
+
+// Log IDs are assigned per contract, so a log can only be decoded once we
+// know which contract emitted it
+fn registry() -> &'static RwLock<HashMap<ContractId, YamaContract>> {
+  static REGISTRY: OnceLock<RwLock<HashMap<ContractId, YamaContract>>>
+    = OnceLock::new();
+  REGISTRY.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+pub fn register_contract(id: &Bech32ContractId, contract: YamaContract) {
+  registry().write().unwrap().insert(ContractId::from(id), contract);
+}
+
+pub fn registered_contract(id: &ContractId) -> Option<YamaContract> {
+  registry().read().unwrap().get(id).copied()
+}
+
+// log_id => (type name, variant names)
+pub type LoggedTypes = HashMap<u64, (String, Vec<String>)>;
+
+type LoggedTypesCache = RwLock<HashMap<YamaContract, Arc<LoggedTypes>>>;
+
+// Every failed call decodes its receipts, so each ABI is only parsed once
+fn logged_types_cache() -> &'static LoggedTypesCache {
+  static CACHE: OnceLock<LoggedTypesCache> = OnceLock::new();
+  CACHE.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+// The `loggedTypes` of a contract's ABI
+pub fn logged_types(contract: YamaContract) -> Arc<LoggedTypes> {
+  if let Some(logged) = logged_types_cache().read().unwrap().get(&contract) {
+    return logged.clone();
+  }
+  let abi: Value = serde_json::from_str(contract.abi_json())
+    .unwrap_or(Value::Null);
+
+  let mut logged: LoggedTypes = HashMap::new();
+
+  let empty: Vec<Value> = vec![];
+  let types = abi["types"].as_array().unwrap_or(&empty);
+  for logged_type in abi["loggedTypes"].as_array().unwrap_or(&empty) {
+    let log_id = match logged_type["logId"].as_u64() {
+      Some(log_id) => log_id,
+      None => continue
+    };
+    let type_id = logged_type["loggedType"]["type"].as_u64();
+    let declaration = types
+      .iter()
+      .find(|t| t["typeId"].as_u64() == type_id);
+    if let Some(declaration) = declaration {
+      let name = declaration["type"]
+        .as_str()
+        .unwrap_or("")
+        .split(' ')
+        .last()
+        .unwrap_or("")
+        .to_string();
+      let variants = declaration["components"]
+        .as_array()
+        .unwrap_or(&empty)
+        .iter()
+        .map(|c| c["name"].as_str().unwrap_or("").to_string())
+        .collect();
+      logged.insert(log_id, (name, variants));
+    }
+  }
+  let logged = Arc::new(logged);
+  logged_types_cache()
+    .write()
+    .unwrap()
+    .insert(contract, logged.clone());
+  logged
+}
+
+fn decode_log(id: &ContractId, log_id: u64, data: &[u8]) -> Option<YamaError> {
+  let contract = registered_contract(id)?;
+  let logged = logged_types(contract);
+  let (error_enum, variants) = logged.get(&log_id)?;
+  if data.len() < 8 {
+    return None;
+  }
+  let tag = u64::from_be_bytes(data[..8].try_into().unwrap()) as usize;
+  YamaError::from_variant(error_enum, variants.get(tag)?)
+}
@@ -0,0 +1,222 @@
+use fuels::{
+  prelude::*,
+  core::abi_decoder::ABIDecoder,
+  client::{PaginationRequest, PageDirection},
+  tx::{ContractId, Receipt, Bytes32, ScriptExecutionResult},
+  types::{
+    block::Block,
+    traits::{Parameterize, Tokenizable}
+  }
+};
+use crate::{
+  abigen::*,
+  errors::{
+    registered_contract,
+    logged_types,
+    YamaContract,
+    YamaError
+  }
+};
+
+fn decode_log_data<T: Parameterize + Tokenizable>(data: &[u8]) -> Option<T> {
+  let token = ABIDecoder::decode_single(&T::param_type(), data).ok()?;
+  T::from_token(token).ok()
+}
+
+// Declares YamaEvent over the log structs in yama_interfaces/src/events.sw.
+// Logs are matched to a variant by the struct name in the emitting
+// contract's ABI.
+macro_rules! yama_events {
+  ($($event:ident),* $(,)?) => {
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum YamaEvent {
+      $($event($event)),*
+    }
+
+    impl YamaEvent {
+      pub fn name(&self) -> &'static str {
+        match self {
+          $(YamaEvent::$event(_) => stringify!($event)),*
+        }
+      }
+
+      fn decode(type_name: &str, data: &[u8]) -> Option<YamaEvent> {
+        match type_name {
+          $(stringify!($event)
+            => decode_log_data::<$event>(data).map(YamaEvent::$event),)*
+          _ => None
+        }
+      }
+    }
+  };
+}
+
+yama_events!(
+  // Modular token
+  SetAllowlist,
+  // Balance sheet
+  AddSurplus,
+  AddDeficit,
+  SetSurplus,
+  SetHandler,
+  // SplitBSH
+  SetRecipient,
+  SetBackstop,
+  ShareSurplus,
+  // Bridge
+  RemoteTransferSent,
+  RemoteTransferReceived,
+  SetBridge,
+  // CDP
+  SetDebt,
+  Borrow,
+  Repay,
+  AddCollateral,
+  RemoveCollateral,
+  CreateVault,
+  Liquidate,
+  AddCollateralType,
+  SetCollateralType,
+  UpdateInterest,
+  ClearVault,
+  PartialClearVault,
+  // Dutch auction liquidator
+  InitializeAuction,
+  ResetAuction,
+  ClaimAuction,
+  SetDefaultCTypeParams,
+  SetCTypeParams,
+  SetPartialLiquidationParams,
+  InitializePartialAuction,
+  // Surplus auction module
+  SetSAMParams,
+  StartSAMAuction,
+  ResetSAMAuction,
+  ClaimSAMAuction,
+  // PSM
+  SetDebtCeiling,
+  SetTokenConfig,
+  SetFees,
+  SetStrategy,
+  SetMaxAllocation,
+  Allocate,
+  Recall,
+  ReportStrategy,
+  Deposit,
+  Withdraw,
+  // PSM lockup
+  Lockup,
+  Redeem,
+  SetExitQueue,
+  RequestRedeem,
+  CancelRedeem,
+  ClaimRedeem,
+  AbsorbDeficit
+);
+
+// An event along with where it was emitted. The block height and transaction
+// ID are only known when the event was read from a block.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EventRecord {
+  pub contract_id: ContractId,
+  pub contract: YamaContract,
+  pub block_height: Option<u64>,
+  pub tx_id: Option<Bytes32>,
+  pub event: YamaEvent
+}
+
+// Decodes the events logged by registered Yama contracts, e.g. from the
+// receipts of a call response. Error logs and logs of unregistered contracts
+// are skipped.
+pub fn decode_receipts(receipts: &[Receipt]) -> Vec<EventRecord> {
+  let mut events: Vec<EventRecord> = vec![];
+
+  for receipt in receipts.iter() {
+    if let Receipt::LogData { id, rb, data, .. } = receipt {
+      let contract = match registered_contract(id) {
+        Some(contract) => contract,
+        None => continue
+      };
+      let types = logged_types(contract);
+      let event = types
+        .get(rb)
+        .and_then(|(type_name, _)| YamaEvent::decode(type_name, data));
+      if let Some(event) = event {
+        events.push(EventRecord {
+          contract_id: id.clone(),
+          contract,
+          block_height: None,
+          tx_id: None,
+          event
+        });
+      }
+    }
+  }
+  events
+}
+
+// A reverted transaction stays in its block along with the logs it emitted
+// before reverting, though none of its state changes happened
+pub fn reverted(receipts: &[Receipt]) -> bool {
+  receipts.iter().any(|receipt| match receipt {
+    Receipt::Revert { .. } | Receipt::Panic { .. } => true,
+    Receipt::ScriptResult { result, .. }
+      => !matches!(result, ScriptExecutionResult::Success),
+    _ => false
+  })
+}
+
+// Reverted transactions have no events
+pub async fn events_in_transaction(
+  provider: &Provider,
+  tx_id: &Bytes32,
+  block_height: u64
+) -> Result<Vec<EventRecord>, YamaError> {
+  let receipts = provider.get_receipts(tx_id).await.map_err(Error::from)?;
+  if reverted(&receipts) {
+    return Ok(vec![]);
+  }
+  Ok(decode_receipts(&receipts)
+    .into_iter()
+    .map(|record| EventRecord {
+      block_height: Some(block_height),
+      tx_id: Some(tx_id.clone()),
+      ..record
+    })
+    .collect())
+}
+
+pub async fn events_in_block(
+  provider: &Provider,
+  block: &Block
+) -> Result<Vec<EventRecord>, YamaError> {
+  let mut events: Vec<EventRecord> = vec![];
+  for tx_id in block.transactions.iter() {
+    events.extend(
+      events_in_transaction(provider, tx_id, block.header.height).await?);
+  }
+  Ok(events)
+}
+
+// Gets the events of the last `num_blocks` blocks, oldest first
+pub async fn recent_events(
+  provider: &Provider,
+  num_blocks: u64
+) -> Result<Vec<EventRecord>, YamaError> {
+  let req = PaginationRequest {
+    cursor: None,
+    results: num_blocks as usize,
+    direction: PageDirection::Backward,
+  };
+  let blocks: Vec<Block> = provider
+    .get_blocks(req)
+    .await
+    .map_err(Error::from)?
+    .results;
+
+  let mut events: Vec<EventRecord> = vec![];
+  for block in blocks.iter().rev() {
+    events.extend(events_in_block(provider, block).await?);
+  }
+  Ok(events)
+}
@@ -1,22 +1,20 @@
 use fuels::prelude::*;
 use crate::{
+    errors::{
+        register_contract,
+        YamaContract,
+        YamaError
+    },
     abigen::*,
+    storage::StorageLayout,
     utils::{
         get_test_wallet,
-        set_storage_val
     },
     modulartoken::{
         get_cid,
         init_custom_modulartoken
     },
 };
-use tokio::sync::Mutex;
-use lazy_static::lazy_static;
-
-lazy_static! {
-    static ref MUTEX: Mutex<i32> = Mutex::new(0i32);
-}
-
 pub async fn init_flashmintmodule() -> (
     WalletUnlocked,
     ModularToken,
@@ -24,9 +22,9 @@ pub async fn init_flashmintmodule() -> (
 ) {
     let wallet: WalletUnlocked = get_test_wallet().await;
     let stable_i: ModularToken
-        = init_custom_modulartoken(&wallet).await;
+        = init_custom_modulartoken(&wallet).await.unwrap();
     let flashmintmodule
-        = init_custom_flashmintmodule(&wallet, &stable_i).await;
+        = init_custom_flashmintmodule(&wallet, &stable_i).await.unwrap();
 
     (
         wallet,
@@ -38,29 +36,20 @@ pub async fn init_flashmintmodule() -> (
 pub async fn init_custom_flashmintmodule(
     wallet: &WalletUnlocked,
     stable_i: &ModularToken
-) -> FlashMintModule {
+) -> Result<FlashMintModule, YamaError> {
     let storage_path = "../flashmintmodule/out/debug/flashmintmodule-storage_slots.json";
 
-    let guard = MUTEX.lock().await;
-
-    set_storage_val(
-        &storage_path,
-        "storage_0",
-        &get_cid(stable_i).to_string()
-    );
+    let storage = StorageLayout::new()
+        .field(0, &get_cid(stable_i));
 
     let id = Contract::deploy(
         "../flashmintmodule/out/debug/flashmintmodule.bin",
         &wallet,
         TxParameters::default(),
-        StorageConfiguration::with_storage_path(Some(
-            storage_path.to_string()
-        ))
+        storage.configuration(storage_path)
     )
-    .await
-    .unwrap();
-
-    drop(guard);
+    .await?;
 
-    FlashMintModule::new(id, wallet.clone())
+    register_contract(&id, YamaContract::FlashMintModule);
+    Ok(FlashMintModule::new(id, wallet.clone()))
 }
\ No newline at end of file
@@ -0,0 +1,401 @@
+use std::fmt;
+use fuels::prelude::*;
+use rand::prelude::{Rng, SeedableRng, SliceRandom, StdRng};
+use crate::{
+  abigen::*,
+  deploy::{
+    deploy,
+    CollateralTypePlan,
+    DeploymentPlan,
+    LiquidatorPlan
+  },
+  errors::YamaError,
+  simulator::{
+    replay_step,
+    Action,
+    ReplayStep,
+    Simulator,
+    SystemSnapshot
+  },
+  ufp128::Ufp128,
+  utils::TAI64_OFFSET
+};
+
+#[derive(Clone, Debug)]
+pub struct FuzzConfig {
+  pub plan: DeploymentPlan,
+  // Actions per run
+  pub steps: usize,
+  // Bound on amounts that don't come from the state, like mints
+  pub max_amount: u64,
+  pub max_advance: u64,
+  // What SetPrice picks from
+  pub prices: Vec<Ufp128>
+}
+
+impl Default for FuzzConfig {
+  fn default() -> Self {
+    FuzzConfig {
+      plan: DeploymentPlan {
+        liquidator: LiquidatorPlan {
+          initial_price_ratio: "1.5".parse::<Ufp128>().unwrap().into(),
+          time_interval: 1,
+          change_rate: "0.9".parse::<Ufp128>().unwrap().into(),
+          reset_threshold: 10
+        },
+        collateral_types: vec![CollateralTypePlan {
+          debt_floor: Ufp128::from_u64(100).into(),
+          debt_ceiling: Ufp128::from_u64(1_000_000).into(),
+          collateral_ratio: "1.5".parse::<Ufp128>().unwrap().into(),
+          // About 0.1% a second, so a few seconds accrue whole tokens
+          interest_rate: "1.001".parse::<Ufp128>().unwrap().into(),
+          borrowing_enabled: true,
+          allowlist_enabled: false,
+          partial_liquidation: None
+        }],
+        mock_price: Some(Ufp128::ONE.into()),
+        leverage_proxy: true,
+        ..DeploymentPlan::default()
+      },
+      steps: 200,
+      max_amount: 100_000,
+      max_advance: 5,
+      prices: ["0.5", "0.8", "1", "1.25", "2"]
+        .iter()
+        .map(|price| price.parse().unwrap())
+        .collect()
+    }
+  }
+}
+
+// Mostly an existing ID, sometimes the first one that doesn't exist
+fn draw_id(rng: &mut StdRng, count: usize) -> u64 {
+  rng.gen_range(0..=count) as u64
+}
+
+// Weighted towards the edges: nothing, exactly `bound` and just past it
+fn draw_amount(rng: &mut StdRng, bound: u64) -> u64 {
+  match rng.gen_range(0..8) {
+    0 => 0,
+    1 => bound,
+    2 => bound.saturating_add(1),
+    _ => rng.gen_range(0..=bound)
+  }
+}
+
+// An action against the simulator's current state, with IDs and amounts
+// drawn around what would succeed so most of them get past the first check
+pub fn random_action(
+  rng: &mut StdRng,
+  simulator: &Simulator,
+  config: &FuzzConfig
+) -> Action {
+  let vault_id = draw_id(rng, simulator.vaults.len());
+  let auction_id = draw_id(rng, simulator.auctions.len());
+  let collateral_type_id = draw_id(rng, simulator.collateral_types.len());
+  let risk = simulator.risk(vault_id).ok();
+  let wallet = simulator.wallet;
+
+  match rng.gen_range(0..20) {
+    0 => Action::AdvanceTime(rng.gen_range(1..=config.max_advance.max(1))),
+    1 => Action::SetPrice(
+      *config.prices.choose(rng).unwrap_or(&simulator.price)),
+    2 => Action::MintCollateral(draw_amount(rng, config.max_amount)),
+    3 => Action::MintStablecoin(draw_amount(rng, config.max_amount)),
+    4 => Action::CreateVault {
+      collateral_type_id,
+      collateral_amount: draw_amount(rng, wallet.collateral)
+    },
+    5 => Action::CreateProxyVault {
+      collateral_type_id,
+      collateral_amount: draw_amount(rng, wallet.collateral)
+    },
+    6 => Action::AddCollateral {
+      vault_id,
+      amount: draw_amount(rng, wallet.collateral)
+    },
+    7 => Action::RemoveCollateral {
+      vault_id,
+      amount: draw_amount(
+        rng, risk.map_or(0, |risk| risk.vault.collateral_amount))
+    },
+    8 | 9 => Action::Borrow {
+      vault_id,
+      amount: draw_amount(
+        rng, risk.map_or(config.max_amount, |risk| risk.max_borrowable()))
+    },
+    10 => Action::Repay {
+      vault_id,
+      amount: draw_amount(rng, risk.map_or(0, |risk| risk.debt().to_u64()))
+    },
+    11 => Action::UpdateInterest { collateral_type_id },
+    12 => Action::Liquidate { vault_id },
+    13 => {
+      let price = simulator.auction_price(auction_id).unwrap_or(0);
+      Action::Claim {
+        auction_id,
+        max_price: draw_amount(rng, price),
+        amount: draw_amount(rng, price.max(wallet.stablecoin))
+      }
+    },
+    14 => Action::ResetAuction { auction_id },
+    15 => Action::PsmDeposit(draw_amount(rng, wallet.collateral)),
+    16 => Action::PsmWithdraw(draw_amount(rng, wallet.stablecoin)),
+    17 => Action::Lockup(draw_amount(rng, wallet.collateral)),
+    18 => Action::Redeem(draw_amount(rng, wallet.lockup_shares)),
+    _ => Action::FlashLoan {
+      vault_id,
+      amount: draw_amount(rng, config.max_amount)
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation {
+  pub invariant: &'static str,
+  pub detail: String
+}
+
+impl fmt::Display for Violation {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}: {}", self.invariant, self.detail)
+  }
+}
+
+fn violation(invariant: &'static str, detail: String) -> Violation {
+  Violation { invariant, detail }
+}
+
+// How far the stablecoin supply less what was issued outside the CDP module
+// is from the debt less the surplus, and how many debt writes led up to it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SurplusDrift {
+  pub drift: i128,
+  pub debt_updates: u64
+}
+
+fn drift_of(snapshot: &SystemSnapshot, state: &Simulator) -> SurplusDrift {
+  let backed = snapshot.stablecoin_supply as i128
+    - state.issuance.total();
+  let expected = state.total_debt() as i128 - snapshot.surplus;
+  SurplusDrift {
+    drift: backed - expected,
+    debt_updates: state.metrics.debt_updates
+  }
+}
+
+pub fn surplus_drift(
+  snapshot: &SystemSnapshot,
+  simulator: &Simulator
+) -> SurplusDrift {
+  let mut state = simulator.clone();
+  state.load(snapshot);
+  drift_of(snapshot, &state)
+}
+
+// Checks the state in `snapshot` against the protocol's invariants.
+// `simulator` supplies what a snapshot doesn't cover: the price, the
+// liquidator's parameters and the stablecoin issued outside the CDP module.
+// `previous` is the surplus drift as of the last step that was checked.
+pub fn check_invariants(
+  snapshot: &SystemSnapshot,
+  simulator: &Simulator,
+  previous: &SurplusDrift
+) -> Vec<Violation> {
+  let mut violations: Vec<Violation> = vec![];
+  let mut state = simulator.clone();
+  state.load(snapshot);
+
+  // Each collateral type's totals are the sums over its vaults
+  for (c_type_id, c_type) in snapshot.collateral_types.iter().enumerate() {
+    let vaults: Vec<&Vault> = snapshot.vaults
+      .iter()
+      .filter(|vault| vault.collateral_type_id == c_type_id as u64)
+      .collect();
+    let initial_debt = vaults
+      .iter()
+      .try_fold(Ufp128::ZERO, |total, vault|
+        total.checked_add(Ufp128::from(&vault.initial_debt)));
+    if initial_debt != Some(Ufp128::from(&c_type.initial_debt)) {
+      violations.push(violation("total_debt", format!(
+        "collateral type {} has {} but its vaults sum to {:?}",
+        c_type_id, Ufp128::from(&c_type.initial_debt), initial_debt)));
+    }
+    let collateral = vaults
+      .iter()
+      .try_fold(0u64, |total, vault|
+        total.checked_add(vault.collateral_amount));
+    if collateral != Some(c_type.total_collateral) {
+      violations.push(violation("total_collateral", format!(
+        "collateral type {} has {} but its vaults sum to {:?}",
+        c_type_id, c_type.total_collateral, collateral)));
+    }
+  }
+
+  // Whatever the CDP module and SimpleBSH minted and burned is still owed as
+  // debt, less the surplus. Every debt write can round a token either way,
+  // so a step can only move the drift by as many tokens as it wrote debts.
+  let drift = drift_of(snapshot, &state);
+  let debt_updates = drift.debt_updates.saturating_sub(previous.debt_updates);
+  if (drift.drift - previous.drift).unsigned_abs() > debt_updates as u128 {
+    violations.push(violation("surplus", format!(
+      "supply and debt less surplus drifted from {} to {} apart over {} \
+      debt writes", previous.drift, drift.drift, debt_updates)));
+  }
+
+  for (vault_id, vault) in snapshot.vaults.iter().enumerate() {
+    let vault_id = vault_id as u64;
+    let open_auctions = snapshot.auctions
+      .iter()
+      .filter(|auction| auction.vault_id == vault_id && !auction.done)
+      .count();
+    let cleared = vault.collateral_amount == 0
+      && Ufp128::from(&vault.initial_debt) == Ufp128::ZERO;
+    if open_auctions != usize::from(vault.is_liquidated && !cleared) {
+      violations.push(violation("open_auction", format!(
+        "vault {} has {} open auctions", vault_id, open_auctions)));
+    }
+
+    let undercollateralized = !vault.is_liquidated
+      && state.risk(vault_id).is_ok_and(|risk| risk.is_undercollateralized());
+    if undercollateralized {
+      let mut liquidation = state.clone();
+      if let Err(error) = liquidation.apply(&Action::Liquidate { vault_id }) {
+        violations.push(violation("liquidatable", format!(
+          "vault {} is undercollateralized but liquidating it fails with {}",
+          vault_id, error)));
+      }
+    }
+  }
+  violations
+}
+
+// A failing run, cut down to the actions up to the first broken invariant
+#[derive(Clone, Debug)]
+pub struct FuzzFailure {
+  pub seed: u64,
+  pub actions: Vec<Action>,
+  pub violations: Vec<Violation>
+}
+
+impl fmt::Display for FuzzFailure {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    writeln!(f, "seed {} failed after {:?}", self.seed, self.actions)?;
+    for violation in self.violations.iter() {
+      writeln!(f, "  {}", violation)?;
+    }
+    Ok(())
+  }
+}
+
+// Runs `actions` on a fresh simulator, returning the index of the first step
+// that breaks an invariant and what it broke
+pub fn check_model(
+  config: &FuzzConfig,
+  actions: &[Action]
+) -> Option<(usize, Vec<Violation>)> {
+  let mut simulator = Simulator::new(&config.plan, TAI64_OFFSET);
+  let mut drift = surplus_drift(&simulator.snapshot(), &simulator);
+  for (index, action) in actions.iter().enumerate() {
+    let _ = simulator.apply(action);
+    let snapshot = simulator.snapshot();
+    let violations = check_invariants(&snapshot, &simulator, &drift);
+    if !violations.is_empty() {
+      return Some((index, violations));
+    }
+    drift = surplus_drift(&snapshot, &simulator);
+  }
+  None
+}
+
+// Drops actions one at a time from the end, keeping every removal after
+// which `fails` still holds
+pub fn shrink(
+  actions: Vec<Action>,
+  fails: impl Fn(&[Action]) -> bool
+) -> Vec<Action> {
+  let mut actions = actions;
+  for index in (0..actions.len()).rev() {
+    let mut candidate = actions.clone();
+    candidate.remove(index);
+    if fails(&candidate) {
+      actions = candidate;
+    }
+  }
+  actions
+}
+
+// Runs a random sequence on the simulator alone. Failures are shrunk, since
+// rerunning the model is cheap.
+pub fn fuzz_model(config: &FuzzConfig, seed: u64) -> Result<(), FuzzFailure> {
+  let mut rng = StdRng::seed_from_u64(seed);
+  let mut simulator = Simulator::new(&config.plan, TAI64_OFFSET);
+  let mut drift = surplus_drift(&simulator.snapshot(), &simulator);
+  let mut actions: Vec<Action> = vec![];
+  for _ in 0..config.steps {
+    let action = random_action(&mut rng, &simulator, config);
+    actions.push(action);
+    let _ = simulator.apply(&action);
+    let snapshot = simulator.snapshot();
+    if check_invariants(&snapshot, &simulator, &drift).is_empty() {
+      drift = surplus_drift(&snapshot, &simulator);
+      continue;
+    }
+
+    let mut actions = shrink(actions, |candidate|
+      check_model(config, candidate).is_some());
+    let (index, violations) = check_model(config, &actions).unwrap();
+    actions.truncate(index + 1);
+    return Err(FuzzFailure { seed, actions, violations });
+  }
+  Ok(())
+}
+
+#[derive(Debug)]
+pub struct ChainFuzzRun {
+  pub seed: u64,
+  pub steps: Vec<ReplayStep>,
+  pub failure: Option<FuzzFailure>
+}
+
+// Runs a random sequence on a fresh deployment of `config.plan` and on a
+// simulator started from it, stopping at the first step where the two
+// disagree or the deployment breaks an invariant. Nothing is shrunk here;
+// the failure's actions can be shrunk with check_model or rerun with replay.
+pub async fn fuzz_chain(
+  wallet: &WalletUnlocked,
+  config: &FuzzConfig,
+  seed: u64
+) -> Result<ChainFuzzRun, YamaError> {
+  let deployment = deploy(wallet, &config.plan).await?;
+  let mut simulator = Simulator::from_chain(&deployment).await?;
+  let mut client = deployment.client().await?;
+  let mut rng = StdRng::seed_from_u64(seed);
+  let mut drift = surplus_drift(&simulator.snapshot(), &simulator);
+
+  let mut actions: Vec<Action> = vec![];
+  let mut steps: Vec<ReplayStep> = vec![];
+  for _ in 0..config.steps {
+    let action = random_action(&mut rng, &simulator, config);
+    actions.push(action);
+    let (step, snapshot) = replay_step(
+      &deployment, &mut client, &mut simulator, &action).await?;
+
+    let mut violations = check_invariants(&snapshot, &simulator, &drift);
+    if !step.matches() {
+      violations.insert(0, violation("matches_model", format!(
+        "chain {:?}, model {:?}, diverged on {:?}",
+        step.chain, step.simulated, step.diverged)));
+    }
+    steps.push(step);
+    if !violations.is_empty() {
+      return Ok(ChainFuzzRun {
+        seed,
+        steps,
+        failure: Some(FuzzFailure { seed, actions, violations })
+      });
+    }
+    drift = surplus_drift(&snapshot, &simulator);
+  }
+  Ok(ChainFuzzRun { seed, steps, failure: None })
+}
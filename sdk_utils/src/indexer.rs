@@ -0,0 +1,627 @@
+use std::collections::BTreeMap;
+use serde::Serialize;
+use fuels::{
+  prelude::*,
+  client::{PaginationRequest, PageDirection},
+  tx::{ContractId, Bytes32},
+  types::{Bits256, Identity, block::Block}
+};
+use crate::{
+  abigen::*,
+  errors::YamaError,
+  events::{
+    events_in_block,
+    EventRecord,
+    YamaEvent
+  }
+};
+
+// Number of blocks requested per page while syncing
+const PAGE_SIZE: usize = 100;
+
+pub fn fp_to_u128(value: &UFP128) -> u128 {
+  ((value.value.upper as u128) << 64) | value.value.lower as u128
+}
+
+fn bits_to_string(bits: &Bits256) -> String {
+  format!("{:#x}", Bytes32::new(bits.0))
+}
+
+fn contract_to_string(id: &ContractId) -> String {
+  format!("{:#x}", Bytes32::new(**id))
+}
+
+fn identity_to_string(identity: &Identity) -> String {
+  match identity {
+    Identity::Address(address)
+      => format!("address:{:#x}", Bytes32::new(**address)),
+    Identity::ContractId(contract_id)
+      => format!("contract:{}", contract_to_string(contract_id))
+  }
+}
+
+// Fixed-point values are kept as their raw 64.64 representation
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct VaultState {
+  pub vault_id: u64,
+  pub owner: String,
+  pub alt_owner: Option<String>,
+  pub collateral_type_id: u64,
+  pub collateral_amount: u64,
+  pub debt: u128,
+  pub initial_debt: u128,
+  pub is_liquidated: bool,
+  pub is_cleared: bool
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct LiquidationParams {
+  pub initial_price_ratio: u128,
+  pub time_interval: u64,
+  pub change_rate: u128,
+  pub reset_threshold: u64,
+  pub enabled: bool
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct PartialLiquidationState {
+  pub enabled: bool,
+  pub target_ratio: u128,
+  pub penalty: u128
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct CollateralTypeState {
+  pub collateral_type_id: u64,
+  pub token: String,
+  pub price_source: String,
+  pub debt_floor: u128,
+  pub debt_ceiling: u128,
+  pub collateral_ratio: u128,
+  pub interest_rate: u128,
+  pub borrowing_enabled: bool,
+  pub allowlist_enabled: bool,
+  pub last_update_time: u64,
+  pub cumulative_interest: u128,
+  pub total_collateral: u64,
+  // Unset while the liquidator's defaults apply
+  pub liquidation_params: Option<LiquidationParams>,
+  pub partial_liquidation: Option<PartialLiquidationState>
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct AuctionState {
+  pub auction_id: u64,
+  pub vault_id: u64,
+  pub start_price: u128,
+  pub start_time: u64,
+  pub done: bool,
+  pub was_reset: bool,
+  pub claimer: Option<String>,
+  pub price: Option<u64>,
+  // Set for partial liquidations, which sell this much collateral to cover
+  // debt_to_cover
+  pub collateral_amount: Option<u64>,
+  pub debt_to_cover: Option<u64>
+}
+
+// One external stablecoin. The primary token has no config until
+// set_token_config is called for it.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct PSMTokenState {
+  pub decimals: Option<u8>,
+  pub debt_ceiling: Option<u64>,
+  pub enabled: Option<bool>,
+  pub deposited: u64,
+  pub withdrawn: u64,
+  // Including what's allocated to strategies
+  pub reserves: u64,
+  // strategy => amount
+  pub allocations: BTreeMap<String, u64>
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct PSMState {
+  // The primary token's debt ceiling
+  pub debt_ceiling: Option<u64>,
+  pub yss_withdrawn: u64,
+  // Keyed by token ID. Amounts of external stablecoins are only tracked per
+  // token, since each has its own decimals.
+  pub tokens: BTreeMap<String, PSMTokenState>,
+  // In basis points
+  pub tin: Option<u64>,
+  pub tout: Option<u64>,
+  // YSS booked as surplus
+  pub fees: u64
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct LockupState {
+  pub ext_stable_locked: u64,
+  pub yama_locked: u64,
+  pub yama_redeemed: u64,
+  // Burned to cover deficits
+  pub yama_absorbed: u64,
+  pub total_supply: u64,
+  pub exit_queue_enabled: bool,
+  pub exit_delay: u64,
+  // Lockup tokens held for open exit tickets, by ticket ID
+  pub queued: BTreeMap<u64, u64>
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct RemoteTransfer {
+  pub chain: u32,
+  pub from: String,
+  pub to: String,
+  pub amount: u64,
+  pub block_height: Option<u64>
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct BridgeState {
+  // chain => remote bridge
+  pub remote_bridges: BTreeMap<u32, String>,
+  pub sent: Vec<RemoteTransfer>,
+  pub received: Vec<RemoteTransfer>,
+  // chain => amount
+  pub total_sent: BTreeMap<u32, u64>,
+  pub total_received: BTreeMap<u32, u64>
+}
+
+// An event that took more from an amount than the state had, e.g. because an
+// earlier event was missed. The amount is left at 0.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Inconsistency {
+  pub block_height: Option<u64>,
+  pub event: String
+}
+
+// Subtracts `amount` from `value`, saturating at 0. False if `value` was short.
+fn debit(value: &mut u64, amount: u64) -> bool {
+  match value.checked_sub(amount) {
+    Some(rest) => {
+      *value = rest;
+      true
+    },
+    None => {
+      *value = 0;
+      false
+    }
+  }
+}
+
+// The protocol state rebuilt from events. PSMs, lockups and bridges are keyed
+// by contract ID.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ProtocolState {
+  pub block_height: u64,
+  pub vaults: BTreeMap<u64, VaultState>,
+  pub collateral_types: BTreeMap<u64, CollateralTypeState>,
+  pub auctions: BTreeMap<u64, AuctionState>,
+  pub default_liquidation_params: Option<LiquidationParams>,
+  pub psms: BTreeMap<String, PSMState>,
+  pub lockups: BTreeMap<String, LockupState>,
+  pub bridges: BTreeMap<String, BridgeState>,
+  pub inconsistencies: Vec<Inconsistency>
+}
+
+impl ProtocolState {
+  pub fn open_vaults(&self) -> Vec<&VaultState> {
+    self.vaults
+      .values()
+      .filter(|vault| !vault.is_liquidated)
+      .collect()
+  }
+
+  pub fn active_auctions(&self) -> Vec<&AuctionState> {
+    self.auctions
+      .values()
+      .filter(|auction| !auction.done)
+      .collect()
+  }
+
+  pub fn vaults_of(&self, owner: &Identity) -> Vec<&VaultState> {
+    let owner = identity_to_string(owner);
+    self.vaults
+      .values()
+      .filter(|vault| vault.owner == owner)
+      .collect()
+  }
+
+  pub fn to_json(&self) -> serde_json::Value {
+    serde_json::to_value(self).unwrap()
+  }
+
+  pub fn snapshot(&self) -> String {
+    serde_json::to_string_pretty(self).unwrap()
+  }
+
+  pub fn apply(&mut self, record: &EventRecord) {
+    if let Some(height) = record.block_height {
+      self.block_height = self.block_height.max(height);
+    }
+    let contract_key = contract_to_string(&record.contract_id);
+    let mut consistent = true;
+
+    match &record.event {
+      // CDP
+      YamaEvent::CreateVault(event) => {
+        self.vaults.insert(event.vault_id, VaultState {
+          vault_id: event.vault_id,
+          owner: identity_to_string(&event.owner),
+          alt_owner: event.alt_owner.as_ref().map(identity_to_string),
+          collateral_type_id: event.collateral_type_id,
+          collateral_amount: event.collateral_amount,
+          ..VaultState::default()
+        });
+        self.collateral_type(event.collateral_type_id).total_collateral
+          += event.collateral_amount;
+      },
+      // Also emitted by create_vault before CreateVault, in which case the
+      // vault isn't known yet
+      YamaEvent::AddCollateral(event) => {
+        if let Some(vault) = self.vaults.get_mut(&event.vault_id) {
+          vault.collateral_amount += event.amount;
+          let c_type_id = vault.collateral_type_id;
+          self.collateral_type(c_type_id).total_collateral += event.amount;
+        }
+      },
+      YamaEvent::RemoveCollateral(event) => {
+        if let Some(vault) = self.vaults.get_mut(&event.vault_id) {
+          consistent &= debit(&mut vault.collateral_amount, event.amount);
+          let c_type_id = vault.collateral_type_id;
+          consistent &= debit(
+            &mut self.collateral_type(c_type_id).total_collateral,
+            event.amount
+          );
+        }
+      },
+      YamaEvent::SetDebt(event) => {
+        let vault = self.vault(event.vault_id);
+        vault.debt = fp_to_u128(&event.debt);
+        vault.initial_debt = fp_to_u128(&event.initial_debt);
+      },
+      YamaEvent::Liquidate(event) => {
+        self.vault(event.vault_id).is_liquidated = true;
+      },
+      YamaEvent::ClearVault(event) => {
+        let vault = self.vault(event.vault_id);
+        let amount = vault.collateral_amount;
+        let c_type_id = vault.collateral_type_id;
+        vault.collateral_amount = 0;
+        vault.is_cleared = true;
+        consistent &= debit(
+          &mut self.collateral_type(c_type_id).total_collateral,
+          amount
+        );
+      },
+      // The vault goes back to its owner
+      YamaEvent::PartialClearVault(event) => {
+        let vault = self.vault(event.vault_id);
+        let c_type_id = vault.collateral_type_id;
+        consistent &= debit(
+          &mut vault.collateral_amount,
+          event.collateral_amount
+        );
+        vault.is_liquidated = false;
+        consistent &= debit(
+          &mut self.collateral_type(c_type_id).total_collateral,
+          event.collateral_amount
+        );
+      },
+      YamaEvent::AddCollateralType(event) => {
+        let c_type = self.collateral_type(event.collateral_type_id);
+        c_type.token = bits_to_string(&event.token);
+        c_type.price_source = bits_to_string(&event.price_source);
+        c_type.debt_floor = fp_to_u128(&event.debt_floor);
+        c_type.debt_ceiling = fp_to_u128(&event.debt_ceiling);
+        c_type.collateral_ratio = fp_to_u128(&event.collateral_ratio);
+        c_type.interest_rate = fp_to_u128(&event.interest_rate);
+        c_type.borrowing_enabled = event.borrowing_enabled;
+        c_type.allowlist_enabled = event.allowlist_enabled;
+      },
+      YamaEvent::SetCollateralType(event) => {
+        let c_type = self.collateral_type(event.collateral_type_id);
+        c_type.token = bits_to_string(&event.token);
+        c_type.price_source = bits_to_string(&event.price_source);
+        c_type.debt_floor = fp_to_u128(&event.debt_floor);
+        c_type.debt_ceiling = fp_to_u128(&event.debt_ceiling);
+        c_type.collateral_ratio = fp_to_u128(&event.collateral_ratio);
+        c_type.interest_rate = fp_to_u128(&event.interest_rate);
+        c_type.borrowing_enabled = event.borrowing_enabled;
+        c_type.allowlist_enabled = event.allowlist_enabled;
+      },
+      YamaEvent::UpdateInterest(event) => {
+        let c_type = self.collateral_type(event.collateral_type_id);
+        c_type.interest_rate = fp_to_u128(&event.interest_rate);
+        c_type.last_update_time = event.last_update_time;
+        c_type.cumulative_interest = fp_to_u128(&event.cumulative_interest);
+      },
+      // Dutch auction liquidator
+      YamaEvent::InitializeAuction(event) => {
+        self.auctions.insert(event.auction_id, AuctionState {
+          auction_id: event.auction_id,
+          vault_id: event.vault_id,
+          start_price: fp_to_u128(&event.start_price),
+          start_time: event.start_time,
+          ..AuctionState::default()
+        });
+      },
+      // Follows InitializeAuction
+      YamaEvent::InitializePartialAuction(event) => {
+        let auction = self.auction(event.auction_id);
+        auction.collateral_amount = Some(event.collateral_amount);
+        auction.debt_to_cover = Some(event.debt);
+      },
+      YamaEvent::ResetAuction(event) => {
+        let auction = self.auction(event.auction_id);
+        auction.done = true;
+        auction.was_reset = true;
+      },
+      YamaEvent::ClaimAuction(event) => {
+        let auction = self.auction(event.auction_id);
+        auction.done = true;
+        auction.claimer = Some(identity_to_string(&event.claimer));
+        auction.price = Some(event.price);
+      },
+      YamaEvent::SetDefaultCTypeParams(event) => {
+        self.default_liquidation_params = Some(LiquidationParams {
+          initial_price_ratio: fp_to_u128(&event.initial_price_ratio),
+          time_interval: event.time_interval,
+          change_rate: fp_to_u128(&event.change_rate),
+          reset_threshold: event.reset_threshold,
+          enabled: true
+        });
+      },
+      YamaEvent::SetCTypeParams(event) => {
+        self.collateral_type(event.collateral_type_id).liquidation_params
+          = Some(LiquidationParams {
+            initial_price_ratio: fp_to_u128(&event.initial_price_ratio),
+            time_interval: event.time_interval,
+            change_rate: fp_to_u128(&event.change_rate),
+            reset_threshold: event.reset_threshold,
+            enabled: event.enabled
+          });
+      },
+      YamaEvent::SetPartialLiquidationParams(event) => {
+        self.collateral_type(event.collateral_type_id).partial_liquidation
+          = Some(PartialLiquidationState {
+            enabled: event.enabled,
+            target_ratio: fp_to_u128(&event.target_ratio),
+            penalty: fp_to_u128(&event.penalty)
+          });
+      },
+      // PSM
+      YamaEvent::SetDebtCeiling(event) => {
+        self.psms.entry(contract_key).or_default().debt_ceiling
+          = Some(event.debt_ceiling);
+      },
+      YamaEvent::SetTokenConfig(event) => {
+        let token = self.psms
+          .entry(contract_key)
+          .or_default()
+          .tokens
+          .entry(bits_to_string(&event.token))
+          .or_default();
+        token.decimals = Some(event.decimals);
+        token.debt_ceiling = Some(event.debt_ceiling);
+        token.enabled = Some(event.enabled);
+      },
+      YamaEvent::SetFees(event) => {
+        let psm = self.psms.entry(contract_key).or_default();
+        psm.tin = Some(event.tin);
+        psm.tout = Some(event.tout);
+      },
+      YamaEvent::Allocate(event) => {
+        *self.psms
+          .entry(contract_key)
+          .or_default()
+          .tokens
+          .entry(bits_to_string(&event.token))
+          .or_default()
+          .allocations
+          .entry(bits_to_string(&event.strategy))
+          .or_default() += event.amount;
+      },
+      YamaEvent::Recall(event) => {
+        let allocation = self.psms
+          .entry(contract_key)
+          .or_default()
+          .tokens
+          .entry(bits_to_string(&event.token))
+          .or_default()
+          .allocations
+          .entry(bits_to_string(&event.strategy))
+          .or_default();
+        consistent &= debit(allocation, event.amount);
+      },
+      // Gains are recalled on top of the allocation, and losses come out of it
+      YamaEvent::ReportStrategy(event) => {
+        let token = self.psms.entry(contract_key).or_default()
+          .tokens
+          .entry(bits_to_string(&event.token))
+          .or_default();
+        token.reserves += event.gain;
+        consistent &= debit(&mut token.reserves, event.loss);
+        consistent &= debit(
+          token.allocations
+            .entry(bits_to_string(&event.strategy))
+            .or_default(),
+          event.loss
+        );
+      },
+      YamaEvent::Deposit(event) => {
+        let psm = self.psms.entry(contract_key).or_default();
+        psm.fees += event.fee;
+        let token = psm.tokens
+          .entry(bits_to_string(&event.token))
+          .or_default();
+        token.deposited += event.ext_stable_amount;
+        token.reserves += event.ext_stable_amount;
+      },
+      YamaEvent::Withdraw(event) => {
+        let psm = self.psms.entry(contract_key).or_default();
+        psm.yss_withdrawn += event.yss_amount;
+        psm.fees += event.fee;
+        let token = psm.tokens
+          .entry(bits_to_string(&event.token))
+          .or_default();
+        token.withdrawn += event.ext_stable_amount;
+        consistent &= debit(&mut token.reserves, event.ext_stable_amount);
+      },
+      // PSM lockup
+      YamaEvent::Lockup(event) => {
+        let lockup = self.lockups.entry(contract_key).or_default();
+        lockup.ext_stable_locked += event.ext_stable_amount;
+        lockup.yama_locked += event.yama_amount;
+        lockup.total_supply += event.lockup_amount;
+      },
+      YamaEvent::Redeem(event) => {
+        let lockup = self.lockups.entry(contract_key).or_default();
+        lockup.yama_redeemed += event.yama_amount;
+        consistent &= debit(&mut lockup.total_supply, event.lockup_amount);
+      },
+      YamaEvent::SetExitQueue(event) => {
+        let lockup = self.lockups.entry(contract_key).or_default();
+        lockup.exit_queue_enabled = event.enabled;
+        lockup.exit_delay = event.delay;
+      },
+      YamaEvent::RequestRedeem(event) => {
+        self.lockups.entry(contract_key).or_default()
+          .queued
+          .insert(event.ticket_id, event.lockup_amount);
+      },
+      YamaEvent::CancelRedeem(event) => {
+        self.lockups.entry(contract_key).or_default()
+          .queued
+          .remove(&event.ticket_id);
+      },
+      YamaEvent::ClaimRedeem(event) => {
+        let lockup = self.lockups.entry(contract_key).or_default();
+        lockup.queued.remove(&event.ticket_id);
+        lockup.yama_redeemed += event.yama_amount;
+        consistent &= debit(&mut lockup.total_supply, event.lockup_amount);
+      },
+      YamaEvent::AbsorbDeficit(event) => {
+        self.lockups.entry(contract_key).or_default()
+          .yama_absorbed += event.yama_amount;
+      },
+      // Bridge
+      YamaEvent::SetBridge(event) => {
+        self.bridges.entry(contract_key).or_default()
+          .remote_bridges
+          .insert(event.chain, bits_to_string(&event.bridge));
+      },
+      YamaEvent::RemoteTransferSent(event) => {
+        let bridge = self.bridges.entry(contract_key).or_default();
+        *bridge.total_sent.entry(event.dst_chain).or_default()
+          += event.amount;
+        bridge.sent.push(RemoteTransfer {
+          chain: event.dst_chain,
+          from: identity_to_string(&event.from_id),
+          to: bits_to_string(&event.to_id),
+          amount: event.amount,
+          block_height: record.block_height
+        });
+      },
+      YamaEvent::RemoteTransferReceived(event) => {
+        let bridge = self.bridges.entry(contract_key).or_default();
+        *bridge.total_received.entry(event.src_chain).or_default()
+          += event.amount;
+        bridge.received.push(RemoteTransfer {
+          chain: event.src_chain,
+          from: bits_to_string(&event.from_id),
+          to: identity_to_string(&event.to_id),
+          amount: event.amount,
+          block_height: record.block_height
+        });
+      },
+      _ => {}
+    }
+
+    if !consistent {
+      self.inconsistencies.push(Inconsistency {
+        block_height: record.block_height,
+        event: record.event.name().to_string()
+      });
+    }
+  }
+
+  fn vault(&mut self, vault_id: u64) -> &mut VaultState {
+    self.vaults.entry(vault_id).or_insert_with(|| VaultState {
+      vault_id,
+      ..VaultState::default()
+    })
+  }
+
+  fn collateral_type(&mut self, c_type_id: u64) -> &mut CollateralTypeState {
+    self.collateral_types.entry(c_type_id).or_insert_with(
+      || CollateralTypeState {
+        collateral_type_id: c_type_id,
+        ..CollateralTypeState::default()
+      })
+  }
+
+  fn auction(&mut self, auction_id: u64) -> &mut AuctionState {
+    self.auctions.entry(auction_id).or_insert_with(|| AuctionState {
+      auction_id,
+      ..AuctionState::default()
+    })
+  }
+}
+
+// Walks the chain from genesis and applies the events of registered Yama
+// contracts to a ProtocolState, skipping reverted transactions. Call `sync`
+// again to catch up with new blocks.
+pub struct Indexer {
+  pub wallet: WalletUnlocked,
+  pub state: ProtocolState,
+  provider: Provider,
+  cursor: Option<String>
+}
+
+impl Indexer {
+  pub fn new(wallet: &WalletUnlocked) -> Indexer {
+    Indexer {
+      wallet: wallet.clone(),
+      state: ProtocolState::default(),
+      provider: wallet.get_provider().unwrap().clone(),
+      cursor: None
+    }
+  }
+
+  // Returns the number of events applied
+  pub async fn sync(&mut self) -> Result<usize, YamaError> {
+    let provider = self.provider.clone();
+    let mut applied: usize = 0;
+    loop {
+      let req = PaginationRequest {
+        cursor: self.cursor.clone(),
+        results: PAGE_SIZE,
+        direction: PageDirection::Forward,
+      };
+      let page = provider.get_blocks(req).await.map_err(Error::from)?;
+      let blocks: Vec<Block> = page.results;
+      for block in blocks.iter() {
+        for record in events_in_block(&provider, block).await? {
+          self.apply(&record).await?;
+          applied += 1;
+        }
+        self.state.block_height = block.header.height;
+      }
+      if page.cursor.is_some() {
+        self.cursor = page.cursor;
+      }
+      if !page.has_next_page {
+        break;
+      }
+    }
+    Ok(applied)
+  }
+
+  pub async fn apply(&mut self, record: &EventRecord) -> Result<(), YamaError> {
+    self.state.apply(record);
+    Ok(())
+  }
+}
@@ -0,0 +1,186 @@
+use std::fmt;
+use crate::{
+  abigen::*,
+  cdp::{
+    get_annual_interest,
+    get_ps_interest
+  },
+  errors::YamaError,
+  ufp128::{Ufp128, ParseUfp128Error}
+};
+
+// stablecoin_library/src/constants.sw
+pub const SECONDS_IN_YEAR: u64 = 31536000;
+
+const BPS_DENOMINATOR: u128 = 10_000;
+
+pub fn from_bps(bps: u64) -> Ufp128 {
+  Ufp128::from_bits(Ufp128::from_u64(bps).bits() / BPS_DENOMINATOR)
+}
+
+// Parses "0.05", "5%" or "500bps" as the fraction 0.05
+pub fn parse_rate(rate: &str) -> Result<Ufp128, ParseUfp128Error> {
+  let rate = rate.trim();
+  if let Some(bps) = rate.strip_suffix("bps") {
+    let bps: Ufp128 = bps.trim_end().parse()?;
+    Ok(Ufp128::from_bits(bps.bits() / BPS_DENOMINATOR))
+  } else if let Some(percent) = rate.strip_suffix('%') {
+    let percent: Ufp128 = percent.trim_end().parse()?;
+    Ok(Ufp128::from_bits(percent.bits() / 100))
+  } else {
+    rate.parse()
+  }
+}
+
+// What the contract's get_annual_interest returns for a per-second rate
+pub fn annual_multiplier(per_second: Ufp128) -> Option<Ufp128> {
+  per_second.checked_powu(SECONDS_IN_YEAR)
+}
+
+// The compounded yearly rate, None below 0% or on overflow
+pub fn per_second_to_apy(per_second: Ufp128) -> Option<Ufp128> {
+  annual_multiplier(per_second)?.checked_sub(Ufp128::ONE)
+}
+
+// The per-second rate times the seconds in a year, None below 0%
+pub fn per_second_to_apr(per_second: Ufp128) -> Option<Ufp128> {
+  per_second
+    .checked_sub(Ufp128::ONE)?
+    .checked_mul(Ufp128::from_u64(SECONDS_IN_YEAR))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateConversion {
+  // The multiplier to pass as a collateral type's `interest_rate`
+  pub per_second: Ufp128,
+  // The APY or APR that was asked for
+  pub requested: Ufp128,
+  // The APY or APR `per_second` works out to on-chain
+  pub achieved: Ufp128,
+  // per_second^SECONDS_IN_YEAR as the contract computes it
+  pub annual_multiplier: Ufp128
+}
+
+impl RateConversion {
+  // achieved - requested
+  pub fn error(&self) -> f64 {
+    self.achieved.to_f64() - self.requested.to_f64()
+  }
+
+  pub fn relative_error(&self) -> f64 {
+    self.error() / self.requested.to_f64()
+  }
+}
+
+impl fmt::Display for RateConversion {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "{} per second ({:#x}): requested {}, achieved {}, error {:+e}",
+      self.per_second,
+      self.per_second.bits(),
+      self.requested,
+      self.achieved,
+      self.error()
+    )
+  }
+}
+
+// Distance between the annual multiplier of `per_second` and `target`,
+// None when the multiplier overflows
+fn annual_distance(per_second: Ufp128, target: Ufp128) -> Option<u128> {
+  let annual = annual_multiplier(per_second)?;
+  Some(annual.bits().abs_diff(target.bits()))
+}
+
+// The per-second rate whose on-chain annual multiplier is closest to
+// 1 + apy. powu rounds down at every step, so this searches the exact
+// results rather than taking a float root.
+pub fn apy_to_per_second(apy: Ufp128) -> Option<RateConversion> {
+  let target = Ufp128::ONE.checked_add(apy)?;
+  let exceeds = |bits: u128| annual_multiplier(Ufp128::from_bits(bits))
+    .map_or(true, |annual| annual > target);
+
+  // The float root lands close, so grow a bracket around it
+  let guess = Ufp128::from_f64(
+    target.to_f64().powf(1.0 / SECONDS_IN_YEAR as f64)
+  ).unwrap_or(Ufp128::ONE).bits();
+  let mut step: u128 = 1 << 16;
+  let mut low = guess.saturating_sub(step).max(Ufp128::ONE.bits());
+  while exceeds(low) && low > Ufp128::ONE.bits() {
+    step <<= 1;
+    low = guess.saturating_sub(step).max(Ufp128::ONE.bits());
+  }
+  let mut high = guess.saturating_add(1 << 16);
+  step = 1 << 16;
+  while !exceeds(high) {
+    step <<= 1;
+    high = guess.saturating_add(step);
+  }
+
+  // The largest rate that doesn't exceed the target
+  while high - low > 1 {
+    let middle = low + (high - low) / 2;
+    if exceeds(middle) {
+      high = middle;
+    } else {
+      low = middle;
+    }
+  }
+  let below = Ufp128::from_bits(low);
+  let above = Ufp128::from_bits(high);
+  let per_second = match (
+    annual_distance(below, target),
+    annual_distance(above, target)
+  ) {
+    (Some(below_distance), Some(above_distance))
+      if above_distance < below_distance => above,
+    _ => below
+  };
+
+  let annual_multiplier = annual_multiplier(per_second)?;
+  Some(RateConversion {
+    per_second,
+    requested: apy,
+    achieved: annual_multiplier.checked_sub(Ufp128::ONE)?,
+    annual_multiplier
+  })
+}
+
+// 1 + apr / SECONDS_IN_YEAR, rounded to the nearest representable rate
+pub fn apr_to_per_second(apr: Ufp128) -> Option<RateConversion> {
+  let seconds = SECONDS_IN_YEAR as u128;
+  let increment = apr.bits().checked_add(seconds / 2)? / seconds;
+  let per_second = Ufp128::ONE.checked_add(Ufp128::from_bits(increment))?;
+  Some(RateConversion {
+    per_second,
+    requested: apr,
+    achieved: per_second_to_apr(per_second)?,
+    annual_multiplier: annual_multiplier(per_second)?
+  })
+}
+
+// A collateral type's interest as the CDP module reports it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CollateralTypeRates {
+  pub per_second: Ufp128,
+  pub annual_multiplier: Ufp128,
+  pub apy: Option<Ufp128>,
+  pub apr: Option<Ufp128>
+}
+
+pub async fn get_rates(
+  instance: &CDP,
+  collateral_type_id: u64
+) -> Result<CollateralTypeRates, YamaError> {
+  let per_second = Ufp128::from(
+    get_ps_interest(instance, collateral_type_id).await?);
+  let annual_multiplier = Ufp128::from(
+    get_annual_interest(instance, collateral_type_id).await?);
+  Ok(CollateralTypeRates {
+    per_second,
+    annual_multiplier,
+    apy: annual_multiplier.checked_sub(Ufp128::ONE),
+    apr: per_second_to_apr(per_second)
+  })
+}
@@ -0,0 +1,237 @@
+use std::{
+  collections::BTreeSet,
+  future::Future,
+  time::Duration
+};
+use async_std::task;
+use fuels::prelude::*;
+use crate::{
+  cdp,
+  client::YamaClient,
+  errors::YamaError,
+  indexer::Indexer
+};
+
+#[derive(Clone, Debug)]
+pub struct KeeperConfig {
+  pub liquidate_gas_limit: u64,
+  pub update_interest_gas_limit: u64,
+  pub gas_price: Option<u64>,
+  // Retries after the first attempt
+  pub max_retries: u32,
+  pub initial_backoff: Duration,
+  pub max_backoff: Duration,
+  pub poll_interval: Duration
+}
+
+impl Default for KeeperConfig {
+  fn default() -> Self {
+    KeeperConfig {
+      liquidate_gas_limit: 4_000_000,
+      update_interest_gas_limit: 32_000_000,
+      gas_price: None,
+      max_retries: 3,
+      initial_backoff: Duration::from_millis(500),
+      max_backoff: Duration::from_secs(8),
+      poll_interval: Duration::from_secs(5)
+    }
+  }
+}
+
+#[derive(Debug, Default)]
+pub struct KeeperReport {
+  // Collateral type IDs
+  pub interest_updated: Vec<u64>,
+  // Vault IDs
+  pub liquidated: Vec<u64>,
+  // Collateral type or vault ID => the error that outlasted the retries
+  pub interest_failures: Vec<(u64, YamaError)>,
+  pub liquidation_failures: Vec<(u64, YamaError)>,
+  // Collateral type or vault ID => an error that was retried
+  pub retried: Vec<(u64, YamaError)>
+}
+
+impl KeeperReport {
+  fn record_retried(&mut self, id: u64, retried: Vec<YamaError>) {
+    self.retried.extend(retried.into_iter().map(|error| (id, error)));
+  }
+}
+
+// Reverts with a decoded Yama error won't succeed on a retry; anything else
+// might be a node hiccup or running out of gas
+pub fn is_retryable(error: &YamaError) -> bool {
+  matches!(error, YamaError::Sdk(_) | YamaError::Reverted { .. })
+}
+
+// The last attempt's result, and the errors of the ones retried before it
+#[derive(Debug)]
+pub struct Retried<T> {
+  pub result: Result<T, YamaError>,
+  pub retried: Vec<YamaError>
+}
+
+pub async fn with_retries<T, F, Fut>(
+  config: &KeeperConfig,
+  mut action: F
+) -> Retried<T>
+where
+  F: FnMut() -> Fut,
+  Fut: Future<Output = Result<T, YamaError>>
+{
+  let mut backoff = config.initial_backoff;
+  let mut retried: Vec<YamaError> = vec![];
+  loop {
+    match action().await {
+      Err(error)
+        if is_retryable(&error) && retried.len() < config.max_retries as usize
+      => {
+        retried.push(error);
+        task::sleep(backoff).await;
+        backoff = (backoff * 2).min(config.max_backoff);
+      },
+      result => return Retried { result, retried }
+    }
+  }
+}
+
+// Tracks every open vault through the indexer and liquidates the ones that
+// are undercollateralized
+pub struct LiquidationKeeper {
+  pub client: YamaClient,
+  pub indexer: Indexer,
+  pub config: KeeperConfig
+}
+
+impl LiquidationKeeper {
+  pub async fn new(
+    cdp_id: &Bech32ContractId,
+    wallet: &WalletUnlocked,
+    config: KeeperConfig
+  ) -> Result<LiquidationKeeper, YamaError> {
+    // Creating the client registers the protocol's contracts, so the indexer
+    // can decode their events
+    let client = YamaClient::new(cdp_id, wallet).await?;
+    Ok(LiquidationKeeper {
+      client,
+      indexer: Indexer::new(wallet),
+      config
+    })
+  }
+
+  // Vaults that have debt and haven't been liquidated, as
+  // (vault_id, collateral_type_id)
+  pub fn open_vaults(&self) -> Vec<(u64, u64)> {
+    self.indexer.state
+      .open_vaults()
+      .into_iter()
+      .filter(|vault| vault.debt > 0)
+      .map(|vault| (vault.vault_id, vault.collateral_type_id))
+      .collect()
+  }
+
+  pub async fn tick(&mut self) -> Result<KeeperReport, YamaError> {
+    self.indexer.sync().await?;
+    let mut report = KeeperReport::default();
+    let open_vaults = self.open_vaults();
+    let deps = self.client.dependencies().clone();
+
+    // Accrue interest first, otherwise vaults are checked against stale debt
+    let collateral_type_ids: BTreeSet<u64> = open_vaults
+      .iter()
+      .map(|(_, c_type_id)| *c_type_id)
+      .collect();
+    for c_type_id in collateral_type_ids {
+      let price_source = self.client.price_source(c_type_id).await?;
+      let tx_params = TxParameters::new(
+        self.config.gas_price,
+        Some(self.config.update_interest_gas_limit),
+        None
+      );
+      let cdp = &self.client.cdp;
+      let Retried { result, retried } = with_retries(
+        &self.config,
+        || cdp::update_interest_with_params(
+          cdp,
+          &deps.stablecoin,
+          &price_source,
+          &deps.balancesheet,
+          &deps.bsh,
+          &deps.bsh_targets,
+          c_type_id,
+          tx_params
+        )
+      ).await;
+      report.record_retried(c_type_id, retried);
+      match result {
+        Ok(()) => report.interest_updated.push(c_type_id),
+        Err(error) => report.interest_failures.push((c_type_id, error))
+      }
+    }
+
+    // A vault that can't be checked is reported like a failed liquidation,
+    // so the rest still get checked
+    for (vault_id, c_type_id) in open_vaults {
+      let price_source = match self.client.price_source(c_type_id).await {
+        Ok(price_source) => price_source,
+        Err(error) => {
+          report.liquidation_failures.push((vault_id, error));
+          continue;
+        }
+      };
+      let cdp = &self.client.cdp;
+      let Retried { result, retried } = with_retries(&self.config, || {
+        cdp::is_undercollateralized(cdp, &price_source, vault_id)
+      }).await;
+      report.record_retried(vault_id, retried);
+      match result {
+        Ok(true) => {},
+        Ok(false) => continue,
+        Err(error) => {
+          report.liquidation_failures.push((vault_id, error));
+          continue;
+        }
+      }
+
+      let tx_params = TxParameters::new(
+        self.config.gas_price,
+        Some(self.config.liquidate_gas_limit),
+        None
+      );
+      let Retried { result, retried } = with_retries(
+        &self.config,
+        || cdp::liquidate_with_params(
+          cdp,
+          &deps.stablecoin,
+          &deps.liquidator,
+          &price_source,
+          &deps.balancesheet,
+          &deps.bsh,
+          &deps.bsh_targets,
+          vault_id,
+          tx_params
+        )
+      ).await;
+      report.record_retried(vault_id, retried);
+      match result {
+        Ok(()) => report.liquidated.push(vault_id),
+        Err(error) => report.liquidation_failures.push((vault_id, error))
+      }
+    }
+
+    Ok(report)
+  }
+
+  // Runs `ticks` ticks, or forever when unset, passing each tick's number and
+  // outcome to `on_tick`
+  pub async fn run<F>(&mut self, ticks: Option<u64>, mut on_tick: F)
+  where
+    F: FnMut(u64, Result<KeeperReport, YamaError>)
+  {
+    let mut tick: u64 = 0;
+    while ticks.map_or(true, |ticks| tick < ticks) {
+      on_tick(tick, self.tick().await);
+      tick += 1;
+      task::sleep(self.config.poll_interval).await;
+    }
+  }
+}
@@ -1,25 +1,23 @@
-use tokio::sync::Mutex;
-use lazy_static::lazy_static;
-use fuels::{prelude::*, tx::ContractId, types::{
-  Identity,
-  Bits256
-}, signers::fuel_crypto::coins_bip32::enc::Test};
+use fuels::{prelude::*, tx::ContractId, types::Bits256};
 use crate::{
+    errors::{
+        register_contract,
+        YamaContract,
+        YamaError
+    },
     abigen::*,
+    storage::StorageLayout,
     utils::{
-        set_storage_val, test_deploy
+        get_test_wallet
     },
-    modulartoken::{
-        get_cid, init_modulartoken
+    modulartoken::get_cid,
+    deploy::{
+        deploy,
+        DeploymentPlan
     },
-    flashmintmodule::init_custom_flashmintmodule, balancesheet, testswapper::init_custom_testswapper, cdp::get_collateral_manager
+    cdp::get_collateral_manager
 };
 
-
-lazy_static! {
-    static ref MUTEX: Mutex<i32> = Mutex::new(0i32);
-}
-
 pub async fn init_lproxy() -> (
     WalletUnlocked,
     ModularToken,
@@ -28,49 +26,19 @@ pub async fn init_lproxy() -> (
     CDP,
     LeverageProxy
 ) {
-    let (
-        _,
-        wallet,
-        stable_i,
-        collat_i,
-        _,
-        _,
-        _,
-        _,
-        cdp,
-        _
-    ) = test_deploy().await;
-    let flashmintmodule
-        = init_custom_flashmintmodule(&wallet, &stable_i).await;
-    let lproxy
-        = init_custom_lproxy(
-            &wallet,
-            &stable_i,
-            &ContractId::from(flashmintmodule.get_contract_id()),
-            &ContractId::from(cdp.get_contract_id())
-        ).await;
-
-    let swapper: TestSwapper = init_custom_testswapper(
-        &wallet,
-        &stable_i,
-        &collat_i
-    ).await;
-
-    set_collateral_type_config(
-        &lproxy,
-        stable_i.get_contract_id(),
-        0,
-        &ContractId::from(collat_i.get_contract_id()),
-        &ContractId::from(swapper.get_contract_id())
-    ).await;
+    let wallet: WalletUnlocked = get_test_wallet().await;
+    let deployment = deploy(&wallet, &DeploymentPlan {
+        leverage_proxy: true,
+        ..DeploymentPlan::default()
+    }).await.unwrap();
 
     (
-        wallet,
-        stable_i,
-        collat_i,
-        flashmintmodule,
-        cdp,
-        lproxy
+        deployment.wallet,
+        deployment.stable_i,
+        deployment.collat_i,
+        deployment.flashmintmodule.unwrap(),
+        deployment.cdp,
+        deployment.lproxy.unwrap()
     )
 }
 
@@ -79,42 +47,24 @@ pub async fn init_custom_lproxy(
     stable_i: &ModularToken,
     flash_mint_module: &ContractId,
     cdp_module: &ContractId,
-) -> LeverageProxy {
+) -> Result<LeverageProxy, YamaError> {
     let storage_path = "../leverageproxy/out/debug/leverageproxy-storage_slots.json";
 
-    let guard = MUTEX.lock().await;
-
-    // Create a Mutex to synchronize access to the storage slots
-    set_storage_val(
-        &storage_path,
-        "storage_0",
-        &get_cid(stable_i).to_string()
-    );
-    set_storage_val(
-        &storage_path,
-        "storage_1",
-        &flash_mint_module.to_string()
-    );
-    set_storage_val(
-        &storage_path,
-        "storage_2",
-        &cdp_module.to_string()
-    );
+    let storage = StorageLayout::new()
+        .field(0, &get_cid(stable_i))
+        .field(1, flash_mint_module)
+        .field(2, cdp_module);
     let id = Contract::deploy(
         "../leverageproxy/out/debug/leverageproxy.bin",
         &wallet,
         TxParameters::default(),
-        StorageConfiguration::with_storage_path(Some(
-            storage_path.to_string()
-        ))
+        storage.configuration(storage_path)
     )
-    .await
-    .unwrap();
-
-    drop(guard);
+    .await?;
 
+    register_contract(&id, YamaContract::LeverageProxy);
     let lproxy = LeverageProxy::new(id, wallet.clone());
-    lproxy
+    Ok(lproxy)
 }
 
 pub async fn set_collateral_type_config(
@@ -123,7 +73,7 @@ pub async fn set_collateral_type_config(
     collateral_type_id: u64,
     collateral: &ContractId,
     swapper: &ContractId,
-) {
+) -> Result<(), YamaError> {
     instance
         .methods()
         .set_collateral_type_config(
@@ -133,8 +83,8 @@ pub async fn set_collateral_type_config(
         )
         .set_contract_ids(&[stable_i.clone()])
         .call()
-        .await
-        .unwrap();
+        .await?;
+    Ok(())
 }
 
 pub async fn create_vault(
@@ -143,26 +93,25 @@ pub async fn create_vault(
     collateral_type_id: u64,
     collateral_amount: u64,
     cdp: &CDP,
-) -> u64 {
+) -> Result<u64, YamaError> {
     let call_params = CallParameters::new(
         Some(collateral_amount),
         Some(AssetId::new(*ContractId::from(collat_i.clone()))),
         None
     );
     
-    instance
+    Ok(instance
         .methods()
         .create_vault(collateral_type_id)
         .call_params(call_params)
         .set_contract_ids(&[
             collat_i.clone(),
-            get_collateral_manager(cdp).await,
+            get_collateral_manager(cdp).await?,
             cdp.get_contract_id().clone()
         ])
         .call()
-        .await
-        .unwrap()
-        .value
+        .await?
+        .value)
 }
 
 pub async fn leverage_up(
@@ -172,18 +121,41 @@ pub async fn leverage_up(
     min_collat_swapped: u64,
     cdp: &CDP,
     flash_mint_module: &Bech32ContractId
-) {
+) -> Result<(), YamaError> {
     instance
         .methods()
         .leverage_up(vault_id, yama_borrowed, min_collat_swapped)
         .set_contract_ids(&[
             flash_mint_module.clone(),
-            get_collateral_manager(cdp).await,
+            get_collateral_manager(cdp).await?,
             cdp.get_contract_id().clone()
         ])
         .call()
-        .await
-        .unwrap();
+        .await?;
+    Ok(())
+}
+
+// leverage_up for when the callback needs more than the CDP: the swapper,
+// both tokens, the price source and the balance sheet chain
+pub async fn leverage_up_with_contracts(
+    instance: &LeverageProxy,
+    vault_id: u64,
+    yama_borrowed: u64,
+    min_collat_swapped: u64,
+    contract_ids: &[Bech32ContractId]
+) -> Result<(), YamaError> {
+    let tx_params = TxParameters::new(
+        None, Some(64_000_000), None);
+
+    instance
+        .methods()
+        .leverage_up(vault_id, yama_borrowed, min_collat_swapped)
+        .set_contract_ids(contract_ids)
+        .append_variable_outputs(1)
+        .tx_params(tx_params)
+        .call()
+        .await?;
+    Ok(())
 }
 
 pub async fn leverage_down(
@@ -193,18 +165,18 @@ pub async fn leverage_down(
     min_yama_repaid: u64,
     cdp: &CDP,
     flash_mint_module: &Bech32ContractId
-) {
+) -> Result<(), YamaError> {
     instance
         .methods()
         .leverage_down(vault_id, collat_sold, min_yama_repaid)
         .set_contract_ids(&[
             flash_mint_module.clone(),
-            get_collateral_manager(cdp).await,
+            get_collateral_manager(cdp).await?,
             cdp.get_contract_id().clone()
         ])
         .call()
-        .await
-        .unwrap();
+        .await?;
+    Ok(())
 }
 pub async fn leverage_down_all(
     instance: &LeverageProxy,
@@ -212,16 +184,16 @@ pub async fn leverage_down_all(
     min_yama_repaid: u64,
     cdp: &CDP,
     flash_mint_module: &Bech32ContractId
-) {
+) -> Result<(), YamaError> {
     instance
         .methods()
         .leverage_down_all(vault_id, min_yama_repaid)
         .set_contract_ids(&[
             flash_mint_module.clone(),
-            get_collateral_manager(cdp).await,
+            get_collateral_manager(cdp).await?,
             cdp.get_contract_id().clone()
         ])
         .call()
-        .await
-        .unwrap();
+        .await?;
+    Ok(())
 }
\ No newline at end of file
@@ -1,14 +1,34 @@
 pub mod abigen;
 pub mod utils;
+pub mod ufp128;
+pub mod interest;
+pub mod risk;
+pub mod storage;
+pub mod errors;
+pub mod events;
+pub mod indexer;
 pub mod modulartoken;
 pub mod cdp;
 pub mod psm;
 pub mod emptycollateralmanager;
 pub mod dutchauctionliquidator;
+pub mod surplusauctionmodule;
 pub mod balancesheet;
 pub mod psmpricesource;
+pub mod mockpricesource;
+pub mod aggregatorpricesource;
+pub mod mockreservestrategy;
 pub mod simplebsh;
+pub mod splitbsh;
 pub mod psmlockup;
 pub mod flashmintmodule;
 pub mod leverageproxy;
-pub mod testswapper;
\ No newline at end of file
+pub mod testswapper;
+pub mod bridgemodule;
+pub mod deploy;
+pub mod client;
+pub mod clock;
+pub mod keeper;
+pub mod bidder;
+pub mod simulator;
+pub mod fuzz;
\ No newline at end of file
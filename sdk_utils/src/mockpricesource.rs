@@ -0,0 +1,202 @@
+use fuels::{prelude::*, types::Identity};
+use crate::{
+  abigen::*,
+  clock::Clock,
+  errors::{
+    register_contract,
+    YamaContract,
+    YamaError
+  },
+  simulator::Action,
+  storage::StorageLayout,
+  ufp128::Ufp128,
+  utils::get_id
+};
+
+// Owned by `wallet`, so it can set the price
+pub async fn init_mockpricesource(
+  wallet: &WalletUnlocked,
+  price: &UFP128
+) -> Result<MockPriceSource, YamaError> {
+  let storage_path
+    = "../mockpricesource/out/debug/mockpricesource-storage_slots.json";
+  let storage = StorageLayout::new()
+    .field(0, &get_id(wallet))
+    .field(1, price);
+
+  let id = Contract::deploy(
+    "../mockpricesource/out/debug/mockpricesource.bin",
+    wallet,
+    TxParameters::default(),
+    storage.configuration(storage_path)
+  )
+  .await?;
+
+  register_contract(&id, YamaContract::MockPriceSource);
+  Ok(MockPriceSource::new(id, wallet.clone()))
+}
+
+// Stamped with the current block time
+pub async fn set_price(
+  instance: &MockPriceSource,
+  price: &UFP128
+) -> Result<(), YamaError> {
+  instance
+    .methods()
+    .set_price(price.clone(), None)
+    .call()
+    .await?;
+  Ok(())
+}
+
+// Stamped with `updated_at` instead, e.g. to make the price stale
+pub async fn set_price_at(
+  instance: &MockPriceSource,
+  price: &UFP128,
+  updated_at: u64
+) -> Result<(), YamaError> {
+  instance
+    .methods()
+    .set_price(price.clone(), Some(updated_at))
+    .call()
+    .await?;
+  Ok(())
+}
+
+pub async fn set_owner(
+  instance: &MockPriceSource,
+  owner: Identity
+) -> Result<(), YamaError> {
+  instance
+    .methods()
+    .set_owner(owner)
+    .call()
+    .await?;
+  Ok(())
+}
+
+pub async fn get_price(
+  instance: &MockPriceSource
+) -> Result<UFP128, YamaError> {
+  Ok(instance
+    .methods()
+    .price()
+    .simulate()
+    .await?
+    .value)
+}
+
+pub async fn get_updated_at(
+  instance: &MockPriceSource
+) -> Result<u64, YamaError> {
+  Ok(instance
+    .methods()
+    .updated_at()
+    .simulate()
+    .await?
+    .value)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PriceStep {
+  // Seconds after the previous step
+  pub delay: u64,
+  pub price: Ufp128
+}
+
+// Prices to set one after another, e.g. to model a market crash
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PricePath {
+  pub steps: Vec<PriceStep>
+}
+
+impl PricePath {
+  pub fn new() -> Self {
+    PricePath { steps: vec![] }
+  }
+
+  pub fn then(mut self, delay: u64, price: Ufp128) -> Self {
+    self.steps.push(PriceStep { delay, price });
+    self
+  }
+
+  // Each price `interval` seconds after the previous, e.g. a series from
+  // simulator::crash_price_path
+  pub fn from_prices(prices: &[Ufp128], interval: u64) -> Self {
+    prices
+      .iter()
+      .fold(PricePath::new(), |path, price| path.then(interval, *price))
+  }
+
+  // `steps` prices `interval` seconds apart, moving from `from` to `to` by
+  // the same amount each step. `from` itself isn't set.
+  pub fn linear(from: Ufp128, to: Ufp128, steps: u64, interval: u64) -> Self {
+    let (from, to) = (from.bits() as i128, to.bits() as i128);
+    (1..=steps).fold(PricePath::new(), |path, step| {
+      let bits = from + (to - from) * step as i128 / steps as i128;
+      path.then(interval, Ufp128::from_bits(bits as u128))
+    })
+  }
+
+  // Like linear, but moving by the same ratio each step, as a crash of a
+  // given percentage per interval would
+  pub fn geometric(
+    from: Ufp128,
+    to: Ufp128,
+    steps: u64,
+    interval: u64
+  ) -> Self {
+    let ratio = (to.to_f64() / from.to_f64()).powf(1.0 / steps as f64);
+    (1..=steps).fold(PricePath::new(), |path, step| {
+      let price = if step == steps {
+        to
+      } else {
+        Ufp128::from_f64(from.to_f64() * ratio.powi(step as i32))
+          .unwrap_or(to)
+      };
+      path.then(interval, price)
+    })
+  }
+
+  // Appends `other`'s steps after this path's
+  pub fn chain(mut self, other: &PricePath) -> Self {
+    self.steps.extend(other.steps.iter().copied());
+    self
+  }
+
+  // Seconds from the start of the path to its last step
+  pub fn duration(&self) -> u64 {
+    self.steps.iter().map(|step| step.delay).sum()
+  }
+
+  pub fn last_price(&self) -> Option<Ufp128> {
+    self.steps.last().map(|step| step.price)
+  }
+
+  // The same path for the Simulator
+  pub fn actions(&self) -> Vec<Action> {
+    let mut actions: Vec<Action> = vec![];
+    for step in self.steps.iter() {
+      if step.delay > 0 {
+        actions.push(Action::AdvanceTime(step.delay));
+      }
+      actions.push(Action::SetPrice(step.price));
+    }
+    actions
+  }
+}
+
+// Advances the chain through each step of `path` and sets its price
+pub async fn follow_price_path(
+  instance: &MockPriceSource,
+  clock: &Clock,
+  path: &PricePath
+) -> Result<(), YamaError> {
+  for step in path.steps.iter() {
+    if step.delay > 0 {
+      clock.advance(step.delay).await?;
+    }
+    set_price(instance, &step.price.into()).await?;
+  }
+  Ok(())
+}
@@ -0,0 +1,74 @@
+use fuels::{prelude::*, tx::ContractId, types::Bits256};
+use crate::{
+  abigen::*,
+  errors::{
+    register_contract,
+    YamaContract,
+    YamaError
+  },
+  storage::StorageLayout
+};
+
+// Only `psm` can withdraw from it. It still has to be allowed with
+// psm::set_strategy before reserves can be allocated to it.
+pub async fn init_mockreservestrategy(
+  wallet: &WalletUnlocked,
+  psm: &ContractId
+) -> Result<MockReserveStrategy, YamaError> {
+  let storage_path
+    = "../mockreservestrategy/out/debug/mockreservestrategy-storage_slots.json";
+  let storage = StorageLayout::new().field(0, psm);
+
+  let id = Contract::deploy(
+    "../mockreservestrategy/out/debug/mockreservestrategy.bin",
+    wallet,
+    TxParameters::default(),
+    storage.configuration(storage_path)
+  )
+  .await?;
+
+  register_contract(&id, YamaContract::MockReserveStrategy);
+  Ok(MockReserveStrategy::new(id, wallet.clone()))
+}
+
+// Sends `amount` of `token` from the wallet, to be reported as yield
+pub async fn add_yield(
+  instance: &MockReserveStrategy,
+  token: &AssetId,
+  amount: u64
+) -> Result<(), YamaError> {
+  instance
+    .methods()
+    .add_yield()
+    .call_params(CallParameters::new(Some(amount), Some(*token), None))
+    .call()
+    .await?;
+  Ok(())
+}
+
+// Sends `amount` of `token` to the wallet, to be reported as a loss
+pub async fn lose(
+  instance: &MockReserveStrategy,
+  token: &AssetId,
+  amount: u64
+) -> Result<(), YamaError> {
+  instance
+    .methods()
+    .lose(Bits256(**token), amount)
+    .append_variable_outputs(1)
+    .call()
+    .await?;
+  Ok(())
+}
+
+pub async fn get_balance(
+  instance: &MockReserveStrategy,
+  token: &ContractId
+) -> Result<u64, YamaError> {
+  Ok(instance
+    .methods()
+    .balance(Bits256(**token))
+    .simulate()
+    .await?
+    .value)
+}
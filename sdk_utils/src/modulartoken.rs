@@ -4,7 +4,13 @@ use fuels::{
     types::Identity
 };
 use crate::{
-    utils::{get_test_wallet, set_storage_val},
+    errors::{
+        register_contract,
+        YamaContract,
+        YamaError
+    },
+    utils::get_test_wallet,
+    storage::StorageLayout,
     abigen::*
 };
 use rand::{
@@ -12,16 +18,11 @@ use rand::{
     rngs::OsRng,
     prelude::{Rng, SeedableRng, StdRng}
 };
-use lazy_static::lazy_static;
-use tokio::sync::Mutex;
-
-lazy_static! {
-    static ref MUTEX: Mutex<i32> = Mutex::new(0i32);
-}
 
 pub async fn init_modulartoken() -> (ModularToken, WalletUnlocked) {
     let wallet: WalletUnlocked = get_test_wallet().await;
-    let instance: ModularToken = init_custom_modulartoken(&wallet).await;
+    let instance: ModularToken
+        = init_custom_modulartoken(&wallet).await.unwrap();
     (
         instance,
         wallet
@@ -30,91 +31,83 @@ pub async fn init_modulartoken() -> (ModularToken, WalletUnlocked) {
 
 pub async fn init_custom_modulartoken(
     wallet: &WalletUnlocked
-) -> ModularToken {
+) -> Result<ModularToken, YamaError> {
     // Launch a local network and deploy the contract
     let mut seed = [0u8; 32];
     OsRng.fill_bytes(&mut seed);
     let mut rng = StdRng::from_seed(seed);
     let salt: [u8; 32] = rng.gen();
 
-    // Find and set the value for sha256("storage_2_1") in the JSON
+    // The wallet becomes the initial owner
     let storage_path = "../modulartoken/out/debug/modulartoken-storage_slots.json";
-
-
-    let guard = MUTEX.lock().await;
-    set_storage_val(
-        &storage_path,
-        "storage_2_1",
-        &wallet.address().hash().to_string()
-    );
+    let storage = StorageLayout::new()
+        .field(2, &Identity::Address(Address::from(*wallet.address().hash())));
 
     let id = Contract::deploy_with_parameters(
         "../modulartoken/out/debug/modulartoken.bin",
         &wallet,
         TxParameters::default(),
-        StorageConfiguration::with_storage_path(Some(
-            storage_path.to_string()
-        )),
+        storage.configuration(storage_path),
         Salt::from(salt)
     )
-    .await
-    .unwrap();
-
-    drop(guard);
+    .await?;
 
+    register_contract(&id, YamaContract::ModularToken);
     let instance = ModularToken::new(id.clone(), wallet.clone());
     
-    init_allowlist(&instance).await;
+    init_allowlist(&instance).await?;
 
-    instance
+    Ok(instance)
 }
 
-pub async fn get_allowlist(instance: &ModularToken, id: Identity) -> bool {
-    instance
+pub async fn get_allowlist(
+    instance: &ModularToken,
+    id: Identity
+) -> Result<bool, YamaError> {
+    Ok(instance
         .methods()
         .get_allowlist(id)
         .call()
-        .await
-        .unwrap()
-        .value
+        .await?
+        .value)
 }
 
 pub async fn mint(
   instance: &ModularToken,
   wallet: &WalletUnlocked,
   amount: u64
-) {
+) -> Result<(), YamaError> {
   instance
       .methods()
       .mint(amount, Identity::Address(wallet.address().into()))
       .append_variable_outputs(1)
       .call()
-      .await
-      .unwrap();
+      .await?;
+  Ok(())
 }
 
 pub async fn init_allowlist(
     instance: &ModularToken
-) {
+) -> Result<(), YamaError> {
     instance
         .methods()
         .init_allowlist()
         .call()
-        .await
-        .unwrap();
+        .await?;
+    Ok(())
 }
 
 pub async fn set_allowlist(
     instance: &ModularToken,
     id: Identity,
     value: bool
-) {
+) -> Result<(), YamaError> {
     instance
         .methods()
         .set_allowlist(id, value)
         .call()
-        .await
-        .unwrap();
+        .await?;
+    Ok(())
 }
 
 pub fn get_cid(contract: &ModularToken) -> ContractId {
@@ -1,10 +1,16 @@
-use tokio::sync::Mutex;
-use lazy_static::lazy_static;
-use fuels::{prelude::*, tx::ContractId, types::Identity};
+use fuels::{prelude::*, tx::ContractId, types::{Bits256, Identity}};
 use crate::{
+  errors::{
+    register_contract,
+    YamaContract,
+    YamaError
+  },
+  simulator::{
+      convert_amount,
+      PSM_FEE_DENOMINATOR
+  },
+  storage::StorageLayout,
   utils::{
-      set_storage_val,
-      get_storage_val,
       get_test_wallet
   },
   modulartoken::{
@@ -12,13 +18,10 @@ use crate::{
       set_allowlist,
       get_cid
   },
+  balancesheet::resolve_handler,
   abigen::*
 };
 
-lazy_static! {
-    static ref MUTEX: Mutex<i32> = Mutex::new(0i32);
-}
-
 pub async fn init_psm() -> (
     PSM,
     WalletUnlocked,
@@ -27,10 +30,11 @@ pub async fn init_psm() -> (
 ) {
     let wallet: WalletUnlocked = get_test_wallet().await;
     let stable_i: ModularToken
-        = init_custom_modulartoken(&wallet).await;
+        = init_custom_modulartoken(&wallet).await.unwrap();
     let ext_i: ModularToken
-        = init_custom_modulartoken(&wallet).await;
-    let psm: PSM = init_custom_psm(&wallet, &stable_i, &ext_i).await;
+        = init_custom_modulartoken(&wallet).await.unwrap();
+    let psm: PSM
+        = init_custom_psm(&wallet, &stable_i, &ext_i).await.unwrap();
 
     (
         psm,
@@ -44,56 +48,50 @@ pub async fn init_custom_psm(
     wallet: &WalletUnlocked,
     stable_i: &ModularToken,
     ext_i: &ModularToken
-) -> PSM {
+) -> Result<PSM, YamaError> {
+    init_configured_psm(wallet, stable_i, ext_i, None, None).await
+}
+
+// Deploys a PSM, overriding the debt ceiling and the external stablecoin's
+// decimals when they're given
+pub async fn init_configured_psm(
+    wallet: &WalletUnlocked,
+    stable_i: &ModularToken,
+    ext_i: &ModularToken,
+    debt_ceiling: Option<u64>,
+    external_stable_decimals: Option<u8>
+) -> Result<PSM, YamaError> {
     let storage_path = "../pegstabilitymodule/out/debug/pegstabilitymodule-storage_slots.json";
-    
-    let guard = MUTEX.lock().await;
-
-    // Create a Mutex to synchronize access to the storage slots
-    set_storage_val(
-        &storage_path,
-        "storage_0",
-        &get_cid(&stable_i).to_string()
-    );
-
-    set_storage_val(
-        &storage_path,
-        "storage_1",
-        &get_cid(&ext_i).to_string()
-    );
+
+    let mut storage = StorageLayout::new()
+        .field(0, &get_cid(&stable_i))
+        .field(1, &get_cid(&ext_i));
+    if let Some(debt_ceiling) = debt_ceiling {
+        storage = storage.field(2, &debt_ceiling);
+    }
+    if let Some(external_stable_decimals) = external_stable_decimals {
+        storage = storage.field(4, &external_stable_decimals);
+    }
 
     let id = Contract::deploy(
         "../pegstabilitymodule/out/debug/pegstabilitymodule.bin",
         &wallet,
         TxParameters::default(),
-        StorageConfiguration::with_storage_path(Some(
-            storage_path.to_string()
-        )),
+        storage.configuration(storage_path),
     )
-    .await
-    .unwrap();
-
-    assert_eq!(
-        get_storage_val(&storage_path, "storage_0").unwrap(),
-        get_cid(&stable_i).to_string()
-    );
-    assert_eq!(
-        get_storage_val(&storage_path, "storage_1").unwrap(),
-        get_cid(&ext_i).to_string()
-    );
-
-    drop(guard);
-
+    .await?;
 
+    register_contract(&id, YamaContract::PSM);
     let instance = PSM::new(id.clone(), wallet.clone());
     set_allowlist(&stable_i, Identity::ContractId(id.clone().into()), true)
-        .await;
+        .await?;
 
-    instance
+    Ok(instance)
 }
+
 pub async fn deposit(
     instance: &PSM, stable_id: &ContractId, ext: &AssetId, amount: u64
-) {
+) -> Result<(), YamaError> {
     instance
         .methods()
         .deposit()
@@ -102,29 +100,368 @@ pub async fn deposit(
             Some(ext.clone()),
             None))
         .append_variable_outputs(1)
-        .set_contract_ids(&[Bech32ContractId::from(*stable_id)])
+        .set_contract_ids(&contract_ids(instance, stable_id).await?)
         .call()
-        .await
-        .unwrap();
+        .await?;
+    Ok(())
 }
 
-
+// Pays out `token`, which has to be the primary token or one configured with
+// set_token_config
 pub async fn withdraw(
     instance: &PSM,
     stable_id: &ContractId,
     stablecoin: &AssetId,
+    token: &AssetId,
     amount: u64
-) {
+) -> Result<(), YamaError> {
     instance
         .methods()
-        .withdraw()
+        .withdraw(Bits256(**token))
         .call_params(CallParameters::new(
             Some(amount),
             Some(stablecoin.clone()),
             None))
         .append_variable_outputs(1)
+        .set_contract_ids(&contract_ids(instance, stable_id).await?)
+        .call()
+        .await?;
+    Ok(())
+}
+
+// Adds another external stablecoin, or reconfigures one
+pub async fn set_token_config(
+    instance: &PSM,
+    stable_id: &ContractId,
+    token: &ContractId,
+    decimals: u8,
+    debt_ceiling: u64,
+    enabled: bool
+) -> Result<(), YamaError> {
+    instance
+        .methods()
+        .set_token_config(Bits256(**token), decimals, debt_ceiling, enabled)
+        .set_contract_ids(&[Bech32ContractId::from(*stable_id)])
+        .call()
+        .await?;
+    Ok(())
+}
+
+pub async fn get_token_config(
+    instance: &PSM,
+    token: &ContractId
+) -> Result<TokenConfig, YamaError> {
+    Ok(instance
+        .methods()
+        .get_token_config(Bits256(**token))
+        .simulate()
+        .await?
+        .value)
+}
+
+// The primary token first, then the others in the order they were added
+pub async fn get_tokens(instance: &PSM) -> Result<Vec<ContractId>, YamaError> {
+    let count = instance
+        .methods()
+        .get_token_count()
+        .simulate()
+        .await?
+        .value;
+    let mut tokens: Vec<ContractId> = vec![];
+    for index in 0..count {
+        let token = instance
+            .methods()
+            .get_token_at(index)
+            .simulate()
+            .await?
+            .value;
+        tokens.push(ContractId::new(token.0));
+    }
+    Ok(tokens)
+}
+
+// Fees are booked through the balance sheet, whose handler mints to its
+// target or recipients, so deposits and withdrawals call into all of them
+// once it's set
+async fn contract_ids(
+    instance: &PSM,
+    stable_id: &ContractId
+) -> Result<Vec<Bech32ContractId>, YamaError> {
+    let mut ids = vec![Bech32ContractId::from(*stable_id)];
+    let balancesheet = get_balancesheet_module(instance).await?;
+    if balancesheet == ContractId::zeroed() {
+        return Ok(ids);
+    }
+    let wallet = instance.get_wallet();
+    let handler = BalanceSheet::new(
+        Bech32ContractId::from(balancesheet), wallet.clone())
+        .methods()
+        .get_handler()
+        .simulate()
+        .await?
+        .value;
+    ids.push(Bech32ContractId::from(balancesheet));
+    if handler.0 != [0u8; 32] {
+        let handler = Bech32ContractId::from(ContractId::new(handler.0));
+        let (_, targets) = resolve_handler(&handler, &wallet).await?;
+        ids.push(handler);
+        ids.extend(targets);
+    }
+    Ok(ids)
+}
+
+pub async fn set_balancesheet_module(
+    instance: &PSM,
+    stable_id: &ContractId,
+    balancesheet: &ContractId
+) -> Result<(), YamaError> {
+    instance
+        .methods()
+        .set_balancesheet_module(Bits256(**balancesheet))
+        .set_contract_ids(&[Bech32ContractId::from(*stable_id)])
+        .call()
+        .await?;
+    Ok(())
+}
+
+pub async fn get_balancesheet_module(
+    instance: &PSM
+) -> Result<ContractId, YamaError> {
+    let balancesheet = instance
+        .methods()
+        .get_balancesheet_module()
+        .simulate()
+        .await?
+        .value;
+    Ok(ContractId::new(balancesheet.0))
+}
+
+pub async fn set_strategy(
+    instance: &PSM,
+    stable_id: &ContractId,
+    strategy: &ContractId,
+    allowed: bool
+) -> Result<(), YamaError> {
+    instance
+        .methods()
+        .set_strategy(Bits256(**strategy), allowed)
+        .set_contract_ids(&[Bech32ContractId::from(*stable_id)])
+        .call()
+        .await?;
+    Ok(())
+}
+
+// In basis points of each token's reserves
+pub async fn set_max_allocation(
+    instance: &PSM,
+    stable_id: &ContractId,
+    max_allocation: u64
+) -> Result<(), YamaError> {
+    instance
+        .methods()
+        .set_max_allocation(max_allocation)
+        .set_contract_ids(&[Bech32ContractId::from(*stable_id)])
+        .call()
+        .await?;
+    Ok(())
+}
+
+pub async fn allocate(
+    instance: &PSM,
+    stable_id: &ContractId,
+    strategy: &ContractId,
+    token: &ContractId,
+    amount: u64
+) -> Result<(), YamaError> {
+    instance
+        .methods()
+        .allocate(Bits256(**strategy), Bits256(**token), amount)
+        .set_contract_ids(&[
+            Bech32ContractId::from(*stable_id),
+            Bech32ContractId::from(*strategy)
+        ])
+        .call()
+        .await?;
+    Ok(())
+}
+
+pub async fn recall(
+    instance: &PSM,
+    stable_id: &ContractId,
+    strategy: &ContractId,
+    token: &ContractId,
+    amount: u64
+) -> Result<(), YamaError> {
+    instance
+        .methods()
+        .recall(Bits256(**strategy), Bits256(**token), amount)
+        .set_contract_ids(&[
+            Bech32ContractId::from(*stable_id),
+            Bech32ContractId::from(*strategy)
+        ])
+        .call()
+        .await?;
+    Ok(())
+}
+
+// Books the strategy's gain or loss since its last report
+pub async fn report(
+    instance: &PSM,
+    stable_id: &ContractId,
+    strategy: &ContractId,
+    token: &ContractId
+) -> Result<(), YamaError> {
+    let mut ids = contract_ids(instance, stable_id).await?;
+    ids.push(Bech32ContractId::from(*strategy));
+    instance
+        .methods()
+        .report(Bits256(**strategy), Bits256(**token))
+        .set_contract_ids(&ids)
+        .call()
+        .await?;
+    Ok(())
+}
+
+pub async fn get_allocation(
+    instance: &PSM,
+    strategy: &ContractId,
+    token: &ContractId
+) -> Result<u64, YamaError> {
+    Ok(instance
+        .methods()
+        .get_allocation(Bits256(**strategy), Bits256(**token))
+        .simulate()
+        .await?
+        .value)
+}
+
+// Held by the PSM and its strategies
+pub async fn get_reserves(
+    instance: &PSM,
+    token: &ContractId
+) -> Result<u64, YamaError> {
+    Ok(instance
+        .methods()
+        .get_reserves(Bits256(**token))
+        .simulate()
+        .await?
+        .value)
+}
+
+// tin and tout are in basis points of the YSS deposited or withdrawn
+pub async fn set_fees(
+    instance: &PSM,
+    stable_id: &ContractId,
+    tin: u64,
+    tout: u64
+) -> Result<(), YamaError> {
+    instance
+        .methods()
+        .set_fees(tin, tout)
         .set_contract_ids(&[Bech32ContractId::from(*stable_id)])
         .call()
-        .await
-        .unwrap();
+        .await?;
+    Ok(())
+}
+
+// (tin, tout)
+pub async fn get_fees(instance: &PSM) -> Result<(u64, u64), YamaError> {
+    let tin = instance.methods().get_tin().simulate().await?.value;
+    let tout = instance.methods().get_tout().simulate().await?.value;
+    Ok((tin, tout))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Quote {
+    // What the caller receives
+    pub amount: u64,
+    // YSS booked as surplus
+    pub fee: u64
+}
+
+// What deposit mints for `amount` of a token with `decimals`, rounded as the
+// contract rounds it. None where the contract would revert on overflow.
+pub fn quote_deposit_amount(
+    amount: u64,
+    decimals: u8,
+    yss_decimals: u8,
+    tin: u64
+) -> Option<Quote> {
+    let gross_amount = convert_amount(amount, decimals, yss_decimals)?;
+    let fee = gross_amount.checked_mul(tin)? / PSM_FEE_DENOMINATOR;
+    Some(Quote { amount: gross_amount - fee, fee })
+}
+
+// What withdraw pays out of a token with `decimals` for `amount` of YSS
+pub fn quote_withdraw_amount(
+    amount: u64,
+    decimals: u8,
+    yss_decimals: u8,
+    tout: u64
+) -> Option<Quote> {
+    let fee = amount.checked_mul(tout)? / PSM_FEE_DENOMINATOR;
+    let ext_amount = convert_amount(amount - fee, yss_decimals, decimals)?;
+    Some(Quote { amount: ext_amount, fee })
+}
+
+// What the PSM reports deposit would mint, e.g. to check quote_deposit
+pub async fn preview_deposit(
+    instance: &PSM,
+    token: &ContractId,
+    amount: u64
+) -> Result<u64, YamaError> {
+    Ok(instance
+        .methods()
+        .preview_deposit(Bits256(**token), amount)
+        .simulate()
+        .await?
+        .value)
+}
+
+pub async fn preview_withdraw(
+    instance: &PSM,
+    token: &ContractId,
+    amount: u64
+) -> Result<u64, YamaError> {
+    Ok(instance
+        .methods()
+        .preview_withdraw(Bits256(**token), amount)
+        .simulate()
+        .await?
+        .value)
+}
+
+// quote_deposit_amount with the PSM's current config for `token`
+pub async fn quote_deposit(
+    instance: &PSM,
+    token: &ContractId,
+    amount: u64
+) -> Result<Quote, YamaError> {
+    let config = get_token_config(instance, token).await?;
+    let yss_decimals = instance
+        .methods()
+        .get_yss_decimals()
+        .simulate()
+        .await?
+        .value;
+    let (tin, _) = get_fees(instance).await?;
+    quote_deposit_amount(amount, config.decimals, yss_decimals, tin)
+        .ok_or_else(|| YamaError::QuoteOverflow("deposit".to_string()))
+}
+
+pub async fn quote_withdraw(
+    instance: &PSM,
+    token: &ContractId,
+    amount: u64
+) -> Result<Quote, YamaError> {
+    let config = get_token_config(instance, token).await?;
+    let yss_decimals = instance
+        .methods()
+        .get_yss_decimals()
+        .simulate()
+        .await?
+        .value;
+    let (_, tout) = get_fees(instance).await?;
+    quote_withdraw_amount(amount, config.decimals, yss_decimals, tout)
+        .ok_or_else(|| YamaError::QuoteOverflow("withdraw".to_string()))
 }
\ No newline at end of file
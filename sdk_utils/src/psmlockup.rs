@@ -1,63 +1,49 @@
-use fuels::prelude::*;
+use fuels::{prelude::*, types::Bits256};
 use crate::{
+    errors::{
+        register_contract,
+        YamaContract,
+        YamaError
+    },
+    storage::StorageLayout,
     utils::{
-        set_storage_val,
         get_test_wallet
     },
     modulartoken::{
         get_cid,
         init_custom_modulartoken
     },
-    psm::init_custom_psm,
+    psm::{
+        init_custom_psm,
+        quote_deposit
+    },
+    ufp128::Ufp128,
+    utils::i256_to_i128,
     abigen::*,
 };
-use tokio::sync::Mutex;
-use lazy_static::lazy_static;
-
-lazy_static! {
-    static ref MUTEX: Mutex<i32> = Mutex::new(0i32);
-}
-
 pub async fn init_custom_psmlockup(
     wallet: &WalletUnlocked,
     stable_i: &ModularToken,
     token: &ContractId,
     psm: &ContractId
-) -> PSMLockup {
+) -> Result<PSMLockup, YamaError> {
     let storage_path = "../psmlockup/out/debug/psmlockup-storage_slots.json";
 
-    let guard = MUTEX.lock().await;
-
-    set_storage_val(
-        &storage_path,
-        "storage_0",
-        &get_cid(stable_i).to_string()
-    );
-
-    set_storage_val(
-        &storage_path,
-        "storage_1",
-        &token.to_string()
-    );
-
-    set_storage_val(
-        &storage_path,
-        "storage_2",
-        &psm.to_string()
-    );
+    let storage = StorageLayout::new()
+        .field(0, &get_cid(stable_i))
+        .field(1, token)
+        .field(2, psm);
 
     let id = Contract::deploy(
         "../psmlockup/out/debug/psmlockup.bin",
         &wallet,
         TxParameters::default(),
-        StorageConfiguration::with_storage_path(Some(
-            storage_path.to_string()
-        ))
+        storage.configuration(storage_path)
     )
-    .await
-    .unwrap();
+    .await?;
 
-    PSMLockup::new(id, wallet.clone())
+    register_contract(&id, YamaContract::PSMLockup);
+    Ok(PSMLockup::new(id, wallet.clone()))
 }
 
 pub async fn init_psmlockup() -> (
@@ -68,14 +54,16 @@ pub async fn init_psmlockup() -> (
     PSM
 ) {
     let wallet: WalletUnlocked = get_test_wallet().await;
-    let stable_i: ModularToken = init_custom_modulartoken(&wallet).await;
-    let collat_i: ModularToken = init_custom_modulartoken(&wallet).await;
+    let stable_i: ModularToken
+        = init_custom_modulartoken(&wallet).await.unwrap();
+    let collat_i: ModularToken
+        = init_custom_modulartoken(&wallet).await.unwrap();
     let psm = init_custom_psm(
-        &wallet, &stable_i, &collat_i).await;
+        &wallet, &stable_i, &collat_i).await.unwrap();
     let psmlockup = init_custom_psmlockup(
         &wallet, &stable_i, &get_cid(&collat_i),
         &ContractId::from(psm.get_contract_id())
-    ).await;
+    ).await.unwrap();
 
     (
         psmlockup,
@@ -86,13 +74,27 @@ pub async fn init_psmlockup() -> (
     )
 }
 
+pub async fn set_bsh_contract(
+    instance: &PSMLockup,
+    stable_i: &Bech32ContractId,
+    bsh: &ContractId
+) -> Result<(), YamaError> {
+    instance
+        .methods()
+        .set_bsh_contract(Bits256(*bsh.clone()))
+        .set_contract_ids(&[stable_i.clone()])
+        .call()
+        .await?;
+    Ok(())
+}
+
 pub async fn lockup(
     instance: &PSMLockup,
     psm: &Bech32ContractId,
     stable_i: &Bech32ContractId,
     token: &AssetId,
     amount: u64
-) {
+) -> Result<(), YamaError> {
     let tx_params = TxParameters::new(
         None, Some(32_000_000), None);
     
@@ -110,8 +112,40 @@ pub async fn lockup(
             psm.clone(),
             Bech32ContractId::from(ContractId::from(*token.clone()))])
         .call()
-        .await
-        .unwrap();
+        .await?;
+    Ok(())
+}
+
+// lockup() for a PSMLockup whose BSH contract is set, which it calls to
+// process the pending revenue share
+pub async fn lockup_with_bsh(
+    instance: &PSMLockup,
+    psm: &Bech32ContractId,
+    stable_i: &Bech32ContractId,
+    bsh: &Bech32ContractId,
+    token: &AssetId,
+    amount: u64
+) -> Result<(), YamaError> {
+    let tx_params = TxParameters::new(
+        None, Some(32_000_000), None);
+
+    instance
+        .methods()
+        .lockup()
+        .call_params(CallParameters::new(
+            Some(amount),
+            Some(token.clone()),
+        None))
+        .tx_params(tx_params)
+        .append_variable_outputs(2)
+        .set_contract_ids(
+            &[stable_i.clone(),
+            psm.clone(),
+            bsh.clone(),
+            Bech32ContractId::from(ContractId::from(*token.clone()))])
+        .call()
+        .await?;
+    Ok(())
 }
 
 pub async fn redeem(
@@ -119,7 +153,7 @@ pub async fn redeem(
     psm: &Bech32ContractId,
     stable_i: &Bech32ContractId,
     amount: u64
-) {
+) -> Result<(), YamaError> {
     let tx_params = TxParameters::new(
         None, Some(32_000_000), None);
 
@@ -136,14 +170,91 @@ pub async fn redeem(
             &[stable_i.clone(),
             psm.clone()])
         .call()
-        .await
-        .unwrap();
+        .await?;
+    Ok(())
+}
+
+pub async fn set_exit_queue(
+    instance: &PSMLockup,
+    stable_i: &Bech32ContractId,
+    enabled: bool,
+    delay: u64
+) -> Result<(), YamaError> {
+    instance
+        .methods()
+        .set_exit_queue(enabled, delay)
+        .set_contract_ids(&[stable_i.clone()])
+        .call()
+        .await?;
+    Ok(())
+}
+
+// Queues `amount` of lockup tokens for redemption, returning the ticket ID
+pub async fn request_redeem(
+    instance: &PSMLockup,
+    amount: u64
+) -> Result<u64, YamaError> {
+    Ok(instance
+        .methods()
+        .request_redeem()
+        .call_params(CallParameters::new(
+            Some(amount),
+            Some(AssetId::new(*ContractId::from(instance.get_contract_id()))),
+        None))
+        .call()
+        .await?
+        .value)
+}
+
+pub async fn cancel_redeem(
+    instance: &PSMLockup,
+    ticket_id: u64
+) -> Result<(), YamaError> {
+    instance
+        .methods()
+        .cancel_redeem(ticket_id)
+        .append_variable_outputs(1)
+        .call()
+        .await?;
+    Ok(())
+}
+
+// Returns the YSS paid out to the ticket's owner
+pub async fn claim_redeem(
+    instance: &PSMLockup,
+    stable_i: &Bech32ContractId,
+    ticket_id: u64
+) -> Result<u64, YamaError> {
+    let tx_params = TxParameters::new(
+        None, Some(32_000_000), None);
+
+    Ok(instance
+        .methods()
+        .claim_redeem(ticket_id)
+        .tx_params(tx_params)
+        .append_variable_outputs(1)
+        .set_contract_ids(&[stable_i.clone()])
+        .call()
+        .await?
+        .value)
+}
+
+pub async fn get_ticket(
+    instance: &PSMLockup,
+    ticket_id: u64
+) -> Result<ExitTicket, YamaError> {
+    Ok(instance
+        .methods()
+        .get_ticket(ticket_id)
+        .simulate()
+        .await?
+        .value)
 }
 
 pub async fn value(
     instance: &PSMLockup,
     stable_i: &Bech32ContractId
-) -> UFP128 {
+) -> Result<UFP128, YamaError> {
     let tx_params: TxParameters = TxParameters::new(
         None, Some(32_000_000), None);
 
@@ -158,9 +269,139 @@ pub async fn value(
         .set_contract_ids(
             &[stable_i.clone()])
         .call()
-        .await
+        .await?
+        .value;
+
+    Ok(value)
+}
+
+// What the lockup reports lockup would mint, given the PSM and SimpleBSH it
+// reads
+pub async fn preview_lockup(
+    instance: &PSMLockup,
+    psm: &Bech32ContractId,
+    bsh: &Bech32ContractId,
+    amount: u64
+) -> Result<u64, YamaError> {
+    Ok(instance
+        .methods()
+        .preview_lockup(amount)
+        .set_contract_ids(&[psm.clone(), bsh.clone()])
+        .simulate()
+        .await?
+        .value)
+}
+
+pub async fn preview_redeem(
+    instance: &PSMLockup,
+    amount: u64
+) -> Result<u64, YamaError> {
+    Ok(instance
+        .methods()
+        .preview_redeem(amount)
+        .simulate()
+        .await?
+        .value)
+}
+
+// The value of a lockup token, rounded as the contract rounds it
+fn value_of(balance: u64, total_supply: u64) -> Option<Ufp128> {
+    if total_supply == 0 {
+        return Some(Ufp128::ONE);
+    }
+    Ufp128::from_u64(balance).checked_div(Ufp128::from_u64(total_supply))
+}
+
+// The lockup tokens minted for `yama_amount` of YSS from the PSM, with the
+// lockup holding `balance` of YSS before `pending_share_amount` is minted to
+// it. None where the contract would revert.
+pub fn quote_lockup_amount(
+    yama_amount: u64,
+    balance: u64,
+    pending_share_amount: u64,
+    total_supply: u64
+) -> Option<u64> {
+    let value = value_of(
+        balance.checked_add(pending_share_amount)?, total_supply)?;
+    Some(Ufp128::from_u64(yama_amount).checked_div(value)?.to_u64())
+}
+
+// The YSS paid out for `amount` of lockup tokens
+pub fn quote_redeem_amount(
+    amount: u64,
+    balance: u64,
+    total_supply: u64
+) -> Option<u64> {
+    let value = value_of(balance, total_supply)?;
+    Some(Ufp128::from_u64(amount).checked_mul(value)?.to_u64())
+}
+
+// The lockup's YSS balance and total supply
+async fn lockup_state(
+    instance: &PSMLockup,
+    stable_id: &ContractId
+) -> Result<(u64, u64), YamaError> {
+    let balance = instance
+        .get_wallet()
+        .get_provider()
         .unwrap()
+        .get_contract_asset_balance(
+            instance.get_contract_id(), AssetId::new(**stable_id))
+        .await
+        .map_err(|error| Error::ProviderError(error.to_string()))?;
+    let total_supply = instance
+        .methods()
+        .total_supply()
+        .simulate()
+        .await?
         .value;
+    Ok((balance, total_supply))
+}
 
-    value
+// quote_lockup_amount for `amount` of the PSM's primary token, after the
+// PSM's fees and decimal conversion and with the SimpleBSH's pending share
+pub async fn quote_lockup(
+    instance: &PSMLockup,
+    psm: &PSM,
+    bsh: &SimpleBSH,
+    stable_id: &ContractId,
+    amount: u64
+) -> Result<u64, YamaError> {
+    let token = ContractId::new(
+        instance.methods().get_token().simulate().await?.value.0);
+    let yama_amount = quote_deposit(psm, &token, amount).await?.amount;
+    let (balance, total_supply) = lockup_state(instance, stable_id).await?;
+
+    // The pending share is only minted to the lockup if it's the target.
+    // A SplitBSH passed as `bsh` reports a zero target and shares surplus
+    // as it's booked, so nothing is pending for it
+    let target = bsh.methods().get_target().simulate().await?.value;
+    let pending_share_amount
+        = if target.0 == *ContractId::from(instance.get_contract_id()) {
+            let pending = bsh
+                .methods()
+                .get_pending_share_amount()
+                .simulate()
+                .await?
+                .value;
+            i256_to_i128(&pending).unwrap_or(0).max(0) as u64
+        } else {
+            0
+        };
+    quote_lockup_amount(
+        yama_amount,
+        balance,
+        pending_share_amount,
+        total_supply
+    ).ok_or_else(|| YamaError::QuoteOverflow("lockup".to_string()))
+}
+
+pub async fn quote_redeem(
+    instance: &PSMLockup,
+    stable_id: &ContractId,
+    amount: u64
+) -> Result<u64, YamaError> {
+    let (balance, total_supply) = lockup_state(instance, stable_id).await?;
+    quote_redeem_amount(amount, balance, total_supply)
+        .ok_or_else(|| YamaError::QuoteOverflow("redeem".to_string()))
 }
\ No newline at end of file
@@ -1,8 +1,16 @@
 use fuels::{prelude::*, tx::ContractId};
-use crate::abigen::*;
+use crate::{
+  abigen::*,
+  aggregatorpricesource::price_source_ids,
+  errors::{
+    register_contract,
+    YamaContract,
+    YamaError
+  }
+};
 
 pub async fn init_psmpricesource(
-  wallet: &WalletUnlocked) -> PSMPriceSource
+  wallet: &WalletUnlocked) -> Result<PSMPriceSource, YamaError>
 {
   let id = Contract::deploy(
     "../psmpricesource/out/debug/psmpricesource.bin",
@@ -10,8 +18,24 @@ pub async fn init_psmpricesource(
     TxParameters::default(),
     StorageConfiguration::default(),
   )
-  .await
-  .unwrap();
+  .await?;
   
-  PSMPriceSource::new(id, wallet.clone())
-}
\ No newline at end of file
+  register_contract(&id, YamaContract::PSMPriceSource);
+  Ok(PSMPriceSource::new(id, wallet.clone()))
+}
+
+// Reads any price source, since they share the PriceSource ABI
+pub async fn get_price(
+  price_source: &Bech32ContractId,
+  wallet: &WalletUnlocked
+) -> Result<UFP128, YamaError> {
+  let instance = PSMPriceSource::new(price_source.clone(), wallet.clone());
+  let ids = price_source_ids(price_source, wallet).await?;
+  Ok(instance
+    .methods()
+    .price()
+    .set_contract_ids(&ids)
+    .simulate()
+    .await?
+    .value)
+}
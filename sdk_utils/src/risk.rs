@@ -0,0 +1,221 @@
+use crate::{
+  abigen::*,
+  errors::CDPError,
+  ufp128::Ufp128
+};
+
+// A vault and its collateral type as cdpmodule/src/main.sw sees them, priced
+// at `price`. Checks mirror the contract's rules and rounding; arithmetic
+// panics where the contract would revert on overflow.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VaultRisk {
+  pub vault: Vault,
+  pub collateral_type: CollateralType,
+  pub price: Ufp128
+}
+
+impl VaultRisk {
+  pub fn new(
+    vault: Vault,
+    collateral_type: CollateralType,
+    price: &UFP128
+  ) -> VaultRisk {
+    VaultRisk {
+      vault,
+      collateral_type,
+      price: Ufp128::from(price)
+    }
+  }
+
+  // Applies update_interest as of TAI64 time `now`, which every borrow,
+  // repay and collateral removal does first
+  pub fn accrue(&mut self, now: u64) {
+    let c_type = &mut self.collateral_type;
+    if now <= c_type.last_update_time {
+      return;
+    }
+    let cumulative_interest = Ufp128::from(&c_type.cumulative_interest)
+      * Ufp128::from(&c_type.interest_rate)
+        .powu(now - c_type.last_update_time);
+    c_type.cumulative_interest = cumulative_interest.into();
+    c_type.last_update_time = now;
+  }
+
+  fn cumulative_interest(&self) -> Ufp128 {
+    Ufp128::from(&self.collateral_type.cumulative_interest)
+  }
+
+  fn collateral_ratio(&self) -> Ufp128 {
+    Ufp128::from(&self.collateral_type.collateral_ratio)
+  }
+
+  pub fn debt(&self) -> Ufp128 {
+    Ufp128::from(&self.vault.initial_debt) * self.cumulative_interest()
+  }
+
+  pub fn total_debt(&self) -> Ufp128 {
+    Ufp128::from(&self.collateral_type.initial_debt)
+      * self.cumulative_interest()
+  }
+
+  fn value_of(&self, collateral_amount: u64) -> Ufp128 {
+    Ufp128::from_u64(collateral_amount) * self.price
+  }
+
+  pub fn collateral_value(&self) -> Ufp128 {
+    self.value_of(self.vault.collateral_amount)
+  }
+
+  pub fn target_collateral_value(&self) -> Ufp128 {
+    self.debt() * self.collateral_ratio()
+  }
+
+  pub fn is_undercollateralized(&self) -> bool {
+    self.collateral_value() < self.target_collateral_value()
+  }
+
+  // An overflowing target counts as invalid rather than panicking
+  pub fn valid_debt_amount(&self, amount: Ufp128) -> bool {
+    amount == Ufp128::ZERO
+      || (amount.ge(Ufp128::from(&self.collateral_type.debt_floor))
+        && amount
+          .checked_mul(self.collateral_ratio())
+          .is_some_and(|target| self.collateral_value().ge(target)))
+  }
+
+  // Collateral value over debt, unset without debt
+  pub fn current_ratio(&self) -> Option<Ufp128> {
+    self.collateral_value().checked_div(self.debt())
+  }
+
+  // The lowest price the vault stays collateralized at, unset without debt
+  // or collateral
+  pub fn liquidation_price(&self) -> Option<Ufp128> {
+    let target = self.target_collateral_value().bits();
+    let amount = self.vault.collateral_amount as u128;
+    if target == 0 || amount == 0 {
+      return None;
+    }
+    // Collateral is valued at amount * price without rounding
+    Some(Ufp128::from_bits(target.div_ceil(amount)))
+  }
+
+  // The collateral type's total debt once the vault's debt is `new_debt`,
+  // after set_debt rounds it through the cumulative interest
+  fn total_debt_with(&self, new_debt: Ufp128) -> Option<Ufp128> {
+    let cumulative_interest = self.cumulative_interest();
+    let new_initial_debt = new_debt.checked_div(cumulative_interest)?;
+    Ufp128::from(&self.collateral_type.initial_debt)
+      .checked_add(new_initial_debt)?
+      .checked_sub(Ufp128::from(&self.vault.initial_debt))?
+      .checked_mul(cumulative_interest)
+  }
+
+  // valid_debt_amount without the debt floor, plus the debt ceiling
+  fn within_ratio_and_ceiling(&self, new_debt: Ufp128) -> bool {
+    let within_ratio = new_debt
+      .checked_mul(self.collateral_ratio())
+      .is_some_and(|target| self.collateral_value().ge(target));
+    let within_ceiling = self.total_debt_with(new_debt)
+      .is_some_and(|total| total.le(
+        Ufp128::from(&self.collateral_type.debt_ceiling)));
+    within_ratio && within_ceiling
+  }
+
+  // The checks `borrow` makes that depend on the vault and its collateral
+  // type. The global borrowing switch and the borrower allowlist aren't
+  // covered.
+  pub fn check_borrow(&self, amount: u64) -> Result<(), CDPError> {
+    if self.vault.is_liquidated {
+      return Err(CDPError::Liquidated);
+    }
+    if !self.collateral_type.borrowing_enabled {
+      return Err(CDPError::CollateralTypeBorrowingDisabled);
+    }
+    let new_debt = self.debt()
+      .checked_add(Ufp128::from_u64(amount))
+      .ok_or(CDPError::InvalidDebtAmount)?;
+    if !self.valid_debt_amount(new_debt) {
+      return Err(CDPError::InvalidDebtAmount);
+    }
+    match self.total_debt_with(new_debt) {
+      Some(total)
+        if total.le(Ufp128::from(&self.collateral_type.debt_ceiling))
+        => Ok(()),
+      _ => Err(CDPError::ExceedsDebtCeiling)
+    }
+  }
+
+  pub fn check_repay(&self, amount: u64) -> Result<(), CDPError> {
+    if self.vault.is_liquidated {
+      return Err(CDPError::Liquidated);
+    }
+    let debt = self.debt();
+    if amount > debt.to_u64() {
+      return Err(CDPError::RepayOverpayment);
+    }
+    if !self.valid_debt_amount(debt - Ufp128::from_u64(amount)) {
+      return Err(CDPError::InvalidDebtAmount);
+    }
+    Ok(())
+  }
+
+  // Amounts above the vault's collateral are reported as Undercollateralized,
+  // though the contract reverts on the underflow instead
+  pub fn check_remove_collateral(&self, amount: u64) -> Result<(), CDPError> {
+    if self.vault.is_liquidated {
+      return Err(CDPError::Liquidated);
+    }
+    match self.vault.collateral_amount.checked_sub(amount) {
+      Some(remaining)
+        if self.value_of(remaining).ge(self.target_collateral_value())
+        => Ok(()),
+      _ => Err(CDPError::Undercollateralized)
+    }
+  }
+
+  // The most `borrow` accepts right now, 0 if even that would leave the
+  // debt below the floor
+  pub fn max_borrowable(&self) -> u64 {
+    if self.vault.is_liquidated || !self.collateral_type.borrowing_enabled {
+      return 0;
+    }
+    let debt = self.debt();
+    let fits = |amount: u64| debt
+      .checked_add(Ufp128::from_u64(amount))
+      .is_some_and(|new_debt| self.within_ratio_and_ceiling(new_debt));
+    if !fits(0) {
+      return 0;
+    }
+
+    // Both limits only get tighter as the amount grows
+    let (mut low, mut high) = (0u64, u64::MAX);
+    while low < high {
+      let middle = low + (high - low) / 2 + 1;
+      if fits(middle) {
+        low = middle;
+      } else {
+        high = middle - 1;
+      }
+    }
+    if self.check_borrow(low).is_ok() { low } else { 0 }
+  }
+
+  // The most `remove_collateral` accepts right now
+  pub fn max_withdrawable(&self) -> u64 {
+    if self.vault.is_liquidated {
+      return 0;
+    }
+    let target = self.target_collateral_value().bits();
+    let price = self.price.bits();
+    let min_remaining = if target == 0 {
+      0
+    } else if price == 0 {
+      return 0;
+    } else {
+      target.div_ceil(price)
+    };
+    let amount = self.vault.collateral_amount as u128;
+    amount.saturating_sub(min_remaining) as u64
+  }
+}
@@ -1,18 +1,14 @@
-use tokio::sync::Mutex;
-use lazy_static::lazy_static;
 use fuels::{
     prelude::*,
     types::Identity
 };
 use crate::{
-    utils::{
-        set_storage_val,
-        get_storage_val,
-        get_test_wallet,
-        int_to_hex,
-        u64_to_fp,
-        u128_to_fp
+    errors::{
+        register_contract,
+        YamaContract,
+        YamaError
     },
+    storage::StorageLayout,
     modulartoken::{
         set_allowlist,
         get_cid
@@ -20,56 +16,49 @@ use crate::{
     abigen::*
 };
 
-lazy_static! {
-    static ref MUTEX: Mutex<i32> = Mutex::new(0i32);
-}
-
 pub async fn init_custom_simplebsh(
     wallet: &WalletUnlocked,
     stable_i: &ModularToken,
     balancesheet_module: &ContractId,
     target: &ContractId
-) -> SimpleBSH {
+) -> Result<SimpleBSH, YamaError> {
     let storage_path = "../simplebsh/out/debug/simplebsh-storage_slots.json";
 
-    let guard = MUTEX.lock().await;
-
-    set_storage_val(
-        &storage_path,
-        "storage_0",
-        &get_cid(stable_i).to_string()
-    );
-
-    set_storage_val(
-        &storage_path,
-        "storage_1",
-        &balancesheet_module.to_string()
-    );
-
-    set_storage_val(
-        &storage_path,
-        "storage_2",
-        &target.to_string()
-    );
+    let storage = StorageLayout::new()
+        .field(0, &get_cid(stable_i))
+        .field(1, balancesheet_module)
+        .field(2, target);
 
     let id = Contract::deploy(
         "../simplebsh/out/debug/simplebsh.bin",
         &wallet,
         TxParameters::default(),
-        StorageConfiguration::with_storage_path(Some(
-            storage_path.to_string()
-        ))
-    ).await.unwrap();
-
-    drop(guard);
+        storage.configuration(storage_path)
+    ).await?;
 
+    register_contract(&id, YamaContract::SimpleBSH);
     let simplebsh = SimpleBSH::new(id.clone(), wallet.clone());
 
     set_allowlist(
         &stable_i,
         Identity::ContractId(id.clone().into()),
         true
-    ).await;
+    ).await?;
+
+    Ok(simplebsh)
+}
 
-    simplebsh
+// Has deficits burn the YSS held by the target PSMLockup
+pub async fn set_absorb_deficits(
+    instance: &SimpleBSH,
+    stable_i: &Bech32ContractId,
+    enabled: bool
+) -> Result<(), YamaError> {
+    instance
+        .methods()
+        .set_absorb_deficits(enabled)
+        .set_contract_ids(&[stable_i.clone()])
+        .call()
+        .await?;
+    Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,1736 @@
+use std::{
+  f64::consts::PI,
+  ops::Range
+};
+use fuels::{prelude::*, tx::ContractId, types::{Bits256, Identity}};
+use rand::prelude::{Rng, SeedableRng, StdRng};
+use crate::{
+  abigen::*,
+  aggregatorpricesource,
+  bidder::{
+    collateral_value,
+    expires_at,
+    interval_at,
+    price_at_interval
+  },
+  cdp::get_collateral_manager,
+  client::YamaClient,
+  clock::Clock,
+  deploy::{
+    BSHTarget,
+    CollateralTypePlan,
+    DeploymentPlan,
+    LiquidatorPlan,
+    YamaDeployment
+  },
+  dutchauctionliquidator,
+  errors::{
+    CDPError,
+    DutchAuctionLiquidatorError,
+    FlashMintModuleError,
+    LeverageProxyError,
+    PSMError,
+    PSMLockupError,
+    YamaError
+  },
+  interest::{
+    apy_to_per_second,
+    parse_rate
+  },
+  modulartoken::{
+    mint,
+    get_aid,
+    get_cid
+  },
+  leverageproxy,
+  mockpricesource,
+  psm,
+  psmlockup,
+  psmpricesource,
+  risk::VaultRisk,
+  ufp128::Ufp128,
+  utils::{
+    get_id,
+    get_timestamp,
+    i256_to_i128,
+    unix_to_tai64,
+    TAI64_OFFSET
+  }
+};
+
+// stablecoin_library/src/constants.sw
+pub const DECIMALS: u8 = 4;
+pub const PSM_CEILING: u64 = 1000000000000;
+pub const PSM_TOKEN_DECIMALS: u8 = 4;
+pub const PSM_FEE_DENOMINATOR: u64 = 10000;
+pub const SBSH_REVENUE_SHARE: u64 = 9000;
+pub const SBSH_DENOMINATOR: u64 = 10000;
+pub const FMM_MAX: u64 = 1000000000000;
+
+const BPS_DENOMINATOR: u64 = 10_000;
+
+// One transaction from the deployment's wallet, or a change to the
+// environment around the contracts
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+  // Moves the clock forward without a transaction
+  AdvanceTime(u64),
+  // Sets what the price source reports for every collateral type
+  SetPrice(Ufp128),
+  MintCollateral(u64),
+  MintStablecoin(u64),
+  CreateVault {
+    collateral_type_id: u64,
+    collateral_amount: u64
+  },
+  // Opens a vault through the LeverageProxy, which owns it with the wallet
+  // as its alt owner
+  CreateProxyVault {
+    collateral_type_id: u64,
+    collateral_amount: u64
+  },
+  AddCollateral {
+    vault_id: u64,
+    amount: u64
+  },
+  Borrow {
+    vault_id: u64,
+    amount: u64
+  },
+  Repay {
+    vault_id: u64,
+    amount: u64
+  },
+  RemoveCollateral {
+    vault_id: u64,
+    amount: u64
+  },
+  UpdateInterest {
+    collateral_type_id: u64
+  },
+  Liquidate {
+    vault_id: u64
+  },
+  // Pays `amount`, of which everything above the price is refunded
+  Claim {
+    auction_id: u64,
+    max_price: u64,
+    amount: u64
+  },
+  ResetAuction {
+    auction_id: u64
+  },
+  // Deposits the collateral token, which is also the PSM's external
+  // stablecoin in a deployment
+  PsmDeposit(u64),
+  PsmWithdraw(u64),
+  Lockup(u64),
+  Redeem(u64),
+  // LeverageProxy::leverage_up, which flash mints `amount`, swaps it 1:1 for
+  // collateral, adds that to the vault and borrows `amount` to repay
+  FlashLoan {
+    vault_id: u64,
+    amount: u64
+  }
+}
+
+impl Action {
+  pub fn is_transaction(&self) -> bool {
+    !matches!(self, Action::AdvanceTime(_) | Action::SetPrice(_))
+  }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Balances {
+  pub stablecoin: u64,
+  pub collateral: u64,
+  pub lockup_shares: u64
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PsmState {
+  pub yss_decimals: u8,
+  pub external_stable_decimals: u8,
+  pub debt_ceiling: u64,
+  // In basis points of YSS
+  pub tin: u64,
+  pub tout: u64,
+  // External stablecoin held
+  pub reserves: u64
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LockupState {
+  // Stablecoin held, which backs the shares
+  pub balance: u64,
+  pub total_supply: u64
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Metrics {
+  pub liquidations: u64,
+  pub claims: u64,
+  pub resets: u64,
+  // Interest accrued across collateral types
+  pub interest: i128,
+  // Paid for collateral at auction
+  pub auction_proceeds: u64,
+  // Debt auctions failed to cover
+  pub bad_debt: u64,
+  // Minted to the SimpleBSH target
+  pub revenue_shared: u64,
+  // Burned from the PSMLockup to cover deficits
+  pub deficit_absorbed: u64,
+  // Paid at partial liquidations beyond the debt they covered
+  pub penalties: u64,
+  // Writes to a vault's debt, each of which can move the collateral type's
+  // total debt a token away from the sum of its vaults' debts
+  pub debt_updates: u64
+}
+
+// Stablecoin minted that the CDP module's debt doesn't back
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Issuance {
+  // Net of withdrawals, including the PSMLockup's deposits. Fees count
+  // too, as they're booked as surplus.
+  pub psm: i128,
+  // Minted straight to the wallet
+  pub wallet: u64,
+  // SimpleBSH's second mint of pending shares
+  pub reminted: u64,
+  // What the supply already exceeded debt minus surplus by at the start
+  pub opening: i128
+}
+
+impl Issuance {
+  pub fn total(&self) -> i128 {
+    self.psm + self.wallet as i128 + self.reminted as i128 + self.opening
+  }
+}
+
+// What replays compare between the simulator and the contracts
+#[derive(Clone, Debug, PartialEq)]
+pub struct SystemSnapshot {
+  pub vaults: Vec<Vault>,
+  pub collateral_types: Vec<CollateralType>,
+  pub auctions: Vec<Auction>,
+  // Indexed by auction, None where the auction sells the whole vault
+  pub partial_lots: Vec<Option<PartialLot>>,
+  pub surplus: i128,
+  pub pending_share_amount: i128,
+  pub psm_reserves: u64,
+  pub lockup: LockupState,
+  pub wallet: Balances,
+  pub stablecoin_supply: u64
+}
+
+impl SystemSnapshot {
+  // Names of the fields that differ
+  pub fn diff(&self, other: &SystemSnapshot) -> Vec<&'static str> {
+    let mut fields: Vec<&'static str> = vec![];
+    if self.vaults != other.vaults {
+      fields.push("vaults");
+    }
+    if self.collateral_types != other.collateral_types {
+      fields.push("collateral_types");
+    }
+    if self.auctions != other.auctions {
+      fields.push("auctions");
+    }
+    if self.partial_lots != other.partial_lots {
+      fields.push("partial_lots");
+    }
+    if self.surplus != other.surplus {
+      fields.push("surplus");
+    }
+    if self.pending_share_amount != other.pending_share_amount {
+      fields.push("pending_share_amount");
+    }
+    if self.psm_reserves != other.psm_reserves {
+      fields.push("psm_reserves");
+    }
+    if self.lockup != other.lockup {
+      fields.push("lockup");
+    }
+    if self.wallet != other.wallet {
+      fields.push("wallet");
+    }
+    if self.stablecoin_supply != other.stablecoin_supply {
+      fields.push("stablecoin_supply");
+    }
+    fields
+  }
+}
+
+fn revert(reason: &str) -> YamaError {
+  YamaError::Reverted { revert_id: 0, reason: reason.to_string() }
+}
+
+fn require(condition: bool, error: YamaError) -> Result<(), YamaError> {
+  if condition { Ok(()) } else { Err(error) }
+}
+
+fn provider_error(error: impl ToString) -> YamaError {
+  YamaError::Sdk(Error::ProviderError(error.to_string()))
+}
+
+fn checked<T>(value: Option<T>) -> Result<T, YamaError> {
+  value.ok_or_else(|| revert("arithmetic overflow"))
+}
+
+fn credit(balance: &mut u64, amount: u64) -> Result<(), YamaError> {
+  *balance = checked(balance.checked_add(amount))?;
+  Ok(())
+}
+
+fn debit(balance: &mut u64, amount: u64) -> Result<(), YamaError> {
+  *balance = balance
+    .checked_sub(amount)
+    .ok_or_else(|| revert("insufficient balance"))?;
+  Ok(())
+}
+
+// convert_amount from stablecoin_library/src/helpers.sw, None where it
+// would revert
+pub fn convert_amount(
+  amount: u64,
+  from_decimals: u8,
+  to_decimals: u8
+) -> Option<u64> {
+  if from_decimals <= to_decimals {
+    10u64
+      .checked_pow((to_decimals - from_decimals) as u32)?
+      .checked_mul(amount)
+  } else {
+    Some(amount / 10u64.checked_pow((from_decimals - to_decimals) as u32)?)
+  }
+}
+
+// A deterministic model of a deployment: the CDP module, balance sheet,
+// SimpleBSH, PSM, PSMLockup, DutchAuctionLiquidator and, when deployed, the
+// LeverageProxy and its flash mint module, with every
+// transaction coming from a single wallet. Amounts round as the contracts
+// round them; VaultRisk's checks panic where the contracts would revert on
+// overflow. Owner checks and borrower allowlists aren't modeled.
+#[derive(Clone, Debug)]
+pub struct Simulator {
+  // TAI64, as the contracts see it
+  pub now: u64,
+  pub price: Ufp128,
+  pub collateral_types: Vec<CollateralType>,
+  // Indexed by collateral type
+  pub c_type_params: Vec<CTypeParams>,
+  // Indexed by collateral type
+  pub partial_params: Vec<PartialLiquidationParams>,
+  pub vaults: Vec<Vault>,
+  pub auctions: Vec<Auction>,
+  // Indexed by auction
+  pub partial_lots: Vec<Option<PartialLot>>,
+  pub owner: Identity,
+  pub surplus: i128,
+  pub revenue_share: u64,
+  pub pending_share_amount: i128,
+  // Whether SimpleBSH mints its share to the PSMLockup
+  pub revenue_to_lockup: bool,
+  // Whether SimpleBSH covers deficits with the PSMLockup's stablecoin
+  pub absorb_deficits: bool,
+  pub psm: PsmState,
+  pub lockup: LockupState,
+  pub wallet: Balances,
+  pub stablecoin_supply: u64,
+  pub issuance: Issuance,
+  pub leverage_proxy: Option<Identity>,
+  pub flash_mint_max: u64,
+  pub metrics: Metrics
+}
+
+impl Simulator {
+  // The state `deploy` leaves behind for `plan`, at TAI64 time `start_time`
+  pub fn new(plan: &DeploymentPlan, start_time: u64) -> Simulator {
+    let params = CTypeParams {
+      initial_price_ratio: plan.liquidator.initial_price_ratio.clone(),
+      time_interval: plan.liquidator.time_interval,
+      change_rate: plan.liquidator.change_rate.clone(),
+      reset_threshold: plan.liquidator.reset_threshold,
+      enabled: true
+    };
+    let collateral_types: Vec<CollateralType> = plan.collateral_types
+      .iter()
+      .map(|c_type| CollateralType {
+        token: Bits256::zeroed(),
+        price_source: Bits256::zeroed(),
+        debt_floor: c_type.debt_floor.clone(),
+        debt_ceiling: c_type.debt_ceiling.clone(),
+        collateral_ratio: c_type.collateral_ratio.clone(),
+        interest_rate: c_type.interest_rate.clone(),
+        total_collateral: 0,
+        last_update_time: start_time,
+        initial_debt: Ufp128::ZERO.into(),
+        cumulative_interest: Ufp128::ONE.into(),
+        borrowing_enabled: c_type.borrowing_enabled,
+        allowlist_enabled: c_type.allowlist_enabled
+      })
+      .collect();
+    let partial_params: Vec<PartialLiquidationParams> = plan.collateral_types
+      .iter()
+      .map(|c_type| match &c_type.partial_liquidation {
+        Some(partial) => PartialLiquidationParams {
+          enabled: true,
+          target_ratio: partial.target_ratio.clone(),
+          penalty: partial.penalty.clone()
+        },
+        None => PartialLiquidationParams {
+          enabled: false,
+          target_ratio: Ufp128::ZERO.into(),
+          penalty: Ufp128::ZERO.into()
+        }
+      })
+      .collect();
+
+    Simulator {
+      now: start_time,
+      price: plan.mock_price
+        .as_ref()
+        .map(Ufp128::from)
+        .unwrap_or(Ufp128::ONE),
+      c_type_params: vec![params; collateral_types.len()],
+      partial_params,
+      collateral_types,
+      vaults: vec![],
+      auctions: vec![],
+      partial_lots: vec![],
+      owner: Identity::Address(Address::zeroed()),
+      surplus: 0,
+      revenue_share: SBSH_REVENUE_SHARE,
+      pending_share_amount: 0,
+      revenue_to_lockup: matches!(plan.bsh_target, BSHTarget::PSMLockup),
+      absorb_deficits: plan.absorb_deficits,
+      psm: PsmState {
+        yss_decimals: DECIMALS,
+        external_stable_decimals: plan.psm.external_stable_decimals
+          .unwrap_or(PSM_TOKEN_DECIMALS),
+        debt_ceiling: plan.psm.debt_ceiling.unwrap_or(PSM_CEILING),
+        tin: plan.psm.tin,
+        tout: plan.psm.tout,
+        reserves: 0
+      },
+      lockup: LockupState::default(),
+      wallet: Balances::default(),
+      stablecoin_supply: 0,
+      issuance: Issuance::default(),
+      leverage_proxy: plan.leverage_proxy
+        .then(|| Identity::ContractId(ContractId::zeroed())),
+      flash_mint_max: FMM_MAX,
+      metrics: Metrics::default()
+    }
+  }
+
+  // Starts from a deployment's current state, so actions can be replayed on
+  // both
+  pub async fn from_chain(
+    deployment: &YamaDeployment
+  ) -> Result<Simulator, YamaError> {
+    let YamaDeployment {
+      wallet,
+      psm,
+      psm_lockup,
+      simplebsh,
+      dutchauctionliquidator,
+      price_source,
+      flashmintmodule,
+      lproxy,
+      ..
+    } = deployment;
+    let snapshot = read_snapshot(deployment).await?;
+    let provider = wallet.get_provider().unwrap();
+
+    let mut c_type_params: Vec<CTypeParams> = vec![];
+    let mut partial_params: Vec<PartialLiquidationParams> = vec![];
+    for c_type_id in 0..snapshot.collateral_types.len() as u64 {
+      c_type_params.push(dutchauctionliquidator::get_c_type_params(
+        dutchauctionliquidator, c_type_id).await?);
+      partial_params.push(
+        dutchauctionliquidator::get_partial_liquidation_params(
+          dutchauctionliquidator, c_type_id).await?);
+    }
+    let bsh_target = simplebsh
+      .methods()
+      .get_target()
+      .simulate()
+      .await?
+      .value;
+    let flash_mint_max = match flashmintmodule {
+      Some(flashmintmodule)
+        => flashmintmodule.methods().get_max().simulate().await?.value,
+      None => FMM_MAX
+    };
+
+    let mut simulator = Simulator {
+      now: unix_to_tai64(get_timestamp(provider).await?),
+      price: Ufp128::from(
+        psmpricesource::get_price(price_source, wallet).await?),
+      collateral_types: snapshot.collateral_types,
+      c_type_params,
+      partial_params,
+      vaults: snapshot.vaults,
+      auctions: snapshot.auctions,
+      partial_lots: snapshot.partial_lots,
+      owner: get_id(wallet),
+      surplus: snapshot.surplus,
+      revenue_share: simplebsh
+        .methods()
+        .get_revenue_share()
+        .simulate()
+        .await?
+        .value,
+      pending_share_amount: snapshot.pending_share_amount,
+      revenue_to_lockup: bsh_target.0
+        == *ContractId::from(psm_lockup.get_contract_id()),
+      absorb_deficits: simplebsh
+        .methods()
+        .is_absorbing_deficits()
+        .simulate()
+        .await?
+        .value,
+      psm: PsmState {
+        yss_decimals: psm.methods().get_yss_decimals().simulate().await?.value,
+        external_stable_decimals: psm
+          .methods()
+          .get_external_stable_decimals()
+          .simulate()
+          .await?
+          .value,
+        debt_ceiling: psm.methods().debt_ceiling().simulate().await?.value,
+        tin: psm.methods().get_tin().simulate().await?.value,
+        tout: psm.methods().get_tout().simulate().await?.value,
+        reserves: snapshot.psm_reserves
+      },
+      lockup: snapshot.lockup,
+      wallet: snapshot.wallet,
+      stablecoin_supply: snapshot.stablecoin_supply,
+      issuance: Issuance::default(),
+      leverage_proxy: lproxy.as_ref().map(|lproxy|
+        Identity::ContractId(ContractId::from(lproxy.get_contract_id()))),
+      flash_mint_max,
+      metrics: Metrics::default()
+    };
+    simulator.issuance.opening = simulator.stablecoin_supply as i128
+      - simulator.total_debt() as i128
+      + simulator.surplus;
+    Ok(simulator)
+  }
+
+  pub fn snapshot(&self) -> SystemSnapshot {
+    SystemSnapshot {
+      vaults: self.vaults.clone(),
+      collateral_types: self.collateral_types.clone(),
+      auctions: self.auctions.clone(),
+      partial_lots: self.partial_lots.clone(),
+      surplus: self.surplus,
+      pending_share_amount: self.pending_share_amount,
+      psm_reserves: self.psm.reserves,
+      lockup: self.lockup,
+      wallet: self.wallet,
+      stablecoin_supply: self.stablecoin_supply
+    }
+  }
+
+  // Replaces the state a snapshot covers, keeping the parameters, clock and
+  // metrics
+  pub fn load(&mut self, snapshot: &SystemSnapshot) {
+    self.vaults = snapshot.vaults.clone();
+    self.collateral_types = snapshot.collateral_types.clone();
+    self.auctions = snapshot.auctions.clone();
+    self.partial_lots = snapshot.partial_lots.clone();
+    self.surplus = snapshot.surplus;
+    self.pending_share_amount = snapshot.pending_share_amount;
+    self.psm.reserves = snapshot.psm_reserves;
+    self.lockup = snapshot.lockup;
+    self.wallet = snapshot.wallet;
+    self.stablecoin_supply = snapshot.stablecoin_supply;
+  }
+
+  // Runs `action` as its transaction would, leaving the state untouched
+  // when it reverts
+  pub fn apply(&mut self, action: &Action) -> Result<(), YamaError> {
+    let saved = self.clone();
+    let result = self.execute(action);
+    if result.is_err() {
+      *self = saved;
+    }
+    result
+  }
+
+  pub fn risk(&self, vault_id: u64) -> Result<VaultRisk, YamaError> {
+    let vault = self.vault(vault_id)?;
+    Ok(VaultRisk {
+      vault: vault.clone(),
+      collateral_type: self.collateral_type(vault.collateral_type_id)?.clone(),
+      price: self.price
+    })
+  }
+
+  // Debt across collateral types as of their last interest update, rounded
+  // as update_interest rounds it
+  pub fn total_debt(&self) -> u64 {
+    self.collateral_types
+      .iter()
+      .map(|c_type| Ufp128::from(&c_type.initial_debt)
+        .checked_mul(Ufp128::from(&c_type.cumulative_interest))
+        .map_or(u64::MAX, |debt| debt.to_u64()))
+      .fold(0, u64::saturating_add)
+  }
+
+  // Debt of liquidated vaults whose auctions haven't been claimed yet
+  pub fn unresolved_debt(&self) -> u64 {
+    (0..self.vaults.len() as u64)
+      .filter_map(|vault_id| self.risk(vault_id).ok())
+      .filter(|risk| risk.vault.is_liquidated)
+      .map(|risk| risk.debt().to_u64())
+      .fold(0, u64::saturating_add)
+  }
+
+  // PSMLockup::value
+  pub fn lockup_value(&self) -> Ufp128 {
+    if self.lockup.total_supply == 0 {
+      return Ufp128::ONE;
+    }
+    Ufp128::from_u64(self.lockup.balance)
+      / Ufp128::from_u64(self.lockup.total_supply)
+  }
+
+  pub fn is_expired(&self, auction_id: u64) -> Result<bool, YamaError> {
+    let auction = self.auction(auction_id)?;
+    let params = self.auction_params(auction_id)?;
+    Ok(self.now >= expires_at(params, auction.start_time))
+  }
+
+  // DutchAuctionLiquidator::get_collateral_amount_of_auction
+  pub fn collateral_amount_of_auction(
+    &self,
+    auction_id: u64
+  ) -> Result<u64, YamaError> {
+    let auction = self.auction(auction_id)?;
+    match &self.partial_lots[auction_id as usize] {
+      Some(lot) => Ok(lot.collateral_amount),
+      None => Ok(self.vault(auction.vault_id)?.collateral_amount)
+    }
+  }
+
+  // DutchAuctionLiquidator::get_price, 0 once the auction is over
+  pub fn auction_price(&self, auction_id: u64) -> Result<u64, YamaError> {
+    let auction = self.auction(auction_id)?;
+    if auction.done || self.is_expired(auction_id)? {
+      return Ok(0);
+    }
+    let params = self.auction_params(auction_id)?;
+    Ok(price_at_interval(
+      Ufp128::from(&auction.start_price).bits(),
+      params,
+      interval_at(params, auction.start_time, self.now)
+    ))
+  }
+
+  fn vault(&self, vault_id: u64) -> Result<&Vault, YamaError> {
+    self.vaults
+      .get(vault_id as usize)
+      .ok_or_else(|| revert("vault doesn't exist"))
+  }
+
+  fn collateral_type(
+    &self,
+    collateral_type_id: u64
+  ) -> Result<&CollateralType, YamaError> {
+    self.collateral_types
+      .get(collateral_type_id as usize)
+      .ok_or_else(|| revert("collateral type doesn't exist"))
+  }
+
+  fn auction(&self, auction_id: u64) -> Result<&Auction, YamaError> {
+    self.auctions
+      .get(auction_id as usize)
+      .ok_or_else(|| revert("auction doesn't exist"))
+  }
+
+  fn auction_params(
+    &self,
+    auction_id: u64
+  ) -> Result<&CTypeParams, YamaError> {
+    let vault = self.vault(self.auction(auction_id)?.vault_id)?;
+    self.c_type_params
+      .get(vault.collateral_type_id as usize)
+      .ok_or_else(|| revert("collateral type doesn't exist"))
+  }
+
+  fn execute(&mut self, action: &Action) -> Result<(), YamaError> {
+    match *action {
+      Action::AdvanceTime(seconds) => {
+        self.now = checked(self.now.checked_add(seconds))?;
+      },
+      Action::SetPrice(price) => {
+        self.price = price;
+      },
+      Action::MintCollateral(amount) => {
+        credit(&mut self.wallet.collateral, amount)?;
+      },
+      Action::MintStablecoin(amount) => {
+        credit(&mut self.wallet.stablecoin, amount)?;
+        credit(&mut self.stablecoin_supply, amount)?;
+        self.issuance.wallet += amount;
+      },
+      Action::CreateVault { collateral_type_id, collateral_amount } => {
+        debit(&mut self.wallet.collateral, collateral_amount)?;
+        self.create_vault(
+          collateral_type_id,
+          collateral_amount,
+          self.owner.clone(),
+          None
+        )?;
+      },
+      Action::CreateProxyVault { collateral_type_id, collateral_amount } => {
+        let proxy = self.leverage_proxy
+          .clone()
+          .ok_or_else(|| revert("no leverage proxy"))?;
+        debit(&mut self.wallet.collateral, collateral_amount)?;
+        self.create_vault(
+          collateral_type_id,
+          collateral_amount,
+          proxy,
+          Some(self.owner.clone())
+        )?;
+      },
+      Action::AddCollateral { vault_id, amount } => {
+        debit(&mut self.wallet.collateral, amount)?;
+        self.add_collateral(vault_id, amount)?;
+      },
+      Action::Borrow { vault_id, amount } => {
+        self.borrow(vault_id, amount)?;
+        credit(&mut self.wallet.stablecoin, amount)?;
+      },
+      Action::Repay { vault_id, amount } => {
+        // Payments the wallet can't fund fail before reaching the contract
+        debit(&mut self.wallet.stablecoin, amount)?;
+        let c_type_id = self.open_vault(vault_id)?;
+        self.update_interest(c_type_id)?;
+        let risk = self.risk(vault_id)?;
+        risk.check_repay(amount).map_err(YamaError::CDP)?;
+        self.set_debt(vault_id, risk.debt() - Ufp128::from_u64(amount))?;
+        debit(&mut self.stablecoin_supply, amount)?;
+      },
+      Action::RemoveCollateral { vault_id, amount } => {
+        let c_type_id = self.open_vault(vault_id)?;
+        self.update_interest(c_type_id)?;
+        let risk = self.risk(vault_id)?;
+        // The subtraction reverts before the collateral check
+        require(
+          amount <= risk.vault.collateral_amount,
+          revert("collateral underflow")
+        )?;
+        risk.check_remove_collateral(amount).map_err(YamaError::CDP)?;
+        self.vaults[vault_id as usize].collateral_amount -= amount;
+        self.collateral_types[c_type_id as usize].total_collateral -= amount;
+        credit(&mut self.wallet.collateral, amount)?;
+      },
+      Action::UpdateInterest { collateral_type_id } => {
+        self.update_interest(collateral_type_id)?;
+      },
+      Action::Liquidate { vault_id } => {
+        // Checked before interest is updated, as the contract does
+        let risk = self.risk(vault_id)?;
+        require(
+          !risk.vault.is_liquidated,
+          YamaError::CDP(CDPError::Liquidated)
+        )?;
+        require(
+          risk.is_undercollateralized(),
+          YamaError::CDP(CDPError::NotUndercollateralized)
+        )?;
+        self.vaults[vault_id as usize].is_liquidated = true;
+        self.update_interest(risk.vault.collateral_type_id)?;
+        self.initialize_auction(vault_id)?;
+        self.metrics.liquidations += 1;
+      },
+      Action::Claim { auction_id, max_price, amount } => {
+        self.claim(auction_id, max_price, amount)?;
+      },
+      Action::ResetAuction { auction_id } => {
+        let auction = self.auction(auction_id)?;
+        let vault_id = auction.vault_id;
+        require(
+          !auction.done,
+          YamaError::DutchAuctionLiquidator(
+            DutchAuctionLiquidatorError::AuctionDone)
+        )?;
+        require(
+          self.is_expired(auction_id)?,
+          YamaError::DutchAuctionLiquidator(
+            DutchAuctionLiquidatorError::AuctionNotExpired)
+        )?;
+        self.auctions[auction_id as usize].done = true;
+        self.initialize_auction(vault_id)?;
+        self.metrics.resets += 1;
+      },
+      Action::PsmDeposit(amount) => {
+        debit(&mut self.wallet.collateral, amount)?;
+        let yama_amount = self.psm_deposit(amount)?;
+        credit(&mut self.wallet.stablecoin, yama_amount)?;
+      },
+      Action::PsmWithdraw(amount) => {
+        debit(&mut self.wallet.stablecoin, amount)?;
+        let quote = checked(psm::quote_withdraw_amount(
+          amount,
+          self.psm.external_stable_decimals,
+          self.psm.yss_decimals,
+          self.psm.tout
+        ))?;
+        debit(&mut self.psm.reserves, quote.amount)?;
+        credit(&mut self.wallet.collateral, quote.amount)?;
+        debit(&mut self.stablecoin_supply, amount)?;
+        self.issuance.psm -= (amount - quote.fee) as i128;
+        self.add_surplus(quote.fee as i128)?;
+      },
+      Action::Lockup(amount) => {
+        debit(&mut self.wallet.collateral, amount)?;
+        self.process_pending_share_amount()?;
+        let saved_value = self.lockup_value();
+        let yama_amount = self.psm_deposit(amount)?;
+        credit(&mut self.lockup.balance, yama_amount)?;
+        let lockup_amount = checked(
+          Ufp128::from_u64(yama_amount).checked_div(saved_value))?.to_u64();
+        credit(&mut self.wallet.lockup_shares, lockup_amount)?;
+        credit(&mut self.lockup.total_supply, lockup_amount)?;
+      },
+      Action::Redeem(amount) => {
+        debit(&mut self.wallet.lockup_shares, amount)?;
+        let yama_amount = checked(
+          Ufp128::from_u64(amount).checked_mul(self.lockup_value()))?.to_u64();
+        require(
+          yama_amount <= self.lockup.balance,
+          YamaError::PSMLockup(PSMLockupError::InsufficientLiquidity)
+        )?;
+        debit(&mut self.lockup.balance, yama_amount)?;
+        credit(&mut self.wallet.stablecoin, yama_amount)?;
+        debit(&mut self.lockup.total_supply, amount)?;
+      },
+      Action::FlashLoan { vault_id, amount } => {
+        let proxy = self.leverage_proxy
+          .clone()
+          .ok_or_else(|| revert("no leverage proxy"))?;
+        let Vault { owner, alt_owner, .. } = self.vault(vault_id)?.clone();
+        // verify_sender_owns_vault unwraps the alt owner
+        let alt_owner = alt_owner
+          .ok_or_else(|| revert("vault has no alt owner"))?;
+        require(
+          alt_owner == self.owner,
+          YamaError::LeverageProxy(LeverageProxyError::NotVaultOwner)
+        )?;
+        require(
+          amount <= self.flash_mint_max,
+          YamaError::FlashMintModule(FlashMintModuleError::ExceedsMax)
+        )?;
+        // The callback calls the CDP module as the proxy
+        require(owner == proxy, YamaError::CDP(CDPError::NotVaultOwner))?;
+        self.add_collateral(vault_id, amount)?;
+        self.borrow(vault_id, amount)?;
+        // FlashMintModule::repay requires loan_index > 0, which the only
+        // outstanding loan, at index 0, never passes
+        return Err(YamaError::FlashMintModule(
+          FlashMintModuleError::NoLoanToRepay));
+      }
+    }
+    Ok(())
+  }
+
+  fn create_vault(
+    &mut self,
+    collateral_type_id: u64,
+    collateral_amount: u64,
+    owner: Identity,
+    alt_owner: Option<Identity>
+  ) -> Result<(), YamaError> {
+    let c_type = self.collateral_types
+      .get_mut(collateral_type_id as usize)
+      .ok_or_else(|| revert("collateral type doesn't exist"))?;
+    credit(&mut c_type.total_collateral, collateral_amount)?;
+    self.vaults.push(Vault {
+      collateral_amount,
+      collateral_type_id,
+      owner,
+      alt_owner,
+      initial_debt: Ufp128::ZERO.into(),
+      is_liquidated: false
+    });
+    Ok(())
+  }
+
+  fn add_collateral(
+    &mut self,
+    vault_id: u64,
+    amount: u64
+  ) -> Result<(), YamaError> {
+    let c_type_id = self.open_vault(vault_id)?;
+    credit(&mut self.vaults[vault_id as usize].collateral_amount, amount)?;
+    credit(
+      &mut self.collateral_types[c_type_id as usize].total_collateral,
+      amount
+    )
+  }
+
+  // Mints the stablecoin borrowed to the caller
+  fn borrow(&mut self, vault_id: u64, amount: u64) -> Result<(), YamaError> {
+    let c_type_id = self.open_vault(vault_id)?;
+    require(
+      self.collateral_type(c_type_id)?.borrowing_enabled,
+      YamaError::CDP(CDPError::CollateralTypeBorrowingDisabled)
+    )?;
+    self.update_interest(c_type_id)?;
+    let risk = self.risk(vault_id)?;
+    risk.check_borrow(amount).map_err(YamaError::CDP)?;
+    self.set_debt(vault_id, risk.debt() + Ufp128::from_u64(amount))?;
+    credit(&mut self.stablecoin_supply, amount)
+  }
+
+  // The vault's collateral type, if the vault exists and isn't liquidated
+  fn open_vault(&self, vault_id: u64) -> Result<u64, YamaError> {
+    let vault = self.vault(vault_id)?;
+    require(!vault.is_liquidated, YamaError::CDP(CDPError::Liquidated))?;
+    Ok(vault.collateral_type_id)
+  }
+
+  fn update_interest(
+    &mut self,
+    collateral_type_id: u64
+  ) -> Result<(), YamaError> {
+    let now = self.now;
+    let c_type = self.collateral_types
+      .get_mut(collateral_type_id as usize)
+      .ok_or_else(|| revert("collateral type doesn't exist"))?;
+    let time_delta = checked(now.checked_sub(c_type.last_update_time))?;
+    if time_delta == 0 {
+      return Ok(());
+    }
+
+    let initial_debt = Ufp128::from(&c_type.initial_debt);
+    let cumulative_interest = Ufp128::from(&c_type.cumulative_interest);
+    let old_total_debt = checked(
+      initial_debt.checked_mul(cumulative_interest))?.to_u64();
+    let cumulative_interest = checked(
+      Ufp128::from(&c_type.interest_rate)
+        .checked_powu(time_delta)
+        .and_then(|growth| cumulative_interest.checked_mul(growth))
+    )?;
+    c_type.cumulative_interest = cumulative_interest.into();
+    c_type.last_update_time = now;
+    let new_total_debt = checked(
+      initial_debt.checked_mul(cumulative_interest))?.to_u64();
+
+    let interest = new_total_debt as i128 - old_total_debt as i128;
+    self.metrics.interest += interest;
+    self.add_surplus(interest)
+  }
+
+  fn set_debt(
+    &mut self,
+    vault_id: u64,
+    new_debt: Ufp128
+  ) -> Result<(), YamaError> {
+    let vault = &self.vaults[vault_id as usize];
+    let c_type = &self.collateral_types[vault.collateral_type_id as usize];
+    let new_initial_debt = checked(
+      new_debt.checked_div(Ufp128::from(&c_type.cumulative_interest)))?;
+    let total_initial_debt = checked(
+      Ufp128::from(&c_type.initial_debt)
+        .checked_add(new_initial_debt)
+        .and_then(|total| total.checked_sub(Ufp128::from(&vault.initial_debt)))
+    )?;
+
+    let c_type_id = vault.collateral_type_id as usize;
+    self.vaults[vault_id as usize].initial_debt = new_initial_debt.into();
+    self.collateral_types[c_type_id].initial_debt = total_initial_debt.into();
+    self.metrics.debt_updates += 1;
+    Ok(())
+  }
+
+  fn initialize_auction(&mut self, vault_id: u64) -> Result<(), YamaError> {
+    let partial_lot = self.partial_lot_of_vault(vault_id)?;
+    let vault = self.vault(vault_id)?;
+    let params = self.c_type_params
+      .get(vault.collateral_type_id as usize)
+      .ok_or_else(|| revert("collateral type doesn't exist"))?;
+    let collateral_amount = partial_lot
+      .as_ref()
+      .map_or(vault.collateral_amount, |lot| lot.collateral_amount);
+    // The CDP module reports the collateral value in whole tokens
+    let collateral_value = checked(
+      Ufp128::from_u64(collateral_amount).checked_mul(self.price))?;
+    let start_price = checked(
+      Ufp128::from_u64(collateral_value.to_u64())
+        .checked_mul(Ufp128::from(&params.initial_price_ratio)))?;
+    self.auctions.push(Auction {
+      vault_id,
+      start_price: start_price.into(),
+      start_time: self.now,
+      done: false
+    });
+    self.partial_lots.push(partial_lot);
+    Ok(())
+  }
+
+  // What a partial liquidation of the vault sells, rounded as the liquidator
+  // rounds it, or None when the whole vault is sold
+  fn partial_lot_of_vault(
+    &self,
+    vault_id: u64
+  ) -> Result<Option<PartialLot>, YamaError> {
+    let risk = self.risk(vault_id)?;
+    let params = self.partial_params
+      .get(risk.vault.collateral_type_id as usize)
+      .ok_or_else(|| revert("collateral type doesn't exist"))?;
+    if !params.enabled {
+      return Ok(None);
+    }
+
+    let debt = risk.debt().to_u64();
+    let target_ratio = Ufp128::from(&params.target_ratio);
+    let penalty = Ufp128::from(&params.penalty);
+    let collateral_value = Ufp128::from_u64(checked(
+      Ufp128::from_u64(risk.vault.collateral_amount)
+        .checked_mul(self.price))?.to_u64());
+    let target_value = checked(
+      Ufp128::from_u64(debt).checked_mul(target_ratio))?;
+    if self.price == Ufp128::ZERO || collateral_value.ge(target_value) {
+      return Ok(None);
+    }
+
+    let debt_to_cover = checked(
+      target_ratio
+        .checked_sub(Ufp128::ONE)
+        .and_then(|ratio| ratio.checked_sub(penalty))
+        .and_then(|divisor| (target_value - collateral_value)
+          .checked_div(divisor))
+    )?;
+    let lot = PartialLot {
+      collateral_amount: checked(
+        Ufp128::ONE
+          .checked_add(penalty)
+          .and_then(|markup| debt_to_cover.checked_mul(markup))
+          .and_then(|value| value.checked_div(self.price))
+      )?.to_u64(),
+      debt: debt_to_cover.to_u64()
+    };
+    if lot.collateral_amount == 0
+      || lot.debt >= debt
+      || lot.collateral_amount >= risk.vault.collateral_amount
+    {
+      return Ok(None);
+    }
+    Ok(Some(lot))
+  }
+
+  fn claim(
+    &mut self,
+    auction_id: u64,
+    max_price: u64,
+    amount: u64
+  ) -> Result<(), YamaError> {
+    require(amount <= self.wallet.stablecoin, revert("insufficient balance"))?;
+    let auction = self.auction(auction_id)?;
+    let vault_id = auction.vault_id;
+    require(
+      !auction.done,
+      YamaError::DutchAuctionLiquidator(
+        DutchAuctionLiquidatorError::AuctionDone)
+    )?;
+    require(
+      !self.is_expired(auction_id)?,
+      YamaError::DutchAuctionLiquidator(
+        DutchAuctionLiquidatorError::AuctionExpired)
+    )?;
+    let price = self.auction_price(auction_id)?;
+    require(
+      amount >= price,
+      YamaError::DutchAuctionLiquidator(
+        DutchAuctionLiquidatorError::InvalidPayment)
+    )?;
+    require(
+      price <= max_price,
+      YamaError::DutchAuctionLiquidator(
+        DutchAuctionLiquidatorError::ExceedsMaxPrice)
+    )?;
+    // The rest of the payment is refunded
+    debit(&mut self.wallet.stablecoin, price)?;
+    debit(&mut self.stablecoin_supply, price)?;
+
+    if let Some(lot) = self.partial_lots[auction_id as usize].clone() {
+      return self.claim_partial(auction_id, &lot, price);
+    }
+    let vault = self.vault(vault_id)?;
+    let c_type_id = vault.collateral_type_id;
+    let collateral_amount = vault.collateral_amount;
+    credit(&mut self.wallet.collateral, collateral_amount)?;
+    self.update_interest(c_type_id)?;
+    let debt = self.risk(vault_id)?.debt().to_u64();
+    self.add_surplus(price as i128 - debt as i128)?;
+
+    // CDPModule::clear_vault
+    self.collateral_types[c_type_id as usize].total_collateral
+      -= collateral_amount;
+    self.vaults[vault_id as usize].collateral_amount = 0;
+    self.set_debt(vault_id, Ufp128::ZERO)?;
+    self.auctions[auction_id as usize].done = true;
+
+    self.metrics.claims += 1;
+    self.metrics.auction_proceeds += price;
+    self.metrics.bad_debt += debt.saturating_sub(price);
+    Ok(())
+  }
+
+  // The rest of a claim on a partial liquidation, once the price is paid
+  fn claim_partial(
+    &mut self,
+    auction_id: u64,
+    lot: &PartialLot,
+    price: u64
+  ) -> Result<(), YamaError> {
+    let vault_id = self.auctions[auction_id as usize].vault_id;
+    let c_type_id = self.vault(vault_id)?.collateral_type_id;
+    credit(&mut self.wallet.collateral, lot.collateral_amount)?;
+    self.update_interest(c_type_id)?;
+    let debt = self.risk(vault_id)?.debt();
+    let debt_repaid = price.min(lot.debt).min(debt.to_u64());
+    self.add_surplus((price - debt_repaid) as i128)?;
+
+    // CDPModule::partial_clear_vault
+    self.collateral_types[c_type_id as usize].total_collateral
+      -= lot.collateral_amount;
+    let vault = &mut self.vaults[vault_id as usize];
+    vault.collateral_amount -= lot.collateral_amount;
+    vault.is_liquidated = false;
+    self.set_debt(vault_id, debt - Ufp128::from_u64(debt_repaid))?;
+    self.auctions[auction_id as usize].done = true;
+
+    self.metrics.claims += 1;
+    self.metrics.auction_proceeds += price;
+    self.metrics.penalties += price - debt_repaid;
+    Ok(())
+  }
+
+  // BalanceSheet::add_surplus followed by SimpleBSH::on_add_surplus
+  fn add_surplus(&mut self, amount: i128) -> Result<(), YamaError> {
+    self.surplus += amount;
+    let share_amount = amount * self.revenue_share as i128
+      / SBSH_DENOMINATOR as i128;
+    if share_amount > 0 {
+      self.pending_share_amount += share_amount;
+      self.mint_to_bsh_target(share_amount as u64)?;
+      // add_deficit, whose handler callback SimpleBSH ignores
+      self.surplus -= share_amount;
+    } else if amount < 0 && self.absorb_deficits {
+      // The lockup's stablecoin is burned and booked as surplus, again
+      // without a callback
+      let absorbed = self.lockup.balance.min(amount.unsigned_abs() as u64);
+      debit(&mut self.lockup.balance, absorbed)?;
+      debit(&mut self.stablecoin_supply, absorbed)?;
+      self.surplus += absorbed as i128;
+      self.metrics.deficit_absorbed += absorbed;
+    }
+    Ok(())
+  }
+
+  // SimpleBSH mints the pending amount again here, on top of what
+  // on_add_surplus already minted
+  fn process_pending_share_amount(&mut self) -> Result<(), YamaError> {
+    if self.pending_share_amount > 0 {
+      self.mint_to_bsh_target(self.pending_share_amount as u64)?;
+      self.issuance.reminted += self.pending_share_amount as u64;
+      self.pending_share_amount = 0;
+    }
+    Ok(())
+  }
+
+  fn mint_to_bsh_target(&mut self, amount: u64) -> Result<(), YamaError> {
+    if self.revenue_to_lockup {
+      credit(&mut self.lockup.balance, amount)?;
+    }
+    credit(&mut self.stablecoin_supply, amount)?;
+    self.metrics.revenue_shared += amount;
+    Ok(())
+  }
+
+  // PegStabilityModule::deposit, returning the stablecoin minted
+  fn psm_deposit(&mut self, amount: u64) -> Result<u64, YamaError> {
+    let quote = checked(psm::quote_deposit_amount(
+      amount,
+      self.psm.external_stable_decimals,
+      self.psm.yss_decimals,
+      self.psm.tin
+    ))?;
+    credit(&mut self.psm.reserves, amount)?;
+    credit(&mut self.stablecoin_supply, quote.amount)?;
+    self.issuance.psm += (quote.amount + quote.fee) as i128;
+    self.add_surplus(quote.fee as i128)?;
+    require(
+      self.psm.reserves <= self.psm.debt_ceiling,
+      YamaError::PSM(PSMError::ExceedsDebtCeiling)
+    )?;
+    Ok(quote.amount)
+  }
+}
+
+// Reads what SystemSnapshot covers from a deployment
+pub async fn read_snapshot(
+  deployment: &YamaDeployment
+) -> Result<SystemSnapshot, YamaError> {
+  let YamaDeployment {
+    wallet,
+    stable_i,
+    collat_i,
+    psm,
+    psm_lockup,
+    balancesheet,
+    simplebsh,
+    cdp,
+    dutchauctionliquidator,
+    collateral_type_ids,
+    ..
+  } = deployment;
+  let provider = wallet.get_provider().unwrap();
+
+  // Neither module exposes a count, and reads past the end revert
+  let mut vaults: Vec<Vault> = vec![];
+  while let Ok(response) = cdp
+    .methods()
+    .get_vault(vaults.len() as u64)
+    .simulate()
+    .await
+  {
+    vaults.push(response.value);
+  }
+  let mut auctions: Vec<Auction> = vec![];
+  let mut partial_lots: Vec<Option<PartialLot>> = vec![];
+  while let Ok(auction) = dutchauctionliquidator::get_auction(
+    dutchauctionliquidator, auctions.len() as u64).await
+  {
+    partial_lots.push(dutchauctionliquidator::get_partial_lot(
+      dutchauctionliquidator, auctions.len() as u64).await?);
+    auctions.push(auction);
+  }
+  let mut collateral_types: Vec<CollateralType> = vec![];
+  for collateral_type_id in collateral_type_ids.iter() {
+    collateral_types.push(cdp
+      .methods()
+      .get_collateral_type(*collateral_type_id)
+      .simulate()
+      .await?
+      .value);
+  }
+
+  let surplus = balancesheet
+    .methods()
+    .total_surplus()
+    .set_contract_ids(&[stable_i.get_contract_id().clone()])
+    .simulate()
+    .await?
+    .value;
+  let pending_share_amount = simplebsh
+    .methods()
+    .get_pending_share_amount()
+    .simulate()
+    .await?
+    .value;
+
+  let lockup_aid = AssetId::new(
+    *ContractId::from(psm_lockup.get_contract_id()));
+  let wallet_balance = |asset_id: AssetId| async move {
+    wallet.get_asset_balance(&asset_id).await.map_err(provider_error)
+  };
+
+  Ok(SystemSnapshot {
+    vaults,
+    collateral_types,
+    auctions,
+    partial_lots,
+    surplus: i256_to_i128(&surplus).ok_or_else(|| revert("surplus overflow"))?,
+    pending_share_amount: i256_to_i128(&pending_share_amount)
+      .ok_or_else(|| revert("pending share amount overflow"))?,
+    psm_reserves: provider
+      .get_contract_asset_balance(psm.get_contract_id(), get_aid(collat_i))
+      .await
+      .map_err(provider_error)?,
+    lockup: LockupState {
+      balance: provider
+        .get_contract_asset_balance(
+          psm_lockup.get_contract_id(), get_aid(stable_i))
+        .await
+        .map_err(provider_error)?,
+      total_supply: psm_lockup
+        .methods()
+        .total_supply()
+        .simulate()
+        .await?
+        .value
+    },
+    wallet: Balances {
+      stablecoin: wallet_balance(get_aid(stable_i)).await?,
+      collateral: wallet_balance(get_aid(collat_i)).await?,
+      lockup_shares: wallet_balance(lockup_aid).await?
+    },
+    stablecoin_supply: stable_i
+      .methods()
+      .total_supply()
+      .simulate()
+      .await?
+      .value
+  })
+}
+
+// Decoded Yama errors have to match exactly; any other failure matches any
+// other failure, since the simulator can't tell them apart
+pub fn same_outcome(
+  a: &Result<(), YamaError>,
+  b: &Result<(), YamaError>
+) -> bool {
+  let decoded = |error: &YamaError| match error {
+    YamaError::Reverted { .. } | YamaError::Sdk(_) => None,
+    error => Some(error.to_string())
+  };
+  match (a, b) {
+    (Ok(()), Ok(())) => true,
+    (Err(a), Err(b)) => decoded(a) == decoded(b),
+    _ => false
+  }
+}
+
+#[derive(Debug)]
+pub struct ReplayStep {
+  pub action: Action,
+  pub chain: Result<(), YamaError>,
+  pub simulated: Result<(), YamaError>,
+  // Snapshot fields the simulator disagrees on after the step
+  pub diverged: Vec<&'static str>
+}
+
+impl ReplayStep {
+  pub fn matches(&self) -> bool {
+    same_outcome(&self.chain, &self.simulated) && self.diverged.is_empty()
+  }
+}
+
+// Runs `action` against a deployment. Without a MockPriceSource the price is
+// fixed, so SetPrice only succeeds for the price it already reports.
+async fn execute_on_chain(
+  deployment: &YamaDeployment,
+  client: &mut YamaClient,
+  action: &Action
+) -> Result<(), YamaError> {
+  let YamaDeployment {
+    wallet,
+    stable_i,
+    collat_i,
+    psm,
+    psm_lockup,
+    simplebsh,
+    price_source,
+    mock_price_source,
+    lproxy,
+    flashmintmodule,
+    swapper,
+    ..
+  } = deployment;
+  match *action {
+    Action::AdvanceTime(seconds) =>
+      Clock::of(wallet).advance(seconds).await.map(|_| ()),
+    Action::SetPrice(price) => match mock_price_source {
+      Some(mock) => mockpricesource::set_price(mock, &price.into()).await,
+      None => {
+        let current = Ufp128::from(
+          psmpricesource::get_price(price_source, wallet).await?);
+        require(current == price, revert("the price source can't be set"))
+      }
+    },
+    Action::MintCollateral(amount) => mint(collat_i, wallet, amount).await,
+    Action::MintStablecoin(amount) => mint(stable_i, wallet, amount).await,
+    Action::CreateVault { collateral_type_id, collateral_amount } => client
+      .create_vault(collateral_type_id, collateral_amount, None)
+      .await
+      .map(|_| ()),
+    Action::CreateProxyVault { collateral_type_id, collateral_amount } => {
+      let lproxy = lproxy
+        .as_ref()
+        .ok_or_else(|| revert("no leverage proxy"))?;
+      leverageproxy::create_vault(
+        lproxy,
+        collat_i.get_contract_id(),
+        collateral_type_id,
+        collateral_amount,
+        &client.cdp
+      ).await.map(|_| ())
+    },
+    Action::AddCollateral { vault_id, amount }
+      => client.add_collateral(vault_id, amount).await,
+    Action::Borrow { vault_id, amount }
+      => client.borrow(vault_id, amount).await,
+    Action::Repay { vault_id, amount }
+      => client.repay(vault_id, amount).await,
+    Action::RemoveCollateral { vault_id, amount }
+      => client.remove_collateral(vault_id, amount).await,
+    Action::UpdateInterest { collateral_type_id }
+      => client.update_interest(collateral_type_id).await,
+    Action::Liquidate { vault_id } => client.liquidate(vault_id).await,
+    Action::Claim { auction_id, max_price, amount }
+      => client.claim(auction_id, max_price, amount).await,
+    Action::ResetAuction { auction_id }
+      => client.reset_auction(auction_id).await,
+    Action::PsmDeposit(amount) => psm::deposit(
+      psm, &get_cid(stable_i), &get_aid(collat_i), amount).await,
+    Action::PsmWithdraw(amount) => psm::withdraw(
+      psm,
+      &get_cid(stable_i),
+      &get_aid(stable_i),
+      &get_aid(collat_i),
+      amount
+    ).await,
+    Action::Lockup(amount) => psmlockup::lockup_with_bsh(
+      psm_lockup,
+      psm.get_contract_id(),
+      stable_i.get_contract_id(),
+      simplebsh.get_contract_id(),
+      &get_aid(collat_i),
+      amount
+    ).await,
+    Action::Redeem(amount) => psmlockup::redeem(
+      psm_lockup,
+      psm.get_contract_id(),
+      stable_i.get_contract_id(),
+      amount
+    ).await,
+    Action::FlashLoan { vault_id, amount } => {
+      let (lproxy, flashmintmodule, swapper) = match (
+        lproxy, flashmintmodule, swapper
+      ) {
+        (Some(lproxy), Some(flashmintmodule), Some(swapper))
+          => (lproxy, flashmintmodule, swapper),
+        _ => return Err(revert("no leverage proxy"))
+      };
+      let vault_price_source = client.price_source_of(vault_id).await?;
+      let price_source_ids = aggregatorpricesource::price_source_ids(
+        &vault_price_source, &client.wallet).await?;
+      let deps = client.dependencies();
+      leverageproxy::leverage_up_with_contracts(
+        lproxy,
+        vault_id,
+        amount,
+        amount,
+        &[
+          vec![
+            flashmintmodule.get_contract_id().clone(),
+            get_collateral_manager(&client.cdp).await?,
+            client.cdp.get_contract_id().clone(),
+            swapper.get_contract_id().clone(),
+            collat_i.get_contract_id().clone(),
+            deps.stablecoin.clone(),
+            deps.balancesheet.clone(),
+            deps.bsh.clone()
+          ],
+          price_source_ids,
+          deps.bsh_targets.clone()
+        ].concat()
+      ).await
+    }
+  }
+}
+
+// Runs `action` on a deployment and on `simulator`, which should be in step
+// with it, stepping a transaction at the time of the block it landed in.
+// Returns the deployment's state after the step.
+pub async fn replay_step(
+  deployment: &YamaDeployment,
+  client: &mut YamaClient,
+  simulator: &mut Simulator,
+  action: &Action
+) -> Result<(ReplayStep, SystemSnapshot), YamaError> {
+  let chain = execute_on_chain(deployment, client, action).await;
+  if action.is_transaction() {
+    let provider = deployment.wallet.get_provider().unwrap();
+    let now = unix_to_tai64(get_timestamp(provider).await?);
+    simulator.now = simulator.now.max(now);
+  }
+  let simulated = simulator.apply(action);
+  let snapshot = read_snapshot(deployment).await?;
+  let step = ReplayStep {
+    action: *action,
+    chain,
+    simulated,
+    diverged: snapshot.diff(&simulator.snapshot())
+  };
+  Ok((step, snapshot))
+}
+
+// Runs `actions` on a deployment and on a simulator started from its state,
+// comparing the two after every step
+pub async fn replay(
+  deployment: &YamaDeployment,
+  actions: &[Action]
+) -> Result<Vec<ReplayStep>, YamaError> {
+  let mut simulator = Simulator::from_chain(deployment).await?;
+  let mut client = deployment.client().await?;
+
+  let mut steps: Vec<ReplayStep> = vec![];
+  for action in actions.iter() {
+    let (step, _) = replay_step(
+      deployment, &mut client, &mut simulator, action).await?;
+    steps.push(step);
+  }
+  Ok(steps)
+}
+
+// Vaults opened at a stable price that then crashes, with keepers
+// liquidating and bidders claiming once auctions trade at a discount
+#[derive(Clone, Debug)]
+pub struct CrashConfig {
+  pub plan: DeploymentPlan,
+  pub vaults: usize,
+  // Inclusive range each vault's collateral is drawn from
+  pub collateral: (u64, u64),
+  // Share of each vault's borrowing limit that's drawn
+  pub utilization: (f64, f64),
+  pub ticks: usize,
+  pub tick_seconds: u64,
+  // Standard deviation of the log price change per tick
+  pub volatility: f64,
+  // Tick range the crash starts in
+  pub crash_start: (usize, usize),
+  pub crash_ticks: usize,
+  // How much of the price the crash wipes out
+  pub crash_depth: (f64, f64),
+  // Bidders claim once the price is this far below the collateral's value
+  pub bidder_discount_bps: u64
+}
+
+impl Default for CrashConfig {
+  fn default() -> Self {
+    let interest_rate = apy_to_per_second(parse_rate("5%").unwrap())
+      .unwrap()
+      .per_second;
+    CrashConfig {
+      plan: DeploymentPlan {
+        liquidator: LiquidatorPlan {
+          initial_price_ratio: "1.5".parse::<Ufp128>().unwrap().into(),
+          time_interval: 60,
+          change_rate: "0.99".parse::<Ufp128>().unwrap().into(),
+          reset_threshold: 120
+        },
+        collateral_types: vec![CollateralTypePlan {
+          debt_floor: Ufp128::from_u64(100).into(),
+          debt_ceiling: Ufp128::from_u64(100_000_000).into(),
+          collateral_ratio: "1.5".parse::<Ufp128>().unwrap().into(),
+          interest_rate: interest_rate.into(),
+          borrowing_enabled: true,
+          allowlist_enabled: false,
+          partial_liquidation: None
+        }],
+        ..DeploymentPlan::default()
+      },
+      vaults: 20,
+      collateral: (1_000, 100_000),
+      utilization: (0.5, 1.0),
+      ticks: 1_440,
+      tick_seconds: 60,
+      volatility: 0.002,
+      crash_start: (60, 720),
+      crash_ticks: 30,
+      crash_depth: (0.2, 0.6),
+      bidder_discount_bps: 500
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScenarioResult {
+  pub seed: u64,
+  pub metrics: Metrics,
+  // Debt still stuck in auctions at the end
+  pub unresolved_debt: u64,
+  pub surplus: i128,
+  pub lowest_price: Ufp128
+}
+
+impl ScenarioResult {
+  pub fn total_loss(&self) -> u64 {
+    self.metrics.bad_debt.saturating_add(self.unresolved_debt)
+  }
+}
+
+// Box-Muller, since rand_distr isn't a dependency
+fn standard_normal(rng: &mut StdRng) -> f64 {
+  let u1: f64 = 1.0 - rng.gen::<f64>();
+  let u2: f64 = rng.gen();
+  (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+}
+
+// A random walk from 1 with the crash spread evenly over its ticks
+pub fn crash_price_path(
+  config: &CrashConfig,
+  rng: &mut StdRng
+) -> Vec<Ufp128> {
+  let crash_start = rng.gen_range(config.crash_start.0..=config.crash_start.1);
+  let depth = rng.gen_range(config.crash_depth.0..=config.crash_depth.1);
+  let crash_step = (1.0 - depth).ln() / config.crash_ticks.max(1) as f64;
+
+  let mut log_price: f64 = 0.0;
+  (0..config.ticks)
+    .map(|tick| {
+      log_price += config.volatility * standard_normal(rng);
+      if tick >= crash_start && tick < crash_start + config.crash_ticks {
+        log_price += crash_step;
+      }
+      Ufp128::from_f64(log_price.exp()).unwrap_or(Ufp128::ZERO)
+    })
+    .collect()
+}
+
+// One tick of keepers and bidders: accrue interest, liquidate what's
+// undercollateralized, claim auctions trading at the bidders' discount and
+// restart expired ones. Reverts are skipped like a keeper would skip them.
+fn run_keepers(simulator: &mut Simulator, bidder_discount_bps: u64) {
+  for collateral_type_id in 0..simulator.collateral_types.len() as u64 {
+    let _ = simulator.apply(&Action::UpdateInterest { collateral_type_id });
+  }
+  for vault_id in 0..simulator.vaults.len() as u64 {
+    let liquidatable = simulator.risk(vault_id).is_ok_and(|risk|
+      !risk.vault.is_liquidated && risk.is_undercollateralized());
+    if liquidatable {
+      let _ = simulator.apply(&Action::Liquidate { vault_id });
+    }
+  }
+
+  let discount = BPS_DENOMINATOR.saturating_sub(bidder_discount_bps) as u128;
+  for auction_id in 0..simulator.auctions.len() as u64 {
+    if simulator.auctions[auction_id as usize].done {
+      continue;
+    }
+    if simulator.is_expired(auction_id).unwrap_or(false) {
+      let _ = simulator.apply(&Action::ResetAuction { auction_id });
+      continue;
+    }
+    let collateral_amount = match simulator
+      .collateral_amount_of_auction(auction_id)
+    {
+      Ok(collateral_amount) => collateral_amount,
+      Err(_) => continue
+    };
+    let value = collateral_value(collateral_amount, &simulator.price.into());
+    let max_price = (value as u128 * discount / BPS_DENOMINATOR as u128) as u64;
+    let price = match simulator.auction_price(auction_id) {
+      Ok(price) if price <= max_price => price,
+      _ => continue
+    };
+    // Bidders bring their own stablecoin
+    let _ = simulator.apply(&Action::MintStablecoin(price));
+    let _ = simulator.apply(&Action::Claim {
+      auction_id,
+      max_price,
+      amount: price
+    });
+  }
+}
+
+pub fn run_crash_scenario(config: &CrashConfig, seed: u64) -> ScenarioResult {
+  let mut rng = StdRng::seed_from_u64(seed);
+  let prices = crash_price_path(config, &mut rng);
+  let mut simulator = Simulator::new(&config.plan, TAI64_OFFSET);
+  let c_type_count = simulator.collateral_types.len() as u64;
+
+  for _ in 0..config.vaults {
+    let collateral_type_id = rng.gen_range(0..c_type_count);
+    let collateral_amount
+      = rng.gen_range(config.collateral.0..=config.collateral.1);
+    let utilization
+      = rng.gen_range(config.utilization.0..=config.utilization.1);
+    let vault_id = simulator.vaults.len() as u64;
+    let opened = simulator.apply(&Action::MintCollateral(collateral_amount))
+      .and_then(|_| simulator.apply(&Action::CreateVault {
+        collateral_type_id,
+        collateral_amount
+      }));
+    if opened.is_err() {
+      continue;
+    }
+    let max_borrowable = simulator.risk(vault_id)
+      .map_or(0, |risk| risk.max_borrowable());
+    // Draws below the debt floor revert and leave the vault without debt
+    let _ = simulator.apply(&Action::Borrow {
+      vault_id,
+      amount: (max_borrowable as f64 * utilization) as u64
+    });
+  }
+
+  let mut lowest_price = simulator.price;
+  for price in prices {
+    let _ = simulator.apply(&Action::AdvanceTime(config.tick_seconds));
+    let _ = simulator.apply(&Action::SetPrice(price));
+    lowest_price = lowest_price.min(price);
+    run_keepers(&mut simulator, config.bidder_discount_bps);
+  }
+
+  ScenarioResult {
+    seed,
+    metrics: simulator.metrics,
+    unresolved_debt: simulator.unresolved_debt(),
+    surplus: simulator.surplus,
+    lowest_price
+  }
+}
+
+#[derive(Clone, Debug)]
+pub struct CrashReport {
+  pub results: Vec<ScenarioResult>
+}
+
+impl CrashReport {
+  pub fn total_bad_debt(&self) -> u64 {
+    self.results
+      .iter()
+      .map(|result| result.metrics.bad_debt)
+      .fold(0, u64::saturating_add)
+  }
+
+  pub fn mean_bad_debt(&self) -> f64 {
+    if self.results.is_empty() {
+      return 0.0;
+    }
+    self.total_bad_debt() as f64 / self.results.len() as f64
+  }
+
+  pub fn max_loss(&self) -> u64 {
+    self.results.iter().map(ScenarioResult::total_loss).max().unwrap_or(0)
+  }
+
+  pub fn runs_with_losses(&self) -> usize {
+    self.results.iter().filter(|result| result.total_loss() > 0).count()
+  }
+
+  // The loss that a `quantile` share of runs stay at or below
+  pub fn loss_quantile(&self, quantile: f64) -> u64 {
+    let mut losses: Vec<u64> = self.results
+      .iter()
+      .map(ScenarioResult::total_loss)
+      .collect();
+    if losses.is_empty() {
+      return 0;
+    }
+    losses.sort_unstable();
+    let index = (quantile.clamp(0.0, 1.0) * (losses.len() - 1) as f64).round();
+    losses[index as usize]
+  }
+}
+
+// Runs one scenario per seed
+pub fn run_crash_scenarios(
+  config: &CrashConfig,
+  seeds: Range<u64>
+) -> CrashReport {
+  CrashReport {
+    results: seeds.map(|seed| run_crash_scenario(config, seed)).collect()
+  }
+}
@@ -0,0 +1,117 @@
+use fuels::{
+    prelude::*,
+    types::{Bits256, Identity}
+};
+use crate::{
+    errors::{
+        register_contract,
+        YamaContract,
+        YamaError
+    },
+    storage::StorageLayout,
+    modulartoken::{
+        set_allowlist,
+        get_cid
+    },
+    abigen::*
+};
+
+// It still has to be set as the balance sheet's handler to receive surplus
+pub async fn init_custom_splitbsh(
+    wallet: &WalletUnlocked,
+    stable_i: &ModularToken,
+    balancesheet_module: &ContractId
+) -> Result<SplitBSH, YamaError> {
+    let storage_path = "../splitbsh/out/debug/splitbsh-storage_slots.json";
+
+    let storage = StorageLayout::new()
+        .field(0, &get_cid(stable_i))
+        .field(1, balancesheet_module);
+
+    let id = Contract::deploy(
+        "../splitbsh/out/debug/splitbsh.bin",
+        &wallet,
+        TxParameters::default(),
+        storage.configuration(storage_path)
+    ).await?;
+
+    register_contract(&id, YamaContract::SplitBSH);
+    let splitbsh = SplitBSH::new(id.clone(), wallet.clone());
+
+    set_allowlist(
+        &stable_i,
+        Identity::ContractId(id.clone().into()),
+        true
+    ).await?;
+
+    Ok(splitbsh)
+}
+
+// `weight` is out of SPLITBSH_DENOMINATOR. A `cap` of 0 is uncapped.
+pub async fn add_recipient(
+    instance: &SplitBSH,
+    stable_i: &Bech32ContractId,
+    recipient: Identity,
+    weight: u64,
+    cap: u64
+) -> Result<u64, YamaError> {
+    Ok(instance
+        .methods()
+        .add_recipient(recipient, weight, cap)
+        .set_contract_ids(&[stable_i.clone()])
+        .call()
+        .await?
+        .value)
+}
+
+pub async fn set_recipient(
+    instance: &SplitBSH,
+    stable_i: &Bech32ContractId,
+    recipient_id: u64,
+    weight: u64,
+    cap: u64
+) -> Result<(), YamaError> {
+    instance
+        .methods()
+        .set_recipient(recipient_id, weight, cap)
+        .set_contract_ids(&[stable_i.clone()])
+        .call()
+        .await?;
+    Ok(())
+}
+
+// The backstop PSMLockup also needs this as its BSH contract
+pub async fn set_backstop(
+    instance: &SplitBSH,
+    stable_i: &Bech32ContractId,
+    backstop: &ContractId
+) -> Result<(), YamaError> {
+    instance
+        .methods()
+        .set_backstop(Bits256(*backstop.clone()))
+        .set_contract_ids(&[stable_i.clone()])
+        .call()
+        .await?;
+    Ok(())
+}
+
+pub async fn get_recipients(
+    instance: &SplitBSH
+) -> Result<Vec<Recipient>, YamaError> {
+    let count = instance
+        .methods()
+        .get_recipient_count()
+        .simulate()
+        .await?
+        .value;
+    let mut recipients: Vec<Recipient> = vec![];
+    for recipient_id in 0..count {
+        recipients.push(instance
+            .methods()
+            .get_recipient(recipient_id)
+            .simulate()
+            .await?
+            .value);
+    }
+    Ok(recipients)
+}
\ No newline at end of file
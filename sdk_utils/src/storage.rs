@@ -0,0 +1,256 @@
+use sha2::{Sha256, Digest};
+use fuels::{
+  prelude::*,
+  tx::{
+    ContractId,
+    StorageSlot,
+    Bytes32
+  },
+  types::{
+    Identity,
+    Bits256,
+    SizedAsciiString
+  }
+};
+use crate::abigen::*;
+
+// Mirrors how forc lays out the initial values of a `storage` block. The
+// field at index `i` is keyed by sha256("storage_i"), and every nested struct
+// field appends "_{field index}" to the key. Enums are a struct of their tag
+// (at "_0") and their payload (at "_1"). Values that don't fit in a single
+// slot, like `str[64]` or enum payloads, are packed into consecutive slots
+// starting at the key.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StorageValue {
+  Word(u64),
+  B256([u8; 32]),
+  Packed(Vec<u8>),
+  Struct(Vec<StorageValue>),
+  Enum(u64, Box<StorageValue>)
+}
+
+pub trait StorageEncode {
+  fn to_storage_value(&self) -> StorageValue;
+}
+
+impl StorageValue {
+  // The bytes of this value when it is packed, e.g. as an enum payload
+  fn packed_bytes(&self) -> Vec<u8> {
+    match self {
+      StorageValue::Word(word) => word.to_be_bytes().to_vec(),
+      StorageValue::B256(bytes) => bytes.to_vec(),
+      StorageValue::Packed(bytes) => bytes.clone(),
+      StorageValue::Struct(fields) => fields
+        .iter()
+        .flat_map(|field| field.packed_bytes())
+        .collect(),
+      StorageValue::Enum(tag, payload) => {
+        let mut bytes = tag.to_be_bytes().to_vec();
+        bytes.extend(payload.packed_bytes());
+        bytes
+      }
+    }
+  }
+
+  fn collect_slots(&self, key: &str, slots: &mut Vec<StorageSlot>) {
+    match self {
+      StorageValue::Word(word) => {
+        let mut value = [0u8; 32];
+        value[..8].copy_from_slice(&word.to_be_bytes());
+        slots.push(StorageSlot::new(storage_key(key), Bytes32::new(value)));
+      },
+      StorageValue::B256(bytes) => {
+        slots.push(StorageSlot::new(storage_key(key), Bytes32::new(*bytes)));
+      },
+      StorageValue::Packed(bytes) => {
+        let start = storage_key(key);
+        for (offset, chunk) in bytes.chunks(32).enumerate() {
+          let mut value = [0u8; 32];
+          value[..chunk.len()].copy_from_slice(chunk);
+          slots.push(StorageSlot::new(
+            add_to_key(&start, offset as u64),
+            Bytes32::new(value)
+          ));
+        }
+      },
+      StorageValue::Struct(fields) => {
+        for (i, field) in fields.iter().enumerate() {
+          field.collect_slots(&format!("{}_{}", key, i), slots);
+        }
+      },
+      StorageValue::Enum(tag, payload) => {
+        StorageValue::Word(*tag)
+          .collect_slots(&format!("{}_0", key), slots);
+        StorageValue::Packed(payload.packed_bytes())
+          .collect_slots(&format!("{}_1", key), slots);
+      }
+    }
+  }
+}
+
+pub fn storage_key(key: &str) -> Bytes32 {
+  let mut hasher = Sha256::new();
+  hasher.update(key.as_bytes());
+  let hash: [u8; 32] = hasher.finalize().into();
+  Bytes32::new(hash)
+}
+
+// Big-endian addition, used to find the consecutive slots of packed values
+fn add_to_key(key: &Bytes32, offset: u64) -> Bytes32 {
+  let mut bytes: [u8; 32] = **key;
+  let mut carry: u64 = offset;
+  for byte in bytes.iter_mut().rev() {
+    if carry == 0 {
+      break;
+    }
+    let sum = *byte as u64 + (carry & 0xff);
+    *byte = sum as u8;
+    carry = (carry >> 8) + (sum >> 8);
+  }
+  Bytes32::new(bytes)
+}
+
+// Gets the storage slots that initialize the field at `field_index` of a
+// contract's storage block to `value`
+pub fn storage_slots<T: StorageEncode>(
+  field_index: usize,
+  value: &T
+) -> Vec<StorageSlot> {
+  let mut slots: Vec<StorageSlot> = vec![];
+  value.to_storage_value()
+    .collect_slots(&format!("storage_{}", field_index), &mut slots);
+  slots
+}
+
+// Collects the storage slot overrides of a contract before it is deployed
+#[derive(Clone, Debug, Default)]
+pub struct StorageLayout {
+  slots: Vec<StorageSlot>
+}
+
+impl StorageLayout {
+  pub fn new() -> Self {
+    StorageLayout { slots: vec![] }
+  }
+
+  pub fn field<T: StorageEncode>(mut self, field_index: usize, value: &T) -> Self {
+    self.slots.extend(storage_slots(field_index, value));
+    self
+  }
+
+  pub fn slots(&self) -> Vec<StorageSlot> {
+    self.slots.clone()
+  }
+
+  // Combines the storage slots generated by forc at `storage_path` with this
+  // layout without touching the build artifacts
+  pub fn configuration(&self, storage_path: &str) -> StorageConfiguration {
+    StorageConfiguration::new(
+      Some(storage_path.to_string()),
+      Some(self.slots())
+    )
+  }
+}
+
+impl StorageEncode for u64 {
+  fn to_storage_value(&self) -> StorageValue {
+    StorageValue::Word(*self)
+  }
+}
+
+impl StorageEncode for u8 {
+  fn to_storage_value(&self) -> StorageValue {
+    StorageValue::Word(*self as u64)
+  }
+}
+
+impl StorageEncode for bool {
+  fn to_storage_value(&self) -> StorageValue {
+    StorageValue::Word(*self as u64)
+  }
+}
+
+impl StorageEncode for [u8; 32] {
+  fn to_storage_value(&self) -> StorageValue {
+    StorageValue::B256(*self)
+  }
+}
+
+impl StorageEncode for Bits256 {
+  fn to_storage_value(&self) -> StorageValue {
+    StorageValue::B256(self.0)
+  }
+}
+
+impl StorageEncode for ContractId {
+  fn to_storage_value(&self) -> StorageValue {
+    StorageValue::B256(**self)
+  }
+}
+
+impl StorageEncode for Address {
+  fn to_storage_value(&self) -> StorageValue {
+    StorageValue::B256(**self)
+  }
+}
+
+impl StorageEncode for Identity {
+  fn to_storage_value(&self) -> StorageValue {
+    match self {
+      Identity::Address(address) => StorageValue::Enum(
+        0, Box::new(address.to_storage_value())),
+      Identity::ContractId(contract_id) => StorageValue::Enum(
+        1, Box::new(contract_id.to_storage_value())),
+    }
+  }
+}
+
+impl<const LEN: usize> StorageEncode for SizedAsciiString<LEN> {
+  fn to_storage_value(&self) -> StorageValue {
+    StorageValue::Packed(self.to_string().into_bytes())
+  }
+}
+
+impl StorageEncode for U128 {
+  fn to_storage_value(&self) -> StorageValue {
+    StorageValue::Struct(vec![
+      self.upper.to_storage_value(),
+      self.lower.to_storage_value()
+    ])
+  }
+}
+
+impl StorageEncode for UFP128 {
+  fn to_storage_value(&self) -> StorageValue {
+    StorageValue::Struct(vec![self.value.to_storage_value()])
+  }
+}
+
+impl StorageEncode for U256 {
+  fn to_storage_value(&self) -> StorageValue {
+    StorageValue::Struct(vec![
+      self.a.to_storage_value(),
+      self.b.to_storage_value(),
+      self.c.to_storage_value(),
+      self.d.to_storage_value()
+    ])
+  }
+}
+
+impl StorageEncode for I256 {
+  fn to_storage_value(&self) -> StorageValue {
+    StorageValue::Struct(vec![self.underlying.to_storage_value()])
+  }
+}
+
+impl StorageEncode for CTypeParams {
+  fn to_storage_value(&self) -> StorageValue {
+    StorageValue::Struct(vec![
+      self.initial_price_ratio.to_storage_value(),
+      self.time_interval.to_storage_value(),
+      self.change_rate.to_storage_value(),
+      self.reset_threshold.to_storage_value(),
+      self.enabled.to_storage_value()
+    ])
+  }
+}
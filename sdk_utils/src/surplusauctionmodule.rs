@@ -0,0 +1,254 @@
+use fuels::{prelude::*, tx::ContractId, types::{Bits256, Identity}};
+use crate::{
+  errors::{
+    register_contract,
+    YamaContract,
+    YamaError
+  },
+  storage::StorageLayout,
+  modulartoken::{
+    set_allowlist,
+    get_cid
+  },
+  abigen::*,
+};
+
+// Both auction types are disabled until their params are set. `gov_i` mints
+// and burns for the module.
+pub async fn init_custom_surplusauctionmodule(
+  wallet: &WalletUnlocked,
+  stable_i: &ModularToken,
+  balancesheet: &ContractId,
+  gov_i: &ModularToken
+) -> Result<SurplusAuctionModule, YamaError> {
+  let storage_path
+    = "../surplusauctionmodule/out/debug/surplusauctionmodule-storage_slots.json";
+  let storage = StorageLayout::new()
+    .field(0, &get_cid(stable_i))
+    .field(1, balancesheet)
+    .field(2, &get_cid(gov_i));
+
+  let id = Contract::deploy(
+    "../surplusauctionmodule/out/debug/surplusauctionmodule.bin",
+    wallet,
+    TxParameters::default(),
+    storage.configuration(storage_path)
+  )
+  .await?;
+
+  register_contract(&id, YamaContract::SurplusAuctionModule);
+  let instance = SurplusAuctionModule::new(id.clone(), wallet.clone());
+
+  // Claims mint and burn both tokens and set the surplus
+  set_allowlist(stable_i, Identity::ContractId(id.clone().into()), true)
+    .await?;
+  set_allowlist(gov_i, Identity::ContractId(id.into()), true)
+    .await?;
+
+  Ok(instance)
+}
+
+pub async fn set_surplus_auction_params(
+  instance: &SurplusAuctionModule,
+  stable_i: &Bech32ContractId,
+  params: &SAMParams
+) -> Result<(), YamaError> {
+  instance
+    .methods()
+    .set_surplus_auction_params(
+      params.threshold,
+      params.lot,
+      params.start_price.clone(),
+      params.time_interval,
+      params.change_rate.clone(),
+      params.reset_threshold
+    )
+    .set_contract_ids(&[stable_i.clone()])
+    .call()
+    .await?;
+  Ok(())
+}
+
+pub async fn set_debt_auction_params(
+  instance: &SurplusAuctionModule,
+  stable_i: &Bech32ContractId,
+  params: &SAMParams
+) -> Result<(), YamaError> {
+  instance
+    .methods()
+    .set_debt_auction_params(
+      params.threshold,
+      params.lot,
+      params.start_price.clone(),
+      params.time_interval,
+      params.change_rate.clone(),
+      params.reset_threshold
+    )
+    .set_contract_ids(&[stable_i.clone()])
+    .call()
+    .await?;
+  Ok(())
+}
+
+// Allows the module on `gov_i` and auctions for it from then on
+pub async fn set_gov_token(
+  instance: &SurplusAuctionModule,
+  stable_i: &Bech32ContractId,
+  gov_i: &ModularToken
+) -> Result<(), YamaError> {
+  set_allowlist(
+    gov_i,
+    Identity::ContractId(ContractId::from(instance.get_contract_id())),
+    true
+  ).await?;
+  instance
+    .methods()
+    .set_gov_token(Bits256(*get_cid(gov_i)))
+    .set_contract_ids(&[stable_i.clone()])
+    .call()
+    .await?;
+  Ok(())
+}
+
+pub async fn start_surplus_auction(
+  instance: &SurplusAuctionModule,
+  balancesheet: &Bech32ContractId
+) -> Result<u64, YamaError> {
+  Ok(instance
+    .methods()
+    .start_surplus_auction()
+    .set_contract_ids(&[balancesheet.clone()])
+    .call()
+    .await?
+    .value)
+}
+
+pub async fn start_debt_auction(
+  instance: &SurplusAuctionModule,
+  balancesheet: &Bech32ContractId
+) -> Result<u64, YamaError> {
+  Ok(instance
+    .methods()
+    .start_debt_auction()
+    .set_contract_ids(&[balancesheet.clone()])
+    .call()
+    .await?
+    .value)
+}
+
+// Pays up to `amount` of the governance token for the auction's lot of YSS
+pub async fn claim_surplus_auction(
+  instance: &SurplusAuctionModule,
+  stable_i: &Bech32ContractId,
+  gov_i: &Bech32ContractId,
+  balancesheet: &Bech32ContractId,
+  auction_id: u64,
+  max_price: u64,
+  amount: u64
+) -> Result<(), YamaError> {
+  let tx_params = TxParameters::new(
+    None, Some(32_000_000), None);
+
+  instance
+    .methods()
+    .claim_surplus_auction(auction_id, max_price)
+    .call_params(CallParameters::new(
+      Some(amount),
+      Some(AssetId::new(*ContractId::from(gov_i.clone()))),
+      None
+    ))
+    .set_contract_ids(&[
+      stable_i.clone(),
+      gov_i.clone(),
+      balancesheet.clone()
+    ])
+    .append_variable_outputs(2)
+    .tx_params(tx_params)
+    .call()
+    .await?;
+  Ok(())
+}
+
+// Pays `amount` of YSS, at least the auction's lot, for at least `min_price`
+// of the governance token
+pub async fn claim_debt_auction(
+  instance: &SurplusAuctionModule,
+  stable_i: &Bech32ContractId,
+  gov_i: &Bech32ContractId,
+  balancesheet: &Bech32ContractId,
+  auction_id: u64,
+  min_price: u64,
+  amount: u64
+) -> Result<(), YamaError> {
+  let tx_params = TxParameters::new(
+    None, Some(32_000_000), None);
+
+  instance
+    .methods()
+    .claim_debt_auction(auction_id, min_price)
+    .call_params(CallParameters::new(
+      Some(amount),
+      Some(AssetId::new(*ContractId::from(stable_i.clone()))),
+      None
+    ))
+    .set_contract_ids(&[
+      stable_i.clone(),
+      gov_i.clone(),
+      balancesheet.clone()
+    ])
+    .append_variable_outputs(2)
+    .tx_params(tx_params)
+    .call()
+    .await?;
+  Ok(())
+}
+
+// Returns the ID of the auction that replaces the expired one
+pub async fn reset_auction(
+  instance: &SurplusAuctionModule,
+  auction_id: u64
+) -> Result<u64, YamaError> {
+  Ok(instance
+    .methods()
+    .reset_auction(auction_id)
+    .call()
+    .await?
+    .value)
+}
+
+// In governance tokens for the auction's lot
+pub async fn get_price(
+  instance: &SurplusAuctionModule,
+  auction_id: u64
+) -> Result<u64, YamaError> {
+  Ok(instance
+    .methods()
+    .get_price(auction_id)
+    .simulate()
+    .await?
+    .value)
+}
+
+pub async fn get_auction(
+  instance: &SurplusAuctionModule,
+  auction_id: u64
+) -> Result<SAMAuction, YamaError> {
+  Ok(instance
+    .methods()
+    .get_auction(auction_id)
+    .simulate()
+    .await?
+    .value)
+}
+
+pub async fn get_reserved(
+  instance: &SurplusAuctionModule,
+  is_debt_auction: bool
+) -> Result<u64, YamaError> {
+  Ok(instance
+    .methods()
+    .get_reserved(is_debt_auction)
+    .simulate()
+    .await?
+    .value)
+}
@@ -1,51 +1,61 @@
-use tokio::sync::Mutex;
-use lazy_static::lazy_static;
 use fuels::{
     prelude::*,
+    tx::ContractId
 };
 use crate::{
+    errors::{
+        register_contract,
+        YamaContract,
+        YamaError
+    },
     abigen::*,
-    utils::set_storage_val,
+    storage::StorageLayout,
     modulartoken::get_cid
 };
 
-lazy_static! {
-    static ref MUTEX: Mutex<i32> = Mutex::new(0i32);
-}
-
 pub async fn init_custom_testswapper(
     wallet: &WalletUnlocked,
     stable_i: &ModularToken,
     collat_i: &ModularToken,
-) -> TestSwapper {
+) -> Result<TestSwapper, YamaError> {
     let storage_path = "../testswapper/out/debug/testswapper-storage_slots.json";
 
-    let guard = MUTEX.lock().await;
-
-    set_storage_val(
-        &storage_path,
-        "storage_0",
-        &get_cid(stable_i).to_string()
-    );
-
-    set_storage_val(
-        &storage_path,
-        "storage_1",
-        &get_cid(collat_i).to_string()
-    );
+    let storage = StorageLayout::new()
+        .field(0, &get_cid(stable_i))
+        .field(1, &get_cid(collat_i));
 
     let id = Contract::deploy(
         "../testswapper/out/debug/testswapper.bin",
         &wallet,
         TxParameters::default(),
-        StorageConfiguration::with_storage_path(Some(
-            storage_path.to_string()
-        ))
+        storage.configuration(storage_path)
     )
-    .await
-    .unwrap();
+    .await?;
 
-    drop(guard);
+    register_contract(&id, YamaContract::TestSwapper);
+    Ok(TestSwapper::new(id, wallet.clone()))
+}
 
-    TestSwapper::new(id, wallet.clone())
-}
\ No newline at end of file
+pub async fn swap_to_yama(
+    instance: &TestSwapper,
+    stable_i: &Bech32ContractId,
+    collateral: &Bech32ContractId,
+    amount: u64,
+    min_output_amount: u64
+) -> Result<u64, YamaError> {
+    let call_params = CallParameters::new(
+        Some(amount),
+        Some(AssetId::new(*ContractId::from(collateral.clone()))),
+        None
+    );
+
+    Ok(instance
+        .methods()
+        .swap_to_yama(min_output_amount)
+        .set_contract_ids(&[stable_i.clone(), collateral.clone()])
+        .call_params(call_params)
+        .append_variable_outputs(1)
+        .call()
+        .await?
+        .value)
+}
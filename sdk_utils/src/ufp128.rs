@@ -0,0 +1,280 @@
+use std::{
+  fmt,
+  ops::{Add, Sub, Mul, Div},
+  str::FromStr
+};
+use crate::{
+  abigen::*,
+  utils::u64_to_i256
+};
+
+const FRACTION_MASK: u128 = u64::MAX as u128;
+// 2^64, i.e. 1.0
+const SCALE: f64 = 18_446_744_073_709_551_616.0;
+
+// Mirrors UFP128 from the fixed_point library and yama_types::ufp128: a 64.64
+// fixed-point number. Arithmetic rounds down and panics where the contracts
+// would revert.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Ufp128(u128);
+
+// floor(a * b / 2^64), if it fits in 128 bits
+fn mul_shifted(a: u128, b: u128) -> Option<u128> {
+  let (a1, a0) = (a >> 64, a & FRACTION_MASK);
+  let (b1, b0) = (b >> 64, b & FRACTION_MASK);
+  // a1 * b1 is the product's upper half, shifted down by 64
+  let high = a1 * b1;
+  if high >> 64 != 0 {
+    return None;
+  }
+  (high << 64)
+    .checked_add(a1 * b0)?
+    .checked_add(a0 * b1)?
+    .checked_add((a0 * b0) >> 64)
+}
+
+// floor(a * 2^64 / b), if it fits in 128 bits
+fn div_shifted(a: u128, b: u128) -> Option<u128> {
+  let high = a / b;
+  if high >> 64 != 0 {
+    return None;
+  }
+  let mut remainder = a % b;
+  let mut low: u128 = 0;
+  for _ in 0..64 {
+    // The remainder is below b, so it can carry out of 128 bits when doubled
+    let carry = remainder >> 127;
+    remainder <<= 1;
+    low <<= 1;
+    if carry == 1 || remainder >= b {
+      remainder = remainder.wrapping_sub(b);
+      low |= 1;
+    }
+  }
+  Some((high << 64) | low)
+}
+
+impl Ufp128 {
+  pub const ZERO: Ufp128 = Ufp128(0);
+  pub const ONE: Ufp128 = Ufp128(1 << 64);
+
+  pub fn from_bits(bits: u128) -> Self {
+    Ufp128(bits)
+  }
+
+  pub fn bits(self) -> u128 {
+    self.0
+  }
+
+  pub fn from_u64(value: u64) -> Self {
+    Ufp128((value as u128) << 64)
+  }
+
+  // Drops the fractional part
+  pub fn to_u64(self) -> u64 {
+    (self.0 >> 64) as u64
+  }
+
+  pub fn to_i256(self) -> I256 {
+    u64_to_i256(self.to_u64())
+  }
+
+  pub fn ge(self, other: Self) -> bool {
+    self >= other
+  }
+
+  pub fn le(self, other: Self) -> bool {
+    self <= other
+  }
+
+  pub fn checked_add(self, other: Self) -> Option<Self> {
+    self.0.checked_add(other.0).map(Ufp128)
+  }
+
+  pub fn checked_sub(self, other: Self) -> Option<Self> {
+    self.0.checked_sub(other.0).map(Ufp128)
+  }
+
+  pub fn checked_mul(self, other: Self) -> Option<Self> {
+    mul_shifted(self.0, other.0).map(Ufp128)
+  }
+
+  // None on division by zero or overflow
+  pub fn checked_div(self, divisor: Self) -> Option<Self> {
+    if divisor.0 == 0 {
+      return None;
+    }
+    div_shifted(self.0, divisor.0).map(Ufp128)
+  }
+
+  // Squares and multiplies in the same order as UFP128::powu, so the
+  // rounding matches
+  pub fn checked_powu(self, exponent: u64) -> Option<Self> {
+    let mut exponent = exponent;
+    let mut base = self;
+    let mut result = if exponent & 1 > 0 { base } else { Ufp128::ONE };
+
+    exponent >>= 1;
+    while exponent > 0 {
+      base = base.checked_mul(base)?;
+      if exponent & 1 > 0 {
+        result = result.checked_mul(base)?;
+      }
+      exponent >>= 1;
+    }
+    Some(result)
+  }
+
+  pub fn powu(self, exponent: u64) -> Self {
+    self.checked_powu(exponent).expect("UFP128 exponentiation overflowed")
+  }
+
+  // Rounds down to the nearest representable value. None for negative, NaN
+  // or out of range values.
+  pub fn from_f64(value: f64) -> Option<Self> {
+    let scaled = value * SCALE;
+    if scaled.is_nan() || scaled < 0.0 || scaled >= u128::MAX as f64 {
+      return None;
+    }
+    Some(Ufp128(scaled as u128))
+  }
+
+  pub fn to_f64(self) -> f64 {
+    (self.0 >> 64) as f64 + (self.0 & FRACTION_MASK) as f64 / SCALE
+  }
+}
+
+impl From<UFP128> for Ufp128 {
+  fn from(value: UFP128) -> Self {
+    Ufp128(((value.value.upper as u128) << 64) | value.value.lower as u128)
+  }
+}
+
+impl From<&UFP128> for Ufp128 {
+  fn from(value: &UFP128) -> Self {
+    Ufp128::from(value.clone())
+  }
+}
+
+impl From<Ufp128> for UFP128 {
+  fn from(value: Ufp128) -> Self {
+    UFP128 {
+      value: U128 {
+        upper: (value.0 >> 64) as u64,
+        lower: value.0 as u64
+      }
+    }
+  }
+}
+
+impl Add for Ufp128 {
+  type Output = Ufp128;
+
+  fn add(self, other: Self) -> Self {
+    self.checked_add(other).expect("UFP128 addition overflowed")
+  }
+}
+
+impl Sub for Ufp128 {
+  type Output = Ufp128;
+
+  fn sub(self, other: Self) -> Self {
+    self.checked_sub(other).expect("UFP128 subtraction underflowed")
+  }
+}
+
+impl Mul for Ufp128 {
+  type Output = Ufp128;
+
+  fn mul(self, other: Self) -> Self {
+    self.checked_mul(other).expect("UFP128 multiplication overflowed")
+  }
+}
+
+impl Div for Ufp128 {
+  type Output = Ufp128;
+
+  fn div(self, divisor: Self) -> Self {
+    self.checked_div(divisor).expect("UFP128 division failed")
+  }
+}
+
+// Writes the exact decimal expansion, which is at most 64 fractional digits.
+// A precision truncates it, e.g. `{:.4}`.
+impl fmt::Display for Ufp128 {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let mut digits = String::new();
+    let mut fraction = self.0 & FRACTION_MASK;
+    while fraction != 0 && f.precision().map_or(true, |p| digits.len() < p) {
+      fraction *= 10;
+      digits.push(char::from(b'0' + (fraction >> 64) as u8));
+      fraction &= FRACTION_MASK;
+    }
+    if let Some(precision) = f.precision() {
+      while digits.len() < precision {
+        digits.push('0');
+      }
+    }
+
+    if digits.is_empty() {
+      write!(f, "{}", self.to_u64())
+    } else {
+      write!(f, "{}.{}", self.to_u64(), digits)
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseUfp128Error {
+  Empty,
+  InvalidDigit,
+  Overflow
+}
+
+impl fmt::Display for ParseUfp128Error {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ParseUfp128Error::Empty
+        => write!(f, "cannot parse UFP128 from empty string"),
+      ParseUfp128Error::InvalidDigit => write!(f, "invalid digit in UFP128"),
+      ParseUfp128Error::Overflow => write!(f, "number too large for UFP128")
+    }
+  }
+}
+
+impl std::error::Error for ParseUfp128Error {}
+
+// Parses a decimal like "1.0000000317". Digits past what 64 fractional bits
+// can hold are rounded down, so any string written by Display parses back to
+// the same value.
+impl FromStr for Ufp128 {
+  type Err = ParseUfp128Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let (integer, fraction) = match s.split_once('.') {
+      Some((integer, fraction)) => (integer, fraction),
+      None => (s, "")
+    };
+    if integer.is_empty() && fraction.is_empty() {
+      return Err(ParseUfp128Error::Empty);
+    }
+    let is_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+    if !is_digits(integer) || !is_digits(fraction) {
+      return Err(ParseUfp128Error::InvalidDigit);
+    }
+
+    let upper: u64 = if integer.is_empty() {
+      0
+    } else {
+      integer.parse().map_err(|_| ParseUfp128Error::Overflow)?
+    };
+    // Horner's method from the last digit. Each step rounds down, which
+    // rounds the whole fraction down since floor(floor(x) / 10) is
+    // floor(x / 10).
+    let mut lower: u128 = 0;
+    for digit in fraction.bytes().rev() {
+      lower = ((((digit - b'0') as u128) << 64) | lower) / 10;
+    }
+    Ok(Ufp128(((upper as u128) << 64) | lower))
+  }
+}
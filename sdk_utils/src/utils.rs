@@ -1,6 +1,3 @@
-use serde_json::Value;
-use sha2::{Sha256, Digest};
-use std::io::Write;
 use fuels::{
   signers::fuel_crypto::SecretKey,
   prelude::*,
@@ -16,43 +13,23 @@ use fuels::{
 };
 use crate::{
   modulartoken::{
-      init_custom_modulartoken,
-      set_allowlist,
-      get_cid,
       mint,
       get_aid
   },
-  balancesheet::{
-    init_custom_balancesheet
-  },
   cdp::{
-    init_custom_cdp,
-    set_liquidator,
-    add_collateral_type,
     create_vault,
     borrow,
     liquidate,
     update_interest
   },
-  emptycollateralmanager::{
-    init_emptycollateralmanager
-  },
-  dutchauctionliquidator::{
-    init_custom_dutchauctionliquidator
+  deploy::{
+    deploy,
+    DeploymentPlan,
+    YamaDeployment
   },
-  psmpricesource::{init_psmpricesource},
-  abigen::*, psm::init_custom_psm, psmlockup::init_custom_psmlockup
+  clock::Clock,
+  abigen::*
 };
-use std::time::Duration;
-use async_std::task;
-
-fn sha256(input: &str) -> String {
-  let mut hasher = Sha256::new();
-  hasher.update(input.as_bytes());
-  let result = hasher.finalize();
-
-  format!("{:x}", result)
-}
 
 pub async fn get_timestamp(provider: &Provider) -> Result<i64, Error> {
   let req = PaginationRequest {
@@ -65,6 +42,14 @@ pub async fn get_timestamp(provider: &Provider) -> Result<i64, Error> {
   Ok(blocks[0].header.time.unwrap().timestamp())
 }
 
+// Contracts read block times as TAI64 labels while the SDK converts them to
+// unix timestamps
+pub const TAI64_OFFSET: u64 = (1 << 62) + 10;
+
+pub fn unix_to_tai64(timestamp: i64) -> u64 {
+  timestamp as u64 + TAI64_OFFSET
+}
+
 pub async fn get_test_wallet() -> WalletUnlocked {
   let key = std::env::var("FUEL_TEST_KEY").expect("FUEL_TEST_KEY is not set.");
   let secret_key: SecretKey = key.parse().unwrap();
@@ -94,67 +79,6 @@ pub async fn get_test_wallet() -> WalletUnlocked {
   wallet
 }
 
-pub fn set_storage_val(json_file_path: &str, key: &str, value: &str) {
-  let file_guard = std::fs::File::open(json_file_path).unwrap();
-  let json_result = serde_json::from_reader(file_guard).unwrap();
-  let mut json: Value = json_result;
-  serde_json::to_string_pretty(&json).unwrap();
-
-  let mut edited: bool = false;
-  let mut found: bool = false;
-
-  for obj in json.as_array_mut().unwrap() {
-      let key_value = obj["key"].as_str().unwrap();
-      if key_value == &sha256(key) {
-          let new_value = Value::String(value.to_string());
-          if obj["value"] != new_value {
-              obj["value"] = new_value;
-              edited = true;
-          }
-          found = true;
-          break;
-      }
-  }
-
-  if !found {
-    panic!("Storage key not found.");
-  }
-  if !edited {
-    return;
-  }
-
-  let json_string = serde_json::to_string_pretty(&json).unwrap();
-
-  let mut json_file_guard = std::fs::OpenOptions::new()
-      .write(true)
-      .create(true)
-      .open(json_file_path)
-      .unwrap();
-  json_file_guard.write_all(json_string.as_bytes()).unwrap();
-}
-
-pub fn int_to_hex(num: u64) -> String {
-  let suffix = "0".repeat(48);
-  format!("{:016x}{}", num, suffix)
-}
-
-
-pub fn get_storage_val(json_file_path: &str, key: &str) -> Option<String> {
-  let file_guard = std::fs::File::open(json_file_path).unwrap();
-  let json_result = serde_json::from_reader(file_guard).unwrap();
-  let json: Value = json_result;
-
-  for obj in json.as_array().unwrap() {
-      let key_value = obj["key"].as_str().unwrap();
-      if key_value == &sha256(key) {
-          let value = obj["value"].as_str().unwrap();
-          return Some(value.to_string());
-      }
-  }
-
-  return None;
-}
-
 pub fn get_id(wallet: &WalletUnlocked) -> Identity {
   let wallet_address = Address::new(*wallet.address().hash());
   Identity::Address(wallet_address)
@@ -180,107 +104,26 @@ pub fn u128_to_fp(value: u128) -> UFP128 {
 }
 
 
-pub async fn test_deploy() -> (
-  DutchAuctionLiquidator,
-  WalletUnlocked,
-  ModularToken,
-  ModularToken,
-  PSM,
-  PSMLockup,
-  BalanceSheet,
-  SimpleBSH,
-  CDP,
-  PSMPriceSource
-) {
+pub async fn test_deploy() -> YamaDeployment {
   let wallet: WalletUnlocked = get_test_wallet().await;
-  let stable_i: ModularToken
-    = init_custom_modulartoken(&wallet).await;
-  let collat_i: ModularToken
-    = init_custom_modulartoken(&wallet).await;
-  let psm = init_custom_psm(
-    &wallet, &stable_i, &collat_i).await;
-  let psm_lockup = init_custom_psmlockup(
-    &wallet, &stable_i, &get_cid(&collat_i),
-    &ContractId::from(psm.get_contract_id())).await;
-  let (balancesheet, simplebsh) = init_custom_balancesheet(
-    &wallet, &stable_i,
-    &ContractId::from(psm_lockup.get_contract_id())
-  ).await;
-  
-  let emptycollateralmanager = init_emptycollateralmanager(wallet.clone()).await;
-  let cdp = init_custom_cdp(
-    &wallet,
-    &stable_i,
-    &ContractId::from(emptycollateralmanager.get_contract_id()),
-    &ContractId::from(balancesheet.get_contract_id()),
-  ).await;
+  deploy(&wallet, &DeploymentPlan::default()).await.unwrap()
+}
 
-  let dutchauctionliquidator
-    = init_custom_dutchauctionliquidator(
-      &wallet,
-      &stable_i,
-      &ContractId::from(balancesheet.get_contract_id()),
-      &cdp,
-      &u128_to_fp(0b11 << 63), // 1.5
-      1,
-      &u128_to_fp(0b1 << 63), // 0.5
-      3
-    ).await;
 
-  // Adding a new collateral type
-  let price_source = init_psmpricesource(&wallet).await;
-  add_collateral_type(
-    &cdp,
-    &get_cid(&stable_i),
-    &get_cid(&collat_i),
-    &ContractId::from(price_source.get_contract_id()),
-    u64_to_fp(100),
-    u64_to_fp(10000),
-    u128_to_fp(0b11 << 63), // 1.5
-    u64_to_fp(2),
-    true,
-    false
-  ).await;
-  
-  (
+pub async fn setup_test_and_liquidate() -> YamaDeployment {
+  let deployment = test_deploy().await;
+  let YamaDeployment {
     dutchauctionliquidator,
     wallet,
     stable_i,
     collat_i,
-    psm,
     psm_lockup,
     balancesheet,
     simplebsh,
     cdp,
-    price_source
-  )
-}
-
-
-pub async fn setup_test_and_liquidate() -> (
-  DutchAuctionLiquidator,
-  WalletUnlocked,
-  ModularToken,
-  ModularToken,
-  PSM,
-  PSMLockup,
-  BalanceSheet,
-  SimpleBSH,
-  CDP,
-  PSMPriceSource
-) {
-  let (
-      dutchauctionliquidator,
-      wallet,
-      stable_i,
-      collat_i,
-      psm,
-      psm_lockup,
-      balancesheet,
-      simplebsh,
-      cdp,
-      price_source
-  ) = test_deploy().await;
+    price_source,
+    ..
+  } = &deployment;
 
   // Print all the contract IDs
   println!("dutchauctionliquidator: {}",
@@ -296,66 +139,55 @@ pub async fn setup_test_and_liquidate() -> (
   println!("cdp: {}",
     ContractId::from(cdp.get_contract_id()));
   println!("price_source: {}",
-    ContractId::from(price_source.get_contract_id()));
+    ContractId::from(price_source));
 
   let collat_amount: u64 = 150;
   let loan_amount: u64 = 100;
 
-  mint(&collat_i, &wallet, collat_amount).await;
+  mint(&collat_i, &wallet, collat_amount).await.unwrap();
   let vault_id = create_vault(
       &cdp,
       0,
       &get_aid(&collat_i),
       collat_amount,
       None
-  ).await;
+  ).await.unwrap();
 
   borrow(
       &cdp,
       vault_id,
       stable_i.get_contract_id(),
-      price_source.get_contract_id(),
+      price_source,
       balancesheet.get_contract_id(),
       simplebsh.get_contract_id(),
-      psm_lockup.get_contract_id(),
+      &[psm_lockup.get_contract_id().clone()],
       loan_amount
-  ).await;
+  ).await.unwrap();
   
-  task::sleep(Duration::from_secs(1)).await;
+  Clock::of(wallet).advance(1).await.unwrap();
 
   update_interest(
       &cdp,
       stable_i.get_contract_id(),
-      price_source.get_contract_id(),
+      price_source,
       balancesheet.get_contract_id(),
       simplebsh.get_contract_id(),
-      psm_lockup.get_contract_id(),
+      &[psm_lockup.get_contract_id().clone()],
       vault_id
-  ).await;
+  ).await.unwrap();
 
   liquidate(
       &cdp,
       stable_i.get_contract_id(),
       dutchauctionliquidator.get_contract_id(),
-      price_source.get_contract_id(),
+      price_source,
       balancesheet.get_contract_id(),
       simplebsh.get_contract_id(),
-      psm_lockup.get_contract_id(),
+      &[psm_lockup.get_contract_id().clone()],
       vault_id
-  ).await;
+  ).await.unwrap();
 
-  (
-      dutchauctionliquidator,
-      wallet,
-      stable_i,
-      collat_i,
-      psm,
-      psm_lockup,
-      balancesheet,
-      simplebsh,
-      cdp,
-      price_source
-  )
+  deployment
 }
 
 
@@ -365,6 +197,18 @@ pub fn u64_to_i256(value: u64) -> I256 {
   }
 }
 
+// I256 stores value + 2^128 in its underlying U256. None outside i128.
+pub fn i256_to_i128(value: &I256) -> Option<i128> {
+  let U256 { a, b, c, d } = value.underlying;
+  let low = ((c as u128) << 64) | d as u128;
+  match (a, b) {
+    (0, 1) => i128::try_from(low).ok(),
+    (0, 0) if low >= 1 << 127 => Some(low.wrapping_sub(1 << 127) as i128
+      + i128::MIN),
+    _ => None
+  }
+}
+
 pub fn get_id_key(asset_id: &AssetId) -> String {
   format!("{asset_id:#x}")
 }
\ No newline at end of file
@@ -0,0 +1,250 @@
+use fuels::{prelude::*, tx::ContractId, types::Identity};
+use sdk_utils::{
+  abigen::*,
+  aggregatorpricesource::{
+    add_feed,
+    get_last_price,
+    get_price,
+    init_aggregatorpricesource,
+    remove_feed,
+    set_feed,
+    set_last_price,
+    set_max_deviation,
+    set_min_feeds,
+    set_mode,
+    set_owner,
+    update,
+    AggregatorConfig,
+    FeedConfig
+  },
+  cdp::add_collateral_type,
+  clock::Clock,
+  deploy::{
+    deploy,
+    DeploymentPlan
+  },
+  errors::{
+    AggregatorPriceSourceError,
+    YamaError
+  },
+  mockpricesource::{
+    init_mockpricesource,
+    set_price,
+    set_price_at
+  },
+  modulartoken::{
+    get_cid,
+    mint
+  },
+  ufp128::Ufp128,
+  utils::get_test_wallet
+};
+
+fn price(value: &str) -> Ufp128 {
+  value.parse().unwrap()
+}
+
+fn mock_id(mock: &MockPriceSource) -> ContractId {
+  ContractId::from(mock.get_contract_id())
+}
+
+async fn aggregated_price(
+  aggregator: &AggregatorPriceSource
+) -> Result<Ufp128, YamaError> {
+  get_price(aggregator).await.map(Ufp128::from)
+}
+
+// One mock per price, each read by the aggregator with a max age of 60
+async fn setup(
+  prices: &[&str]
+) -> (WalletUnlocked, Vec<MockPriceSource>, AggregatorPriceSource) {
+  let wallet = get_test_wallet().await;
+  let mut mocks: Vec<MockPriceSource> = vec![];
+  for mock_price in prices.iter() {
+    mocks.push(
+      init_mockpricesource(&wallet, &price(mock_price).into()).await.unwrap());
+  }
+  let config = AggregatorConfig {
+    feeds: mocks
+      .iter()
+      .map(|mock| FeedConfig { source: mock_id(mock), max_age: 60 })
+      .collect(),
+    ..AggregatorConfig::default()
+  };
+  let aggregator = init_aggregatorpricesource(&wallet, &config).await.unwrap();
+
+  // Stamp every price with the current time
+  for (mock, mock_price) in mocks.iter().zip(prices.iter()) {
+    set_price(mock, &price(mock_price).into()).await.unwrap();
+  }
+  (wallet, mocks, aggregator)
+}
+
+#[tokio::test]
+async fn test_median() {
+  let (wallet, mocks, aggregator) = setup(&["1", "1.2", "0.9"]).await;
+  assert_eq!(aggregated_price(&aggregator).await.unwrap(), price("1"));
+
+  // An even number of feeds averages the middle two
+  let mock = init_mockpricesource(&wallet, &price("1.1").into()).await.unwrap();
+  add_feed(&aggregator, &mock_id(&mock), 0).await.unwrap();
+  assert_eq!(
+    aggregated_price(&aggregator).await.unwrap(),
+    (price("1") + price("1.1")) / Ufp128::from_u64(2)
+  );
+
+  // A zero price counts as missing
+  set_price(&mocks[1], &Ufp128::ZERO.into()).await.unwrap();
+  assert_eq!(aggregated_price(&aggregator).await.unwrap(), price("1"));
+
+  remove_feed(&aggregator, 3).await.unwrap();
+  assert!(matches!(
+    remove_feed(&aggregator, 3).await,
+    Err(YamaError::AggregatorPriceSource(
+      AggregatorPriceSourceError::InvalidFeed))
+  ));
+}
+
+#[tokio::test]
+async fn test_fallback() {
+  let (wallet, mocks, aggregator) = setup(&["1", "0.8"]).await;
+  set_mode(&aggregator, AggregationMode::Fallback()).await.unwrap();
+  assert_eq!(aggregated_price(&aggregator).await.unwrap(), price("1"));
+
+  // The primary goes stale, so the fallback is used
+  let now = Clock::of(&wallet).now_tai64().await.unwrap();
+  set_price_at(&mocks[0], &price("1").into(), now - 61).await.unwrap();
+  assert_eq!(aggregated_price(&aggregator).await.unwrap(), price("0.8"));
+}
+
+#[tokio::test]
+async fn test_stale_primary_falls_back() {
+  let (wallet, mocks, aggregator) = setup(&["1", "0.8"]).await;
+  set_mode(&aggregator, AggregationMode::Fallback()).await.unwrap();
+  set_feed(&aggregator, 0, &mock_id(&mocks[0]), 30).await.unwrap();
+
+  // The primary still returns its price, but the aggregator finds it stale
+  // and falls back rather than reverting
+  let now = Clock::of(&wallet).now_tai64().await.unwrap();
+  set_price_at(&mocks[0], &price("1").into(), now - 31).await.unwrap();
+  assert_eq!(aggregated_price(&aggregator).await.unwrap(), price("0.8"));
+
+  // And goes back to it once it's fresh again
+  set_price(&mocks[0], &price("1").into()).await.unwrap();
+  assert_eq!(aggregated_price(&aggregator).await.unwrap(), price("1"));
+}
+
+#[tokio::test]
+async fn test_stale_feeds() {
+  let (wallet, mocks, aggregator) = setup(&["1", "1", "1"]).await;
+  let clock = Clock::of(&wallet);
+
+  // Two of three is too few once three are required
+  set_min_feeds(&aggregator, 3).await.unwrap();
+  let now = clock.now_tai64().await.unwrap();
+  set_price_at(&mocks[2], &price("1").into(), now - 61).await.unwrap();
+  assert!(matches!(
+    aggregated_price(&aggregator).await,
+    Err(YamaError::AggregatorPriceSource(
+      AggregatorPriceSourceError::NotEnoughFreshFeeds))
+  ));
+  set_min_feeds(&aggregator, 1).await.unwrap();
+  assert_eq!(aggregated_price(&aggregator).await.unwrap(), price("1"));
+
+  // Everything goes stale
+  clock.advance(61).await.unwrap();
+  assert!(matches!(
+    aggregated_price(&aggregator).await,
+    Err(YamaError::AggregatorPriceSource(
+      AggregatorPriceSourceError::NotEnoughFreshFeeds))
+  ));
+  assert!(matches!(
+    update(&aggregator).await,
+    Err(YamaError::AggregatorPriceSource(
+      AggregatorPriceSourceError::NotEnoughFreshFeeds))
+  ));
+}
+
+#[tokio::test]
+async fn test_max_deviation() {
+  let (_wallet, mocks, aggregator) = setup(&["1"]).await;
+  set_max_deviation(&aggregator, &price("0.1").into()).await.unwrap();
+  assert_eq!(Ufp128::from(update(&aggregator).await.unwrap()), price("1"));
+  assert_eq!(
+    Ufp128::from(get_last_price(&aggregator).await.unwrap()),
+    price("1")
+  );
+
+  set_price(&mocks[0], &price("0.95").into()).await.unwrap();
+  assert_eq!(aggregated_price(&aggregator).await.unwrap(), price("0.95"));
+
+  // Moves are measured from the last accepted price, not the last read one
+  set_price(&mocks[0], &price("0.85").into()).await.unwrap();
+  assert!(matches!(
+    aggregated_price(&aggregator).await,
+    Err(YamaError::AggregatorPriceSource(
+      AggregatorPriceSourceError::ExceedsMaxDeviation))
+  ));
+  assert!(update(&aggregator).await.is_err());
+
+  // The owner resets the breaker
+  set_last_price(&aggregator, &price("0.85").into()).await.unwrap();
+  assert_eq!(aggregated_price(&aggregator).await.unwrap(), price("0.85"));
+}
+
+#[tokio::test]
+async fn test_not_owner() {
+  let (_wallet, mocks, aggregator) = setup(&["1"]).await;
+  set_owner(
+    &aggregator,
+    Identity::ContractId(mock_id(&mocks[0]))
+  ).await.unwrap();
+
+  assert!(matches!(
+    add_feed(&aggregator, &mock_id(&mocks[0]), 0).await,
+    Err(YamaError::AggregatorPriceSource(
+      AggregatorPriceSourceError::NotOwner))
+  ));
+  assert!(matches!(
+    set_last_price(&aggregator, &Ufp128::ONE.into()).await,
+    Err(YamaError::AggregatorPriceSource(
+      AggregatorPriceSourceError::NotOwner))
+  ));
+  // Accepting the current price is open to anyone
+  assert!(update(&aggregator).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_cdp_priced_by_aggregator() {
+  let (wallet, _mocks, aggregator) = setup(&["1"]).await;
+  let deployment = deploy(&wallet, &DeploymentPlan::default()).await.unwrap();
+  let c_type = DeploymentPlan::default().collateral_types[0].clone();
+  let c_type_id = add_collateral_type(
+    &deployment.cdp,
+    &get_cid(&deployment.stable_i),
+    &get_cid(&deployment.collat_i),
+    &ContractId::from(aggregator.get_contract_id()),
+    c_type.debt_floor,
+    c_type.debt_ceiling,
+    c_type.collateral_ratio,
+    c_type.interest_rate,
+    c_type.borrowing_enabled,
+    c_type.allowlist_enabled
+  ).await.unwrap();
+  let mut client = deployment.client().await.unwrap();
+
+  // The default interest rate doubles the debt every second
+  mint(&deployment.collat_i, &wallet, 150).await.unwrap();
+  let vault_id = client.create_vault(c_type_id, 150, None).await.unwrap();
+  client.borrow(vault_id, 100).await.unwrap();
+  Clock::of(&wallet).advance(1).await.unwrap();
+  client.update_interest(c_type_id).await.unwrap();
+  client.liquidate(vault_id).await.unwrap();
+  assert!(client.cdp
+    .methods()
+    .is_liquidated(vault_id)
+    .simulate()
+    .await
+    .unwrap()
+    .value);
+}
@@ -25,10 +25,10 @@ async fn test_add_surplus() {
 
     add_surplus(&balancesheet, stable_i.get_contract_id(),
         simplebsh.get_contract_id(),
-        stable_i.get_contract_id(),
-        u64_to_i256(100)).await;
+        &[stable_i.get_contract_id().clone()],
+        u64_to_i256(100)).await.unwrap();
     assert_eq!(
-        total_surplus(&balancesheet, stable_i.get_contract_id()).await,
+        total_surplus(&balancesheet, stable_i.get_contract_id()).await.unwrap(),
         u64_to_i256(10)
     );
     assert_eq!(
@@ -38,10 +38,10 @@ async fn test_add_surplus() {
 
     add_deficit(&balancesheet, stable_i.get_contract_id(),
         simplebsh.get_contract_id(),
-        stable_i.get_contract_id(),
-        u64_to_i256(10)).await;
+        &[stable_i.get_contract_id().clone()],
+        u64_to_i256(10)).await.unwrap();
     assert_eq!(
-        total_surplus(&balancesheet, stable_i.get_contract_id()).await,
+        total_surplus(&balancesheet, stable_i.get_contract_id()).await.unwrap(),
         u64_to_i256(0)
     );
     assert_eq!(
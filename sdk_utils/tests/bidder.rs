@@ -0,0 +1,168 @@
+use std::time::Duration;
+use fuels::{prelude::*, tx::ContractId, types::Identity};
+use sdk_utils::{
+  clock::Clock,
+  modulartoken::{
+    mint,
+    set_allowlist,
+    get_aid,
+    get_cid
+  },
+  testswapper::init_custom_testswapper,
+  deploy::YamaDeployment,
+  bidder::{
+    projected_price,
+    first_profitable_interval,
+    AuctionBidder,
+    BidderConfig,
+    BidderReport,
+    TestSwapperVenue
+  },
+  abigen::*,
+  errors::{
+    ModularTokenError,
+    YamaError
+  },
+  utils::{
+    setup_test_and_liquidate,
+    u128_to_fp
+  }
+};
+
+fn test_c_type_params() -> CTypeParams {
+  CTypeParams {
+    initial_price_ratio: u128_to_fp(0b11 << 63), // 1.5
+    time_interval: 1,
+    change_rate: u128_to_fp(0b1 << 63), // 0.5
+    reset_threshold: 3,
+    enabled: true
+  }
+}
+
+async fn setup_bidder() -> (YamaDeployment, AuctionBidder<TestSwapperVenue>) {
+  let deployment = setup_test_and_liquidate().await;
+  let YamaDeployment {
+    wallet,
+    stable_i,
+    collat_i,
+    cdp,
+    ..
+  } = &deployment;
+
+  let swapper = init_custom_testswapper(wallet, stable_i, collat_i)
+    .await
+    .unwrap();
+  let swapper_id = Identity::ContractId(
+    ContractId::from(swapper.get_contract_id()));
+  set_allowlist(stable_i, swapper_id.clone(), true).await.unwrap();
+  set_allowlist(collat_i, swapper_id, true).await.unwrap();
+
+  // Enough to pay the starting price on top of the 100 borrowed
+  mint(stable_i, wallet, 200).await.unwrap();
+
+  let venue = TestSwapperVenue {
+    swapper,
+    stablecoin: stable_i.get_contract_id().clone(),
+    collateral: get_cid(collat_i)
+  };
+  let config = BidderConfig {
+    min_profit: 10,
+    ..BidderConfig::default()
+  };
+  let bidder = AuctionBidder::new(cdp.get_contract_id(), wallet, venue, config)
+    .await
+    .unwrap();
+  (deployment, bidder)
+}
+
+#[test]
+fn test_projected_price() {
+  let params = test_c_type_params();
+  let start_price: u128 = 225 << 64;
+  let start_time: u64 = 1_000;
+
+  assert_eq!(projected_price(start_price, &params, start_time, start_time), 225);
+  assert_eq!(
+    projected_price(start_price, &params, start_time, start_time + 1), 112);
+  assert_eq!(
+    projected_price(start_price, &params, start_time, start_time + 2), 56);
+  // Expired
+  assert_eq!(
+    projected_price(start_price, &params, start_time, start_time + 3), 0);
+
+  assert_eq!(first_profitable_interval(start_price, &params, 225), Some(0));
+  assert_eq!(first_profitable_interval(start_price, &params, 140), Some(1));
+  assert_eq!(first_profitable_interval(start_price, &params, 10), None);
+}
+
+#[tokio::test]
+async fn test_bidder_claims_profitable_auction() {
+  let (deployment, mut bidder) = setup_bidder().await;
+  let YamaDeployment { wallet, collat_i, .. } = &deployment;
+
+  // 150 of collateral at a price of 1 is worth paying up to 139
+  Clock::of(wallet).advance(1).await.unwrap();
+  let report = bidder.tick().await.unwrap();
+
+  assert!(report.failures.is_empty());
+  assert!(report.sell_failures.is_empty());
+  assert_eq!(report.claimed.len(), 1);
+  let claimed = &report.claimed[0];
+  assert_eq!(claimed.auction_id, 0);
+  assert!(claimed.price <= 139);
+  assert_eq!(claimed.collateral_amount, 150);
+  assert_eq!(claimed.sold_for, Some(150));
+
+  assert_eq!(
+    wallet.get_asset_balance(&get_aid(collat_i)).await.unwrap(),
+    0
+  );
+  bidder.indexer.sync().await.unwrap();
+  assert!(bidder.indexer.state.auctions[&0].done);
+}
+
+#[tokio::test]
+async fn test_bidder_resets_expired_auction() {
+  let (deployment, mut bidder) = setup_bidder().await;
+
+  Clock::of(&deployment.wallet).advance(3).await.unwrap();
+  let report = bidder.tick().await.unwrap();
+
+  assert!(report.failures.is_empty());
+  assert!(report.claimed.is_empty());
+  assert_eq!(report.reset, vec![0]);
+
+  // The reset starts a new auction at the full price
+  let report = bidder.tick().await.unwrap();
+  assert!(bidder.indexer.state.auctions[&0].was_reset);
+  assert_eq!(report.waiting.len(), 1);
+  let plan = &report.waiting[0];
+  assert_eq!(plan.auction_id, 1);
+  assert_eq!(plan.max_price, Some(139));
+  assert_eq!(
+    plan.claim_at,
+    Some(bidder.indexer.state.auctions[&1].start_time + 1)
+  );
+}
+
+#[tokio::test]
+async fn test_bidder_reports_failed_sale() {
+  let (deployment, mut bidder) = setup_bidder().await;
+  let YamaDeployment { wallet, stable_i, .. } = &deployment;
+  let swapper_id = Identity::ContractId(
+    ContractId::from(bidder.venue.swapper.get_contract_id()));
+  set_allowlist(stable_i, swapper_id, false).await.unwrap();
+
+  // The claim goes through, but the swapper can't mint the stablecoin
+  Clock::of(wallet).advance(1).await.unwrap();
+  bidder.config.retry.poll_interval = Duration::ZERO;
+  let mut outcomes: Vec<BidderReport> = vec![];
+  bidder.run(Some(1), |_, outcome| outcomes.push(outcome.unwrap())).await;
+  let report = &outcomes[0];
+  assert_eq!(report.claimed.len(), 1);
+  assert_eq!(report.claimed[0].sold_for, None);
+  assert!(matches!(
+    report.sell_failures.as_slice(),
+    [(0, YamaError::ModularToken(ModularTokenError::UnauthorizedUser))]
+  ));
+}
@@ -1,6 +1,7 @@
 use fuels::{prelude::*, tx::ContractId};
 
 use sdk_utils::{
+  clock::Clock,
   modulartoken::{
     mint,
     get_aid
@@ -13,83 +14,82 @@ use sdk_utils::{
     remove_collateral,
     borrow, update_interest, get_target_collateral_value
   },
+  deploy::YamaDeployment,
+  errors::{
+    YamaError,
+    CDPError
+  },
   abigen::*, utils::{
     get_timestamp,
     test_deploy
   }
 };
 
-use std::time::Duration;
-use async_std::task;
-
 #[tokio::test]
 async fn test_create_vault() {
-  let (
-    _dutchauctionliquidator,
+  let YamaDeployment {
     wallet,
-    _stable_i,
     collat_i,
-    _psm,
-    _psm_lockup,
-    _balancesheet,
-    _simplebsh,
     cdp,
-    _price_source
-  ) = test_deploy().await;
+    ..
+  } = test_deploy().await;
   
   let collat_amount: u64 = 150;
 
-  mint(&collat_i, &wallet, collat_amount).await;
+  mint(&collat_i, &wallet, collat_amount).await.unwrap();
   create_vault(
     &cdp,
     0,
     &get_aid(&collat_i),
     collat_amount,
     None
-  ).await;
+  ).await.unwrap();
 }
 
 #[tokio::test]
-#[should_panic]
 async fn test_bad_liquidation() {
-  let (
+  let YamaDeployment {
     dutchauctionliquidator,
     wallet,
     stable_i,
     collat_i,
-    _psm,
     psm_lockup,
     balancesheet,
     simplebsh,
     cdp,
-    price_source
-  ) = test_deploy().await;
+    price_source,
+    ..
+  } = test_deploy().await;
   
   let collat_amount: u64 = 150;
 
-  mint(&collat_i, &wallet, collat_amount).await;
+  mint(&collat_i, &wallet, collat_amount).await.unwrap();
   let vault_id = create_vault(
     &cdp,
     0,
     &get_aid(&collat_i),
     collat_amount,
     None
-  ).await;
+  ).await.unwrap();
 
-  liquidate(
+  let result = liquidate(
     &cdp,
     stable_i.get_contract_id(),
     dutchauctionliquidator.get_contract_id(),
-    price_source.get_contract_id(),
+    &price_source,
     balancesheet.get_contract_id(),
     simplebsh.get_contract_id(),
-    psm_lockup.get_contract_id(),
+    &[psm_lockup.get_contract_id().clone()],
     vault_id).await;
+  assert!(matches!(
+    result,
+    Err(YamaError::CDP(CDPError::NotUndercollateralized))
+  ));
 }
 
 #[tokio::test]
 async fn test_good_liquidation() {
-  let (
+  let YamaDeployment {
     dutchauctionliquidator,
     wallet,
     stable_i,
@@ -99,20 +99,21 @@ async fn test_good_liquidation() {
     balancesheet,
     simplebsh,
     cdp,
-    price_source
-  ) = test_deploy().await;
+    price_source,
+    ..
+  } = test_deploy().await;
   
   let collat_amount: u64 = 150;
   let loan_amount: u64 = 100;
 
-  mint(&collat_i, &wallet, collat_amount).await;
+  mint(&collat_i, &wallet, collat_amount).await.unwrap();
   let vault_id = create_vault(
     &cdp,
     0,
     &get_aid(&collat_i),
     collat_amount,
     None
-  ).await;
+  ).await.unwrap();
 
   assert_eq!(
     wallet.get_asset_balance(&get_aid(&stable_i)).await.unwrap(),
@@ -123,71 +124,70 @@ async fn test_good_liquidation() {
     &cdp,
     vault_id,
     stable_i.get_contract_id(),
-    price_source.get_contract_id(),
+    &price_source,
     balancesheet.get_contract_id(),
     simplebsh.get_contract_id(),
-    psm_lockup.get_contract_id(),
+    &[psm_lockup.get_contract_id().clone()],
     loan_amount
-  ).await;
+  ).await.unwrap();
 
   assert_eq!(
     wallet.get_asset_balance(&get_aid(&stable_i)).await.unwrap(),
     loan_amount
   );
 
-  task::sleep(Duration::from_secs(1)).await;
+  Clock::of(&wallet).advance(1).await.unwrap();
 
   update_interest(
     &cdp,
     stable_i.get_contract_id(),
-    price_source.get_contract_id(),
+    &price_source,
     balancesheet.get_contract_id(),
     simplebsh.get_contract_id(),
-    psm_lockup.get_contract_id(),
+    &[psm_lockup.get_contract_id().clone()],
     vault_id
-  ).await;
+  ).await.unwrap();
   let target = get_target_collateral_value(
-    &cdp, vault_id).await;
+    &cdp, vault_id).await.unwrap();
   println!("target: {}", target);
 
   liquidate(
     &cdp,
     stable_i.get_contract_id(),
     dutchauctionliquidator.get_contract_id(),
-    price_source.get_contract_id(),
+    &price_source,
     balancesheet.get_contract_id(),
     simplebsh.get_contract_id(),
-    psm_lockup.get_contract_id(),
-    vault_id).await;
+    &[psm_lockup.get_contract_id().clone()],
+    vault_id).await.unwrap();
 
 }
 
 #[tokio::test]
 async fn test_repay() {
-  let (
-    _dutchauctionliquidator,
+  let YamaDeployment {
     wallet,
     stable_i,
     collat_i,
-    _psm,
     psm_lockup,
     balancesheet,
     simplebsh,
     cdp,
-    price_source
-  ) = test_deploy().await;
+    price_source,
+    ..
+  } = test_deploy().await;
   
   let collat_amount: u64 = 150;
   let loan_amount: u64 = 100;
 
-  mint(&collat_i, &wallet, collat_amount).await;
+  mint(&collat_i, &wallet, collat_amount).await.unwrap();
   let vault_id = create_vault(
     &cdp,
     0,
     &get_aid(&collat_i),
     collat_amount,
     None
-  ).await;
+  ).await.unwrap();
 
   assert_eq!(
     wallet.get_asset_balance(&get_aid(&stable_i)).await.unwrap(),
@@ -198,79 +198,78 @@ async fn test_repay() {
     &cdp,
     vault_id,
     stable_i.get_contract_id(),
-    price_source.get_contract_id(),
+    &price_source,
     balancesheet.get_contract_id(),
     simplebsh.get_contract_id(),
-    psm_lockup.get_contract_id(),
+    &[psm_lockup.get_contract_id().clone()],
     loan_amount
-  ).await;
+  ).await.unwrap();
 
   assert_eq!(
     wallet.get_asset_balance(&get_aid(&stable_i)).await.unwrap(),
     loan_amount
   );
 
-  task::sleep(Duration::from_secs(1)).await;
+  Clock::of(&wallet).advance(1).await.unwrap();
 
   update_interest(
     &cdp,
     stable_i.get_contract_id(),
-    price_source.get_contract_id(),
+    &price_source,
     balancesheet.get_contract_id(),
     simplebsh.get_contract_id(),
-    psm_lockup.get_contract_id(),
+    &[psm_lockup.get_contract_id().clone()],
     vault_id
-  ).await;
+  ).await.unwrap();
 
-  let debt = get_debt(&cdp, vault_id).await;
+  let debt = get_debt(&cdp, vault_id).await.unwrap();
 
-  mint(&stable_i, &wallet, debt).await;
+  mint(&stable_i, &wallet, debt).await.unwrap();
 
   repay(
     &cdp,
     vault_id,
     stable_i.get_contract_id(),
-    price_source.get_contract_id(),
+    &price_source,
     balancesheet.get_contract_id(),
     simplebsh.get_contract_id(),
-    psm_lockup.get_contract_id(),
+    &[psm_lockup.get_contract_id().clone()],
     debt
-  ).await;
+  ).await.unwrap();
 
   assert_eq!(
     wallet.get_asset_balance(&get_aid(&stable_i)).await.unwrap(),
     loan_amount
   );
 
-  assert!(get_debt(&cdp, vault_id).await < debt);
+  assert!(get_debt(&cdp, vault_id).await.unwrap() < debt);
 }
 
 #[tokio::test]
 async fn test_remove_collateral() {
-  let (
-    _dutchauctionliquidator,
+  let YamaDeployment {
     wallet,
     stable_i,
     collat_i,
-    _psm,
     psm_lockup,
     balancesheet,
     simplebsh,
     cdp,
-    price_source
-  ) = test_deploy().await;
+    price_source,
+    ..
+  } = test_deploy().await;
   
   let collat_amount: u64 = 150;
   let loan_amount: u64 = 100;
 
-  mint(&collat_i, &wallet, collat_amount).await;
+  mint(&collat_i, &wallet, collat_amount).await.unwrap();
   let vault_id = create_vault(
     &cdp,
     0,
     &get_aid(&collat_i),
     collat_amount,
     None
-  ).await;
+  ).await.unwrap();
 
   let collat_balance = wallet.get_asset_balance(&get_aid(&collat_i)).await.unwrap();
   println!("collat_balance: {}", collat_balance);
@@ -280,15 +279,79 @@ async fn test_remove_collateral() {
     vault_id,
     collat_i.get_contract_id(),
     stable_i.get_contract_id(),
-    price_source.get_contract_id(),
+    &price_source,
     balancesheet.get_contract_id(),
     simplebsh.get_contract_id(),
-    psm_lockup.get_contract_id(),
+    &[psm_lockup.get_contract_id().clone()],
     collat_amount
-  ).await;
+  ).await.unwrap();
 
   assert_eq!(
     wallet.get_asset_balance(&get_aid(&collat_i)).await.unwrap(),
     collat_balance + collat_amount
   );
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_client() {
+  let deployment = test_deploy().await;
+  let YamaDeployment {
+    wallet,
+    stable_i,
+    collat_i,
+    balancesheet,
+    simplebsh,
+    psm_lockup,
+    dutchauctionliquidator,
+    price_source,
+    ..
+  } = &deployment;
+  let mut client = deployment.client().await.unwrap();
+
+  let deps = client.dependencies().clone();
+  assert_eq!(&deps.stablecoin, stable_i.get_contract_id());
+  assert_eq!(&deps.balancesheet, balancesheet.get_contract_id());
+  assert_eq!(&deps.bsh, simplebsh.get_contract_id());
+  assert_eq!(deps.bsh_targets, vec![psm_lockup.get_contract_id().clone()]);
+  assert_eq!(&deps.liquidator, dutchauctionliquidator.get_contract_id());
+  assert_eq!(
+    &client.price_source(0).await.unwrap(),
+    &price_source
+  );
+
+  let collat_amount: u64 = 150;
+  let loan_amount: u64 = 100;
+
+  mint(&collat_i, &wallet, collat_amount).await.unwrap();
+  let vault_id = client.create_vault(0, collat_amount, None).await.unwrap();
+
+  client.borrow(vault_id, loan_amount).await.unwrap();
+  assert_eq!(
+    wallet.get_asset_balance(&get_aid(&stable_i)).await.unwrap(),
+    loan_amount
+  );
+  assert_eq!(client.collateral_type_of(vault_id).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_borrow_invalid_debt_amount() {
+  let deployment = test_deploy().await;
+  let YamaDeployment {
+    wallet,
+    collat_i,
+    ..
+  } = &deployment;
+  let mut client = deployment.client().await.unwrap();
+
+  let collat_amount: u64 = 150;
+
+  mint(&collat_i, &wallet, collat_amount).await.unwrap();
+  let vault_id = client.create_vault(0, collat_amount, None).await.unwrap();
+
+  // The collateral ratio is 1.5, so at most 100 can be borrowed
+  let result = client.borrow(vault_id, 101).await;
+  assert!(matches!(
+    result,
+    Err(YamaError::CDP(CDPError::InvalidDebtAmount))
+  ));
+}
@@ -0,0 +1,109 @@
+use sdk_utils::{
+  client::YamaClient,
+  clock::{
+    assert_interest_after,
+    assert_price_after_intervals,
+    Clock
+  },
+  deploy::{
+    deploy,
+    CollateralTypePlan,
+    DeploymentPlan,
+    LiquidatorPlan,
+    YamaDeployment
+  },
+  interest::{
+    apy_to_per_second,
+    parse_rate,
+    SECONDS_IN_YEAR
+  },
+  modulartoken::mint,
+  ufp128::Ufp128,
+  utils::{
+    get_test_wallet,
+    test_deploy
+  }
+};
+
+#[tokio::test]
+async fn test_advance() {
+  let YamaDeployment { wallet, .. } = test_deploy().await;
+  let clock = Clock::of(&wallet);
+
+  let start = clock.now_tai64().await.unwrap();
+  let later = clock.advance(100).await.unwrap();
+  assert!(later >= start + 100);
+  assert_eq!(clock.now_tai64().await.unwrap(), later);
+
+  // Going back is a no-op
+  assert_eq!(clock.advance_to(start).await.unwrap(), later);
+  let target = later + 3600;
+  assert!(clock.advance_to(target).await.unwrap() >= target);
+}
+
+#[tokio::test]
+async fn test_year_of_interest() {
+  let template = DeploymentPlan::default().collateral_types[0].clone();
+  let conversion = apy_to_per_second(parse_rate("5%").unwrap()).unwrap();
+  let plan = DeploymentPlan {
+    collateral_types: vec![CollateralTypePlan {
+      interest_rate: conversion.per_second.into(),
+      ..template
+    }],
+    ..DeploymentPlan::default()
+  };
+  let wallet = get_test_wallet().await;
+  let deployment = deploy(&wallet, &plan).await.unwrap();
+  let mut client = YamaClient::new(
+    deployment.cdp.get_contract_id(), &wallet).await.unwrap();
+  let c_type_id = deployment.collateral_type_ids[0];
+
+  let elapsed = assert_interest_after(
+    &Clock::of(&wallet), &mut client, c_type_id, SECONDS_IN_YEAR).await;
+  assert!(elapsed >= SECONDS_IN_YEAR);
+
+  let c_type = client.cdp
+    .methods()
+    .get_collateral_type(c_type_id)
+    .simulate()
+    .await
+    .unwrap()
+    .value;
+  assert!(
+    Ufp128::from(&c_type.cumulative_interest) >= conversion.annual_multiplier
+  );
+}
+
+#[tokio::test]
+async fn test_full_auction_decay() {
+  let plan = DeploymentPlan {
+    liquidator: LiquidatorPlan {
+      time_interval: 3600,
+      reset_threshold: 24,
+      ..DeploymentPlan::default().liquidator
+    },
+    ..DeploymentPlan::default()
+  };
+  let wallet = get_test_wallet().await;
+  let deployment = deploy(&wallet, &plan).await.unwrap();
+  let mut client = YamaClient::new(
+    deployment.cdp.get_contract_id(), &wallet).await.unwrap();
+  let clock = Clock::of(&wallet);
+
+  // The default interest rate doubles the debt every second
+  mint(&deployment.collat_i, &wallet, 150).await.unwrap();
+  let vault_id = client.create_vault(0, 150, None).await.unwrap();
+  client.borrow(vault_id, 100).await.unwrap();
+  clock.advance(1).await.unwrap();
+  client.update_interest(0).await.unwrap();
+  client.liquidate(vault_id).await.unwrap();
+
+  let mut last_price = u64::MAX;
+  for intervals in 0..=plan.liquidator.reset_threshold {
+    let price = assert_price_after_intervals(
+      &clock, &client, 0, intervals).await;
+    assert!(price <= last_price);
+    last_price = price;
+  }
+  assert_eq!(last_price, 0);
+}
@@ -1,6 +1,7 @@
 use fuels::{prelude::*, tx::ContractId};
 
 use sdk_utils::{
+  clock::Clock,
   modulartoken::{
     mint,
     get_aid
@@ -10,43 +11,48 @@ use sdk_utils::{
   },
   dutchauctionliquidator::{
     get_price, is_expired, get_default_c_type_params,
-    claim, get_collateral_amount_of_auction, reset_auction
+    claim, get_collateral_amount_of_auction, reset_auction,
+    set_partial_liquidation_params, get_partial_liquidation_params
+  },
+  deploy::YamaDeployment,
+  errors::{
+    YamaError,
+    DutchAuctionLiquidatorError
   },
   abigen::*,
   utils::{
     get_timestamp,
     test_deploy,
     setup_test_and_liquidate,
-    fp_to_u64
+    fp_to_u64,
+    u64_to_fp,
+    u128_to_fp
   }, psmpricesource
 };
 
-use std::time::Duration;
-use async_std::task;
-
 #[tokio::test]
 async fn test_collateral_amount() {
-  let (
+  let YamaDeployment {
     dutchauctionliquidator,
     wallet,
     stable_i,
     collat_i,
-    _psm,
     psm_lockup,
     balancesheet,
     simplebsh,
     cdp,
-    price_source
-  ) = setup_test_and_liquidate().await;
+    price_source,
+    ..
+  } = setup_test_and_liquidate().await;
 
   let default_c_type_params
-    = get_default_c_type_params(&dutchauctionliquidator).await;
+    = get_default_c_type_params(&dutchauctionliquidator).await.unwrap();
 
   let collateral_amount = get_collateral_amount_of_auction(
     &dutchauctionliquidator,
     cdp.get_contract_id(),
     0
-  ).await;
+  ).await.unwrap();
 
   assert_eq!(
     collateral_amount,
@@ -55,7 +61,7 @@ async fn test_collateral_amount() {
 
   assert_eq!(
     collateral_amount,
-    get_collateral_amount(&cdp, 0).await
+    get_collateral_amount(&cdp, 0).await.unwrap()
   );
 
   assert_eq!(
@@ -68,59 +74,64 @@ async fn test_collateral_amount() {
 }
 
 #[tokio::test]
-#[should_panic]
 async fn test_underpay() {
-  let (
+  let YamaDeployment {
     dutchauctionliquidator,
-    _wallet,
+    wallet,
     stable_i,
-    _collat_i,
-    _psm,
     psm_lockup,
     balancesheet,
     simplebsh,
     cdp,
-    _price_source
-  ) = setup_test_and_liquidate().await;
+    ..
+  } = setup_test_and_liquidate().await;
 
   let price = get_price(
     &dutchauctionliquidator,
     cdp.get_contract_id(),
     0
-  ).await;
+  ).await.unwrap();
+
+  mint(&stable_i, &wallet, price - 1).await.unwrap();
 
-  claim(
+  let result = claim(
     &dutchauctionliquidator,
     cdp.get_contract_id(),
     stable_i.get_contract_id(),
     balancesheet.get_contract_id(),
     simplebsh.get_contract_id(),
-    psm_lockup.get_contract_id(),
+    &[psm_lockup.get_contract_id().clone()],
     0,
+    price,
     price - 1
   ).await;
+  assert!(matches!(
+    result,
+    Err(YamaError::DutchAuctionLiquidator(
+      DutchAuctionLiquidatorError::InvalidPayment))
+  ));
 }
 
 #[tokio::test]
 async fn test_claim_auction() {
-  let (
+  let YamaDeployment {
     dutchauctionliquidator,
     wallet,
     stable_i,
     collat_i,
-    _psm,
     psm_lockup,
     balancesheet,
     simplebsh,
     cdp,
-    price_source
-  ) = setup_test_and_liquidate().await;
+    price_source,
+    ..
+  } = setup_test_and_liquidate().await;
 
     let price = get_price(
         &dutchauctionliquidator,
         cdp.get_contract_id(),
         0
-    ).await;
+    ).await.unwrap();
 
     assert_eq!(
       price,
@@ -132,23 +143,23 @@ async fn test_claim_auction() {
         &dutchauctionliquidator,
         cdp.get_contract_id(),
         0
-      ).await,
+      ).await.unwrap(),
       false
     );
 
 
 
-    task::sleep(Duration::from_secs(1)).await;
+    Clock::of(&wallet).advance(1).await.unwrap();
 
     let second_price = get_price(
         &dutchauctionliquidator,
         cdp.get_contract_id(),
         0
-    ).await;
+    ).await.unwrap();
 
     assert!(second_price < price);
 
-    mint(&stable_i, &wallet, second_price).await;
+    mint(&stable_i, &wallet, second_price).await.unwrap();
 
 
     claim(
@@ -157,10 +168,11 @@ async fn test_claim_auction() {
       stable_i.get_contract_id(),
       balancesheet.get_contract_id(),
       simplebsh.get_contract_id(),
-      psm_lockup.get_contract_id(),
+      &[psm_lockup.get_contract_id().clone()],
       0,
+      second_price,
       second_price
-    ).await;
+    ).await.unwrap();
 
     assert_eq!(
       wallet.get_asset_balance(&get_aid(&collat_i)).await.unwrap(),
@@ -170,26 +182,25 @@ async fn test_claim_auction() {
 }
 
 #[tokio::test]
-#[should_panic]
 async fn test_claim_expired_auction() {
-  let (
+  let YamaDeployment {
     dutchauctionliquidator,
     wallet,
     stable_i,
     collat_i,
-    _psm,
     psm_lockup,
     balancesheet,
     simplebsh,
     cdp,
-    price_source
-  ) = setup_test_and_liquidate().await;
+    price_source,
+    ..
+  } = setup_test_and_liquidate().await;
 
   let price = get_price(
     &dutchauctionliquidator,
     cdp.get_contract_id(),
     0
-  ).await;
+  ).await.unwrap();
 
   assert_eq!(
     price,
@@ -201,95 +212,101 @@ async fn test_claim_expired_auction() {
       &dutchauctionliquidator,
       cdp.get_contract_id(),
       0
-    ).await,
+    ).await.unwrap(),
     false
   );
 
-  task::sleep(Duration::from_secs(3)).await;
+  Clock::of(&wallet).advance(3).await.unwrap();
 
   let second_price = get_price(
     &dutchauctionliquidator,
     cdp.get_contract_id(),
     0
-  ).await;
+  ).await.unwrap();
 
   assert!(second_price < price);
 
-  mint(&stable_i, &wallet, second_price).await;
+  mint(&stable_i, &wallet, second_price).await.unwrap();
 
   assert_eq!(
     is_expired(
       &dutchauctionliquidator,
       cdp.get_contract_id(),
       0
-    ).await,
+    ).await.unwrap(),
     true
   );
 
-  claim(
+  let result = claim(
     &dutchauctionliquidator,
     cdp.get_contract_id(),
     stable_i.get_contract_id(),
     balancesheet.get_contract_id(),
     simplebsh.get_contract_id(),
-    psm_lockup.get_contract_id(),
+    &[psm_lockup.get_contract_id().clone()],
     0,
+    second_price,
     second_price
   ).await;
+  assert!(matches!(
+    result,
+    Err(YamaError::DutchAuctionLiquidator(
+      DutchAuctionLiquidatorError::AuctionExpired))
+  ));
 }
 
 #[tokio::test]
 async fn test_reset_expired_auction() {
-  let (
+  let YamaDeployment {
     dutchauctionliquidator,
     wallet,
     stable_i,
     collat_i,
-    _psm,
     psm_lockup,
     balancesheet,
     simplebsh,
     cdp,
-    price_source
-  ) = setup_test_and_liquidate().await;
+    price_source,
+    ..
+  } = setup_test_and_liquidate().await;
 
   assert_eq!(
     is_expired(
       &dutchauctionliquidator,
       cdp.get_contract_id(),
       0
-    ).await,
+    ).await.unwrap(),
     false
   );
 
-  task::sleep(Duration::from_secs(3)).await;
+  Clock::of(&wallet).advance(3).await.unwrap();
 
   assert_eq!(
     is_expired(
       &dutchauctionliquidator,
       cdp.get_contract_id(),
       0
-    ).await,
+    ).await.unwrap(),
     true
   );
 
   reset_auction(
     &dutchauctionliquidator,
     cdp.get_contract_id(),
-    price_source.get_contract_id(),
+    &price_source,
     stable_i.get_contract_id(),
     balancesheet.get_contract_id(),
     simplebsh.get_contract_id(),
-    psm_lockup.get_contract_id(),
+    &[psm_lockup.get_contract_id().clone()],
     0
-  ).await;
+  ).await.unwrap();
 
   assert_eq!(
     is_expired(
       &dutchauctionliquidator,
       cdp.get_contract_id(),
       1
-    ).await,
+    ).await.unwrap(),
     false
   );
 
@@ -297,38 +314,100 @@ async fn test_reset_expired_auction() {
 }
 
 #[tokio::test]
-#[should_panic]
 async fn test_premature_reset() {
-  let (
+  let YamaDeployment {
     dutchauctionliquidator,
     wallet,
     stable_i,
     collat_i,
-    _psm,
     psm_lockup,
     balancesheet,
     simplebsh,
     cdp,
-    price_source
-  ) = setup_test_and_liquidate().await;
+    price_source,
+    ..
+  } = setup_test_and_liquidate().await;
 
   assert_eq!(
     is_expired(
       &dutchauctionliquidator,
       cdp.get_contract_id(),
       0
-    ).await,
+    ).await.unwrap(),
     false
   );
 
-  reset_auction(
+  let result = reset_auction(
     &dutchauctionliquidator,
-    price_source.get_contract_id(),
     cdp.get_contract_id(),
+    &price_source,
     stable_i.get_contract_id(),
     balancesheet.get_contract_id(),
     simplebsh.get_contract_id(),
-    psm_lockup.get_contract_id(),
+    &[psm_lockup.get_contract_id().clone()],
     0
   ).await;
+  assert!(matches!(
+    result,
+    Err(YamaError::DutchAuctionLiquidator(
+      DutchAuctionLiquidatorError::AuctionNotExpired))
+  ));
+}
+
+#[tokio::test]
+async fn test_partial_liquidation_params() {
+  let YamaDeployment {
+    dutchauctionliquidator,
+    cdp,
+    stable_i,
+    ..
+  } = test_deploy().await;
+
+  assert!(!get_partial_liquidation_params(&dutchauctionliquidator, 0)
+    .await.unwrap().enabled);
+
+  // A target ratio of 1 + penalty or less never restores the vault
+  assert!(matches!(
+    set_partial_liquidation_params(
+      &dutchauctionliquidator,
+      cdp.get_contract_id(),
+      stable_i.get_contract_id(),
+      0,
+      true,
+      &u128_to_fp(0b11 << 63), // 1.5
+      &u128_to_fp(0b1 << 63) // 0.5
+    ).await,
+    Err(YamaError::DutchAuctionLiquidator(
+      DutchAuctionLiquidatorError::InvalidPartialLiquidationParams))
+  ));
+
+  // Nor does one below the collateral ratio of 1.5, even above 1 + penalty
+  assert!(matches!(
+    set_partial_liquidation_params(
+      &dutchauctionliquidator,
+      cdp.get_contract_id(),
+      stable_i.get_contract_id(),
+      0,
+      true,
+      &u128_to_fp(0b1011 << 61), // 1.375
+      &u128_to_fp(0b1 << 62) // 0.25
+    ).await,
+    Err(YamaError::DutchAuctionLiquidator(
+      DutchAuctionLiquidatorError::InvalidPartialLiquidationParams))
+  ));
+
+  set_partial_liquidation_params(
+    &dutchauctionliquidator,
+    cdp.get_contract_id(),
+    stable_i.get_contract_id(),
+    0,
+    true,
+    &u64_to_fp(2),
+    &u128_to_fp(0b1 << 62) // 0.25
+  ).await.unwrap();
+  let params = get_partial_liquidation_params(&dutchauctionliquidator, 0)
+    .await.unwrap();
+  assert!(params.enabled);
+  assert_eq!(params.target_ratio, u64_to_fp(2));
+  assert_eq!(params.penalty, u128_to_fp(0b1 << 62));
 }
\ No newline at end of file
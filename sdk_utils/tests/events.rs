@@ -0,0 +1,144 @@
+use std::sync::Arc;
+use fuels::{prelude::*, tx::ContractId, types::Identity};
+use sdk_utils::{
+  modulartoken::{
+    mint,
+    get_aid,
+    get_cid
+  },
+  psm::deposit,
+  cdp::{
+    create_vault,
+    borrow
+  },
+  deploy::{
+    deploy,
+    DeploymentPlan,
+    PSMPlan,
+    YamaDeployment
+  },
+  errors::{
+    logged_types,
+    PSMError,
+    YamaContract,
+    YamaError
+  },
+  events::{
+    recent_events,
+    YamaEvent
+  },
+  abigen::*,
+  utils::{
+    get_test_wallet,
+    test_deploy
+  }
+};
+
+#[test]
+fn test_logged_types_cached() {
+  let logged = logged_types(YamaContract::CDP);
+  assert!(!logged.is_empty());
+  assert!(Arc::ptr_eq(&logged, &logged_types(YamaContract::CDP)));
+}
+
+#[tokio::test]
+async fn test_vault_events() {
+  let YamaDeployment {
+    wallet,
+    stable_i,
+    collat_i,
+    psm_lockup,
+    balancesheet,
+    simplebsh,
+    cdp,
+    price_source,
+    ..
+  } = test_deploy().await;
+
+  let collat_amount: u64 = 150;
+  let loan_amount: u64 = 100;
+
+  mint(&collat_i, &wallet, collat_amount).await.unwrap();
+  let vault_id = create_vault(
+    &cdp,
+    0,
+    &get_aid(&collat_i),
+    collat_amount,
+    None
+  ).await.unwrap();
+  borrow(
+    &cdp,
+    vault_id,
+    stable_i.get_contract_id(),
+    &price_source,
+    balancesheet.get_contract_id(),
+    simplebsh.get_contract_id(),
+    &[psm_lockup.get_contract_id().clone()],
+    loan_amount
+  ).await.unwrap();
+
+  let events = recent_events(wallet.get_provider().unwrap(), 3)
+    .await
+    .unwrap();
+
+  let create_vault_event = events
+    .iter()
+    .find(|record| record.event.name() == "CreateVault")
+    .unwrap();
+  assert_eq!(
+    create_vault_event.contract_id,
+    ContractId::from(cdp.get_contract_id())
+  );
+  assert_eq!(create_vault_event.contract, YamaContract::CDP);
+  assert!(create_vault_event.block_height.is_some());
+  match &create_vault_event.event {
+    YamaEvent::CreateVault(event) => {
+      assert_eq!(event.vault_id, vault_id);
+      assert_eq!(event.collateral_amount, collat_amount);
+    },
+    _ => unreachable!()
+  }
+
+  let borrow_event = events
+    .iter()
+    .find(|record| record.event.name() == "Borrow")
+    .unwrap();
+  assert_eq!(
+    borrow_event.event,
+    YamaEvent::Borrow(Borrow {
+      account: Identity::Address(wallet.address().into()),
+      vault_id,
+      amount: loan_amount
+    })
+  );
+  assert!(borrow_event.block_height >= create_vault_event.block_height);
+}
+
+#[tokio::test]
+async fn test_reverted_events_skipped() {
+  let plan = DeploymentPlan {
+    psm: PSMPlan { debt_ceiling: Some(100), ..PSMPlan::default() },
+    ..DeploymentPlan::default()
+  };
+  let YamaDeployment {
+    wallet,
+    stable_i,
+    collat_i,
+    psm,
+    ..
+  } = deploy(&get_test_wallet().await, &plan).await.unwrap();
+
+  mint(&collat_i, &wallet, 1_000).await.unwrap();
+  // Deposit is logged before the debt ceiling is checked
+  let result = deposit(
+    &psm, &get_cid(&stable_i), &get_aid(&collat_i), 1_000).await;
+  assert!(matches!(
+    result,
+    Err(YamaError::PSM(PSMError::ExceedsDebtCeiling))
+  ));
+
+  let events = recent_events(wallet.get_provider().unwrap(), 1)
+    .await
+    .unwrap();
+  assert!(events.iter().all(|record| record.event.name() != "Deposit"));
+}
@@ -0,0 +1,158 @@
+use sdk_utils::{
+  deploy::DeploymentPlan,
+  errors::{
+    FlashMintModuleError,
+    YamaError
+  },
+  fuzz::{
+    check_invariants,
+    fuzz_chain,
+    fuzz_model,
+    shrink,
+    surplus_drift,
+    FuzzConfig
+  },
+  simulator::{
+    Action,
+    Simulator,
+    SystemSnapshot,
+    FMM_MAX
+  },
+  ufp128::Ufp128,
+  utils::get_test_wallet
+};
+
+fn invariants_broken(
+  simulator: &Simulator,
+  tamper: impl Fn(&mut SystemSnapshot)
+) -> Vec<&'static str> {
+  let mut snapshot = simulator.snapshot();
+  let drift = surplus_drift(&snapshot, simulator);
+  tamper(&mut snapshot);
+  check_invariants(&snapshot, simulator, &drift)
+    .iter()
+    .map(|violation| violation.invariant)
+    .collect()
+}
+
+#[test]
+fn test_shrink() {
+  let actions = vec![
+    Action::MintCollateral(1),
+    Action::AdvanceTime(1),
+    Action::MintCollateral(7),
+    Action::Lockup(3)
+  ];
+  let shrunk = shrink(actions, |candidate|
+    candidate.contains(&Action::MintCollateral(7)));
+  assert_eq!(shrunk, vec![Action::MintCollateral(7)]);
+}
+
+#[test]
+fn test_invariants_catch_broken_accounting() {
+  let mut simulator = Simulator::new(&FuzzConfig::default().plan, 1_000);
+  simulator.apply(&Action::MintCollateral(300)).unwrap();
+  simulator.apply(&Action::CreateVault {
+    collateral_type_id: 0,
+    collateral_amount: 300
+  }).unwrap();
+  simulator.apply(&Action::Borrow { vault_id: 0, amount: 100 }).unwrap();
+  simulator.apply(&Action::MintCollateral(50)).unwrap();
+  simulator.apply(&Action::PsmDeposit(50)).unwrap();
+  assert!(invariants_broken(&simulator, |_| ()).is_empty());
+
+  assert_eq!(
+    invariants_broken(&simulator, |snapshot|
+      snapshot.collateral_types[0].total_collateral += 1),
+    vec!["total_collateral"]
+  );
+  assert_eq!(
+    invariants_broken(&simulator, |snapshot|
+      snapshot.collateral_types[0].initial_debt = Ufp128::ZERO.into()),
+    vec!["total_debt", "surplus"]
+  );
+  // Unbacked stablecoin beyond what rounding explains
+  assert_eq!(
+    invariants_broken(&simulator, |snapshot| snapshot.stablecoin_supply += 5),
+    vec!["surplus"]
+  );
+  // Even a token, with no debt written since the drift was taken
+  assert_eq!(
+    invariants_broken(&simulator, |snapshot| snapshot.stablecoin_supply += 1),
+    vec!["surplus"]
+  );
+  assert_eq!(
+    invariants_broken(&simulator, |snapshot|
+      snapshot.vaults[0].is_liquidated = true),
+    vec!["open_auction"]
+  );
+}
+
+#[test]
+fn test_flash_loans_revert() {
+  let mut simulator = Simulator::new(&FuzzConfig::default().plan, 1_000);
+  simulator.apply(&Action::MintCollateral(600)).unwrap();
+  simulator.apply(&Action::CreateVault {
+    collateral_type_id: 0,
+    collateral_amount: 300
+  }).unwrap();
+  simulator.apply(&Action::CreateProxyVault {
+    collateral_type_id: 0,
+    collateral_amount: 300
+  }).unwrap();
+  let snapshot = simulator.snapshot();
+
+  // The proxy can't act on vaults it doesn't own
+  assert!(matches!(
+    simulator.apply(&Action::FlashLoan { vault_id: 0, amount: 100 }),
+    Err(YamaError::Reverted { .. })
+  ));
+  assert!(matches!(
+    simulator.apply(&Action::FlashLoan { vault_id: 1, amount: FMM_MAX + 1 }),
+    Err(YamaError::FlashMintModule(FlashMintModuleError::ExceedsMax))
+  ));
+  // A lone loan sits at index 0, which repay rejects
+  assert!(matches!(
+    simulator.apply(&Action::FlashLoan { vault_id: 1, amount: 100 }),
+    Err(YamaError::FlashMintModule(FlashMintModuleError::NoLoanToRepay))
+  ));
+  assert_eq!(simulator.snapshot(), snapshot);
+
+  let mut simulator = Simulator::new(&DeploymentPlan::default(), 1_000);
+  simulator.apply(&Action::MintCollateral(300)).unwrap();
+  assert!(matches!(
+    simulator.apply(&Action::CreateProxyVault {
+      collateral_type_id: 0,
+      collateral_amount: 300
+    }),
+    Err(YamaError::Reverted { .. })
+  ));
+}
+
+#[test]
+fn test_fuzz_model() {
+  let config = FuzzConfig::default();
+  for seed in 0..32 {
+    if let Err(failure) = fuzz_model(&config, seed) {
+      panic!("{}", failure);
+    }
+  }
+}
+
+#[tokio::test]
+async fn test_fuzz_chain() {
+  let wallet = get_test_wallet().await;
+  let config = FuzzConfig {
+    steps: 40,
+    max_advance: 2,
+    ..FuzzConfig::default()
+  };
+
+  for seed in 0..2 {
+    let run = fuzz_chain(&wallet, &config, seed).await.unwrap();
+    if let Some(failure) = run.failure {
+      panic!("{}", failure);
+    }
+    assert_eq!(run.steps.len(), config.steps);
+  }
+}
@@ -1,4 +1,21 @@
 mod modulartoken;
 mod pegstabilitymodule;
 mod cdpmodule;
-mod dutchauctionliquidator;
\ No newline at end of file
+mod dutchauctionliquidator;
+mod storage;
+mod events;
+mod indexer;
+mod keeper;
+
+mod bidder;
+mod ufp128;
+mod interest;
+mod risk;
+mod simulator;
+mod fuzz;
+mod clock;
+mod mockpricesource;
+mod aggregatorpricesource;
+mod mockreservestrategy;
+mod splitbsh;
+mod surplusauctionmodule;
\ No newline at end of file
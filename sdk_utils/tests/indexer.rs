@@ -0,0 +1,124 @@
+use fuels::{
+  prelude::*,
+  tx::ContractId
+};
+use sdk_utils::{
+  abigen::Redeem,
+  deploy::{
+    deploy,
+    DeploymentPlan,
+    PSMPlan,
+    YamaDeployment
+  },
+  errors::YamaContract,
+  events::{EventRecord, YamaEvent},
+  indexer::{Inconsistency, Indexer, ProtocolState},
+  modulartoken::{
+    get_aid,
+    get_cid,
+    mint
+  },
+  psm::deposit,
+  utils::{
+    get_id,
+    get_test_wallet,
+    setup_test_and_liquidate
+  }
+};
+
+#[tokio::test]
+async fn test_index_liquidation() {
+  let YamaDeployment {
+    wallet,
+    ..
+  } = setup_test_and_liquidate().await;
+
+  let mut indexer = Indexer::new(&wallet);
+  assert!(indexer.sync().await.unwrap() > 0);
+  let state = &indexer.state;
+
+  let vault = state.vaults.get(&0).unwrap();
+  assert_eq!(vault.collateral_type_id, 0);
+  assert_eq!(vault.collateral_amount, 150);
+  assert!(vault.debt > 0);
+  assert!(vault.is_liquidated);
+  assert_eq!(state.vaults_of(&get_id(&wallet)).len(), 1);
+  assert!(state.open_vaults().is_empty());
+
+  let c_type = state.collateral_types.get(&0).unwrap();
+  assert_eq!(c_type.total_collateral, 150);
+  assert!(c_type.borrowing_enabled);
+
+  let auction = state.auctions.get(&0).unwrap();
+  assert_eq!(auction.vault_id, 0);
+  assert!(!auction.done);
+  assert_eq!(state.active_auctions().len(), 1);
+
+  // Syncing again only picks up new blocks
+  assert_eq!(indexer.sync().await.unwrap(), 0);
+
+  let snapshot: serde_json::Value
+    = serde_json::from_str(&indexer.state.snapshot()).unwrap();
+  assert_eq!(snapshot["vaults"]["0"]["is_liquidated"], true);
+  assert_eq!(snapshot, indexer.state.to_json());
+}
+
+#[tokio::test]
+async fn test_skip_reverted_deposit() {
+  let plan = DeploymentPlan {
+    psm: PSMPlan { debt_ceiling: Some(100), ..PSMPlan::default() },
+    ..DeploymentPlan::default()
+  };
+  let YamaDeployment {
+    wallet,
+    stable_i,
+    collat_i,
+    psm,
+    ..
+  } = deploy(&get_test_wallet().await, &plan).await.unwrap();
+
+  mint(&collat_i, &wallet, 1_050).await.unwrap();
+  deposit(&psm, &get_cid(&stable_i), &get_aid(&collat_i), 50)
+    .await
+    .unwrap();
+  // Logs Deposit before reverting on the debt ceiling
+  assert!(
+    deposit(&psm, &get_cid(&stable_i), &get_aid(&collat_i), 1_000)
+      .await
+      .is_err()
+  );
+
+  let mut indexer = Indexer::new(&wallet);
+  indexer.sync().await.unwrap();
+  let psm_state = indexer.state.psms.values().next().unwrap();
+  let token = psm_state.tokens.values().next().unwrap();
+  assert_eq!(token.deposited, 50);
+  assert_eq!(token.reserves, 50);
+  assert!(indexer.state.inconsistencies.is_empty());
+}
+
+#[test]
+fn test_flag_overdrawn_balance() {
+  let mut state = ProtocolState::default();
+  // A redemption from a lockup the indexer never saw deposits into, e.g. when
+  // syncing started after the deposits
+  state.apply(&EventRecord {
+    contract_id: ContractId::zeroed(),
+    contract: YamaContract::PSMLockup,
+    block_height: Some(7),
+    tx_id: None,
+    event: YamaEvent::Redeem(Redeem {
+      account: Identity::Address(Address::zeroed()),
+      yama_amount: 10,
+      lockup_amount: 10
+    })
+  });
+
+  let lockup = state.lockups.values().next().unwrap();
+  assert_eq!(lockup.total_supply, 0);
+  assert_eq!(lockup.yama_redeemed, 10);
+  assert_eq!(state.inconsistencies, vec![Inconsistency {
+    block_height: Some(7),
+    event: "Redeem".to_string()
+  }]);
+}
@@ -0,0 +1,111 @@
+use sdk_utils::{
+  deploy::{
+    deploy,
+    CollateralTypePlan,
+    DeploymentPlan,
+    YamaDeployment
+  },
+  interest::{
+    apr_to_per_second,
+    apy_to_per_second,
+    from_bps,
+    get_rates,
+    parse_rate,
+    per_second_to_apr,
+    per_second_to_apy,
+    SECONDS_IN_YEAR
+  },
+  ufp128::Ufp128,
+  utils::get_test_wallet
+};
+
+#[test]
+fn test_parse_rate() {
+  let five_percent: Ufp128 = "0.05".parse().unwrap();
+  assert_eq!(parse_rate("5%"), Ok(five_percent));
+  assert_eq!(parse_rate("500bps"), Ok(five_percent));
+  assert_eq!(parse_rate(" 500 bps "), Ok(five_percent));
+  assert_eq!(from_bps(500), five_percent);
+  assert_eq!(parse_rate("2.5%"), Ok(from_bps(250)));
+  assert!(parse_rate("five%").is_err());
+}
+
+#[test]
+fn test_apy_to_per_second() {
+  let zero = apy_to_per_second(Ufp128::ZERO).unwrap();
+  assert_eq!(zero.per_second, Ufp128::ONE);
+  assert_eq!(zero.achieved, Ufp128::ZERO);
+
+  for bps in [1, 50, 500, 2_000, 10_000, 100_000] {
+    let apy = from_bps(bps);
+    let conversion = apy_to_per_second(apy).unwrap();
+    assert_eq!(
+      per_second_to_apy(conversion.per_second),
+      Some(conversion.achieved)
+    );
+    assert!(conversion.error().abs() < 1e-9, "{}", conversion);
+
+    // Neither neighbouring rate gets closer to the target
+    let target = Ufp128::ONE + apy;
+    let distance = |per_second: Ufp128| per_second
+      .checked_powu(SECONDS_IN_YEAR)
+      .map(|annual| annual.bits().abs_diff(target.bits()));
+    let best = distance(conversion.per_second).unwrap();
+    let bits = conversion.per_second.bits();
+    for neighbour in [bits - 1, bits + 1] {
+      if let Some(neighbour_distance) = distance(Ufp128::from_bits(neighbour)) {
+        assert!(neighbour_distance >= best);
+      }
+    }
+  }
+}
+
+#[test]
+fn test_apr_to_per_second() {
+  let apr = from_bps(1_000);
+  let conversion = apr_to_per_second(apr).unwrap();
+  assert_eq!(
+    per_second_to_apr(conversion.per_second),
+    Some(conversion.achieved)
+  );
+  // Off by at most half a step of the per-second rate
+  let step = Ufp128::from_bits(1).to_f64() * SECONDS_IN_YEAR as f64;
+  assert!(conversion.error().abs() <= step / 2.0);
+  // Compounding every second earns more than the APR
+  assert!(per_second_to_apy(conversion.per_second).unwrap() > apr);
+}
+
+#[tokio::test]
+async fn test_rates_match_chain() {
+  let template = DeploymentPlan::default().collateral_types[0].clone();
+  let conversions = [
+    apy_to_per_second(parse_rate("5%").unwrap()).unwrap(),
+    apr_to_per_second(parse_rate("10%").unwrap()).unwrap()
+  ];
+  let plan = DeploymentPlan {
+    collateral_types: conversions
+      .iter()
+      .map(|conversion| CollateralTypePlan {
+        interest_rate: conversion.per_second.into(),
+        ..template.clone()
+      })
+      .collect(),
+    ..DeploymentPlan::default()
+  };
+  let wallet = get_test_wallet().await;
+  let YamaDeployment {
+    cdp,
+    collateral_type_ids,
+    ..
+  } = deploy(&wallet, &plan).await.unwrap();
+
+  for (c_type_id, conversion) in collateral_type_ids.iter().zip(conversions) {
+    let rates = get_rates(&cdp, *c_type_id).await.unwrap();
+    assert_eq!(rates.per_second, conversion.per_second);
+    assert_eq!(rates.annual_multiplier, conversion.annual_multiplier);
+    assert_eq!(
+      rates.apy,
+      Some(conversion.annual_multiplier - Ufp128::ONE)
+    );
+  }
+}
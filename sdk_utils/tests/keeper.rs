@@ -0,0 +1,171 @@
+use std::time::Duration;
+use fuels::{prelude::*, tx::ContractId};
+use sdk_utils::{
+  aggregatorpricesource::{
+    init_aggregatorpricesource,
+    AggregatorConfig,
+    FeedConfig
+  },
+  clock::Clock,
+  modulartoken::{
+    mint,
+    get_aid,
+    get_cid
+  },
+  cdp::{
+    add_collateral_type,
+    create_vault,
+    borrow
+  },
+  deploy::{
+    deploy,
+    DeploymentPlan,
+    YamaDeployment
+  },
+  errors::{
+    AggregatorPriceSourceError,
+    YamaError
+  },
+  keeper::{
+    LiquidationKeeper,
+    KeeperConfig
+  },
+  mockpricesource::{
+    set_price,
+    set_price_at
+  },
+  ufp128::Ufp128,
+  utils::{
+    get_test_wallet,
+    test_deploy
+  }
+};
+
+#[tokio::test]
+async fn test_keeper_liquidates() {
+  let YamaDeployment {
+    wallet,
+    stable_i,
+    collat_i,
+    psm_lockup,
+    balancesheet,
+    simplebsh,
+    cdp,
+    price_source,
+    ..
+  } = test_deploy().await;
+
+  let collat_amount: u64 = 150;
+  let loan_amount: u64 = 100;
+
+  mint(&collat_i, &wallet, collat_amount).await.unwrap();
+  let vault_id = create_vault(
+    &cdp,
+    0,
+    &get_aid(&collat_i),
+    collat_amount,
+    None
+  ).await.unwrap();
+
+  let mut keeper = LiquidationKeeper::new(
+    cdp.get_contract_id(),
+    &wallet,
+    KeeperConfig {
+      poll_interval: Duration::ZERO,
+      ..KeeperConfig::default()
+    }
+  ).await.unwrap();
+
+  // Nothing to do while the vault has no debt
+  let report = keeper.tick().await.unwrap();
+  assert!(report.liquidated.is_empty());
+
+  borrow(
+    &cdp,
+    vault_id,
+    stable_i.get_contract_id(),
+    &price_source,
+    balancesheet.get_contract_id(),
+    simplebsh.get_contract_id(),
+    &[psm_lockup.get_contract_id().clone()],
+    loan_amount
+  ).await.unwrap();
+
+  // The interest rate doubles the debt every second
+  Clock::of(&wallet).advance(1).await.unwrap();
+
+  let report = keeper.tick().await.unwrap();
+  assert_eq!(report.interest_updated, vec![0]);
+  assert_eq!(report.liquidated, vec![vault_id]);
+  assert!(report.liquidation_failures.is_empty());
+  assert!(report.retried.is_empty());
+  assert!(cdp.methods().is_liquidated(vault_id).simulate().await.unwrap().value);
+
+  // The liquidated vault is no longer tracked
+  let report = keeper.tick().await.unwrap();
+  assert!(report.liquidated.is_empty());
+  assert!(keeper.open_vaults().is_empty());
+
+  // Each tick's outcome is handed back rather than printed
+  let mut outcomes: Vec<(u64, bool)> = vec![];
+  keeper.run(Some(2), |tick, outcome| {
+    outcomes.push((tick, outcome.is_ok()))
+  }).await;
+  assert_eq!(outcomes, vec![(0, true), (1, true)]);
+}
+
+#[tokio::test]
+async fn test_keeper_reports_failed_check() {
+  let wallet = get_test_wallet().await;
+  let deployment = deploy(&wallet, &DeploymentPlan {
+    mock_price: Some(Ufp128::ONE.into()),
+    ..DeploymentPlan::default()
+  }).await.unwrap();
+  let mock = deployment.mock_price_source.as_ref().unwrap();
+  // Priced by an aggregator that accepts the mock's price for 60 seconds
+  let aggregator = init_aggregatorpricesource(&wallet, &AggregatorConfig {
+    feeds: vec![FeedConfig {
+      source: ContractId::from(mock.get_contract_id()),
+      max_age: 60
+    }],
+    ..AggregatorConfig::default()
+  }).await.unwrap();
+  let c_type = DeploymentPlan::default().collateral_types[0].clone();
+  let c_type_id = add_collateral_type(
+    &deployment.cdp,
+    &get_cid(&deployment.stable_i),
+    &get_cid(&deployment.collat_i),
+    &ContractId::from(aggregator.get_contract_id()),
+    c_type.debt_floor,
+    c_type.debt_ceiling,
+    c_type.collateral_ratio,
+    c_type.interest_rate,
+    c_type.borrowing_enabled,
+    c_type.allowlist_enabled
+  ).await.unwrap();
+  set_price(mock, &Ufp128::ONE.into()).await.unwrap();
+
+  let mut client = deployment.client().await.unwrap();
+  mint(&deployment.collat_i, &wallet, 300).await.unwrap();
+  let vault_id = client.create_vault(c_type_id, 300, None).await.unwrap();
+  client.borrow(vault_id, 100).await.unwrap();
+
+  let mut keeper = LiquidationKeeper::new(
+    deployment.cdp.get_contract_id(),
+    &wallet,
+    KeeperConfig::default()
+  ).await.unwrap();
+
+  // The price goes stale, so checking the vault reverts without ending the
+  // tick
+  let now = Clock::of(&wallet).now_tai64().await.unwrap();
+  set_price_at(mock, &Ufp128::ONE.into(), now - 61).await.unwrap();
+  let report = keeper.tick().await.unwrap();
+  assert!(report.liquidated.is_empty());
+  assert!(matches!(
+    report.liquidation_failures.as_slice(),
+    [(id, YamaError::AggregatorPriceSource(
+      AggregatorPriceSourceError::NotEnoughFreshFeeds))]
+      if *id == vault_id
+  ));
+}
@@ -15,7 +15,7 @@ async fn test_lproxy() {
     ) = init_lproxy().await;
     
     let collat_amount: u64 = 150;
-    mint(&collat_i, &wallet, collat_amount).await;
+    mint(&collat_i, &wallet, collat_amount).await.unwrap();
 
     create_vault(
         &lproxy,
@@ -23,7 +23,7 @@ async fn test_lproxy() {
         0,
         collat_amount,
         &cdp
-    ).await;
+    ).await.unwrap();
 
     leverage_up(
         &lproxy,
@@ -32,7 +32,7 @@ async fn test_lproxy() {
         collat_amount + 25,
         &cdp,
         flashmintmodule.get_contract_id()
-    ).await;
+    ).await.unwrap();
 
     leverage_down(
         &lproxy,
@@ -41,7 +41,7 @@ async fn test_lproxy() {
         collat_amount,
         &cdp,
         flashmintmodule.get_contract_id()
-    ).await;
+    ).await.unwrap();
 
     leverage_down_all(
         &lproxy,
@@ -49,5 +49,5 @@ async fn test_lproxy() {
         0,
         &cdp,
         flashmintmodule.get_contract_id()
-    ).await;
+    ).await.unwrap();
 }
\ No newline at end of file
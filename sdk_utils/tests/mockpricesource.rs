@@ -0,0 +1,140 @@
+use fuels::{tx::ContractId, types::Identity};
+use sdk_utils::{
+  clock::Clock,
+  deploy::{
+    deploy,
+    CollateralTypePlan,
+    DeploymentPlan,
+    YamaDeployment
+  },
+  errors::{
+    CDPError,
+    MockPriceSourceError,
+    YamaError
+  },
+  mockpricesource::{
+    follow_price_path,
+    get_price,
+    get_updated_at,
+    set_owner,
+    set_price,
+    set_price_at,
+    PricePath
+  },
+  modulartoken::mint,
+  psmpricesource,
+  simulator::Action,
+  ufp128::Ufp128,
+  utils::get_test_wallet
+};
+
+fn price(value: &str) -> Ufp128 {
+  value.parse().unwrap()
+}
+
+// No interest, so only the price can move a vault's health
+async fn deploy_with_mock() -> YamaDeployment {
+  let template = DeploymentPlan::default().collateral_types[0].clone();
+  let plan = DeploymentPlan {
+    collateral_types: vec![CollateralTypePlan {
+      interest_rate: Ufp128::ONE.into(),
+      ..template
+    }],
+    mock_price: Some(Ufp128::ONE.into()),
+    ..DeploymentPlan::default()
+  };
+  deploy(&get_test_wallet().await, &plan).await.unwrap()
+}
+
+#[test]
+fn test_price_paths() {
+  let linear = PricePath::linear(Ufp128::ONE, price("0.5"), 4, 60);
+  let prices: Vec<Ufp128> = linear.steps
+    .iter()
+    .map(|step| step.price)
+    .collect();
+  assert_eq!(
+    prices,
+    vec![price("0.875"), price("0.75"), price("0.625"), price("0.5")]
+  );
+  assert_eq!(linear.duration(), 240);
+
+  let geometric = PricePath::geometric(Ufp128::ONE, price("0.25"), 2, 10);
+  assert!((geometric.steps[0].price.to_f64() - 0.5).abs() < 1e-9);
+  assert_eq!(geometric.last_price(), Some(price("0.25")));
+
+  let path = PricePath::new()
+    .then(0, price("2"))
+    .chain(&PricePath::from_prices(&[price("1.5")], 30));
+  assert_eq!(path.actions(), vec![
+    Action::SetPrice(price("2")),
+    Action::AdvanceTime(30),
+    Action::SetPrice(price("1.5"))
+  ]);
+}
+
+#[tokio::test]
+async fn test_set_price() {
+  let deployment = deploy_with_mock().await;
+  let mock = deployment.mock_price_source.as_ref().unwrap();
+  let clock = Clock::of(&deployment.wallet);
+
+  set_price(mock, &price("0.5").into()).await.unwrap();
+  assert_eq!(Ufp128::from(get_price(mock).await.unwrap()), price("0.5"));
+  // The CDP reads it through the PriceSource ABI
+  assert_eq!(
+    Ufp128::from(
+      psmpricesource::get_price(&deployment.price_source, &deployment.wallet)
+        .await
+        .unwrap()),
+    price("0.5")
+  );
+  let updated_at = get_updated_at(mock).await.unwrap();
+  assert!(updated_at > 0);
+  assert!(updated_at <= clock.now_tai64().await.unwrap());
+
+  let psm_lockup = deployment.psm_lockup.get_contract_id();
+  set_owner(mock, Identity::ContractId(ContractId::from(psm_lockup)))
+    .await
+    .unwrap();
+  assert!(matches!(
+    set_price(mock, &Ufp128::ONE.into()).await,
+    Err(YamaError::MockPriceSource(MockPriceSourceError::NotOwner))
+  ));
+}
+
+#[tokio::test]
+async fn test_stale_price() {
+  let deployment = deploy_with_mock().await;
+  let mock = deployment.mock_price_source.as_ref().unwrap();
+  let clock = Clock::of(&deployment.wallet);
+
+  // An old price is still returned, with its timestamp for consumers to judge
+  let now = clock.now_tai64().await.unwrap();
+  set_price_at(mock, &price("0.9").into(), now - 3600).await.unwrap();
+  clock.advance(60).await.unwrap();
+  assert_eq!(Ufp128::from(get_price(mock).await.unwrap()), price("0.9"));
+  assert_eq!(get_updated_at(mock).await.unwrap(), now - 3600);
+}
+
+#[tokio::test]
+async fn test_crash_liquidation() {
+  let deployment = deploy_with_mock().await;
+  let mock = deployment.mock_price_source.as_ref().unwrap();
+  let clock = Clock::of(&deployment.wallet);
+  let mut client = deployment.client().await.unwrap();
+
+  mint(&deployment.collat_i, &deployment.wallet, 300).await.unwrap();
+  let vault_id = client.create_vault(0, 300, None).await.unwrap();
+  client.borrow(vault_id, 100).await.unwrap();
+  assert!(matches!(
+    client.liquidate(vault_id).await,
+    Err(YamaError::CDP(CDPError::NotUndercollateralized))
+  ));
+
+  // 300 of collateral backs 100 of debt down to a price of 0.5
+  let crash = PricePath::geometric(Ufp128::ONE, price("0.4"), 5, 60);
+  follow_price_path(mock, &clock, &crash).await.unwrap();
+  assert_eq!(Ufp128::from(get_price(mock).await.unwrap()), price("0.4"));
+  client.liquidate(vault_id).await.unwrap();
+}
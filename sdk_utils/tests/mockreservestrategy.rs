@@ -0,0 +1,178 @@
+use fuels::{tx::ContractId, types::Bits256};
+use sdk_utils::{
+  balancesheet::total_surplus,
+  deploy::{
+    deploy,
+    DeploymentPlan,
+    YamaDeployment
+  },
+  errors::{
+    MockReserveStrategyError,
+    ModularTokenError,
+    PSMError,
+    YamaError
+  },
+  mockreservestrategy::{
+    add_yield,
+    get_balance,
+    init_mockreservestrategy,
+    lose
+  },
+  modulartoken::{
+    get_aid,
+    get_cid,
+    mint,
+    set_allowlist
+  },
+  psm::{
+    allocate,
+    deposit,
+    get_allocation,
+    get_reserves,
+    recall,
+    report,
+    set_max_allocation,
+    set_strategy
+  },
+  utils::{
+    get_id,
+    get_test_wallet,
+    i256_to_i128
+  }
+};
+
+async fn surplus(deployment: &YamaDeployment) -> i128 {
+  let surplus = total_surplus(
+    &deployment.balancesheet,
+    deployment.stable_i.get_contract_id()
+  ).await.unwrap();
+  i256_to_i128(&surplus).unwrap()
+}
+
+#[tokio::test]
+async fn test_allocate_and_report() {
+  let wallet = get_test_wallet().await;
+  let deployment = deploy(&wallet, &DeploymentPlan::default()).await.unwrap();
+  let (psm, stable_id, token) = (
+    &deployment.psm,
+    get_cid(&deployment.stable_i),
+    get_cid(&deployment.collat_i)
+  );
+  let token_aid = get_aid(&deployment.collat_i);
+  mint(&deployment.collat_i, &wallet, 10_200).await.unwrap();
+  deposit(psm, &stable_id, &token_aid, 10_000).await.unwrap();
+
+  let strategy = init_mockreservestrategy(
+    &wallet, &ContractId::from(psm.get_contract_id())).await.unwrap();
+  let strategy_id = ContractId::from(strategy.get_contract_id());
+  assert!(matches!(
+    allocate(psm, &stable_id, &strategy_id, &token, 1_000).await,
+    Err(YamaError::PSM(PSMError::StrategyNotAllowed))
+  ));
+
+  // Never more than all of the reserves
+  assert!(matches!(
+    set_max_allocation(psm, &stable_id, 10_001).await,
+    Err(YamaError::PSM(PSMError::MaxAllocationExceedsDenominator))
+  ));
+
+  // Up to half of the reserves
+  set_strategy(psm, &stable_id, &strategy_id, true).await.unwrap();
+  set_max_allocation(psm, &stable_id, 5_000).await.unwrap();
+  assert!(matches!(
+    allocate(psm, &stable_id, &strategy_id, &token, 5_001).await,
+    Err(YamaError::PSM(PSMError::ExceedsMaxAllocation))
+  ));
+  allocate(psm, &stable_id, &strategy_id, &token, 5_000).await.unwrap();
+  assert_eq!(get_balance(&strategy, &token).await.unwrap(), 5_000);
+  assert_eq!(get_reserves(psm, &token).await.unwrap(), 10_000);
+
+  // Yield is recalled and booked as surplus, 90% of which is shared
+  let surplus_before = surplus(&deployment).await;
+  add_yield(&strategy, &token_aid, 200).await.unwrap();
+  report(psm, &stable_id, &strategy_id, &token).await.unwrap();
+  assert_eq!(get_balance(&strategy, &token).await.unwrap(), 5_000);
+  assert_eq!(get_reserves(psm, &token).await.unwrap(), 10_200);
+  assert_eq!(surplus(&deployment).await, surplus_before + 20);
+
+  // Losses are written off the allocation as a deficit
+  lose(&strategy, &token_aid, 1_000).await.unwrap();
+  report(psm, &stable_id, &strategy_id, &token).await.unwrap();
+  assert_eq!(
+    get_allocation(psm, &strategy_id, &token).await.unwrap(),
+    4_000
+  );
+  assert_eq!(get_reserves(psm, &token).await.unwrap(), 9_200);
+  assert_eq!(surplus(&deployment).await, surplus_before + 20 - 1_000);
+
+  // Disallowed strategies can still be recalled from
+  set_strategy(psm, &stable_id, &strategy_id, false).await.unwrap();
+  assert!(matches!(
+    recall(psm, &stable_id, &strategy_id, &token, 4_001).await,
+    Err(YamaError::PSM(PSMError::ExceedsAllocation))
+  ));
+  recall(psm, &stable_id, &strategy_id, &token, 4_000).await.unwrap();
+  assert_eq!(get_balance(&strategy, &token).await.unwrap(), 0);
+  assert_eq!(get_reserves(psm, &token).await.unwrap(), 9_200);
+}
+
+#[tokio::test]
+async fn test_only_psm_withdraws() {
+  let wallet = get_test_wallet().await;
+  let deployment = deploy(&wallet, &DeploymentPlan::default()).await.unwrap();
+  let token = get_cid(&deployment.collat_i);
+  let strategy = init_mockreservestrategy(
+    &wallet, &ContractId::from(deployment.psm.get_contract_id())
+  ).await.unwrap();
+
+  mint(&deployment.collat_i, &wallet, 100).await.unwrap();
+  add_yield(&strategy, &get_aid(&deployment.collat_i), 100).await.unwrap();
+  let result = strategy
+    .methods()
+    .withdraw(Bits256(*token), 100)
+    .append_variable_outputs(1)
+    .call()
+    .await;
+  assert!(matches!(
+    result.map_err(YamaError::from),
+    Err(YamaError::MockReserveStrategy(MockReserveStrategyError::NotPSM))
+  ));
+  assert!(matches!(
+    lose(&strategy, &get_aid(&deployment.collat_i), 101).await,
+    Err(YamaError::MockReserveStrategy(
+      MockReserveStrategyError::InsufficientBalance))
+  ));
+}
+
+#[tokio::test]
+async fn test_report_requires_allowed() {
+  let wallet = get_test_wallet().await;
+  let deployment = deploy(&wallet, &DeploymentPlan::default()).await.unwrap();
+  let (psm, stable_id, token) = (
+    &deployment.psm,
+    get_cid(&deployment.stable_i),
+    get_cid(&deployment.collat_i)
+  );
+
+  // A strategy that was never allowed can't book its reported balance as
+  // surplus, whatever it claims to hold
+  let fake = init_mockreservestrategy(
+    &wallet, &ContractId::from(psm.get_contract_id())).await.unwrap();
+  let fake_id = ContractId::from(fake.get_contract_id());
+  mint(&deployment.collat_i, &wallet, 1_000).await.unwrap();
+  add_yield(&fake, &get_aid(&deployment.collat_i), 1_000).await.unwrap();
+  let surplus_before = surplus(&deployment).await;
+  assert!(matches!(
+    report(psm, &stable_id, &fake_id, &token).await,
+    Err(YamaError::PSM(PSMError::StrategyNotAllowed))
+  ));
+
+  // Nor can a caller outside the allowlist report an allowed one
+  set_strategy(psm, &stable_id, &fake_id, true).await.unwrap();
+  set_allowlist(&deployment.stable_i, get_id(&wallet), false).await.unwrap();
+  assert!(matches!(
+    report(psm, &stable_id, &fake_id, &token).await,
+    Err(YamaError::ModularToken(ModularTokenError::UnauthorizedUser))
+  ));
+  assert_eq!(surplus(&deployment).await, surplus_before);
+}
@@ -1,6 +1,10 @@
 use fuels::{prelude::*, types::Identity};
 use sdk_utils::{
     utils::get_id,
+    errors::{
+        YamaError,
+        ModularTokenError
+    },
     modulartoken::{
         mint,
         init_modulartoken,
@@ -20,7 +24,7 @@ async fn authorized_mint() {
     println!("{}", get_aid(&instance));
     let wallet_address = Address::new(*wallet.address().hash());
     let wallet_id = Identity::Address(wallet_address);
-    println!("{}", get_allowlist(&instance, wallet_id).await);
+    println!("{}", get_allowlist(&instance, wallet_id).await.unwrap());
 
 
     assert_eq!(
@@ -29,7 +33,7 @@ async fn authorized_mint() {
     );
 
     println!("Test2");
-    mint(&instance, &wallet, 10).await;
+    mint(&instance, &wallet, 10).await.unwrap();
     
     println!("Test3");
     assert_eq!(
@@ -46,19 +50,23 @@ async fn authorization() {
     let wallet_id = Identity::Address(wallet_address);
     
     assert_eq!(get_allowlist(&instance,
-        Identity::ContractId(ContractId::zeroed())).await, false);
+        Identity::ContractId(ContractId::zeroed())).await.unwrap(), false);
 
 
-    assert_eq!(get_allowlist(&instance, wallet_id).await, true);
+    assert_eq!(get_allowlist(&instance, wallet_id).await.unwrap(), true);
 }
 
 #[tokio::test]
-#[should_panic]
 async fn unauthorized_mint() {
     let (instance, wallet)
         = init_modulartoken().await;
-    
-    mint(&instance, &wallet, 10).await;
+
+    set_allowlist(&instance, get_id(&wallet), false).await.unwrap();
+    let result = mint(&instance, &wallet, 10).await;
+    assert!(matches!(
+        result,
+        Err(YamaError::ModularToken(ModularTokenError::UnauthorizedUser))
+    ));
     assert_eq!(
         wallet.get_asset_balance(&get_aid(&instance)).await.unwrap(),
         0
@@ -71,7 +79,7 @@ async fn burn_test() {
     let (instance, wallet)
         = init_modulartoken().await;
     
-    mint(&instance, &wallet, 10).await;
+    mint(&instance, &wallet, 10).await.unwrap();
 
     assert_eq!(
         wallet.get_asset_balance(&get_aid(&instance)).await.unwrap(),
@@ -93,30 +101,32 @@ async fn burn_test() {
 }
 
 #[tokio::test]
-#[should_panic]
 async fn unauthorized_burn() {
     let (instance, wallet)
         = init_modulartoken().await;
     // Mint tokens
     
-    mint(&instance, &wallet, 10).await;
+    mint(&instance, &wallet, 10).await.unwrap();
 
     assert_eq!(
         wallet.get_asset_balance(&get_aid(&instance)).await.unwrap(),
         10
     );
 
-    set_allowlist(&instance, get_id(&wallet), false).await;
+    set_allowlist(&instance, get_id(&wallet), false).await.unwrap();
 
     // Burn tokens
-    let _result = instance
+    let result = instance
         .methods()
         .burn()
         .call_params(CallParameters::new(Some(10), Some(get_aid(&instance)), None))
         .call()
         .await
-        .unwrap();
-
+        .map_err(YamaError::from);
+    assert!(matches!(
+        result,
+        Err(YamaError::ModularToken(ModularTokenError::UnauthorizedUser))
+    ));
 }
 
 
@@ -128,8 +138,8 @@ async fn set_allowlist_test() {
 
     let wallet_id = get_id(&wallet);
 
-    assert_eq!(get_allowlist(&instance, wallet_id.clone()).await, true);
+    assert_eq!(get_allowlist(&instance, wallet_id.clone()).await.unwrap(), true);
 
-    set_allowlist(&instance, wallet_id.clone(), false).await;
-    assert_eq!(get_allowlist(&instance, wallet_id).await, false);
+    set_allowlist(&instance, wallet_id.clone(), false).await.unwrap();
+    assert_eq!(get_allowlist(&instance, wallet_id).await.unwrap(), false);
 }
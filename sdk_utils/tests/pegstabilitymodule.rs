@@ -1,10 +1,21 @@
 use sdk_utils::{
+  deploy::{
+    deploy,
+    DeploymentPlan,
+    PSMPlan
+  },
   psm::*,
+  errors::{
+    YamaError,
+    PSMError
+  },
   modulartoken::{
+    init_custom_modulartoken,
     mint,
     get_cid,
     get_aid
-  }
+  },
+  utils::get_test_wallet
 };
 
 #[tokio::test]
@@ -18,7 +29,7 @@ async fn deposit_test() {
 
   let amount = 53;
 
-  mint(&ext_i, &wallet, amount).await;
+  mint(&ext_i, &wallet, amount).await.unwrap();
 
   assert_eq!(
       wallet.get_asset_balance(&get_aid(&stable_i)).await.unwrap(),
@@ -29,7 +40,7 @@ async fn deposit_test() {
       amount
   );
 
-  deposit(&instance, &get_cid(&stable_i), &get_aid(&ext_i), amount).await;
+  deposit(&instance, &get_cid(&stable_i), &get_aid(&ext_i), amount).await.unwrap();
 
 
   assert_eq!(
@@ -53,8 +64,8 @@ async fn withdraw_test() {
 
   let amount = 53;
 
-  mint(&ext_i, &wallet, amount).await;
-  deposit(&instance, &get_cid(&stable_i), &get_aid(&ext_i), amount).await;
+  mint(&ext_i, &wallet, amount).await.unwrap();
+  deposit(&instance, &get_cid(&stable_i), &get_aid(&ext_i), amount).await.unwrap();
 
   assert_eq!(
       wallet.get_asset_balance(&get_aid(&stable_i)).await.unwrap(),
@@ -65,7 +76,9 @@ async fn withdraw_test() {
       0
   );
 
-  withdraw(&instance, &get_cid(&stable_i), &get_aid(&stable_i), 53).await;
+  withdraw(
+      &instance, &get_cid(&stable_i), &get_aid(&stable_i), &get_aid(&ext_i), 53
+  ).await.unwrap();
 
   assert_eq!(
       wallet.get_asset_balance(&get_aid(&stable_i)).await.unwrap(),
@@ -78,7 +91,6 @@ async fn withdraw_test() {
 }
 
 #[tokio::test]
-#[should_panic]
 async fn deposit_exceeds_debt_ceiling() {
   let (
       instance,
@@ -89,6 +101,123 @@ async fn deposit_exceeds_debt_ceiling() {
 
   let amount = u64::MAX / 2;
 
-  mint(&ext_i, &wallet, amount).await;
-  deposit(&instance, &get_cid(&stable_i), &get_aid(&ext_i), amount).await;
-}
\ No newline at end of file
+  mint(&ext_i, &wallet, amount).await.unwrap();
+  let result = deposit(
+      &instance, &get_cid(&stable_i), &get_aid(&ext_i), amount).await;
+  assert!(matches!(
+      result,
+      Err(YamaError::PSM(PSMError::ExceedsDebtCeiling))
+  ));
+}
+
+#[tokio::test]
+async fn second_token_test() {
+  let (
+      instance,
+      wallet,
+      stable_i,
+      ext_i,
+  ) = init_psm().await;
+  let usdc_i = init_custom_modulartoken(&wallet).await.unwrap();
+
+  // Not accepted until it's configured
+  mint(&usdc_i, &wallet, 2_000_000).await.unwrap();
+  let result = deposit(
+      &instance, &get_cid(&stable_i), &get_aid(&usdc_i), 1_000_000).await;
+  assert!(matches!(
+      result,
+      Err(YamaError::PSM(PSMError::UnsupportedToken))
+  ));
+
+  // 6 decimals against the stablecoin's 4
+  set_token_config(
+      &instance, &get_cid(&stable_i), &get_cid(&usdc_i), 6, 1_500_000, true
+  ).await.unwrap();
+  assert_eq!(
+      get_tokens(&instance).await.unwrap(),
+      vec![get_cid(&ext_i), get_cid(&usdc_i)]
+  );
+  assert_eq!(
+      get_token_config(&instance, &get_cid(&usdc_i)).await.unwrap().decimals,
+      6
+  );
+
+  deposit(&instance, &get_cid(&stable_i), &get_aid(&usdc_i), 1_000_000)
+      .await.unwrap();
+  assert_eq!(
+      wallet.get_asset_balance(&get_aid(&stable_i)).await.unwrap(),
+      10_000
+  );
+
+  // Each token has its own debt ceiling
+  let result = deposit(
+      &instance, &get_cid(&stable_i), &get_aid(&usdc_i), 1_000_000).await;
+  assert!(matches!(
+      result,
+      Err(YamaError::PSM(PSMError::ExceedsDebtCeiling))
+  ));
+
+  // Disabled tokens can still be withdrawn
+  set_token_config(
+      &instance, &get_cid(&stable_i), &get_cid(&usdc_i), 6, 1_500_000, false
+  ).await.unwrap();
+  let result = deposit(
+      &instance, &get_cid(&stable_i), &get_aid(&usdc_i), 100).await;
+  assert!(matches!(
+      result,
+      Err(YamaError::PSM(PSMError::TokenDisabled))
+  ));
+  withdraw(
+      &instance,
+      &get_cid(&stable_i),
+      &get_aid(&stable_i),
+      &get_aid(&usdc_i),
+      10_000
+  ).await.unwrap();
+  assert_eq!(
+      wallet.get_asset_balance(&get_aid(&usdc_i)).await.unwrap(),
+      2_000_000
+  );
+  assert_eq!(
+      wallet.get_asset_balance(&get_aid(&stable_i)).await.unwrap(),
+      0
+  );
+}
+
+#[tokio::test]
+async fn fees_test() {
+  let plan = DeploymentPlan {
+    psm: PSMPlan { tin: 25, tout: 110, ..PSMPlan::default() },
+    ..DeploymentPlan::default()
+  };
+  let wallet = get_test_wallet().await;
+  let deployment = deploy(&wallet, &plan).await.unwrap();
+  let (psm, stable_i, ext_i) = (
+      &deployment.psm, &deployment.stable_i, &deployment.collat_i);
+  assert_eq!(get_fees(psm).await.unwrap(), (25, 110));
+
+  mint(ext_i, &wallet, 12_345).await.unwrap();
+  let quote = quote_deposit(psm, &get_cid(ext_i), 12_345).await.unwrap();
+  assert_eq!(quote, Quote { amount: 12_315, fee: 30 });
+  deposit(psm, &get_cid(stable_i), &get_aid(ext_i), 12_345).await.unwrap();
+  assert_eq!(
+      wallet.get_asset_balance(&get_aid(stable_i)).await.unwrap(),
+      quote.amount
+  );
+
+  let quote = quote_withdraw(psm, &get_cid(ext_i), 10_000).await.unwrap();
+  assert_eq!(quote, Quote { amount: 9_890, fee: 110 });
+  withdraw(
+      psm, &get_cid(stable_i), &get_aid(stable_i), &get_aid(ext_i), 10_000
+  ).await.unwrap();
+  assert_eq!(
+      wallet.get_asset_balance(&get_aid(ext_i)).await.unwrap(),
+      quote.amount
+  );
+
+  let result = set_fees(psm, &get_cid(stable_i), 10_001, 0).await;
+  assert!(matches!(
+      result,
+      Err(YamaError::PSM(PSMError::FeeExceedsDenominator))
+  ));
+}
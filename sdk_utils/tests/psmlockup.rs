@@ -1,10 +1,23 @@
 use fuels::prelude::*;
 use sdk_utils::{
+    deploy::{
+        deploy,
+        DeploymentPlan,
+        PSMPlan
+    },
+    psm,
     psmlockup::*,
     modulartoken::{
         mint,
-        get_aid
+        get_aid,
+        get_cid
+    },
+    clock::Clock,
+    errors::{
+        PSMLockupError,
+        YamaError
     },
+    utils::get_test_wallet,
     abigen::*
 };
 
@@ -20,14 +33,14 @@ async fn test_lockup() {
     let stable_aid = get_aid(&stable_i);
     let ext_aid = get_aid(&ext_i);
     let lockup_aid = AssetId::new(*ContractId::from(psm_lockup.get_contract_id()));
-    mint(&ext_i, &wallet, 100).await;
+    mint(&ext_i, &wallet, 100).await.unwrap();
     lockup(
         &psm_lockup,
         &psm.get_contract_id(),
         &stable_i.get_contract_id(),
         &ext_aid,
         100
-    ).await;
+    ).await.unwrap();
 
     assert_eq!(
         wallet.get_asset_balance(&stable_aid).await.unwrap(),
@@ -39,10 +52,10 @@ async fn test_lockup() {
         100
     );
     assert_eq!(
-        value(&psm_lockup, &stable_i.get_contract_id()).await,
+        value(&psm_lockup, &stable_i.get_contract_id()).await.unwrap(),
         UFP128 { value: U128 { upper: 1, lower: 0 } }
     );
-    mint(&stable_i, &wallet, 100).await;
+    mint(&stable_i, &wallet, 100).await.unwrap();
     wallet.force_transfer_to_contract(
         psm_lockup.get_contract_id(),
         100,
@@ -50,7 +63,7 @@ async fn test_lockup() {
         TxParameters::default()
     ).await.unwrap();
     assert_eq!(
-        value(&psm_lockup, &stable_i.get_contract_id()).await,
+        value(&psm_lockup, &stable_i.get_contract_id()).await.unwrap(),
         UFP128 { value: U128 { upper: 2, lower: 0 } }
     );
     redeem(
@@ -58,10 +71,176 @@ async fn test_lockup() {
         &psm.get_contract_id(),
         &stable_i.get_contract_id(),
         100
-    ).await;
+    ).await.unwrap();
 
     assert_eq!(
         wallet.get_asset_balance(&stable_aid).await.unwrap(),
         200
     );
+}
+
+#[tokio::test]
+async fn test_previews_and_quotes() {
+    let plan = DeploymentPlan {
+        psm: PSMPlan { tout: 100, ..PSMPlan::default() },
+        ..DeploymentPlan::default()
+    };
+    let wallet = get_test_wallet().await;
+    let deployment = deploy(&wallet, &plan).await.unwrap();
+    let (psm_lockup, psm_i, simplebsh) = (
+        &deployment.psm_lockup, &deployment.psm, &deployment.simplebsh);
+    let stable_id = get_cid(&deployment.stable_i);
+    let ext_id = get_cid(&deployment.collat_i);
+    let ext_aid = get_aid(&deployment.collat_i);
+    let lockup_aid = AssetId::new(*ContractId::from(psm_lockup.get_contract_id()));
+
+    mint(&deployment.collat_i, &wallet, 20_000).await.unwrap();
+    lockup_with_bsh(
+        psm_lockup,
+        psm_i.get_contract_id(),
+        deployment.stable_i.get_contract_id(),
+        simplebsh.get_contract_id(),
+        &ext_aid,
+        10_000
+    ).await.unwrap();
+
+    assert_eq!(
+        psm::preview_deposit(psm_i, &ext_id, 5_000).await.unwrap(),
+        psm::quote_deposit(psm_i, &ext_id, 5_000).await.unwrap().amount
+    );
+    assert_eq!(
+        psm::preview_withdraw(psm_i, &ext_id, 5_000).await.unwrap(),
+        4_950
+    );
+
+    // The 50 withdrawal fee leaves 45 minted to the lockup and 45 more
+    // pending, which the next lockup mints before valuing itself
+    psm::deposit(psm_i, &stable_id, &ext_aid, 5_000).await.unwrap();
+    psm::withdraw(
+        psm_i, &stable_id, &get_aid(&deployment.stable_i), &ext_aid, 5_000
+    ).await.unwrap();
+
+    let quote = quote_lockup(
+        psm_lockup, psm_i, simplebsh, &stable_id, 1_000).await.unwrap();
+    assert_eq!(quote, 991);
+    assert_eq!(
+        preview_lockup(
+            psm_lockup,
+            psm_i.get_contract_id(),
+            simplebsh.get_contract_id(),
+            1_000
+        ).await.unwrap(),
+        quote
+    );
+    lockup_with_bsh(
+        psm_lockup,
+        psm_i.get_contract_id(),
+        deployment.stable_i.get_contract_id(),
+        simplebsh.get_contract_id(),
+        &ext_aid,
+        1_000
+    ).await.unwrap();
+    assert_eq!(
+        wallet.get_asset_balance(&lockup_aid).await.unwrap(),
+        10_000 + quote
+    );
+
+    let quote = quote_redeem(psm_lockup, &stable_id, 500).await.unwrap();
+    assert_eq!(preview_redeem(psm_lockup, 500).await.unwrap(), quote);
+    let stable_before = wallet
+        .get_asset_balance(&get_aid(&deployment.stable_i))
+        .await
+        .unwrap();
+    redeem(
+        psm_lockup,
+        psm_i.get_contract_id(),
+        deployment.stable_i.get_contract_id(),
+        500
+    ).await.unwrap();
+    assert_eq!(
+        wallet.get_asset_balance(&get_aid(&deployment.stable_i)).await.unwrap(),
+        stable_before + quote
+    );
+}
+
+#[tokio::test]
+async fn test_exit_queue() {
+    let (
+        psm_lockup,
+        wallet,
+        stable_i,
+        ext_i,
+        psm
+    ) = init_psmlockup().await;
+    let stable_aid = get_aid(&stable_i);
+    let lockup_aid = AssetId::new(*ContractId::from(psm_lockup.get_contract_id()));
+    mint(&ext_i, &wallet, 100).await.unwrap();
+    lockup(
+        &psm_lockup,
+        &psm.get_contract_id(),
+        &stable_i.get_contract_id(),
+        &get_aid(&ext_i),
+        100
+    ).await.unwrap();
+    assert!(matches!(
+        request_redeem(&psm_lockup, 10).await,
+        Err(YamaError::PSMLockup(PSMLockupError::QueueDisabled))
+    ));
+    set_exit_queue(
+        &psm_lockup, &stable_i.get_contract_id(), true, 100).await.unwrap();
+
+    assert!(matches!(
+        redeem(
+            &psm_lockup,
+            &psm.get_contract_id(),
+            &stable_i.get_contract_id(),
+            10
+        ).await,
+        Err(YamaError::PSMLockup(PSMLockupError::QueueEnabled))
+    ));
+
+    let requested_at = Clock::of(&wallet).now_tai64().await.unwrap();
+    let ticket_id = request_redeem(&psm_lockup, 40).await.unwrap();
+    assert_eq!(ticket_id, 0);
+    assert_eq!(wallet.get_asset_balance(&lockup_aid).await.unwrap(), 60);
+    let unlock_time = get_ticket(&psm_lockup, ticket_id)
+        .await
+        .unwrap()
+        .unlock_time;
+    assert!(unlock_time >= requested_at + 100);
+
+    // The unlock time is fixed when the ticket is requested
+    set_exit_queue(
+        &psm_lockup, &stable_i.get_contract_id(), true, 1000).await.unwrap();
+    assert_eq!(
+        get_ticket(&psm_lockup, ticket_id).await.unwrap().unlock_time,
+        unlock_time
+    );
+
+    // Cancelling returns the lockup tokens and closes the ticket
+    let cancelled_id = request_redeem(&psm_lockup, 20).await.unwrap();
+    cancel_redeem(&psm_lockup, cancelled_id).await.unwrap();
+    assert_eq!(wallet.get_asset_balance(&lockup_aid).await.unwrap(), 60);
+    assert!(matches!(
+        cancel_redeem(&psm_lockup, cancelled_id).await,
+        Err(YamaError::PSMLockup(PSMLockupError::TicketClosed))
+    ));
+
+    // The lockup holds enough YSS, so the ticket is claimable before it
+    // unlocks
+    assert_eq!(
+        claim_redeem(
+            &psm_lockup, &stable_i.get_contract_id(), ticket_id).await.unwrap(),
+        40
+    );
+    assert_eq!(wallet.get_asset_balance(&stable_aid).await.unwrap(), 40);
+    assert!(get_ticket(&psm_lockup, ticket_id).await.unwrap().done);
+    assert_eq!(
+        psm_lockup.methods().total_supply().simulate().await.unwrap().value,
+        60
+    );
+    assert!(matches!(
+        claim_redeem(&psm_lockup, &stable_i.get_contract_id(), ticket_id).await,
+        Err(YamaError::PSMLockup(PSMLockupError::TicketClosed))
+    ));
 }
\ No newline at end of file
@@ -0,0 +1,175 @@
+use fuels::{prelude::*, types::{Bits256, Identity}};
+use sdk_utils::{
+  modulartoken::mint,
+  deploy::{
+    deploy,
+    CollateralTypePlan,
+    DeploymentPlan,
+    YamaDeployment
+  },
+  errors::{
+    CDPError,
+    YamaError
+  },
+  risk::VaultRisk,
+  ufp128::Ufp128,
+  abigen::*,
+  utils::get_test_wallet
+};
+
+fn test_vault(collateral_amount: u64, initial_debt: Ufp128) -> Vault {
+  Vault {
+    collateral_amount,
+    collateral_type_id: 0,
+    owner: Identity::Address(Address::zeroed()),
+    alt_owner: None,
+    initial_debt: initial_debt.into(),
+    is_liquidated: false
+  }
+}
+
+fn test_collateral_type(initial_debt: Ufp128) -> CollateralType {
+  CollateralType {
+    token: Bits256::zeroed(),
+    price_source: Bits256::zeroed(),
+    debt_floor: Ufp128::from_u64(100).into(),
+    debt_ceiling: Ufp128::from_u64(10_000).into(),
+    collateral_ratio: "1.5".parse::<Ufp128>().unwrap().into(),
+    interest_rate: Ufp128::from_u64(2).into(),
+    total_collateral: 0,
+    last_update_time: 1_000,
+    initial_debt: initial_debt.into(),
+    cumulative_interest: Ufp128::ONE.into(),
+    borrowing_enabled: true,
+    allowlist_enabled: false
+  }
+}
+
+fn test_risk(collateral_amount: u64, debt: u64, price: &str) -> VaultRisk {
+  let debt = Ufp128::from_u64(debt);
+  VaultRisk::new(
+    test_vault(collateral_amount, debt),
+    test_collateral_type(debt),
+    &price.parse::<Ufp128>().unwrap().into()
+  )
+}
+
+#[test]
+fn test_health() {
+  let risk = test_risk(300, 100, "1");
+  assert_eq!(risk.debt(), Ufp128::from_u64(100));
+  assert_eq!(risk.collateral_value(), Ufp128::from_u64(300));
+  assert_eq!(risk.target_collateral_value(), Ufp128::from_u64(150));
+  assert_eq!(risk.current_ratio(), Some(Ufp128::from_u64(3)));
+  assert_eq!(risk.liquidation_price(), Some("0.5".parse().unwrap()));
+  assert!(!risk.is_undercollateralized());
+
+  // Just below the liquidation price
+  let risk = test_risk(300, 100, "0.49");
+  assert!(risk.is_undercollateralized());
+  assert_eq!(test_risk(300, 0, "1").liquidation_price(), None);
+}
+
+#[test]
+fn test_accrue() {
+  let mut risk = test_risk(300, 100, "1");
+  risk.accrue(1_000);
+  assert_eq!(risk.debt(), Ufp128::from_u64(100));
+  // Doubles every second
+  risk.accrue(1_002);
+  assert_eq!(risk.debt(), Ufp128::from_u64(400));
+  assert_eq!(risk.total_debt(), Ufp128::from_u64(400));
+  assert!(risk.is_undercollateralized());
+}
+
+#[test]
+fn test_borrow_limits() {
+  let risk = test_risk(300, 0, "1");
+  assert_eq!(risk.max_borrowable(), 200);
+  assert_eq!(risk.check_borrow(200), Ok(()));
+  assert_eq!(risk.check_borrow(201), Err(CDPError::InvalidDebtAmount));
+  // Below the debt floor
+  assert_eq!(risk.check_borrow(99), Err(CDPError::InvalidDebtAmount));
+  // Too little collateral to reach the debt floor
+  assert_eq!(test_risk(120, 0, "1").max_borrowable(), 0);
+
+  let mut ceiling = test_risk(30_000, 0, "1");
+  ceiling.collateral_type.debt_ceiling = Ufp128::from_u64(1_000).into();
+  assert_eq!(ceiling.max_borrowable(), 1_000);
+  assert_eq!(ceiling.check_borrow(1_001), Err(CDPError::ExceedsDebtCeiling));
+
+  let mut disabled = test_risk(300, 0, "1");
+  disabled.collateral_type.borrowing_enabled = false;
+  assert_eq!(
+    disabled.check_borrow(100),
+    Err(CDPError::CollateralTypeBorrowingDisabled)
+  );
+  assert_eq!(disabled.max_borrowable(), 0);
+}
+
+#[test]
+fn test_repay_and_withdraw_limits() {
+  let risk = test_risk(300, 150, "1");
+  assert_eq!(risk.check_repay(150), Ok(()));
+  assert_eq!(risk.check_repay(151), Err(CDPError::RepayOverpayment));
+  // Would leave 60, below the debt floor
+  assert_eq!(risk.check_repay(90), Err(CDPError::InvalidDebtAmount));
+
+  assert_eq!(risk.max_withdrawable(), 75);
+  assert_eq!(risk.check_remove_collateral(75), Ok(()));
+  assert_eq!(
+    risk.check_remove_collateral(76),
+    Err(CDPError::Undercollateralized)
+  );
+  assert_eq!(
+    risk.check_remove_collateral(301),
+    Err(CDPError::Undercollateralized)
+  );
+
+  let mut liquidated = test_risk(300, 150, "1");
+  liquidated.vault.is_liquidated = true;
+  assert_eq!(liquidated.check_repay(1), Err(CDPError::Liquidated));
+  assert_eq!(liquidated.max_withdrawable(), 0);
+}
+
+#[tokio::test]
+async fn test_limits_match_chain() {
+  // Without interest, so the limits don't move between calls
+  let template = DeploymentPlan::default().collateral_types[0].clone();
+  let plan = DeploymentPlan {
+    collateral_types: vec![CollateralTypePlan {
+      interest_rate: Ufp128::ONE.into(),
+      ..template
+    }],
+    ..DeploymentPlan::default()
+  };
+  let wallet = get_test_wallet().await;
+  let deployment = deploy(&wallet, &plan).await.unwrap();
+  let YamaDeployment { collat_i, .. } = &deployment;
+  let mut client = deployment.client().await.unwrap();
+
+  mint(collat_i, &wallet, 300).await.unwrap();
+  let vault_id = client.create_vault(0, 300, None).await.unwrap();
+
+  let risk = client.vault_risk(vault_id).await.unwrap();
+  let max_borrowable = risk.max_borrowable();
+  assert_eq!(max_borrowable, 200);
+  assert!(matches!(
+    client.borrow(vault_id, max_borrowable + 1).await,
+    Err(YamaError::CDP(CDPError::InvalidDebtAmount))
+  ));
+  client.borrow(vault_id, max_borrowable).await.unwrap();
+
+  let risk = client.vault_risk(vault_id).await.unwrap();
+  assert_eq!(risk.debt(), Ufp128::from_u64(200));
+  assert!(!risk.is_undercollateralized());
+  assert_eq!(risk.max_withdrawable(), 0);
+  assert_eq!(
+    risk.check_remove_collateral(1),
+    Err(CDPError::Undercollateralized)
+  );
+  assert!(matches!(
+    client.remove_collateral(vault_id, 1).await,
+    Err(YamaError::CDP(CDPError::Undercollateralized))
+  ));
+}
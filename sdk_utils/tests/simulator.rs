@@ -0,0 +1,503 @@
+use sdk_utils::{
+  abigen::PartialLot,
+  deploy::{
+    deploy,
+    BSHTarget,
+    CollateralTypePlan,
+    DeploymentPlan,
+    PartialLiquidationPlan,
+    PSMPlan
+  },
+  errors::{
+    CDPError,
+    DutchAuctionLiquidatorError,
+    PSMError,
+    YamaError
+  },
+  psmlockup,
+  simulator::{
+    convert_amount,
+    replay,
+    run_crash_scenarios,
+    Action,
+    CrashConfig,
+    Simulator
+  },
+  ufp128::Ufp128,
+  utils::get_test_wallet
+};
+
+fn open_vault(simulator: &mut Simulator, collateral: u64, debt: u64) {
+  let vault_id = simulator.vaults.len() as u64;
+  simulator.apply(&Action::MintCollateral(collateral)).unwrap();
+  simulator.apply(&Action::CreateVault {
+    collateral_type_id: 0,
+    collateral_amount: collateral
+  }).unwrap();
+  simulator.apply(&Action::Borrow { vault_id, amount: debt }).unwrap();
+}
+
+// No interest and a mock price, with partial liquidations back to a ratio of
+// 2 at a 25% penalty when `partial` is set
+fn liquidation_plan(partial: bool) -> DeploymentPlan {
+  let template = DeploymentPlan::default().collateral_types[0].clone();
+  DeploymentPlan {
+    collateral_types: vec![CollateralTypePlan {
+      interest_rate: Ufp128::ONE.into(),
+      partial_liquidation: partial.then(|| PartialLiquidationPlan {
+        target_ratio: Ufp128::from_u64(2).into(),
+        penalty: "0.25".parse::<Ufp128>().unwrap().into()
+      }),
+      ..template
+    }],
+    mock_price: Some(Ufp128::ONE.into()),
+    ..DeploymentPlan::default()
+  }
+}
+
+#[test]
+fn test_convert_amount() {
+  assert_eq!(convert_amount(1_234_567, 6, 4), Some(12_345));
+  assert_eq!(convert_amount(12_345, 4, 6), Some(1_234_500));
+  assert_eq!(convert_amount(7, 4, 4), Some(7));
+  assert_eq!(convert_amount(u64::MAX, 4, 6), None);
+}
+
+#[test]
+fn test_interest_and_revenue_share() {
+  // Interest doubles debt every second
+  let mut simulator = Simulator::new(&DeploymentPlan::default(), 1_000);
+  open_vault(&mut simulator, 300, 100);
+  simulator.apply(&Action::AdvanceTime(1)).unwrap();
+  simulator.apply(&Action::UpdateInterest { collateral_type_id: 0 }).unwrap();
+
+  assert_eq!(simulator.risk(0).unwrap().debt(), Ufp128::from_u64(200));
+  // 90% of the 100 in interest goes to the lockup
+  assert_eq!(simulator.metrics.interest, 100);
+  assert_eq!(simulator.surplus, 10);
+  assert_eq!(simulator.pending_share_amount, 90);
+  assert_eq!(simulator.lockup.balance, 90);
+
+  // The pending share is minted again before the lockup is priced
+  simulator.apply(&Action::MintCollateral(100)).unwrap();
+  simulator.apply(&Action::Lockup(100)).unwrap();
+  assert_eq!(simulator.pending_share_amount, 0);
+  assert_eq!(simulator.wallet.lockup_shares, 100);
+  assert_eq!(simulator.lockup.balance, 280);
+  assert_eq!(simulator.psm.reserves, 100);
+
+  // 2.8 rounds down, as does the payout
+  simulator.apply(&Action::Redeem(50)).unwrap();
+  assert_eq!(simulator.wallet.stablecoin, 100 + 139);
+  assert_eq!(simulator.lockup.balance, 141);
+  assert_eq!(simulator.lockup.total_supply, 50);
+}
+
+#[test]
+fn test_liquidation_and_claim() {
+  let mut simulator = Simulator::new(&DeploymentPlan::default(), 1_000);
+  open_vault(&mut simulator, 150, 100);
+  assert!(matches!(
+    simulator.apply(&Action::Liquidate { vault_id: 0 }),
+    Err(YamaError::CDP(CDPError::NotUndercollateralized))
+  ));
+
+  // Liquidation checks the debt before accruing interest
+  simulator.apply(&Action::AdvanceTime(1)).unwrap();
+  assert!(matches!(
+    simulator.apply(&Action::Liquidate { vault_id: 0 }),
+    Err(YamaError::CDP(CDPError::NotUndercollateralized))
+  ));
+  simulator.apply(&Action::UpdateInterest { collateral_type_id: 0 }).unwrap();
+  simulator.apply(&Action::Liquidate { vault_id: 0 }).unwrap();
+  assert_eq!(simulator.auction_price(0).unwrap(), 225);
+  assert!(matches!(
+    simulator.apply(&Action::Borrow { vault_id: 0, amount: 1 }),
+    Err(YamaError::CDP(CDPError::Liquidated))
+  ));
+
+  simulator.apply(&Action::AdvanceTime(1)).unwrap();
+  simulator.apply(&Action::MintStablecoin(200)).unwrap();
+  assert!(matches!(
+    simulator.apply(&Action::Claim {
+      auction_id: 0,
+      max_price: 111,
+      amount: 200
+    }),
+    Err(YamaError::DutchAuctionLiquidator(
+      DutchAuctionLiquidatorError::ExceedsMaxPrice))
+  ));
+  assert!(matches!(
+    simulator.apply(&Action::Claim {
+      auction_id: 0,
+      max_price: 112,
+      amount: 100
+    }),
+    Err(YamaError::DutchAuctionLiquidator(
+      DutchAuctionLiquidatorError::InvalidPayment))
+  ));
+  simulator.apply(&Action::Claim {
+    auction_id: 0,
+    max_price: 112,
+    amount: 200
+  }).unwrap();
+
+  // Pays 112 for debt that doubled to 400 by the claim
+  assert_eq!(simulator.wallet.stablecoin, 300 - 112);
+  assert_eq!(simulator.wallet.collateral, 150);
+  assert_eq!(simulator.metrics.bad_debt, 288);
+  assert_eq!(simulator.surplus, 10 + 20 - 288);
+  assert_eq!(simulator.vaults[0].collateral_amount, 0);
+  assert_eq!(simulator.risk(0).unwrap().total_debt(), Ufp128::ZERO);
+  assert_eq!(simulator.unresolved_debt(), 0);
+  assert!(matches!(
+    simulator.apply(&Action::Claim {
+      auction_id: 0,
+      max_price: 112,
+      amount: 112
+    }),
+    Err(YamaError::DutchAuctionLiquidator(
+      DutchAuctionLiquidatorError::AuctionDone))
+  ));
+}
+
+#[test]
+fn test_reset_expired_auction() {
+  let mut simulator = Simulator::new(&DeploymentPlan::default(), 1_000);
+  open_vault(&mut simulator, 150, 100);
+  simulator.apply(&Action::AdvanceTime(1)).unwrap();
+  simulator.apply(&Action::UpdateInterest { collateral_type_id: 0 }).unwrap();
+  simulator.apply(&Action::Liquidate { vault_id: 0 }).unwrap();
+
+  simulator.apply(&Action::AdvanceTime(3)).unwrap();
+  assert!(simulator.is_expired(0).unwrap());
+  assert_eq!(simulator.auction_price(0).unwrap(), 0);
+  assert_eq!(simulator.unresolved_debt(), 200);
+  assert!(matches!(
+    simulator.apply(&Action::Claim { auction_id: 0, max_price: 0, amount: 0 }),
+    Err(YamaError::DutchAuctionLiquidator(
+      DutchAuctionLiquidatorError::AuctionExpired))
+  ));
+
+  simulator.apply(&Action::ResetAuction { auction_id: 0 }).unwrap();
+  assert_eq!(simulator.auctions.len(), 2);
+  assert_eq!(simulator.auctions[1].start_time, 1_004);
+  assert_eq!(simulator.auction_price(1).unwrap(), 225);
+  assert!(matches!(
+    simulator.apply(&Action::ResetAuction { auction_id: 1 }),
+    Err(YamaError::DutchAuctionLiquidator(
+      DutchAuctionLiquidatorError::AuctionNotExpired))
+  ));
+}
+
+#[test]
+fn test_reverts_leave_state_untouched() {
+  let mut simulator = Simulator::new(&DeploymentPlan::default(), 1_000);
+  simulator.apply(&Action::MintCollateral(300)).unwrap();
+  simulator.apply(&Action::CreateVault {
+    collateral_type_id: 0,
+    collateral_amount: 300
+  }).unwrap();
+  let snapshot = simulator.snapshot();
+
+  assert!(matches!(
+    simulator.apply(&Action::Borrow { vault_id: 0, amount: 201 }),
+    Err(YamaError::CDP(CDPError::InvalidDebtAmount))
+  ));
+  assert!(matches!(
+    simulator.apply(&Action::RemoveCollateral { vault_id: 0, amount: 301 }),
+    Err(YamaError::Reverted { .. })
+  ));
+  // Nothing to pay with
+  assert!(matches!(
+    simulator.apply(&Action::Repay { vault_id: 0, amount: 1 }),
+    Err(YamaError::Reverted { .. })
+  ));
+  assert_eq!(simulator.snapshot(), snapshot);
+}
+
+#[test]
+fn test_psm_decimals_and_ceiling() {
+  let plan = DeploymentPlan {
+    psm: PSMPlan {
+      debt_ceiling: Some(2_000_000),
+      external_stable_decimals: Some(6),
+      ..PSMPlan::default()
+    },
+    ..DeploymentPlan::default()
+  };
+  let mut simulator = Simulator::new(&plan, 1_000);
+  simulator.apply(&Action::MintCollateral(3_000_000)).unwrap();
+  simulator.apply(&Action::PsmDeposit(1_234_567)).unwrap();
+  assert_eq!(simulator.wallet.stablecoin, 12_345);
+  assert!(matches!(
+    simulator.apply(&Action::PsmDeposit(1_000_000)),
+    Err(YamaError::PSM(PSMError::ExceedsDebtCeiling))
+  ));
+
+  simulator.apply(&Action::PsmWithdraw(12_345)).unwrap();
+  assert_eq!(simulator.wallet.stablecoin, 0);
+  // What the deposit's rounding dropped stays in the PSM
+  assert_eq!(simulator.psm.reserves, 67);
+  assert_eq!(simulator.wallet.collateral, 3_000_000 - 67);
+}
+
+#[test]
+fn test_psm_fees() {
+  let plan = DeploymentPlan {
+    psm: PSMPlan { tin: 100, tout: 200, ..PSMPlan::default() },
+    ..DeploymentPlan::default()
+  };
+  let mut simulator = Simulator::new(&plan, 1_000);
+  simulator.apply(&Action::MintCollateral(10_000)).unwrap();
+
+  // 1% of the deposit is surplus, 90% of which goes to the lockup
+  simulator.apply(&Action::PsmDeposit(10_000)).unwrap();
+  assert_eq!(simulator.wallet.stablecoin, 9_900);
+  assert_eq!(simulator.surplus, 10);
+  assert_eq!(simulator.lockup.balance, 90);
+
+  simulator.apply(&Action::PsmWithdraw(1_000)).unwrap();
+  assert_eq!(simulator.wallet.collateral, 980);
+  assert_eq!(simulator.psm.reserves, 10_000 - 980);
+  assert_eq!(simulator.surplus, 12);
+  assert_eq!(simulator.lockup.balance, 108);
+}
+
+#[test]
+fn test_deficit_absorption() {
+  let plan = DeploymentPlan {
+    absorb_deficits: true,
+    ..DeploymentPlan::default()
+  };
+  let mut simulator = Simulator::new(&plan, 1_000);
+  simulator.apply(&Action::MintCollateral(500)).unwrap();
+  simulator.apply(&Action::Lockup(500)).unwrap();
+  open_vault(&mut simulator, 150, 100);
+  simulator.apply(&Action::AdvanceTime(1)).unwrap();
+  simulator.apply(&Action::UpdateInterest { collateral_type_id: 0 }).unwrap();
+  simulator.apply(&Action::Liquidate { vault_id: 0 }).unwrap();
+  simulator.apply(&Action::AdvanceTime(1)).unwrap();
+  simulator.apply(&Action::MintStablecoin(200)).unwrap();
+
+  let value_before = simulator.lockup_value();
+  simulator.apply(&Action::Claim {
+    auction_id: 0,
+    max_price: 112,
+    amount: 200
+  }).unwrap();
+
+  // The lockup gets 90% of both rounds of interest, then the 288 of bad
+  // debt is burned from it instead of lowering the surplus
+  assert_eq!(simulator.metrics.bad_debt, 288);
+  assert_eq!(simulator.metrics.deficit_absorbed, 288);
+  assert_eq!(simulator.surplus, 10 + 20);
+  assert_eq!(simulator.lockup.balance, 500 + 90 + 180 - 288);
+  assert!(simulator.lockup_value() < value_before);
+}
+
+#[test]
+fn test_partial_liquidation() {
+  let half = "0.5".parse::<Ufp128>().unwrap();
+  let mut full = Simulator::new(&liquidation_plan(false), 1_000);
+  let mut partial = Simulator::new(&liquidation_plan(true), 1_000);
+  for simulator in [&mut full, &mut partial] {
+    open_vault(simulator, 280, 100);
+    simulator.apply(&Action::SetPrice(half)).unwrap();
+    simulator.apply(&Action::Liquidate { vault_id: 0 }).unwrap();
+    simulator.apply(&Action::MintStablecoin(1_000)).unwrap();
+  }
+
+  // Repaying 80 of the debt sells 80 * 1.25 worth of collateral, leaving 40
+  // worth against 20 of debt
+  assert_eq!(full.partial_lots[0], None);
+  assert_eq!(full.collateral_amount_of_auction(0).unwrap(), 280);
+  assert_eq!(full.auction_price(0).unwrap(), 210);
+  assert_eq!(
+    partial.partial_lots[0],
+    Some(PartialLot { collateral_amount: 200, debt: 80 })
+  );
+  assert_eq!(partial.collateral_amount_of_auction(0).unwrap(), 200);
+  assert_eq!(partial.auction_price(0).unwrap(), 150);
+
+  for simulator in [&mut full, &mut partial] {
+    simulator.apply(&Action::Claim {
+      auction_id: 0,
+      max_price: 1_000,
+      amount: 1_000
+    }).unwrap();
+  }
+
+  // Full liquidation sells the whole vault and books everything above the
+  // debt as surplus, 90% of which is shared
+  assert_eq!(full.wallet.collateral, 280);
+  assert_eq!(full.vaults[0].collateral_amount, 0);
+  assert_eq!(full.risk(0).unwrap().debt(), Ufp128::ZERO);
+  assert_eq!(full.surplus, 11);
+  assert_eq!(full.metrics.penalties, 0);
+
+  // The partial claim covers the lot's debt and charges the rest as a
+  // penalty, then hands the vault back
+  assert_eq!(partial.wallet.collateral, 200);
+  assert_eq!(partial.vaults[0].collateral_amount, 80);
+  assert!(!partial.vaults[0].is_liquidated);
+  assert_eq!(partial.risk(0).unwrap().debt(), Ufp128::from_u64(20));
+  assert!(!partial.risk(0).unwrap().is_undercollateralized());
+  assert_eq!(partial.surplus, 7);
+  assert_eq!(partial.metrics.penalties, 70);
+  assert_eq!(partial.metrics.bad_debt, 0);
+  assert_eq!(partial.unresolved_debt(), 0);
+  partial.apply(&Action::Repay { vault_id: 0, amount: 20 }).unwrap();
+  partial.apply(&Action::RemoveCollateral { vault_id: 0, amount: 80 })
+    .unwrap();
+
+  // Vaults too far underwater to restore are still sold whole
+  let mut underwater = Simulator::new(&liquidation_plan(true), 1_000);
+  open_vault(&mut underwater, 280, 100);
+  underwater.apply(&Action::SetPrice("0.25".parse().unwrap())).unwrap();
+  underwater.apply(&Action::Liquidate { vault_id: 0 }).unwrap();
+  assert_eq!(underwater.partial_lots[0], None);
+  assert_eq!(underwater.collateral_amount_of_auction(0).unwrap(), 280);
+}
+
+#[test]
+fn test_crash_scenarios() {
+  let config = CrashConfig {
+    vaults: 5,
+    ticks: 400,
+    crash_start: (50, 100),
+    crash_depth: (0.9, 0.9),
+    ..CrashConfig::default()
+  };
+  let report = run_crash_scenarios(&config, 0..4);
+  assert_eq!(report.results, run_crash_scenarios(&config, 0..4).results);
+
+  // A 90% crash leaves every vault underwater before bidders step in
+  for result in report.results.iter() {
+    assert_eq!(result.metrics.liquidations, 5);
+    assert!(result.lowest_price < "0.2".parse::<Ufp128>().unwrap());
+    assert!(result.total_loss() > 0);
+  }
+  assert_eq!(report.runs_with_losses(), 4);
+  assert_eq!(report.loss_quantile(1.0), report.max_loss());
+}
+
+#[tokio::test]
+async fn test_replay_matches_chain() {
+  let wallet = get_test_wallet().await;
+  let deployment = deploy(&wallet, &DeploymentPlan::default()).await.unwrap();
+
+  let steps = replay(&deployment, &[
+    Action::MintCollateral(1_000),
+    Action::CreateVault { collateral_type_id: 0, collateral_amount: 150 },
+    Action::Borrow { vault_id: 0, amount: 100 },
+    Action::AdvanceTime(1),
+    Action::UpdateInterest { collateral_type_id: 0 },
+    Action::Liquidate { vault_id: 0 },
+    Action::Borrow { vault_id: 0, amount: 1 },
+    Action::MintStablecoin(1_000),
+    // The price depends on when the claim lands, so overpay
+    Action::Claim { auction_id: 0, max_price: 1_000, amount: 1_000 },
+    Action::PsmDeposit(100),
+    Action::Lockup(100),
+    Action::Redeem(50),
+    Action::PsmWithdraw(50),
+    Action::SetPrice(Ufp128::ONE)
+  ]).await.unwrap();
+
+  for step in steps.iter() {
+    assert!(step.matches(), "{:?}", step);
+  }
+}
+
+#[tokio::test]
+async fn test_replay_psm_fees() {
+  let plan = DeploymentPlan {
+    psm: PSMPlan { tin: 30, tout: 70, ..PSMPlan::default() },
+    ..DeploymentPlan::default()
+  };
+  let deployment = deploy(&get_test_wallet().await, &plan).await.unwrap();
+
+  let steps = replay(&deployment, &[
+    Action::MintCollateral(1_000),
+    Action::PsmDeposit(999),
+    Action::PsmWithdraw(500)
+  ]).await.unwrap();
+
+  for step in steps.iter() {
+    assert!(step.matches(), "{:?}", step);
+  }
+}
+
+#[tokio::test]
+async fn test_replay_deficit_absorption() {
+  let plan = DeploymentPlan {
+    absorb_deficits: true,
+    ..DeploymentPlan::default()
+  };
+  let deployment = deploy(&get_test_wallet().await, &plan).await.unwrap();
+  let stable_id = deployment.stable_i.get_contract_id();
+
+  let mut steps = replay(&deployment, &[
+    Action::MintCollateral(2_000),
+    Action::Lockup(1_000),
+    Action::CreateVault { collateral_type_id: 0, collateral_amount: 150 },
+    Action::Borrow { vault_id: 0, amount: 100 },
+    Action::AdvanceTime(1),
+    Action::UpdateInterest { collateral_type_id: 0 },
+    Action::Liquidate { vault_id: 0 },
+    Action::MintStablecoin(1_000)
+  ]).await.unwrap();
+  let value_before = Ufp128::from(
+    psmlockup::value(&deployment.psm_lockup, stable_id).await.unwrap());
+
+  // The auction clears well below the doubling debt
+  steps.extend(replay(&deployment, &[
+    Action::Claim { auction_id: 0, max_price: 1_000, amount: 1_000 }
+  ]).await.unwrap());
+  for step in steps.iter() {
+    assert!(step.matches(), "{:?}", step);
+  }
+  assert!(
+    Ufp128::from(
+      psmlockup::value(&deployment.psm_lockup, stable_id).await.unwrap())
+    < value_before
+  );
+}
+
+#[tokio::test]
+async fn test_deficit_absorption_needs_lockup() {
+  let plan = DeploymentPlan {
+    bsh_target: BSHTarget::Stablecoin,
+    absorb_deficits: true,
+    ..DeploymentPlan::default()
+  };
+  assert!(matches!(
+    deploy(&get_test_wallet().await, &plan).await,
+    Err(YamaError::InvalidPlan(_))
+  ));
+}
+
+
+#[tokio::test]
+async fn test_replay_partial_liquidation() {
+  let deployment = deploy(
+    &get_test_wallet().await, &liquidation_plan(true)).await.unwrap();
+
+  let steps = replay(&deployment, &[
+    Action::MintCollateral(1_000),
+    Action::CreateVault { collateral_type_id: 0, collateral_amount: 280 },
+    Action::Borrow { vault_id: 0, amount: 100 },
+    Action::SetPrice("0.5".parse().unwrap()),
+    Action::Liquidate { vault_id: 0 },
+    Action::MintStablecoin(1_000),
+    Action::Claim { auction_id: 0, max_price: 1_000, amount: 1_000 },
+    // The vault is the owner's again
+    Action::AddCollateral { vault_id: 0, amount: 20 },
+    Action::RemoveCollateral { vault_id: 0, amount: 10 }
+  ]).await.unwrap();
+
+  for step in steps.iter() {
+    assert!(step.matches(), "{:?}", step);
+  }
+}
@@ -0,0 +1,188 @@
+use fuels::prelude::*;
+use sdk_utils::{
+  balancesheet::{
+    add_surplus,
+    add_deficit,
+    total_surplus
+  },
+  client::YamaClient,
+  clock::Clock,
+  deploy::{
+    deploy,
+    deploy_splitbsh,
+    BSHTarget,
+    DeploymentPlan,
+    RecipientPlan,
+    SplitPlan
+  },
+  errors::{
+    SplitBSHError,
+    YamaError
+  },
+  modulartoken::{
+    get_aid,
+    mint
+  },
+  splitbsh::{
+    add_recipient,
+    get_recipients,
+    set_recipient
+  },
+  utils::{
+    get_id,
+    get_id_key,
+    get_test_wallet,
+    i256_to_i128,
+    u64_to_i256
+  },
+  abigen::*
+};
+
+#[tokio::test]
+async fn test_split_and_backstop() {
+  let wallet = get_test_wallet().await;
+  let deployment = deploy(&wallet, &DeploymentPlan::default()).await.unwrap();
+  let splitbsh = deploy_splitbsh(&deployment, &SplitPlan {
+    recipients: vec![
+      RecipientPlan { target: BSHTarget::PSMLockup, weight: 6000, cap: 0 },
+      RecipientPlan { target: BSHTarget::Stablecoin, weight: 2000, cap: 150 }
+    ],
+    backstop: true
+  }).await.unwrap();
+  let stable_id = deployment.stable_i.get_contract_id();
+  let splitbsh_id = splitbsh.get_contract_id();
+  let lockup_id = deployment.psm_lockup.get_contract_id();
+  let provider = wallet.get_provider().unwrap();
+  let stable_aid = get_aid(&deployment.stable_i);
+
+  assert!(matches!(
+    add_recipient(
+      &splitbsh,
+      stable_id,
+      get_id(&wallet),
+      3000,
+      0
+    ).await,
+    Err(YamaError::SplitBSH(SplitBSHError::WeightsExceedDenominator))
+  ));
+
+  // The stablecoin's share is capped at 150, and the rest stays as surplus
+  add_surplus(
+    &deployment.balancesheet, stable_id, splitbsh_id, &[lockup_id.clone()],
+    u64_to_i256(1000)).await.unwrap();
+  add_surplus(
+    &deployment.balancesheet, stable_id, splitbsh_id, &[lockup_id.clone()],
+    u64_to_i256(1000)).await.unwrap();
+  let total = total_surplus(&deployment.balancesheet, stable_id)
+    .await
+    .unwrap();
+  assert_eq!(i256_to_i128(&total), Some(2000 - 1200 - 150));
+  assert_eq!(
+    provider.get_contract_asset_balance(lockup_id, stable_aid).await.unwrap(),
+    1200
+  );
+  assert_eq!(
+    *deployment.stable_i
+      .get_balances()
+      .await
+      .unwrap()
+      .get(&get_id_key(&stable_aid))
+      .unwrap(),
+    150
+  );
+  let received: Vec<u64> = get_recipients(&splitbsh)
+    .await
+    .unwrap()
+    .iter()
+    .map(|recipient| recipient.received)
+    .collect();
+  assert_eq!(received, vec![1200, 150]);
+
+  // The backstop covers what it can of the deficit
+  add_deficit(
+    &deployment.balancesheet, stable_id, splitbsh_id, &[lockup_id.clone()],
+    u64_to_i256(2000)).await.unwrap();
+  let total = total_surplus(&deployment.balancesheet, stable_id)
+    .await
+    .unwrap();
+  assert_eq!(i256_to_i128(&total), Some(650 - 2000 + 1200));
+  assert_eq!(
+    provider.get_contract_asset_balance(lockup_id, stable_aid).await.unwrap(),
+    0
+  );
+}
+
+#[tokio::test]
+async fn test_cap_lowered_below_received() {
+  let wallet = get_test_wallet().await;
+  let deployment = deploy(&wallet, &DeploymentPlan::default()).await.unwrap();
+  let splitbsh = deploy_splitbsh(&deployment, &SplitPlan {
+    recipients: vec![
+      RecipientPlan { target: BSHTarget::Stablecoin, weight: 5000, cap: 200 }
+    ],
+    backstop: false
+  }).await.unwrap();
+  let stable_id = deployment.stable_i.get_contract_id();
+  let splitbsh_id = splitbsh.get_contract_id();
+
+  add_surplus(
+    &deployment.balancesheet, stable_id, splitbsh_id, &[stable_id.clone()],
+    u64_to_i256(1000)).await.unwrap();
+  set_recipient(&splitbsh, stable_id, 0, 5000, 100).await.unwrap();
+
+  // Surplus keeps being booked, with nothing more shared
+  add_surplus(
+    &deployment.balancesheet, stable_id, splitbsh_id, &[stable_id.clone()],
+    u64_to_i256(1000)).await.unwrap();
+  let total = total_surplus(&deployment.balancesheet, stable_id)
+    .await
+    .unwrap();
+  assert_eq!(i256_to_i128(&total), Some(2000 - 200));
+  assert_eq!(get_recipients(&splitbsh).await.unwrap()[0].received, 200);
+}
+
+#[tokio::test]
+async fn test_client_interest_through_split() {
+  let wallet = get_test_wallet().await;
+  let deployment = deploy(&wallet, &DeploymentPlan::default()).await.unwrap();
+  let splitbsh = deploy_splitbsh(&deployment, &SplitPlan {
+    recipients: vec![
+      RecipientPlan { target: BSHTarget::PSMLockup, weight: 6000, cap: 0 },
+      RecipientPlan { target: BSHTarget::Stablecoin, weight: 2000, cap: 0 }
+    ],
+    backstop: true
+  }).await.unwrap();
+  let stable_id = deployment.stable_i.get_contract_id();
+  let lockup_id = deployment.psm_lockup.get_contract_id();
+  let mut client = YamaClient::new(
+    deployment.cdp.get_contract_id(), &wallet).await.unwrap();
+
+  // Both recipients, then the backstop
+  let deps = client.dependencies().clone();
+  assert_eq!(&deps.bsh, splitbsh.get_contract_id());
+  assert_eq!(
+    deps.bsh_targets,
+    vec![lockup_id.clone(), stable_id.clone(), lockup_id.clone()]
+  );
+
+  // The default interest rate doubles the debt every second
+  mint(&deployment.collat_i, &wallet, 150).await.unwrap();
+  let vault_id = client.create_vault(0, 150, None).await.unwrap();
+  client.borrow(vault_id, 100).await.unwrap();
+  Clock::of(&wallet).advance(1).await.unwrap();
+  client.update_interest(0).await.unwrap();
+
+  let received: Vec<u64> = get_recipients(&splitbsh)
+    .await
+    .unwrap()
+    .iter()
+    .map(|recipient| recipient.received)
+    .collect();
+  assert!(received[0] > 0 && received[1] > 0);
+  let provider = wallet.get_provider().unwrap();
+  let stable_aid = get_aid(&deployment.stable_i);
+  assert_eq!(
+    provider.get_contract_asset_balance(lockup_id, stable_aid).await.unwrap(),
+    received[0]
+  );
+}
\ No newline at end of file
@@ -0,0 +1,88 @@
+use fuels::{prelude::*, tx::Bytes32, types::SizedAsciiString};
+use sdk_utils::{
+  storage::{
+    storage_key,
+    storage_slots,
+    StorageLayout
+  },
+  abigen::*,
+  utils::{u64_to_fp, u128_to_fp}
+};
+
+fn word(value: u64) -> Bytes32 {
+  let mut bytes = [0u8; 32];
+  bytes[..8].copy_from_slice(&value.to_be_bytes());
+  Bytes32::new(bytes)
+}
+
+#[test]
+fn test_b256_field() {
+  let id = ContractId::new([7u8; 32]);
+  let slots = storage_slots(3, &id);
+
+  assert_eq!(slots.len(), 1);
+  assert_eq!(*slots[0].key(), storage_key("storage_3"));
+  assert_eq!(*slots[0].value(), Bytes32::new([7u8; 32]));
+}
+
+#[test]
+fn test_c_type_params_field() {
+  let params = CTypeParams {
+    initial_price_ratio: u128_to_fp(0b11 << 63), // 1.5
+    time_interval: 1,
+    change_rate: u128_to_fp(0b1 << 63), // 0.5
+    reset_threshold: 3,
+    enabled: true
+  };
+  let slots = storage_slots(3, &params);
+  let expected = vec![
+    ("storage_3_0_0_0", word(1)),
+    ("storage_3_0_0_1", word(1 << 63)),
+    ("storage_3_1", word(1)),
+    ("storage_3_2_0_0", word(0)),
+    ("storage_3_2_0_1", word(1 << 63)),
+    ("storage_3_3", word(3)),
+    ("storage_3_4", word(1)),
+  ];
+
+  assert_eq!(slots.len(), expected.len());
+  for (slot, (key, value)) in slots.iter().zip(expected.iter()) {
+    assert_eq!(*slot.key(), storage_key(key));
+    assert_eq!(*slot.value(), *value);
+  }
+}
+
+#[test]
+fn test_str_field_spans_slots() {
+  let name: SizedAsciiString<64> = SizedAsciiString::new(
+    format!("{:<64}", "Yama Stablecoin")).unwrap();
+  let slots = storage_slots(4, &name);
+
+  assert_eq!(slots.len(), 2);
+  assert_eq!(*slots[0].key(), storage_key("storage_4"));
+
+  // The second half of the string lives in the slot right after the key
+  let mut next_key: [u8; 32] = *storage_key("storage_4");
+  for byte in next_key.iter_mut().rev() {
+    let (sum, overflow) = byte.overflowing_add(1);
+    *byte = sum;
+    if !overflow {
+      break;
+    }
+  }
+  assert_eq!(*slots[1].key(), Bytes32::new(next_key));
+  assert_eq!(&slots[1].value()[..], &format!("{:<32}", "").as_bytes()[..]);
+}
+
+#[test]
+fn test_layout_combines_fields() {
+  let layout = StorageLayout::new()
+    .field(0, &ContractId::zeroed())
+    .field(2, &u64_to_fp(5))
+    .field(4, &(6 as u8));
+
+  let slots = layout.slots();
+  assert_eq!(slots.len(), 4);
+  assert_eq!(*slots[3].key(), storage_key("storage_4"));
+  assert_eq!(*slots[3].value(), word(6));
+}
@@ -0,0 +1,225 @@
+use fuels::{prelude::*, types::Bits256};
+use sdk_utils::{
+  balancesheet::{
+    add_deficit,
+    set_surplus,
+    total_surplus
+  },
+  clock::Clock,
+  deploy::{
+    deploy,
+    DeploymentPlan
+  },
+  errors::{
+    ModularTokenError,
+    SurplusAuctionModuleError,
+    YamaError
+  },
+  modulartoken::{
+    get_aid,
+    init_custom_modulartoken,
+    mint,
+    set_allowlist
+  },
+  surplusauctionmodule::*,
+  utils::{
+    get_id,
+    get_test_wallet,
+    i256_to_i128,
+    u64_to_fp,
+    u128_to_fp,
+    u64_to_i256
+  },
+  abigen::*
+};
+
+#[tokio::test]
+async fn test_surplus_and_debt_auctions() {
+  let wallet = get_test_wallet().await;
+  let deployment = deploy(&wallet, &DeploymentPlan::default()).await.unwrap();
+  let gov_i = init_custom_modulartoken(&wallet).await.unwrap();
+  let balancesheet = &deployment.balancesheet;
+  let sam = init_custom_surplusauctionmodule(
+    &wallet,
+    &deployment.stable_i,
+    &ContractId::from(balancesheet.get_contract_id()),
+    &gov_i
+  ).await.unwrap();
+  let stable_id = deployment.stable_i.get_contract_id();
+  let stable_aid = get_aid(&deployment.stable_i);
+  let gov_aid = get_aid(&gov_i);
+  let balancesheet_id = balancesheet.get_contract_id();
+
+  assert!(matches!(
+    start_surplus_auction(&sam, balancesheet_id).await,
+    Err(YamaError::SurplusAuctionModule(
+      SurplusAuctionModuleError::AuctionsDisabled))
+  ));
+  set_surplus_auction_params(&sam, stable_id, &SAMParams {
+    threshold: 100,
+    lot: 50,
+    start_price: u64_to_fp(200),
+    time_interval: 10,
+    change_rate: u128_to_fp(0b1 << 63), // 0.5
+    reset_threshold: 3
+  }).await.unwrap();
+  assert!(matches!(
+    start_surplus_auction(&sam, balancesheet_id).await,
+    Err(YamaError::SurplusAuctionModule(
+      SurplusAuctionModuleError::BelowThreshold))
+  ));
+
+  // Sells 50 of the surplus for at most 200 of the governance token
+  set_surplus(balancesheet, stable_id, u64_to_i256(500)).await.unwrap();
+  let auction_id = start_surplus_auction(&sam, balancesheet_id)
+    .await
+    .unwrap();
+  assert_eq!(get_reserved(&sam, false).await.unwrap(), 50);
+  mint(&gov_i, &wallet, 1000).await.unwrap();
+  claim_surplus_auction(
+    &sam,
+    stable_id,
+    gov_i.get_contract_id(),
+    balancesheet_id,
+    auction_id,
+    200,
+    1000
+  ).await.unwrap();
+  let gov_spent = 1000 - wallet.get_asset_balance(&gov_aid).await.unwrap();
+  assert!(gov_spent > 0 && gov_spent <= 200);
+  assert_eq!(wallet.get_asset_balance(&stable_aid).await.unwrap(), 50);
+  assert_eq!(
+    i256_to_i128(&total_surplus(balancesheet, stable_id).await.unwrap()),
+    Some(450)
+  );
+  assert_eq!(get_reserved(&sam, false).await.unwrap(), 0);
+
+  // Raises 50 of YSS for at least 10 of the governance token
+  add_deficit(
+    balancesheet,
+    stable_id,
+    deployment.simplebsh.get_contract_id(),
+    &[deployment.psm_lockup.get_contract_id().clone()],
+    u64_to_i256(750)
+  ).await.unwrap();
+  set_debt_auction_params(&sam, stable_id, &SAMParams {
+    threshold: 100,
+    lot: 50,
+    start_price: u64_to_fp(10),
+    time_interval: 10,
+    change_rate: u64_to_fp(2),
+    reset_threshold: 3
+  }).await.unwrap();
+  let auction_id = start_debt_auction(&sam, balancesheet_id).await.unwrap();
+  claim_debt_auction(
+    &sam,
+    stable_id,
+    gov_i.get_contract_id(),
+    balancesheet_id,
+    auction_id,
+    10,
+    50
+  ).await.unwrap();
+  assert_eq!(wallet.get_asset_balance(&stable_aid).await.unwrap(), 0);
+  assert!(wallet.get_asset_balance(&gov_aid).await.unwrap()
+    >= 1000 - gov_spent + 10);
+  assert_eq!(
+    i256_to_i128(&total_surplus(balancesheet, stable_id).await.unwrap()),
+    Some(-250)
+  );
+
+  // Expired auctions restart with the lot still reserved
+  let auction_id = start_debt_auction(&sam, balancesheet_id).await.unwrap();
+  Clock::of(&wallet).advance(30).await.unwrap();
+  mint(&deployment.stable_i, &wallet, 50).await.unwrap();
+  assert!(matches!(
+    claim_debt_auction(
+      &sam,
+      stable_id,
+      gov_i.get_contract_id(),
+      balancesheet_id,
+      auction_id,
+      0,
+      50
+    ).await,
+    Err(YamaError::SurplusAuctionModule(
+      SurplusAuctionModuleError::AuctionExpired))
+  ));
+  let new_auction_id = reset_auction(&sam, auction_id).await.unwrap();
+  assert!(get_auction(&sam, auction_id).await.unwrap().done);
+  assert!(!get_auction(&sam, new_auction_id).await.unwrap().done);
+  assert_eq!(get_reserved(&sam, true).await.unwrap(), 50);
+}
+
+#[tokio::test]
+async fn test_invalid_params() {
+  let wallet = get_test_wallet().await;
+  let deployment = deploy(&wallet, &DeploymentPlan::default()).await.unwrap();
+  let gov_i = init_custom_modulartoken(&wallet).await.unwrap();
+  let sam = init_custom_surplusauctionmodule(
+    &wallet,
+    &deployment.stable_i,
+    &ContractId::from(deployment.balancesheet.get_contract_id()),
+    &gov_i
+  ).await.unwrap();
+  let stable_id = deployment.stable_i.get_contract_id();
+  let params = SAMParams {
+    threshold: 100,
+    lot: 50,
+    start_price: u64_to_fp(200),
+    time_interval: 0,
+    change_rate: u128_to_fp(0b1 << 63), // 0.5
+    reset_threshold: 3
+  };
+
+  assert!(matches!(
+    set_surplus_auction_params(&sam, stable_id, &params).await,
+    Err(YamaError::SurplusAuctionModule(
+      SurplusAuctionModuleError::InvalidParams))
+  ));
+  let params = SAMParams {
+    time_interval: 10,
+    reset_threshold: 0,
+    ..params
+  };
+  assert!(matches!(
+    set_debt_auction_params(&sam, stable_id, &params).await,
+    Err(YamaError::SurplusAuctionModule(
+      SurplusAuctionModuleError::InvalidParams))
+  ));
+
+  // Disabled auctions don't need either
+  set_surplus_auction_params(&sam, stable_id, &SAMParams {
+    lot: 0,
+    time_interval: 0,
+    ..params
+  }).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_set_gov_token() {
+  let wallet = get_test_wallet().await;
+  let deployment = deploy(&wallet, &DeploymentPlan::default()).await.unwrap();
+  let gov_i = init_custom_modulartoken(&wallet).await.unwrap();
+  let sam = init_custom_surplusauctionmodule(
+    &wallet,
+    &deployment.stable_i,
+    &ContractId::from(deployment.balancesheet.get_contract_id()),
+    &gov_i
+  ).await.unwrap();
+  let stable_id = deployment.stable_i.get_contract_id();
+  let new_gov_i = init_custom_modulartoken(&wallet).await.unwrap();
+
+  set_gov_token(&sam, stable_id, &new_gov_i).await.unwrap();
+  assert_eq!(
+    sam.methods().get_gov_token().simulate().await.unwrap().value,
+    Bits256(*get_aid(&new_gov_i))
+  );
+
+  // Only callers the stablecoin allows
+  set_allowlist(&deployment.stable_i, get_id(&wallet), false).await.unwrap();
+  assert!(matches!(
+    set_gov_token(&sam, stable_id, &gov_i).await,
+    Err(YamaError::ModularToken(ModularTokenError::UnauthorizedUser))
+  ));
+}
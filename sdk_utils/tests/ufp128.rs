@@ -0,0 +1,257 @@
+use fuels::prelude::*;
+use rand::prelude::{Rng, SeedableRng, StdRng};
+use std::cmp::Ordering;
+use sdk_utils::{
+  cdp::get_annual_interest,
+  dutchauctionliquidator::{
+    get_auction,
+    get_price
+  },
+  deploy::{
+    deploy,
+    CollateralTypePlan,
+    DeploymentPlan,
+    YamaDeployment
+  },
+  psmpricesource,
+  bidder::price_at_interval,
+  interest::SECONDS_IN_YEAR,
+  ufp128::{Ufp128, ParseUfp128Error},
+  abigen::*,
+  utils::{
+    get_test_wallet,
+    setup_test_and_liquidate,
+    u64_to_i256
+  }
+};
+
+// Schoolbook multiplication over 32 bit limbs, independent of Ufp128's
+// implementation. Returns the little-endian limbs of the 256 bit product.
+fn wide_mul(a: u128, b: u128) -> [u64; 8] {
+  let limbs = |x: u128| {
+    [0, 32, 64, 96].map(|shift| (x >> shift) as u32 as u64)
+  };
+  let (a, b) = (limbs(a), limbs(b));
+  let mut out = [0u64; 8];
+  for i in 0..4 {
+    let mut carry = 0u64;
+    for j in 0..4 {
+      let t = out[i + j] + a[i] * b[j] + carry;
+      out[i + j] = t & 0xffff_ffff;
+      carry = t >> 32;
+    }
+    out[i + 4] += carry;
+  }
+  out
+}
+
+fn cmp_wide(a: &[u64; 8], b: &[u64; 8]) -> Ordering {
+  a.iter().rev().cmp(b.iter().rev())
+}
+
+// a * 2^64 as 32 bit limbs
+fn shifted_limbs(a: u128) -> [u64; 8] {
+  let mut out = [0u64; 8];
+  for i in 0..4 {
+    out[i + 2] = (a >> (32 * i)) as u32 as u64;
+  }
+  out
+}
+
+// Mixes small, fractional and full width values
+fn random_bits(rng: &mut StdRng) -> u128 {
+  let width = rng.gen_range(1..=128);
+  rng.gen::<u128>() >> (128 - width)
+}
+
+#[test]
+fn test_mul_matches_wide_multiplication() {
+  let mut rng = StdRng::seed_from_u64(0);
+  for _ in 0..10_000 {
+    let (a, b) = (random_bits(&mut rng), random_bits(&mut rng));
+    let product = wide_mul(a, b);
+    let expected = if product[6] != 0 || product[7] != 0 {
+      None
+    } else {
+      Some((2..6).fold(0u128, |acc, i| {
+        acc | ((product[i] as u128) << (32 * (i - 2)))
+      }))
+    };
+    let actual = Ufp128::from_bits(a).checked_mul(Ufp128::from_bits(b));
+    assert_eq!(actual.map(Ufp128::bits), expected);
+  }
+}
+
+#[test]
+fn test_div_rounds_down() {
+  let mut rng = StdRng::seed_from_u64(1);
+  for _ in 0..10_000 {
+    let (a, b) = (random_bits(&mut rng), random_bits(&mut rng).max(1));
+    let dividend = shifted_limbs(a);
+    match Ufp128::from_bits(a).checked_div(Ufp128::from_bits(b)) {
+      // q * b <= a * 2^64 < (q + 1) * b
+      Some(quotient) => {
+        let q = quotient.bits();
+        assert_ne!(cmp_wide(&wide_mul(q, b), &dividend), Ordering::Greater);
+        if q < u128::MAX {
+          assert_eq!(
+            cmp_wide(&wide_mul(q + 1, b), &dividend),
+            Ordering::Greater
+          );
+        }
+      },
+      // The quotient doesn't fit in 128 bits
+      None => assert_ne!(
+        cmp_wide(&wide_mul(u128::MAX, b), &dividend),
+        Ordering::Greater
+      )
+    }
+  }
+  assert_eq!(Ufp128::ONE.checked_div(Ufp128::ZERO), None);
+}
+
+#[test]
+fn test_powu_order() {
+  let mut rng = StdRng::seed_from_u64(2);
+  for _ in 0..1_000 {
+    // Below 2^16 so the fourth power doesn't overflow
+    let a = Ufp128::from_bits(rng.gen_range(0..1u128 << 80));
+    assert_eq!(a.powu(0), Ufp128::ONE);
+    assert_eq!(a.powu(1), a);
+    assert_eq!(a.powu(2), Ufp128::ONE * (a * a));
+    assert_eq!(a.powu(3), a * (a * a));
+    assert_eq!(a.powu(4), Ufp128::ONE * ((a * a) * (a * a)));
+  }
+  assert_eq!(Ufp128::from_u64(2).powu(2).to_u64(), 4);
+  assert_eq!(Ufp128::from_u64(1 << 32).checked_powu(2), None);
+}
+
+#[test]
+fn test_comparisons_and_conversions() {
+  let half: Ufp128 = "0.5".parse().unwrap();
+  assert!(half.le(Ufp128::ONE));
+  assert!(half.le(half) && half.ge(half));
+  assert!(!half.ge(Ufp128::ONE));
+
+  assert_eq!(Ufp128::from_u64(42).to_u64(), 42);
+  assert_eq!((Ufp128::from_u64(42) + half).to_u64(), 42);
+  assert_eq!(Ufp128::from_u64(7).to_i256(), u64_to_i256(7));
+
+  let fp: UFP128 = Ufp128::from_bits(0b11 << 63).into();
+  assert_eq!(fp.value, U128 { upper: 1, lower: 1 << 63 });
+  assert_eq!(Ufp128::from(fp), Ufp128::from_bits(0b11 << 63));
+}
+
+#[test]
+fn test_parse_and_format() {
+  assert_eq!("1.5".parse::<Ufp128>(), Ok(Ufp128::from_bits(0b11 << 63)));
+  assert_eq!(".25".parse::<Ufp128>(), Ok(Ufp128::from_bits(1 << 62)));
+  assert_eq!("3".parse::<Ufp128>(), Ok(Ufp128::from_u64(3)));
+  assert_eq!("".parse::<Ufp128>(), Err(ParseUfp128Error::Empty));
+  assert_eq!("1.5x".parse::<Ufp128>(), Err(ParseUfp128Error::InvalidDigit));
+  assert_eq!(
+    "18446744073709551616".parse::<Ufp128>(),
+    Err(ParseUfp128Error::Overflow)
+  );
+
+  assert_eq!(Ufp128::from_bits(0b11 << 63).to_string(), "1.5");
+  assert_eq!(format!("{:.2}", Ufp128::from_bits(0b11 << 63)), "1.50");
+  let third = Ufp128::ONE / Ufp128::from_u64(3);
+  assert_eq!(format!("{:.4}", third), "0.3333");
+  // The smallest step has 64 fractional digits
+  assert_eq!(
+    Ufp128::from_bits(1).to_string(),
+    "0.0000000000000000000542101086242752217003726400434970855712890625"
+  );
+
+  let mut rng = StdRng::seed_from_u64(3);
+  for _ in 0..1_000 {
+    let value = Ufp128::from_bits(random_bits(&mut rng));
+    assert_eq!(value.to_string().parse::<Ufp128>(), Ok(value));
+  }
+}
+
+#[test]
+fn test_floats() {
+  assert_eq!(Ufp128::from_f64(1.5), Some(Ufp128::from_bits(0b11 << 63)));
+  assert_eq!(Ufp128::from_bits(1 << 62).to_f64(), 0.25);
+  assert_eq!(Ufp128::from_f64(-1.0), None);
+  assert_eq!(Ufp128::from_f64(f64::NAN), None);
+  assert_eq!(Ufp128::from_f64(1e20), None);
+}
+
+#[tokio::test]
+async fn test_powu_matches_chain() {
+  let mut rng = StdRng::seed_from_u64(4);
+  let template = DeploymentPlan::default().collateral_types[0].clone();
+  // Per-second rates a little above and below 1
+  let rates: Vec<Ufp128> = (0..8)
+    .map(|i| {
+      let offset = Ufp128::from_bits(rng.gen_range(0..1u128 << 40));
+      if i % 2 == 0 { Ufp128::ONE + offset } else { Ufp128::ONE - offset }
+    })
+    .collect();
+  let plan = DeploymentPlan {
+    collateral_types: rates
+      .iter()
+      .map(|rate| CollateralTypePlan {
+        interest_rate: (*rate).into(),
+        ..template.clone()
+      })
+      .collect(),
+    ..DeploymentPlan::default()
+  };
+  let wallet = get_test_wallet().await;
+  let YamaDeployment {
+    cdp,
+    collateral_type_ids,
+    ..
+  } = deploy(&wallet, &plan).await.unwrap();
+
+  for (c_type_id, rate) in collateral_type_ids.iter().zip(rates) {
+    let annual = get_annual_interest(&cdp, *c_type_id).await.unwrap();
+    assert_eq!(Ufp128::from(annual), rate.powu(SECONDS_IN_YEAR));
+  }
+}
+
+#[tokio::test]
+async fn test_auction_prices_match_chain() {
+  let YamaDeployment {
+    wallet,
+    dutchauctionliquidator,
+    cdp,
+    price_source,
+    ..
+  } = setup_test_and_liquidate().await;
+  let plan = DeploymentPlan::default().liquidator;
+
+  // The CDP values the 150 collateral in whole tokens, which the liquidator
+  // scales by the initial price ratio
+  let auction = get_auction(&dutchauctionliquidator, 0).await.unwrap();
+  let price = psmpricesource::get_price(&price_source, &wallet)
+    .await
+    .unwrap();
+  let collateral_value = Ufp128::from_u64(150) * Ufp128::from(price);
+  assert_eq!(
+    Ufp128::from(auction.start_price.clone()),
+    Ufp128::from_u64(collateral_value.to_u64())
+      * Ufp128::from(&plan.initial_price_ratio)
+  );
+
+  let params = CTypeParams {
+    initial_price_ratio: plan.initial_price_ratio,
+    time_interval: plan.time_interval,
+    change_rate: plan.change_rate,
+    reset_threshold: plan.reset_threshold,
+    enabled: true
+  };
+  let price = get_price(&dutchauctionliquidator, cdp.get_contract_id(), 0)
+    .await
+    .unwrap();
+  let start_price = Ufp128::from(auction.start_price).bits();
+  // Time has passed since the liquidation, so the price is at one of the
+  // intervals before expiry
+  assert!((0..params.reset_threshold).any(|interval| {
+    price_at_interval(start_price, &params, interval) == price
+  }));
+}
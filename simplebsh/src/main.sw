@@ -7,6 +7,7 @@ use std::{
 };
 use yama_interfaces::{
     simplebsh_abi::SimpleBSH,
+    psmlockup_abi::PSMLockup,
     errors::SimpleBSHError
 };
 use stablecoin_library::{
@@ -14,6 +15,8 @@ use stablecoin_library::{
         verify_sender_allowed,
         total_surplus,
         mint,
+        burn,
+        add_surplus,
         add_deficit,
         u64_to_i256,
         i256_to_u64
@@ -36,6 +39,10 @@ storage {
     revenue_share: u64 = SBSH_REVENUE_SHARE,
     pending_share_amount: I256 = I256::new(),
     last_block_update: u64 = 0,
+    absorb_deficits: bool = false,
+    // Set while SimpleBSH books its own surplus or deficit, so that the
+    // callbacks that triggers are ignored
+    booking: bool = false,
 }
 
 impl SimpleBSH for Contract {
@@ -45,6 +52,9 @@ impl SimpleBSH for Contract {
             caller_contract_id().value == storage.balancesheet_module,
             SimpleBSHError::NotBalanceSheet
         );
+        if storage.booking {
+            return;
+        }
         let share_amount = (
             amount * u64_to_i256(storage.revenue_share)
             / u64_to_i256(SBSH_DENOMINATOR));
@@ -55,15 +65,28 @@ impl SimpleBSH for Contract {
                 Identity::ContractId(ContractId {value: storage.target}),
                 storage.stablecoin_contract
             );
+            storage.booking = true;
             add_deficit(
                 share_amount,
                 storage.balancesheet_module
             );
+            storage.booking = false;
+        } else if amount < u64_to_i256(0) {
+            // Negative surplus, such as an auction clearing below debt
+            absorb_deficit(u64_to_i256(0) - amount);
         }
     }
 
     #[storage(read, write)]
-    fn on_add_deficit(amount: I256) {}
+    fn on_add_deficit(amount: I256) {
+        require(
+            caller_contract_id().value == storage.balancesheet_module,
+            SimpleBSHError::NotBalanceSheet
+        );
+        if !storage.booking && amount > u64_to_i256(0) {
+            absorb_deficit(amount);
+        }
+    }
 
     #[storage(read, write)]
     fn get_revenue_share() -> u64 {
@@ -85,6 +108,12 @@ impl SimpleBSH for Contract {
         storage.revenue_share = amount;
     }
 
+    #[storage(read, write)]
+    fn set_absorb_deficits(enabled: bool) {
+        verify_sender_allowed(storage.stablecoin_contract);
+        storage.absorb_deficits = enabled;
+    }
+
     #[storage(read, write)]
     fn set_balancesheet_module(value: b256) {
         verify_sender_allowed(storage.stablecoin_contract);
@@ -115,6 +144,28 @@ impl SimpleBSH for Contract {
     fn get_last_block_update() -> u64 {
         storage.last_block_update
     }
+
+    #[storage(read)]
+    fn is_absorbing_deficits() -> bool {
+        storage.absorb_deficits
+    }
+}
+
+// Burns what the target PSMLockup holds, up to `amount`, and books it as
+// surplus to cover that much of the deficit
+#[storage(read, write)]
+fn absorb_deficit(amount: I256) {
+    if !storage.absorb_deficits {
+        return;
+    }
+    let lockup = abi(PSMLockup, storage.target);
+    let absorbed = lockup.absorb_deficit(i256_to_u64(amount));
+    if absorbed > 0 {
+        burn(absorbed, storage.stablecoin_contract);
+        storage.booking = true;
+        add_surplus(u64_to_i256(absorbed), storage.balancesheet_module);
+        storage.booking = false;
+    }
 }
 
 #[storage(read, write)]
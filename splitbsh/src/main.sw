@@ -0,0 +1,258 @@
+contract;
+
+use signed_integers::i256::I256;
+use std::{
+    u256::U256,
+    logging::log,
+    storage::StorageVec,
+    auth::caller_contract_id
+};
+use yama_interfaces::{
+    balancesheethandler_abi::BalanceSheetHandler,
+    splitbsh_abi::{
+        SplitBSH,
+        Recipient
+    },
+    psmlockup_abi::PSMLockup,
+    events::{
+        SetRecipient,
+        SetBackstop,
+        ShareSurplus
+    },
+    errors::SplitBSHError
+};
+use stablecoin_library::{
+    helpers::{
+        verify_sender_allowed,
+        sender_id,
+        mint,
+        burn,
+        add_surplus,
+        add_deficit,
+        u64_to_i256,
+        i256_to_u64
+    },
+    constants::{
+        ZERO_B256,
+        SPLITBSH_DENOMINATOR
+    },
+};
+
+// Splits surplus across weighted recipients, leaving the rest as surplus,
+// and covers deficits from a PSMLockup backstop
+storage {
+    stablecoin_contract: b256 = ZERO_B256,
+    balancesheet_module: b256 = ZERO_B256,
+    recipients: StorageVec<Recipient> = StorageVec{},
+    total_weight: u64 = 0,
+    backstop: b256 = ZERO_B256,
+    // Set while SplitBSH books its own surplus or deficit, so that the
+    // callbacks that triggers are ignored
+    booking: bool = false,
+}
+
+impl BalanceSheetHandler for Contract {
+    #[storage(read, write)]
+    fn on_add_surplus(amount: I256) {
+        verify_sender_balancesheet();
+        if storage.booking {
+            return;
+        }
+        if amount > u64_to_i256(0) {
+            share_surplus(amount);
+        } else if amount < u64_to_i256(0) {
+            // Negative surplus, such as an auction clearing below debt
+            absorb_deficit(u64_to_i256(0) - amount);
+        }
+    }
+
+    #[storage(read, write)]
+    fn on_add_deficit(amount: I256) {
+        verify_sender_balancesheet();
+        if !storage.booking && amount > u64_to_i256(0) {
+            absorb_deficit(amount);
+        }
+    }
+}
+
+impl SplitBSH for Contract {
+    #[storage(read, write)]
+    fn add_recipient(recipient: Identity, weight: u64, cap: u64) -> u64 {
+        verify_sender_allowed(storage.stablecoin_contract);
+        set_total_weight(storage.total_weight + weight);
+        let recipient_id = storage.recipients.len();
+        storage.recipients.push(Recipient {
+            recipient: recipient,
+            weight: weight,
+            cap: cap,
+            received: 0
+        });
+        log(SetRecipient {
+            account: sender_id(),
+            recipient_id: recipient_id,
+            recipient: recipient,
+            weight: weight,
+            cap: cap
+        });
+
+        recipient_id
+    }
+
+    #[storage(read, write)]
+    fn set_recipient(recipient_id: u64, weight: u64, cap: u64) {
+        verify_sender_allowed(storage.stablecoin_contract);
+        let mut recipient = get_recipient(recipient_id);
+        set_total_weight(storage.total_weight - recipient.weight + weight);
+        recipient.weight = weight;
+        recipient.cap = cap;
+        storage.recipients.set(recipient_id, recipient);
+        log(SetRecipient {
+            account: sender_id(),
+            recipient_id: recipient_id,
+            recipient: recipient.recipient,
+            weight: weight,
+            cap: cap
+        });
+    }
+
+    #[storage(read, write)]
+    fn set_backstop(backstop: b256) {
+        verify_sender_allowed(storage.stablecoin_contract);
+        storage.backstop = backstop;
+        log(SetBackstop {
+            account: sender_id(),
+            backstop: backstop
+        });
+    }
+
+    #[storage(read, write)]
+    fn set_balancesheet_module(value: b256) {
+        verify_sender_allowed(storage.stablecoin_contract);
+        storage.balancesheet_module = value;
+    }
+
+    #[storage(read)]
+    fn get_recipient_count() -> u64 {
+        storage.recipients.len()
+    }
+
+    #[storage(read)]
+    fn get_recipient(recipient_id: u64) -> Recipient {
+        get_recipient(recipient_id)
+    }
+
+    #[storage(read)]
+    fn get_total_weight() -> u64 {
+        storage.total_weight
+    }
+
+    #[storage(read)]
+    fn get_backstop() -> b256 {
+        storage.backstop
+    }
+
+    #[storage(read)]
+    fn get_stablecoin_contract() -> b256 {
+        storage.stablecoin_contract
+    }
+
+    #[storage(read)]
+    fn get_balancesheet_module() -> b256 {
+        storage.balancesheet_module
+    }
+
+    #[storage(read, write)]
+    fn process_pending_share_amount() {}
+
+    #[storage(read)]
+    fn get_pending_share_amount() -> I256 {
+        u64_to_i256(0)
+    }
+
+    #[storage(read)]
+    fn get_target() -> b256 {
+        ZERO_B256
+    }
+}
+
+#[storage(read)]
+fn verify_sender_balancesheet() {
+    require(
+        caller_contract_id().value == storage.balancesheet_module,
+        SplitBSHError::NotBalanceSheet
+    );
+}
+
+#[storage(read)]
+fn get_recipient(recipient_id: u64) -> Recipient {
+    require(
+        recipient_id < storage.recipients.len(),
+        SplitBSHError::InvalidRecipient
+    );
+    storage.recipients.get(recipient_id).unwrap()
+}
+
+#[storage(write)]
+fn set_total_weight(total_weight: u64) {
+    require(
+        total_weight <= SPLITBSH_DENOMINATOR,
+        SplitBSHError::WeightsExceedDenominator
+    );
+    storage.total_weight = total_weight;
+}
+
+// Mints each recipient its share, up to its cap, and books what was minted
+// as a deficit
+#[storage(read, write)]
+fn share_surplus(amount: I256) {
+    let mut total_shared = 0;
+    let mut i = 0;
+    while i < storage.recipients.len() {
+        let mut recipient = storage.recipients.get(i).unwrap();
+        let mut share = i256_to_u64(
+            amount * u64_to_i256(recipient.weight)
+            / u64_to_i256(SPLITBSH_DENOMINATOR));
+        // set_recipient can lower a cap below what was already received
+        if recipient.cap > 0 && recipient.received >= recipient.cap {
+            share = 0;
+        } else if recipient.cap > 0
+            && share > recipient.cap - recipient.received
+        {
+            share = recipient.cap - recipient.received;
+        }
+        if share > 0 {
+            mint(share, recipient.recipient, storage.stablecoin_contract);
+            recipient.received += share;
+            storage.recipients.set(i, recipient);
+            total_shared += share;
+            log(ShareSurplus {
+                recipient_id: i,
+                recipient: recipient.recipient,
+                amount: share
+            });
+        }
+        i += 1;
+    }
+    if total_shared > 0 {
+        storage.booking = true;
+        add_deficit(u64_to_i256(total_shared), storage.balancesheet_module);
+        storage.booking = false;
+    }
+}
+
+// Burns what the backstop holds, up to `amount`, and books it as surplus to
+// cover that much of the deficit
+#[storage(read, write)]
+fn absorb_deficit(amount: I256) {
+    if storage.backstop == ZERO_B256 {
+        return;
+    }
+    let backstop = abi(PSMLockup, storage.backstop);
+    let absorbed = backstop.absorb_deficit(i256_to_u64(amount));
+    if absorbed > 0 {
+        burn(absorbed, storage.stablecoin_contract);
+        storage.booking = true;
+        add_surplus(u64_to_i256(absorbed), storage.balancesheet_module);
+        storage.booking = false;
+    }
+}
\ No newline at end of file
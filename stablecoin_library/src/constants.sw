@@ -26,6 +26,17 @@ pub const PSM_CEILING: u64 = 1000000000000;
 
 pub const PSM_TOKEN_DECIMALS: u8 = 4;
 
+pub const PSM_TIN: u64 = 0;
+pub const PSM_TOUT: u64 = 0;
+pub const PSM_FEE_DENOMINATOR: u64 = 10000;
+
+pub const PSM_MAX_ALLOCATION: u64 = 0;
+pub const PSM_ALLOCATION_DENOMINATOR: u64 = 10000;
+
+// PSMLockup
+
+pub const PSMLOCKUP_EXIT_DELAY: u64 = 0;
+
 // DutchAuctionLiquidator
 
 pub const DAL_DEFAULT_INITIAL_PRICE_RATIO: UFP128 = UFP128::zero();
@@ -37,6 +48,11 @@ pub const DAL_RESET_THRESHOLD: u64 = 0;
 
 pub const DAL_ENABLED: bool = true;  // Ignored
 
+// SurplusAuctionModule
+
+pub const SAM_DEFAULT_START_PRICE: UFP128 = UFP128::zero();
+pub const SAM_DEFAULT_CHANGE_RATE: UFP128 = UFP128::zero();
+
 // FlashMintModule
 
 pub const FMM_MAX: u64 = 1000000000000;
@@ -45,4 +61,8 @@ pub const FMM_MAX: u64 = 1000000000000;
 
 pub const SBSH_REVENUE_SHARE: u64 = 9000;
 pub const SBSH_DENOMINATOR: u64 = 10000;
+
+// SplitBSH
+
+pub const SPLITBSH_DENOMINATOR: u64 = 10000;
 pub const SECONDS_IN_YEAR: u64 = 31536000;
\ No newline at end of file
@@ -9,7 +9,8 @@ use std::{
   contract_id::ContractId,
   revert::require,
   u256::U256,
-  bytes::Bytes
+  bytes::Bytes,
+  block::timestamp
 };
 use yama_interfaces::{
   modulartoken_abi::ModularToken,
@@ -58,6 +59,11 @@ pub fn add_deficit(amount: I256, balancesheet_module: b256) {
   balancesheet.add_deficit(amount);
 }
 
+pub fn set_surplus(amount: I256, balancesheet_module: b256) {
+  let balancesheet = abi(BalanceSheetModule, balancesheet_module);
+  balancesheet.set_surplus(amount);
+}
+
 pub fn total_surplus(balancesheet_module: b256) -> I256 {
   let balancesheet = abi(BalanceSheetModule, balancesheet_module);
   balancesheet.total_surplus()
@@ -101,6 +107,27 @@ pub fn convert_amount_u256(amount: U256, from_decimals: u8, to_decimals: u8) ->
   }
 }
 
+// The price of a Dutch auction that started at `start_price` and has been
+// multiplied by `change_rate` every `time_interval` seconds since
+pub fn dutch_auction_price(
+  start_price: UFP128,
+  start_time: u64,
+  time_interval: u64,
+  change_rate: UFP128
+) -> u64 {
+  let intervals_elapsed: u64 = (timestamp() - start_time) / time_interval;
+  (start_price * change_rate.powu(intervals_elapsed)).to_u64()
+}
+
+// Whether a Dutch auction has gone `reset_threshold` intervals
+pub fn dutch_auction_expired(
+  start_time: u64,
+  time_interval: u64,
+  reset_threshold: u64
+) -> bool {
+  timestamp() >= start_time + (time_interval * reset_threshold)
+}
+
 pub fn tokens_to_fp(amount: u64) -> UFP128 {
   UFP128::from_u64(amount)
 }
@@ -0,0 +1,438 @@
+contract;
+
+use yama_interfaces::{
+    surplusauctionmodule_abi::{
+        SurplusAuctionModule,
+        SAMParams,
+        SAMAuction
+    },
+    errors::SurplusAuctionModuleError,
+    events::{
+        SetSAMParams,
+        StartSAMAuction,
+        ResetSAMAuction,
+        ClaimSAMAuction
+    }
+};
+use fixed_point::ufp128::UFP128;
+use yama_types::ufp128::*;
+use stablecoin_library::{
+    constants::{
+        ZERO_B256,
+        SAM_DEFAULT_START_PRICE,
+        SAM_DEFAULT_CHANGE_RATE
+    },
+    helpers::{
+        sender_id,
+        mint,
+        burn,
+        set_surplus,
+        total_surplus,
+        verify_sender_allowed,
+        verify_tokens_from,
+        u64_to_i256,
+        dutch_auction_price,
+        dutch_auction_expired
+    }
+};
+
+use std::{
+    storage::StorageVec,
+    block::timestamp,
+    context::msg_amount,
+    u256::U256,
+    logging::log,
+    token::transfer
+};
+
+use signed_integers::i256::I256;
+
+// Auctions off surplus past a threshold for the governance token, and mints
+// the governance token to recapitalize deficits past a threshold. Both use
+// the DutchAuctionLiquidator's price mechanics. Claims move the surplus with
+// set_surplus, so the balance sheet handler doesn't share or cover them.
+storage {
+    stablecoin_contract: b256 = ZERO_B256,
+    balancesheet_module: b256 = ZERO_B256,
+    // A ModularToken that allows this module
+    gov_token: b256 = ZERO_B256,
+    surplus_auction_params: SAMParams = SAMParams {
+        threshold: 0,
+        lot: 0,
+        start_price: SAM_DEFAULT_START_PRICE,
+        time_interval: 0,
+        change_rate: SAM_DEFAULT_CHANGE_RATE,
+        reset_threshold: 0
+    },
+    debt_auction_params: SAMParams = SAMParams {
+        threshold: 0,
+        lot: 0,
+        start_price: SAM_DEFAULT_START_PRICE,
+        time_interval: 0,
+        change_rate: SAM_DEFAULT_CHANGE_RATE,
+        reset_threshold: 0
+    },
+    auctions: StorageVec<SAMAuction> = StorageVec{},
+    surplus_reserved: u64 = 0,
+    debt_reserved: u64 = 0
+}
+
+impl SurplusAuctionModule for Contract {
+    #[storage(read, write)]
+    fn set_surplus_auction_params(
+        threshold: u64,
+        lot: u64,
+        start_price: UFP128,
+        time_interval: u64,
+        change_rate: UFP128,
+        reset_threshold: u64
+    ) {
+        verify_sender_allowed(storage.stablecoin_contract);
+        let params = SAMParams {
+            threshold: threshold,
+            lot: lot,
+            start_price: start_price,
+            time_interval: time_interval,
+            change_rate: change_rate,
+            reset_threshold: reset_threshold
+        };
+        verify_params(params);
+        storage.surplus_auction_params = params;
+        log_params(false, params);
+    }
+
+    #[storage(read, write)]
+    fn set_debt_auction_params(
+        threshold: u64,
+        lot: u64,
+        start_price: UFP128,
+        time_interval: u64,
+        change_rate: UFP128,
+        reset_threshold: u64
+    ) {
+        verify_sender_allowed(storage.stablecoin_contract);
+        let params = SAMParams {
+            threshold: threshold,
+            lot: lot,
+            start_price: start_price,
+            time_interval: time_interval,
+            change_rate: change_rate,
+            reset_threshold: reset_threshold
+        };
+        verify_params(params);
+        storage.debt_auction_params = params;
+        log_params(true, params);
+    }
+
+    #[storage(read, write)]
+    fn set_balancesheet_module(value: b256) {
+        verify_sender_allowed(storage.stablecoin_contract);
+        storage.balancesheet_module = value;
+    }
+
+    #[storage(read, write)]
+    fn set_gov_token(gov_token: b256) {
+        verify_sender_allowed(storage.stablecoin_contract);
+        storage.gov_token = gov_token;
+    }
+
+    #[storage(read, write)]
+    fn start_surplus_auction() -> u64 {
+        let params = storage.surplus_auction_params;
+        require(params.lot > 0, SurplusAuctionModuleError::AuctionsDisabled);
+        require(
+            total_surplus(storage.balancesheet_module) >= u64_to_i256(
+                params.threshold + storage.surplus_reserved + params.lot),
+            SurplusAuctionModuleError::BelowThreshold
+        );
+        storage.surplus_reserved += params.lot;
+        start_auction(false, params.lot, params.start_price)
+    }
+
+    #[storage(read, write)]
+    fn start_debt_auction() -> u64 {
+        let params = storage.debt_auction_params;
+        require(params.lot > 0, SurplusAuctionModuleError::AuctionsDisabled);
+        let deficit = u64_to_i256(0) - total_surplus(
+            storage.balancesheet_module);
+        require(
+            deficit >= u64_to_i256(
+                params.threshold + storage.debt_reserved + params.lot),
+            SurplusAuctionModuleError::BelowThreshold
+        );
+        storage.debt_reserved += params.lot;
+        start_auction(true, params.lot, params.start_price)
+    }
+
+    #[payable]
+    #[storage(read, write)]
+    fn claim_surplus_auction(auction_id: u64, max_price: u64) {
+        let mut auction = get_open_auction(auction_id);
+        require(
+            !auction.is_debt_auction,
+            SurplusAuctionModuleError::WrongAuctionType
+        );
+        let price = get_price(auction_id);
+        verify_tokens_from(storage.gov_token);
+        require(
+            msg_amount() >= price,
+            SurplusAuctionModuleError::InvalidPayment
+        );
+        require(price <= max_price, SurplusAuctionModuleError::ExceedsMaxPrice);
+
+        if price > 0 {
+            burn(price, storage.gov_token);
+        }
+        if msg_amount() > price {
+            transfer(
+                msg_amount() - price,
+                ContractId::from(storage.gov_token),
+                sender_id()
+            );
+        }
+        mint(auction.lot, sender_id(), storage.stablecoin_contract);
+        set_surplus(
+            total_surplus(storage.balancesheet_module)
+                - u64_to_i256(auction.lot),
+            storage.balancesheet_module
+        );
+        storage.surplus_reserved -= auction.lot;
+
+        auction.done = true;
+        storage.auctions.set(auction_id, auction);
+        log_claim(auction_id, auction, price);
+    }
+
+    #[payable]
+    #[storage(read, write)]
+    fn claim_debt_auction(auction_id: u64, min_price: u64) {
+        let mut auction = get_open_auction(auction_id);
+        require(
+            auction.is_debt_auction,
+            SurplusAuctionModuleError::WrongAuctionType
+        );
+        let price = get_price(auction_id);
+        verify_tokens_from(storage.stablecoin_contract);
+        require(
+            msg_amount() >= auction.lot,
+            SurplusAuctionModuleError::InvalidPayment
+        );
+        require(price >= min_price, SurplusAuctionModuleError::BelowMinAmount);
+
+        burn(auction.lot, storage.stablecoin_contract);
+        if msg_amount() > auction.lot {
+            transfer(
+                msg_amount() - auction.lot,
+                ContractId::from(storage.stablecoin_contract),
+                sender_id()
+            );
+        }
+        if price > 0 {
+            mint(price, sender_id(), storage.gov_token);
+        }
+        set_surplus(
+            total_surplus(storage.balancesheet_module)
+                + u64_to_i256(auction.lot),
+            storage.balancesheet_module
+        );
+        storage.debt_reserved -= auction.lot;
+
+        auction.done = true;
+        storage.auctions.set(auction_id, auction);
+        log_claim(auction_id, auction, price);
+    }
+
+    #[storage(read, write)]
+    fn reset_auction(auction_id: u64) -> u64 {
+        let mut auction = get_auction(auction_id);
+        require(!auction.done, SurplusAuctionModuleError::AuctionDone);
+        require(
+            is_expired(auction_id),
+            SurplusAuctionModuleError::AuctionNotExpired
+        );
+        auction.done = true;
+        storage.auctions.set(auction_id, auction);
+
+        // The lot stays reserved for the new auction
+        let new_auction_id = start_auction(
+            auction.is_debt_auction,
+            auction.lot,
+            get_params(auction.is_debt_auction).start_price
+        );
+        log(ResetSAMAuction {
+            initiator: sender_id(),
+            auction_id: auction_id,
+            new_auction_id: new_auction_id
+        });
+
+        new_auction_id
+    }
+
+    #[storage(read)]
+    fn get_price(auction_id: u64) -> u64 {
+        get_price(auction_id)
+    }
+
+    #[storage(read)]
+    fn is_expired(auction_id: u64) -> bool {
+        is_expired(auction_id)
+    }
+
+    #[storage(read)]
+    fn get_auction(auction_id: u64) -> SAMAuction {
+        get_auction(auction_id)
+    }
+
+    #[storage(read)]
+    fn get_auction_count() -> u64 {
+        storage.auctions.len()
+    }
+
+    #[storage(read)]
+    fn get_surplus_auction_params() -> SAMParams {
+        storage.surplus_auction_params
+    }
+
+    #[storage(read)]
+    fn get_debt_auction_params() -> SAMParams {
+        storage.debt_auction_params
+    }
+
+    #[storage(read)]
+    fn get_reserved(is_debt_auction: bool) -> u64 {
+        if is_debt_auction {
+            storage.debt_reserved
+        } else {
+            storage.surplus_reserved
+        }
+    }
+
+    #[storage(read)]
+    fn get_stablecoin_contract() -> b256 {
+        storage.stablecoin_contract
+    }
+
+    #[storage(read)]
+    fn get_balancesheet_module() -> b256 {
+        storage.balancesheet_module
+    }
+
+    #[storage(read)]
+    fn get_gov_token() -> b256 {
+        storage.gov_token
+    }
+}
+
+#[storage(read, write)]
+fn start_auction(is_debt_auction: bool, lot: u64, start_price: UFP128) -> u64 {
+    let auction = SAMAuction {
+        is_debt_auction: is_debt_auction,
+        lot: lot,
+        start_price: start_price,
+        start_time: timestamp(),
+        done: false
+    };
+    let auction_id = storage.auctions.len();
+    storage.auctions.push(auction);
+
+    log(StartSAMAuction {
+        initiator: sender_id(),
+        auction_id: auction_id,
+        is_debt_auction: is_debt_auction,
+        lot: lot,
+        start_price: start_price,
+        start_time: auction.start_time
+    });
+
+    auction_id
+}
+
+#[storage(read)]
+fn get_params(is_debt_auction: bool) -> SAMParams {
+    if is_debt_auction {
+        storage.debt_auction_params
+    } else {
+        storage.surplus_auction_params
+    }
+}
+
+#[storage(read)]
+fn get_auction(auction_id: u64) -> SAMAuction {
+    require(
+        auction_id < storage.auctions.len(),
+        SurplusAuctionModuleError::InvalidAuction
+    );
+    storage.auctions.get(auction_id).unwrap()
+}
+
+#[storage(read)]
+fn get_open_auction(auction_id: u64) -> SAMAuction {
+    let auction = get_auction(auction_id);
+    require(!auction.done, SurplusAuctionModuleError::AuctionDone);
+    require(
+        !is_expired(auction_id),
+        SurplusAuctionModuleError::AuctionExpired
+    );
+    auction
+}
+
+#[storage(read)]
+fn get_price(auction_id: u64) -> u64 {
+    let auction = get_auction(auction_id);
+
+    if (auction.done || is_expired(auction_id)) {
+        return 0;
+    }
+
+    let params = get_params(auction.is_debt_auction);
+    dutch_auction_price(
+        auction.start_price,
+        auction.start_time,
+        params.time_interval,
+        params.change_rate
+    )
+}
+
+#[storage(read)]
+fn is_expired(auction_id: u64) -> bool {
+    let auction = get_auction(auction_id);
+    let params = get_params(auction.is_debt_auction);
+
+    dutch_auction_expired(
+        auction.start_time,
+        params.time_interval,
+        params.reset_threshold
+    )
+}
+
+// Otherwise an enabled auction would price with a zero interval and never
+// expire
+fn verify_params(params: SAMParams) {
+    require(
+        params.lot == 0
+            || (params.time_interval > 0 && params.reset_threshold > 0),
+        SurplusAuctionModuleError::InvalidParams
+    );
+}
+
+fn log_params(is_debt_auction: bool, params: SAMParams) {
+    log(SetSAMParams {
+        is_debt_auction: is_debt_auction,
+        threshold: params.threshold,
+        lot: params.lot,
+        start_price: params.start_price,
+        time_interval: params.time_interval,
+        change_rate: params.change_rate,
+        reset_threshold: params.reset_threshold
+    });
+}
+
+fn log_claim(auction_id: u64, auction: SAMAuction, price: u64) {
+    log(ClaimSAMAuction {
+        claimer: sender_id(),
+        auction_id: auction_id,
+        is_debt_auction: auction.is_debt_auction,
+        lot: auction.lot,
+        price: price
+    });
+}
\ No newline at end of file
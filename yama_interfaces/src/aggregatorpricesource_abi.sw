@@ -0,0 +1,84 @@
+library aggregatorpricesource_abi;
+
+use fixed_point::ufp128::UFP128;
+use yama_types::ufp128::*;
+
+pub enum AggregationMode {
+    // The median of every fresh feed
+    Median: (),
+    // The first fresh feed, in the order they were added
+    Fallback: (),
+}
+
+pub struct Feed {
+    // Has to return 0 rather than revert when it has no price, since a
+    // revert can't be caught and takes the aggregator's price down with it
+    source: b256,
+    // Feeds older than this many seconds are skipped. 0 for feeds that don't
+    // implement TimestampedPriceSource.
+    max_age: u64
+}
+
+abi AggregatorPriceSource {
+    // PriceSource's price(), which reads the feeds from storage
+    #[storage(read)]
+    fn price() -> UFP128;
+
+    #[storage(read, write)]
+    fn add_feed(source: b256, max_age: u64);
+
+    #[storage(read, write)]
+    fn set_feed(index: u64, source: b256, max_age: u64);
+
+    #[storage(read, write)]
+    fn remove_feed(index: u64);
+
+    #[storage(read, write)]
+    fn set_mode(mode: AggregationMode);
+
+    // The fewest fresh feeds the median is taken over
+    #[storage(read, write)]
+    fn set_min_feeds(min_feeds: u64);
+
+    // How far the price may move from the last accepted one, as a fraction of
+    // it. 0 disables the check.
+    #[storage(read, write)]
+    fn set_max_deviation(max_deviation: UFP128);
+
+    // Accepts a price without checking it, e.g. to reset the breaker after
+    // a legitimate move larger than the max deviation
+    #[storage(read, write)]
+    fn set_last_price(price: UFP128);
+
+    #[storage(read, write)]
+    fn set_owner(owner: Identity);
+
+    // Accepts the current price, so later prices deviate from it. Anyone can
+    // call it.
+    #[storage(read, write)]
+    fn update() -> UFP128;
+
+    #[storage(read)]
+    fn get_feed_count() -> u64;
+
+    #[storage(read)]
+    fn get_feed(index: u64) -> Feed;
+
+    #[storage(read)]
+    fn get_mode() -> AggregationMode;
+
+    #[storage(read)]
+    fn get_min_feeds() -> u64;
+
+    #[storage(read)]
+    fn get_max_deviation() -> UFP128;
+
+    #[storage(read)]
+    fn get_last_price() -> UFP128;
+
+    #[storage(read)]
+    fn get_last_update() -> u64;
+
+    #[storage(read)]
+    fn get_owner() -> Identity;
+}
\ No newline at end of file
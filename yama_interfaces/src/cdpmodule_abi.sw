@@ -64,6 +64,15 @@ abi CDPModule {
     // Used by the liquidator contract to write off liquidated vaults
     #[storage(read, write)]
     fn clear_vault(vault_id: u64);
+
+    // Used by the liquidator contract to settle a partial liquidation,
+    // returning the vault to its owner
+    #[storage(read, write)]
+    fn partial_clear_vault(
+        vault_id: u64,
+        collateral_amount: u64,
+        debt_repaid: u64
+    );
     
     // Sets the liquidator for this module
     #[storage(read, write)]
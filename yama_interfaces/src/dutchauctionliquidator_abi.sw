@@ -18,6 +18,21 @@ pub struct Auction {
     done: bool
 }
 
+// Partial liquidations sell just enough collateral to bring a vault back to
+// target_ratio, with the penalty charged on top of the debt repaid, and
+// return the vault to its owner
+pub struct PartialLiquidationParams {
+    enabled: bool,
+    target_ratio: UFP128,
+    penalty: UFP128
+}
+
+// What a partial liquidation's auction sells and the debt it repays
+pub struct PartialLot {
+    collateral_amount: u64,
+    debt: u64
+}
+
 abi DutchAuctionLiquidator {
     #[storage(read, write)]
     fn set_c_type_params(
@@ -37,6 +52,16 @@ abi DutchAuctionLiquidator {
         reset_threshold: u64
     );
 
+    // target_ratio must exceed 1 + penalty and the collateral type's
+    // collateral ratio
+    #[storage(read, write)]
+    fn set_partial_liquidation_params(
+        collateral_type_id: u64,
+        enabled: bool,
+        target_ratio: UFP128,
+        penalty: UFP128
+    );
+
     #[storage(read, write)]
     fn liquidate(vault_id: u64);
 
@@ -68,6 +93,15 @@ abi DutchAuctionLiquidator {
     #[storage(read)]
     fn get_collateral_amount_of_auction(auction_id: u64) -> u64;
 
+    #[storage(read)]
+    fn get_partial_liquidation_params(
+        collateral_type_id: u64
+    ) -> PartialLiquidationParams;
+
+    // None when the auction sells the whole vault
+    #[storage(read)]
+    fn get_partial_lot(auction_id: u64) -> Option<PartialLot>;
+
     // Sets the balancesheet module
     #[storage(read, write)]
     fn set_balancesheet_module(value: b256);
@@ -28,6 +28,7 @@ pub enum DutchAuctionLiquidatorError {
   InvalidPayment: (),
   AuctionNotExpired: (),
   ExceedsMaxPrice: (),
+  InvalidPartialLiquidationParams: (),
 }
 
 pub enum ModularTokenError {
@@ -37,6 +38,24 @@ pub enum ModularTokenError {
 
 pub enum PSMError {
   ExceedsDebtCeiling: (),
+  UnsupportedToken: (),
+  TokenDisabled: (),
+  FeeExceedsDenominator: (),
+  StrategyNotAllowed: (),
+  ExceedsMaxAllocation: (),
+  ExceedsAllocation: (),
+  MaxAllocationExceedsDenominator: (),
+}
+
+pub enum PSMLockupError {
+  QueueEnabled: (),
+  QueueDisabled: (),
+  InsufficientLiquidity: (),
+  InvalidTicket: (),
+  NotTicketOwner: (),
+  TicketClosed: (),
+  ExitDelayNotElapsed: (),
+  NotBSH: (),
 }
 
 pub enum FlashMintModuleError {
@@ -64,4 +83,40 @@ pub enum LeverageProxyError {
 
 pub enum SwapperError {
   InsufficientInput: (),
+}
+
+pub enum MockPriceSourceError {
+  NotOwner: (),
+}
+
+pub enum SurplusAuctionModuleError {
+  AuctionsDisabled: (),
+  BelowThreshold: (),
+  InvalidAuction: (),
+  WrongAuctionType: (),
+  AuctionDone: (),
+  AuctionExpired: (),
+  AuctionNotExpired: (),
+  InvalidPayment: (),
+  ExceedsMaxPrice: (),
+  BelowMinAmount: (),
+  InvalidParams: (),
+}
+
+pub enum SplitBSHError {
+  NotBalanceSheet: (),
+  WeightsExceedDenominator: (),
+  InvalidRecipient: (),
+}
+
+pub enum MockReserveStrategyError {
+  NotPSM: (),
+  InsufficientBalance: (),
+}
+
+pub enum AggregatorPriceSourceError {
+  NotOwner: (),
+  NotEnoughFreshFeeds: (),
+  ExceedsMaxDeviation: (),
+  InvalidFeed: (),
 }
\ No newline at end of file
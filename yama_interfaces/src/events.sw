@@ -33,6 +33,27 @@ pub struct SetHandler {
   handler: b256
 }
 
+// SplitBSH
+
+pub struct SetRecipient {
+  account: Identity,
+  recipient_id: u64,
+  recipient: Identity,
+  weight: u64,
+  cap: u64
+}
+
+pub struct SetBackstop {
+  account: Identity,
+  backstop: b256
+}
+
+pub struct ShareSurplus {
+  recipient_id: u64,
+  recipient: Identity,
+  amount: u64
+}
+
 // Bridge
 
 pub struct RemoteTransferSent {
@@ -139,6 +160,13 @@ pub struct ClearVault {
   vault_id: u64
 }
 
+pub struct PartialClearVault {
+  account: Identity,
+  vault_id: u64,
+  collateral_amount: u64,
+  debt_repaid: u64
+}
+
 // Dutch Auction liquidator
 
 pub struct InitializeAuction {
@@ -177,6 +205,55 @@ pub struct SetCTypeParams {
   enabled: bool
 }
 
+pub struct SetPartialLiquidationParams {
+  collateral_type_id: u64,
+  enabled: bool,
+  target_ratio: UFP128,
+  penalty: UFP128
+}
+
+pub struct InitializePartialAuction {
+  vault_id: u64,
+  auction_id: u64,
+  collateral_amount: u64,
+  debt: u64
+}
+
+// SurplusAuctionModule
+
+pub struct SetSAMParams {
+  is_debt_auction: bool,
+  threshold: u64,
+  lot: u64,
+  start_price: UFP128,
+  time_interval: u64,
+  change_rate: UFP128,
+  reset_threshold: u64
+}
+
+pub struct StartSAMAuction {
+  initiator: Identity,
+  auction_id: u64,
+  is_debt_auction: bool,
+  lot: u64,
+  start_price: UFP128,
+  start_time: u64
+}
+
+pub struct ResetSAMAuction {
+  initiator: Identity,
+  auction_id: u64,
+  new_auction_id: u64
+}
+
+pub struct ClaimSAMAuction {
+  claimer: Identity,
+  auction_id: u64,
+  is_debt_auction: bool,
+  lot: u64,
+  price: u64
+}
+
 // PSM
 
 pub struct SetDebtCeiling {
@@ -184,14 +261,68 @@ pub struct SetDebtCeiling {
   debt_ceiling: u64
 }
 
+pub struct SetTokenConfig {
+  account: Identity,
+  token: b256,
+  decimals: u8,
+  debt_ceiling: u64,
+  enabled: bool
+}
+
+pub struct SetFees {
+  account: Identity,
+  tin: u64,
+  tout: u64
+}
+
+pub struct SetStrategy {
+  account: Identity,
+  strategy: b256,
+  allowed: bool
+}
+
+pub struct SetMaxAllocation {
+  account: Identity,
+  max_allocation: u64
+}
+
+pub struct Allocate {
+  account: Identity,
+  strategy: b256,
+  token: b256,
+  amount: u64
+}
+
+pub struct Recall {
+  account: Identity,
+  strategy: b256,
+  token: b256,
+  amount: u64
+}
+
+// `gain` and `loss` are in `token`
+pub struct ReportStrategy {
+  account: Identity,
+  strategy: b256,
+  token: b256,
+  gain: u64,
+  loss: u64
+}
+
 pub struct Deposit {
   account: Identity,
-  ext_stable_amount: u64
+  ext_stable_amount: u64,
+  token: b256,
+  yss_amount: u64,
+  fee: u64
 }
 
 pub struct Withdraw {
   account: Identity,
-  yss_amount: u64
+  yss_amount: u64,
+  token: b256,
+  ext_stable_amount: u64,
+  fee: u64
 }
 
 // PSM Lockup
@@ -207,4 +338,35 @@ pub struct Redeem {
   account: Identity,
   yama_amount: u64,
   lockup_amount: u64
+}
+
+pub struct SetExitQueue {
+  account: Identity,
+  enabled: bool,
+  delay: u64
+}
+
+pub struct RequestRedeem {
+  account: Identity,
+  ticket_id: u64,
+  lockup_amount: u64
+}
+
+pub struct CancelRedeem {
+  account: Identity,
+  ticket_id: u64,
+  lockup_amount: u64
+}
+
+pub struct ClaimRedeem {
+  account: Identity,
+  ticket_id: u64,
+  yama_amount: u64,
+  lockup_amount: u64
+}
+
+pub struct AbsorbDeficit {
+  account: Identity,
+  deficit: u64,
+  yama_amount: u64
 }
\ No newline at end of file
@@ -14,10 +14,16 @@ dep modulartoken_abi;
 dep pegstabilitymodule_abi;
 dep pricesource_abi;
 dep psmpricesource_abi;
+dep mockpricesource_abi;
+dep aggregatorpricesource_abi;
 dep flashmintmodule_abi;
 dep flashmintborrower_abi;
 dep balancesheethandler_abi;
 dep simplebsh_abi;
+dep splitbsh_abi;
+dep surplusauctionmodule_abi;
 dep psmlockup_abi;
+dep reservestrategy_abi;
+dep mockreservestrategy_abi;
 dep leverageproxy_abi;
 dep swapper_abi;
\ No newline at end of file
@@ -0,0 +1,18 @@
+library mockpricesource_abi;
+
+use fixed_point::ufp128::UFP128;
+use yama_types::ufp128::*;
+
+abi MockPriceSource {
+    // Records `updated_at` as when the price was observed, or the current
+    // time if it's None. A stale price is still returned; consumers judge
+    // its age through TimestampedPriceSource.
+    #[storage(read, write)]
+    fn set_price(price: UFP128, updated_at: Option<u64>);
+
+    #[storage(read, write)]
+    fn set_owner(owner: Identity);
+
+    #[storage(read)]
+    fn get_owner() -> Identity;
+}
@@ -0,0 +1,15 @@
+library mockreservestrategy_abi;
+
+abi MockReserveStrategy {
+    // Adds the forwarded coins to what's held for the PSM, as yield
+    #[payable]
+    #[storage(read, write)]
+    fn add_yield();
+
+    // Sends `amount` of `token` held for the PSM to the sender, as a loss
+    #[storage(read, write)]
+    fn lose(token: b256, amount: u64);
+
+    #[storage(read)]
+    fn get_psm() -> b256;
+}
\ No newline at end of file
@@ -3,24 +3,82 @@ library pegstabilitymodule_abi;
 dep events;
 dep errors;
 
+pub struct TokenConfig {
+    decimals: u8,
+    // Most of the token the PSM may hold
+    debt_ceiling: u64,
+    // Disabled tokens can still be withdrawn, but not deposited
+    enabled: bool
+}
+
 abi PegStabilityModule {
-    // Sets the debt ceiling
+    // Sets the primary token's debt ceiling
     #[storage(read, write)]
     fn set_debt_ceiling(debt_ceiling: u64);
 
+    // Adds an external stablecoin, or reconfigures one
+    #[storage(read, write)]
+    fn set_token_config(
+        token: b256,
+        decimals: u8,
+        debt_ceiling: u64,
+        enabled: bool
+    );
+
+    // Sets the fees on deposits and withdrawals, in basis points of YSS
+    #[storage(read, write)]
+    fn set_fees(tin: u64, tout: u64);
+
+    // Where fees are booked as surplus
+    #[storage(read, write)]
+    fn set_balancesheet_module(value: b256);
+
+    // Allows or disallows a ReserveStrategy to be allocated reserves.
+    // Disallowed strategies can still be recalled from and reported on.
+    #[storage(read, write)]
+    fn set_strategy(strategy: b256, allowed: bool);
+
+    // Sets the most of each token's reserves that strategies may hold
+    // together, in basis points, up to all of them
+    #[storage(read, write)]
+    fn set_max_allocation(max_allocation: u64);
+
+    // Moves `amount` of `token` to `strategy`
+    #[storage(read, write)]
+    fn allocate(strategy: b256, token: b256, amount: u64);
+
+    // Moves `amount` of `token` back from `strategy`
+    #[storage(read, write)]
+    fn recall(strategy: b256, token: b256, amount: u64);
+
+    // Recalls what `strategy` holds beyond its allocation of `token` and
+    // books what arrives as surplus, or books what's missing as a deficit.
+    // Only callable by allowed contracts, for allowed strategies.
+    #[storage(read, write)]
+    fn report(strategy: b256, token: b256);
+
     // Used by allowed contracts to transfer tokens out
     #[storage(read)]
     fn transfer(token: ContractId, to: Identity, amount: u64);
 
-    // Deposits the external stablecoin in exchange for YSS
+    // Deposits any enabled external stablecoin in exchange for YSS, less tin
     #[payable]
     #[storage(read)]
     fn deposit() -> u64;
 
-    // Withdraws the external stablecoin by burning YSS
+    // Withdraws `token` by burning YSS, less tout
     #[payable]
     #[storage(read)]
-    fn withdraw() -> u64;
+    fn withdraw(token: b256) -> u64;
+
+    // What deposit would mint for `amount` of `token`, reverting where it
+    // would revert
+    #[storage(read)]
+    fn preview_deposit(token: b256, amount: u64) -> u64;
+
+    // What withdraw would pay out of `token` for `amount` of YSS
+    #[storage(read)]
+    fn preview_withdraw(token: b256, amount: u64) -> u64;
 
     #[storage(read)]
     fn get_stablecoin_contract() -> b256;
@@ -36,4 +94,40 @@ abi PegStabilityModule {
 
     #[storage(read)]
     fn get_external_stable_decimals() -> u8;
+
+    #[storage(read)]
+    fn get_token_config(token: b256) -> TokenConfig;
+
+    // The primary token comes first
+    #[storage(read)]
+    fn get_token_count() -> u64;
+
+    #[storage(read)]
+    fn get_token_at(index: u64) -> b256;
+
+    #[storage(read)]
+    fn get_tin() -> u64;
+
+    #[storage(read)]
+    fn get_tout() -> u64;
+
+    #[storage(read)]
+    fn get_balancesheet_module() -> b256;
+
+    #[storage(read)]
+    fn is_strategy(strategy: b256) -> bool;
+
+    #[storage(read)]
+    fn get_max_allocation() -> u64;
+
+    #[storage(read)]
+    fn get_allocation(strategy: b256, token: b256) -> u64;
+
+    // Held by all strategies
+    #[storage(read)]
+    fn get_allocated(token: b256) -> u64;
+
+    // Held by the PSM and its strategies
+    #[storage(read)]
+    fn get_reserves(token: b256) -> u64;
 }
\ No newline at end of file
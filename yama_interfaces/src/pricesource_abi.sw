@@ -6,3 +6,14 @@ use yama_types::ufp128::*;
 abi PriceSource {
     fn price() -> UFP128;
 }
+
+// Implemented by price sources that know when their price was observed, so
+// consumers can reject stale prices. Its price() is PriceSource's read from
+// storage, so PriceSource consumers can call it too.
+abi TimestampedPriceSource {
+    #[storage(read)]
+    fn price() -> UFP128;
+
+    #[storage(read)]
+    fn updated_at() -> u64;
+}
\ No newline at end of file
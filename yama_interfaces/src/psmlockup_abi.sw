@@ -3,6 +3,16 @@ library psmlockup_abi;
 use fixed_point::ufp128::UFP128;
 use yama_types::ufp128::*;
 
+// Lockup tokens held by the lockup until they're claimed, which redeems them
+// at the value then, or cancelled, which returns them. The unlock time is
+// fixed by the exit delay when the ticket is requested.
+pub struct ExitTicket {
+    owner: Identity,
+    lockup_amount: u64,
+    unlock_time: u64,
+    done: bool
+}
+
 abi PSMLockup {
     #[storage(read, write)]
     fn set_bsh_contract(bsh_contract: b256);
@@ -11,13 +21,50 @@ abi PSMLockup {
     #[storage(read, write)]
     fn lockup() -> u64;
 
+    // Reverts while the exit queue is enabled
     #[payable]
     #[storage(read, write)]
     fn redeem() -> u64;
 
+    // While enabled, lockup tokens can only be redeemed through tickets.
+    // `delay` applies to tickets requested afterwards.
+    #[storage(read, write)]
+    fn set_exit_queue(enabled: bool, delay: u64);
+
+    // Takes the forwarded lockup tokens and returns a ticket ID. Reverts
+    // unless the exit queue is enabled.
+    #[payable]
+    #[storage(read, write)]
+    fn request_redeem() -> u64;
+
+    // Returns the ticket's lockup tokens to its owner
+    #[storage(read, write)]
+    fn cancel_redeem(ticket_id: u64);
+
+    // Redeems the ticket's lockup tokens for its owner, returning the YSS
+    // paid out. Claimable as soon as the lockup holds enough YSS to pay the
+    // ticket out, or regardless once it unlocks, paying out up to what the
+    // lockup holds. Anyone can claim a ticket.
+    #[storage(read, write)]
+    fn claim_redeem(ticket_id: u64) -> u64;
+
+    // Sends the BSH contract up to `deficit` of the lockup's YSS to burn,
+    // returning the amount sent. Only callable by the BSH contract.
+    #[storage(read)]
+    fn absorb_deficit(deficit: u64) -> u64;
+
     #[storage(read)]
     fn value() -> UFP128;
 
+    // What lockup would mint for `amount` of the external stablecoin, once
+    // the SimpleBSH's pending share has been minted to the lockup
+    #[storage(read)]
+    fn preview_lockup(amount: u64) -> u64;
+
+    // What redeem would pay out for `amount` of the lockup token
+    #[storage(read)]
+    fn preview_redeem(amount: u64) -> u64;
+
     #[storage(read)]
     fn total_supply() -> u64;
 
@@ -41,4 +88,16 @@ abi PSMLockup {
 
     #[storage(read)]
     fn get_bsh_contract() -> b256;
+
+    #[storage(read)]
+    fn is_exit_queue_enabled() -> bool;
+
+    #[storage(read)]
+    fn get_exit_delay() -> u64;
+
+    #[storage(read)]
+    fn get_ticket_count() -> u64;
+
+    #[storage(read)]
+    fn get_ticket(ticket_id: u64) -> ExitTicket;
 }
\ No newline at end of file
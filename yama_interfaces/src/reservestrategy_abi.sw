@@ -0,0 +1,18 @@
+library reservestrategy_abi;
+
+// Somewhere the PSM can put idle reserves to earn yield. Strategies hold
+// reserves on behalf of the PSM, and only the PSM can withdraw them.
+abi ReserveStrategy {
+    // Takes the forwarded coins as reserves
+    #[payable]
+    #[storage(read, write)]
+    fn deposit();
+
+    // Sends `amount` of `token` back to the PSM
+    #[storage(read, write)]
+    fn withdraw(token: b256, amount: u64);
+
+    // Reserves of `token` held for the PSM, including yield
+    #[storage(read)]
+    fn balance(token: b256) -> u64;
+}
\ No newline at end of file
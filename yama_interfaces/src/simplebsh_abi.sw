@@ -18,6 +18,11 @@ abi SimpleBSH {
     #[storage(read, write)]
     fn process_pending_share_amount();
 
+    // While enabled, deficits are covered by burning YSS held by the target,
+    // which must be a PSMLockup
+    #[storage(read, write)]
+    fn set_absorb_deficits(enabled: bool);
+
     // Sets the balancesheet module
     #[storage(read, write)]
     fn set_balancesheet_module(value: b256);
@@ -36,4 +41,7 @@ abi SimpleBSH {
 
     #[storage(read)]
     fn get_last_block_update() -> u64;
+
+    #[storage(read)]
+    fn is_absorbing_deficits() -> bool;
 }
\ No newline at end of file
@@ -0,0 +1,60 @@
+library splitbsh_abi;
+
+use signed_integers::i256::I256;
+
+// `weight` is out of SPLITBSH_DENOMINATOR. A nonzero `cap` limits what the
+// recipient receives in total.
+pub struct Recipient {
+    recipient: Identity,
+    weight: u64,
+    cap: u64,
+    received: u64
+}
+
+// Implemented alongside BalanceSheetHandler
+abi SplitBSH {
+    // Returns the recipient ID
+    #[storage(read, write)]
+    fn add_recipient(recipient: Identity, weight: u64, cap: u64) -> u64;
+
+    // A weight of 0 stops the recipient's share
+    #[storage(read, write)]
+    fn set_recipient(recipient_id: u64, weight: u64, cap: u64);
+
+    // Deficits are covered by burning YSS held by the backstop, which must be
+    // a PSMLockup with this as its BSH contract. Zero to leave them uncovered.
+    #[storage(read, write)]
+    fn set_backstop(backstop: b256);
+
+    #[storage(read, write)]
+    fn set_balancesheet_module(value: b256);
+
+    #[storage(read)]
+    fn get_recipient_count() -> u64;
+
+    #[storage(read)]
+    fn get_recipient(recipient_id: u64) -> Recipient;
+
+    #[storage(read)]
+    fn get_total_weight() -> u64;
+
+    #[storage(read)]
+    fn get_backstop() -> b256;
+
+    #[storage(read)]
+    fn get_stablecoin_contract() -> b256;
+
+    #[storage(read)]
+    fn get_balancesheet_module() -> b256;
+
+    // As in SimpleBSH, so a PSMLockup can use this as its BSH contract.
+    // Shares are minted right away, so nothing is ever pending.
+    #[storage(read, write)]
+    fn process_pending_share_amount();
+
+    #[storage(read)]
+    fn get_pending_share_amount() -> I256;
+
+    #[storage(read)]
+    fn get_target() -> b256;
+}
\ No newline at end of file
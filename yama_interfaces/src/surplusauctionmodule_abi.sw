@@ -0,0 +1,115 @@
+library surplusauctionmodule_abi;
+
+use fixed_point::ufp128::UFP128;
+use yama_types::ufp128::*;
+
+// Surplus auctions sell `lot` of YSS for the governance token, starting at
+// `start_price` and falling by `change_rate` every `time_interval`. Debt
+// auctions mint governance tokens for `lot` of YSS, starting at `start_price`
+// and rising by `change_rate`. Either starts once the surplus or deficit
+// exceeds `threshold` by a lot, and a `lot` of 0 disables it. An enabled
+// auction needs a nonzero `time_interval` and `reset_threshold`.
+pub struct SAMParams {
+    threshold: u64,
+    lot: u64,
+    start_price: UFP128,
+    time_interval: u64,
+    change_rate: UFP128,
+    reset_threshold: u64
+}
+
+// `start_price` is in governance tokens for the whole lot
+pub struct SAMAuction {
+    is_debt_auction: bool,
+    lot: u64,
+    start_price: UFP128,
+    start_time: u64,
+    done: bool
+}
+
+abi SurplusAuctionModule {
+    #[storage(read, write)]
+    fn set_surplus_auction_params(
+        threshold: u64,
+        lot: u64,
+        start_price: UFP128,
+        time_interval: u64,
+        change_rate: UFP128,
+        reset_threshold: u64
+    );
+
+    #[storage(read, write)]
+    fn set_debt_auction_params(
+        threshold: u64,
+        lot: u64,
+        start_price: UFP128,
+        time_interval: u64,
+        change_rate: UFP128,
+        reset_threshold: u64
+    );
+
+    #[storage(read, write)]
+    fn set_balancesheet_module(value: b256);
+
+    // The new token has to allow this module. Open auctions are claimed in
+    // whichever token is set when they're claimed.
+    #[storage(read, write)]
+    fn set_gov_token(gov_token: b256);
+
+    // Anyone can start an auction once the surplus, less the lots of open
+    // surplus auctions, exceeds the threshold by a lot. Returns the auction
+    // ID.
+    #[storage(read, write)]
+    fn start_surplus_auction() -> u64;
+
+    // As start_surplus_auction, for the deficit and debt auctions
+    #[storage(read, write)]
+    fn start_debt_auction() -> u64;
+
+    // Takes the forwarded governance tokens, burns the price and refunds the
+    // rest, and mints the lot to the sender
+    #[payable]
+    #[storage(read, write)]
+    fn claim_surplus_auction(auction_id: u64, max_price: u64);
+
+    // Takes the forwarded YSS, burns the lot and refunds the rest, and mints
+    // the price in governance tokens to the sender
+    #[payable]
+    #[storage(read, write)]
+    fn claim_debt_auction(auction_id: u64, min_price: u64);
+
+    // Restarts an expired auction for the same lot, returning the new ID
+    #[storage(read, write)]
+    fn reset_auction(auction_id: u64) -> u64;
+
+    #[storage(read)]
+    fn get_price(auction_id: u64) -> u64;
+
+    #[storage(read)]
+    fn is_expired(auction_id: u64) -> bool;
+
+    #[storage(read)]
+    fn get_auction(auction_id: u64) -> SAMAuction;
+
+    #[storage(read)]
+    fn get_auction_count() -> u64;
+
+    #[storage(read)]
+    fn get_surplus_auction_params() -> SAMParams;
+
+    #[storage(read)]
+    fn get_debt_auction_params() -> SAMParams;
+
+    // The lots of open surplus or debt auctions
+    #[storage(read)]
+    fn get_reserved(is_debt_auction: bool) -> u64;
+
+    #[storage(read)]
+    fn get_stablecoin_contract() -> b256;
+
+    #[storage(read)]
+    fn get_balancesheet_module() -> b256;
+
+    #[storage(read)]
+    fn get_gov_token() -> b256;
+}
\ No newline at end of file